@@ -0,0 +1,237 @@
+//! Read-only trees which borrow all of their strings.
+
+use crate::{Element, Error, Tag, Tags, Xml};
+use std::{borrow::Cow, ops::Range};
+
+/// Parse an XML document into a read-only tree which never allocates per node.
+///
+/// Names, text and attributes are always slices of the input, and all nodes and attributes
+/// are stored in two flat lists, so parsing allocates only as those lists grow. This suits
+/// analytics over documents which are already in memory. For mutation, convert the tree with
+/// [`BorrowedTree::to_xml`].
+///
+/// Parsing otherwise behaves as with [`document`](crate::document).
+///
+/// # Examples
+///
+/// ```rust
+/// let text = r#"<order id="7"><item sku="a"/>text<item sku="b"/></order>"#;
+/// let tree = xmlite::document_borrowed(text)?;
+/// let root = tree.root();
+/// assert_eq!(root.attr("id"), Some("\"7\""));
+/// let skus = root.elements().filter_map(|item| item.attr("sku")).collect::<Vec<_>>();
+/// assert_eq!(skus, ["\"a\"", "\"b\""]);
+/// assert_eq!(tree.len(), 4);
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+pub fn document_borrowed(text: &str) -> Result<BorrowedTree<'_>, Error> {
+    let mut tags = Tags::new(text);
+    let mut tree = BorrowedTree {
+        nodes: vec![],
+        attrs: vec![],
+    };
+    // open elements, with their node indices, names and the spans of their start tags
+    let mut stack: Vec<(usize, &str, (usize, usize))> = vec![];
+    loop {
+        let at_root = stack.is_empty();
+        match tags.next().ok_or(Error::Eof)? {
+            Tag::Declaration { .. } | Tag::Comment(_) | Tag::Doctype(_) => {}
+            Tag::Text(text) if at_root && text.trim().is_empty() => {}
+            Tag::Text(text) => {
+                tree.nodes.push(Node::Text(text));
+                if at_root {
+                    return Ok(tree);
+                }
+            }
+            Tag::Tag { name, attrs, kind } if !kind.is_closing() => {
+                let index = tree.nodes.len();
+                let start = tree.attrs.len();
+                tree.attrs.extend(attrs);
+                tree.nodes.push(Node::Element {
+                    name,
+                    attrs: start..tree.attrs.len(),
+                    end: index + 1,
+                });
+                match kind.is_self_closing() {
+                    true if at_root => return Ok(tree),
+                    true => {}
+                    false => stack.push((index, name, tags.span())),
+                }
+            }
+            Tag::Tag { name, .. } => match stack.pop() {
+                Some((index, open, _)) if open == name => {
+                    let len = tree.nodes.len();
+                    if let Node::Element { end, .. } = &mut tree.nodes[index] {
+                        *end = len;
+                    }
+                    if stack.is_empty() {
+                        return Ok(tree);
+                    }
+                }
+                opened => {
+                    return Err(Error::Mismatched {
+                        expected: opened
+                            .map_or("any opening tag", |(_, open, _)| open)
+                            .to_owned(),
+                        found: name.to_owned(),
+                        span: tags.report(),
+                        opened: opened.map(|(_, _, span)| span),
+                    });
+                }
+            },
+        }
+    }
+}
+
+/// Read-only XML tree which borrows all of its strings, see [`document_borrowed`].
+///
+/// Nodes are accessed through [`BorrowedNode`] handles, starting at [`BorrowedTree::root`].
+/// Attribute values keep their quotes and text is not unescaped, as when parsing an [`Xml`]
+/// tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorrowedTree<'a> {
+    // nodes in document order, each element followed by its descendants
+    nodes: Vec<Node<'a>>,
+    attrs: Vec<(&'a str, &'a str)>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node<'a> {
+    // `end` is the index after the last descendant of the element
+    Element {
+        name: &'a str,
+        attrs: Range<usize>,
+        end: usize,
+    },
+    Text(&'a str),
+}
+impl<'a> BorrowedTree<'a> {
+    /// Get the root node.
+    pub fn root(&self) -> BorrowedNode<'_, 'a> {
+        BorrowedNode {
+            tree: self,
+            index: 0,
+        }
+    }
+    /// Get the number of nodes, including text nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    /// Check if the tree has no nodes, which is never the case for a parsed tree.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+    /// Convert the tree into an [`Xml`] tree borrowing the same strings, e.g. to modify it.
+    pub fn to_xml(&self) -> Xml<'a> {
+        self.root().to_xml()
+    }
+}
+
+/// Node of a [`BorrowedTree`].
+#[derive(Debug, Clone, Copy)]
+pub struct BorrowedNode<'t, 'a> {
+    tree: &'t BorrowedTree<'a>,
+    index: usize,
+}
+impl<'t, 'a> BorrowedNode<'t, 'a> {
+    fn node(&self) -> &'t Node<'a> {
+        &self.tree.nodes[self.index]
+    }
+    fn end(&self) -> usize {
+        match self.node() {
+            Node::Element { end, .. } => *end,
+            Node::Text(_) => self.index + 1,
+        }
+    }
+
+    /// Get the name of an element.
+    pub fn name(&self) -> Option<&'a str> {
+        match self.node() {
+            Node::Element { name, .. } => Some(name),
+            Node::Text(_) => None,
+        }
+    }
+    /// Get the text of a text node.
+    pub fn content(&self) -> Option<&'a str> {
+        match self.node() {
+            Node::Text(text) => Some(text),
+            Node::Element { .. } => None,
+        }
+    }
+    /// Get the attributes of an element in document order, or none for a text node.
+    pub fn attrs(&self) -> &'t [(&'a str, &'a str)] {
+        match self.node() {
+            Node::Element { attrs, .. } => &self.tree.attrs[attrs.clone()],
+            Node::Text(_) => &[],
+        }
+    }
+    /// Get the value of an attribute.
+    pub fn attr(&self, key: &str) -> Option<&'a str> {
+        self.attrs()
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    }
+    /// Check if the node is an element.
+    pub fn is_element(&self) -> bool {
+        matches!(self.node(), Node::Element { .. })
+    }
+    /// Check if the node is a text node.
+    pub fn is_text(&self) -> bool {
+        matches!(self.node(), Node::Text(_))
+    }
+
+    /// Iterate over the child nodes.
+    pub fn children(&self) -> impl Iterator<Item = BorrowedNode<'t, 'a>> + use<'t, 'a> {
+        let (tree, end) = (self.tree, self.end());
+        let mut index = self.index + 1;
+        std::iter::from_fn(move || {
+            let child = (index < end).then_some(BorrowedNode { tree, index })?;
+            index = child.end();
+            Some(child)
+        })
+    }
+    /// Iterate over the child elements, skipping text.
+    pub fn elements(&self) -> impl Iterator<Item = BorrowedNode<'t, 'a>> + use<'t, 'a> {
+        self.children().filter(BorrowedNode::is_element)
+    }
+    /// Iterate over the descendants (excluding self) in document order.
+    pub fn descendants(&self) -> impl Iterator<Item = BorrowedNode<'t, 'a>> + use<'t, 'a> {
+        let tree = self.tree;
+        (self.index + 1..self.end()).map(move |index| BorrowedNode { tree, index })
+    }
+
+    /// Convert the node and its descendants into an [`Xml`] tree borrowing the same strings.
+    pub fn to_xml(&self) -> Xml<'a> {
+        match self.node() {
+            Node::Element { name, .. } => Xml::Element(Element {
+                name: Cow::Borrowed(name),
+                attrs: (self.attrs().iter())
+                    .map(|&(k, v)| (Cow::Borrowed(k), Cow::Borrowed(v)))
+                    .collect(),
+                children: self.children().map(|child| child.to_xml()).collect(),
+            }),
+            Node::Text(text) => Xml::Text(Cow::Borrowed(text)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_as_document() {
+        let text = "<?xml version='1.0'?><a x='1'><b>t<c/></b>u<d y='2' z='3'/></a>";
+        let tree = document_borrowed(text).unwrap();
+        assert_eq!(tree.to_xml(), crate::document(text).unwrap());
+        let names = tree.root().descendants().filter_map(|n| n.name());
+        assert_eq!(names.collect::<Vec<_>>(), ["b", "c", "d"]);
+        assert_eq!(tree.root().children().count(), 3);
+
+        assert!(matches!(
+            document_borrowed("<a><b></a>"),
+            Err(Error::Mismatched { .. })
+        ));
+        assert!(matches!(document_borrowed("<a><b/>"), Err(Error::Eof)));
+    }
+}
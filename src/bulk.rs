@@ -0,0 +1,104 @@
+//! Word-at-a-time text scanning.
+//!
+//! Processes eight bytes at a time using plain integer arithmetic, which works on every target
+//! without `unsafe` or platform intrinsics.
+
+const ONES: u64 = u64::from_ne_bytes([0x01; 8]);
+const HIGHS: u64 = u64::from_ne_bytes([0x80; 8]);
+const LOWS: u64 = u64::from_ne_bytes([0x7F; 8]);
+
+/// Set the high bit of every zero byte in a word, and clear all other bits.
+fn zero_bytes(word: u64) -> u64 {
+    !(((word & LOWS) + LOWS) | word) & HIGHS
+}
+
+/// Read an eight byte chunk as a word in native byte order.
+fn word_ne(chunk: &[u8]) -> u64 {
+    u64::from_ne_bytes(chunk.try_into().unwrap())
+}
+
+/// Count the occurrences of a byte.
+pub(crate) fn count(bytes: &[u8], byte: u8) -> usize {
+    let words = bytes.chunks_exact(8);
+    let rest = words.remainder();
+    let splat = ONES * byte as u64;
+    let full = words
+        .map(|word| zero_bytes(word_ne(word) ^ splat).count_ones() as usize)
+        .sum::<usize>();
+    full + rest.iter().filter(|&&b| b == byte).count()
+}
+
+/// Find the position of the first occurrence of any of a few bytes.
+pub(crate) fn find_any(bytes: &[u8], needles: &[u8]) -> Option<usize> {
+    let words = bytes.chunks_exact(8);
+    let rest = words.remainder();
+    for (i, word) in words.enumerate() {
+        let word = u64::from_le_bytes(word.try_into().unwrap());
+        let found = needles.iter().fold(0, |found, &byte| {
+            found | zero_bytes(word ^ (ONES * byte as u64))
+        });
+        if found != 0 {
+            return Some(i * 8 + found.trailing_zeros() as usize / 8);
+        }
+    }
+    let offset = bytes.len() - rest.len();
+    rest.iter()
+        .position(|b| needles.contains(b))
+        .map(|i| offset + i)
+}
+
+/// Find the position of the last occurrence of a byte.
+pub(crate) fn rfind(bytes: &[u8], byte: u8) -> Option<usize> {
+    let words = bytes.rchunks_exact(8);
+    let rest = words.remainder();
+    let splat = ONES * byte as u64;
+    for (i, word) in words.rev().enumerate().rev() {
+        let found = zero_bytes(u64::from_le_bytes(word.try_into().unwrap()) ^ splat);
+        if found != 0 {
+            let offset = 7 - found.leading_zeros() as usize / 8;
+            return Some(rest.len() + i * 8 + offset);
+        }
+    }
+    rest.iter().rposition(|&b| b == byte)
+}
+
+/// Count the characters in UTF-8 text, by counting the bytes that do not continue a character.
+pub(crate) fn count_chars(bytes: &[u8]) -> usize {
+    let words = bytes.chunks_exact(8);
+    let rest = words.remainder();
+    let continuations = words
+        .map(|word| {
+            let word = word_ne(word);
+            // continuation bytes look like 0b10xxxxxx
+            (word & !(word << 1) & HIGHS).count_ones() as usize
+        })
+        .sum::<usize>();
+    let continuations = continuations + rest.iter().filter(|&&b| b & 0xC0 == 0x80).count();
+    bytes.len() - continuations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts() {
+        let text = "blåbærgrød\nmed\nfløde og sukker, tak!\n€";
+        assert_eq!(count(text.as_bytes(), b'\n'), 3);
+        assert_eq!(count(text.as_bytes(), b'k'), 3);
+        assert_eq!(count_chars(text.as_bytes()), text.chars().count());
+        assert_eq!(rfind(text.as_bytes(), b'\n'), text.rfind('\n'));
+        assert_eq!(rfind(text.as_bytes(), b'b'), text.rfind('b'));
+        assert_eq!(rfind(&text.as_bytes()[..4], b'l'), Some(1));
+        assert_eq!(rfind(text.as_bytes(), b'x'), None);
+        assert_eq!(find_any(text.as_bytes(), b"\n"), text.find('\n'));
+        assert_eq!(find_any(text.as_bytes(), b"ks"), text.find(['k', 's']));
+        assert_eq!(find_any(&text.as_bytes()[..40], b"!"), Some(39));
+        assert_eq!(find_any(text.as_bytes(), b"x<"), None);
+        for i in 0..text.len() {
+            let bytes = &text.as_bytes()[i..];
+            let expected = bytes.iter().filter(|&&b| b & 0xC0 != 0x80).count();
+            assert_eq!(count_chars(bytes), expected);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Character classes from the XML specification.
+
+/// Check if a code point matches the `Char` production.
+pub(crate) fn is_char(c: u32) -> bool {
+    matches!(c, 0x9 | 0xA | 0xD | 0x20..=0xD7FF | 0xE000..=0xFFFD | 0x10000..=0x10FFFF)
+}
+
+/// Check if a character matches the `NameStartChar` production.
+pub(crate) fn is_name_start_char(c: char) -> bool {
+    matches!(c,
+        ':' | 'A'..='Z' | '_' | 'a'..='z'
+        | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}')
+}
+
+/// Check if a character matches the `NameChar` production.
+pub(crate) fn is_name_char(c: char) -> bool {
+    is_name_start_char(c)
+        || matches!(c,
+            '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+/// Check if a string matches the `Name` production.
+pub(crate) fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_name_start_char) && chars.all(is_name_char)
+}
+
+/// Find the first illegal character or character reference in text or an attribute value.
+///
+/// Returns the byte offset and the offending code point.
+pub(crate) fn find_invalid(text: &str) -> Option<(usize, u32)> {
+    for (i, c) in text.char_indices() {
+        if !is_char(c as u32) {
+            return Some((i, c as u32));
+        }
+        if c != '&' || !text[i..].starts_with("&#") {
+            continue;
+        }
+        let Some(end) = text[i..].find(';') else {
+            continue;
+        };
+        let code = match &text[i + 2..i + end] {
+            hex if hex.starts_with('x') => u32::from_str_radix(&hex[1..], 16),
+            dec => dec.parse(),
+        };
+        match code {
+            Ok(code) if !is_char(code) => return Some((i, code)),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid() {
+        assert_eq!(find_invalid("hello\tworld\n"), None);
+        assert_eq!(find_invalid("a\u{1}b"), Some((1, 0x1)));
+        assert_eq!(find_invalid("a&#xD800;"), Some((1, 0xD800)));
+        assert_eq!(find_invalid("&#0;"), Some((0, 0)));
+        assert_eq!(find_invalid("&#65;&amp;"), None);
+    }
+
+    #[test]
+    fn names() {
+        assert!(is_name("wl_display"));
+        assert!(is_name("xml:lang"));
+        assert!(is_name("café·2"));
+        assert!(!is_name("1abc"));
+        assert!(!is_name("-abc"));
+        assert!(!is_name(""));
+    }
+}
@@ -0,0 +1,311 @@
+//! Reordering, deduplication and grouping of the children of elements.
+
+use crate::{Element, Xml};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
+    hash::Hash,
+    mem,
+};
+
+/// Child of an element in mixed content, see [`Xml::items`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentItem<'x, 'a> {
+    /// Text, escaped as in the document.
+    Text(&'x str),
+    /// Element.
+    Element(&'x Xml<'a>),
+}
+
+impl<'a> Xml<'a> {
+    /// Iterate over the text and element children in document order, with their indices
+    /// among all children, e.g. for use with [`Xml::child_at`].
+    ///
+    /// This is for document-oriented content where text and markup are interleaved, such as
+    /// paragraphs with inline emphasis. Comments and other raw nodes are skipped, so indices
+    /// may have gaps.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use xmlite::ContentItem;
+    /// let xml = xmlite::document("<p>Call <code>init</code> first &amp; <em>once</em>.</p>")?;
+    /// let mut markdown = String::new();
+    /// for (_, item) in xml.items() {
+    ///     match item {
+    ///         ContentItem::Text(text) => markdown.push_str(text),
+    ///         ContentItem::Element(code) if code.name() == Some("code") => {
+    ///             markdown.push_str(&format!("`{}`", code.inner_xml()))
+    ///         }
+    ///         ContentItem::Element(other) => markdown.push_str(&format!("*{}*", other.inner_xml())),
+    ///     }
+    /// }
+    /// assert_eq!(markdown, "Call `init` first &amp; *once*.");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn items(&self) -> impl Iterator<Item = (usize, ContentItem<'_, 'a>)> {
+        self.children()
+            .enumerate()
+            .filter_map(|(i, child)| match child {
+                Xml::Text(text) => Some((i, ContentItem::Text(text))),
+                Xml::Element(_) => Some((i, ContentItem::Element(child))),
+                _ => None,
+            })
+    }
+
+    /// Sort the child elements with a comparator, keeping text, comments and other nodes at
+    /// their positions.
+    ///
+    /// Elements are moved between the positions of elements only, so the whitespace of
+    /// indented documents stays where it was. The sort is stable and does not descend into
+    /// the children. Does nothing for nodes other than elements.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut xml = xmlite::document("<a>\n  <c/>\n  <b/>\n  <a/>\n</a>")?;
+    /// xml.sort_children_by(|x, y| x.name().cmp(&y.name()));
+    /// assert_eq!(xml.to_string(), "<a>\n  <a/>\n  <b/>\n  <c/>\n</a>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn sort_children_by(&mut self, mut compare: impl FnMut(&Xml<'a>, &Xml<'a>) -> Ordering) {
+        let Xml::Element(Element { children, .. }) = self else {
+            return;
+        };
+        let (mut elements, mut slots) = (vec![], vec![]);
+        for child in mem::take(children) {
+            match child.is_element() {
+                true => {
+                    elements.push(child);
+                    slots.push(None);
+                }
+                false => slots.push(Some(child)),
+            }
+        }
+        elements.sort_by(&mut compare);
+        let mut elements = elements.into_iter();
+        *children = slots
+            .into_iter()
+            .map(|slot| slot.unwrap_or_else(|| elements.next().expect("a slot per element")))
+            .collect();
+    }
+    /// Sort the child elements by the unescaped value of an attribute, as strings.
+    ///
+    /// Elements without the attribute are placed after those with it, in their original
+    /// order. See [`Xml::sort_children_by`] for how other nodes are handled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut xml = xmlite::document("<deps><dep id='serde'/><dep/><dep id='regex'/></deps>")?;
+    /// xml.sort_children_by_attr("id");
+    /// assert_eq!(xml.to_string(), "<deps><dep id='regex'/><dep id='serde'/><dep/></deps>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn sort_children_by_attr(&mut self, key: &str) {
+        self.sort_children_by(
+            |x, y| match (x.attr_unescaped(key), y.attr_unescaped(key)) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                (x, y) => y.is_some().cmp(&x.is_some()),
+            },
+        );
+    }
+
+    /// Remove child elements which are structurally equal to an earlier child element,
+    /// returning the number of elements removed.
+    ///
+    /// Only elements are compared, and text and other nodes are kept, except for whitespace
+    /// directly before a removed element. This keeps indented documents tidy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut xml = xmlite::document("<deps><dep id='a'/><dep id='b'/><dep id='a'/></deps>")?;
+    /// assert_eq!(xml.dedup_children(), 1);
+    /// assert_eq!(xml.to_string(), "<deps><dep id='a'/><dep id='b'/></deps>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn dedup_children(&mut self) -> usize {
+        let mut seen = HashSet::new();
+        let removed = self
+            .children()
+            .filter(|child| child.is_element())
+            .map(|child| !seen.insert(child))
+            .collect();
+        self.remove_elements(removed)
+    }
+    /// Remove child elements which are structurally equal to the child element before them,
+    /// as in [`Vec::dedup`], returning the number of elements removed.
+    ///
+    /// Nodes between elements are ignored when comparing, and handled as in
+    /// [`Xml::dedup_children`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut xml = xmlite::document("<log><x/>\n<x/>\n<y/>\n<x/></log>")?;
+    /// assert_eq!(xml.dedup_consecutive_children(), 1);
+    /// assert_eq!(xml.to_string(), "<log><x/>\n<y/>\n<x/></log>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn dedup_consecutive_children(&mut self) -> usize {
+        let mut previous = None;
+        let removed = self
+            .children()
+            .filter(|child| child.is_element())
+            .map(|child| previous.replace(child) == Some(child))
+            .collect();
+        self.remove_elements(removed)
+    }
+    /// Remove child elements with the same key as an earlier child element, returning the
+    /// number of elements removed.
+    ///
+    /// Nodes other than elements are handled as in [`Xml::dedup_children`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut xml = xmlite::document("<deps><dep id='a' v='1'/><dep id='a' v='2'/></deps>")?;
+    /// let removed = xml.dedup_children_by_key(|dep| dep.attr_unescaped("id").map(|id| id.into_owned()));
+    /// assert_eq!((removed, xml.children().len()), (1, 1));
+    /// assert_eq!(xml.children().next().unwrap().attr("v"), Some("'1'"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn dedup_children_by_key<K: Hash + Eq>(
+        &mut self,
+        mut key: impl FnMut(&Xml<'a>) -> K,
+    ) -> usize {
+        let mut seen = HashSet::new();
+        let removed = self
+            .children()
+            .filter(|child| child.is_element())
+            .map(|child| !seen.insert(key(child)))
+            .collect();
+        self.remove_elements(removed)
+    }
+
+    /// Group the child elements by name, keeping document order within each group.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<book><author>A</author><title>T</title><author>B</author></book>")?;
+    /// let groups = xml.children_grouped();
+    /// assert_eq!(groups["author"].len(), 2);
+    /// assert_eq!(groups["title"][0].inner_xml(), "T");
+    /// assert!(!groups.contains_key("isbn"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn children_grouped(&self) -> BTreeMap<&str, Vec<&Xml<'a>>> {
+        let mut groups = BTreeMap::<_, Vec<_>>::new();
+        for child in self.children() {
+            if let Some(name) = child.name() {
+                groups.entry(name).or_default().push(child);
+            }
+        }
+        groups
+    }
+    /// Split the child elements into those for which a predicate returns true and the rest,
+    /// keeping document order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<deps><dep optional='true'>a</dep><dep>b</dep>text</deps>")?;
+    /// let (optional, required) = xml.partition(|dep| dep.attr("optional").is_some());
+    /// assert_eq!((optional.len(), required.len()), (1, 1));
+    /// assert_eq!(required[0].inner_xml(), "b");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn partition(
+        &self,
+        predicate: impl FnMut(&&Xml<'a>) -> bool,
+    ) -> (Vec<&Xml<'a>>, Vec<&Xml<'a>>) {
+        self.children()
+            .filter(|child| child.is_element())
+            .partition(predicate)
+    }
+
+    /// Remove the child elements marked as removed, in order, along with the whitespace
+    /// directly before them.
+    fn remove_elements(&mut self, removed: Vec<bool>) -> usize {
+        let Xml::Element(Element { children, .. }) = self else {
+            return 0;
+        };
+        let mut removed = removed.into_iter();
+        let count = removed
+            .as_slice()
+            .iter()
+            .filter(|&&removed| removed)
+            .count();
+        let mut kept = Vec::with_capacity(children.len() - count);
+        for child in mem::take(children) {
+            if !child.is_element() || !removed.next().unwrap_or(false) {
+                kept.push(child);
+            } else if kept
+                .last()
+                .is_some_and(|node| matches!(node, Xml::Text(text) if text.trim().is_empty()))
+            {
+                kept.pop();
+            }
+        }
+        *children = kept;
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort() {
+        let mut xml = crate::document("<a>1<b k='2'/>2<b k='&#49;'/>3<c/></a>").unwrap();
+        xml.sort_children_by_attr("k");
+        assert_eq!(xml.to_string(), "<a>1<b k='&#49;'/>2<b k='2'/>3<c/></a>");
+        xml.sort_children_by(|x, y| y.name().cmp(&x.name()));
+        assert_eq!(xml.to_string(), "<a>1<c/>2<b k='&#49;'/>3<b k='2'/></a>");
+
+        let mut text = crate::Xml::text("x");
+        text.sort_children_by_attr("k");
+        assert_eq!(text.to_string(), "x");
+    }
+
+    #[test]
+    fn dedup() {
+        let text = "<a>\n  <b>1</b>\n  <b>1</b>\n  <b>2</b>\n  t\n  <b>1</b>\n</a>";
+        let mut xml = crate::document(text).unwrap();
+        assert_eq!(xml.clone().dedup_consecutive_children(), 1);
+        assert_eq!(xml.dedup_children(), 2);
+        assert_eq!(
+            xml.to_string(),
+            "<a>\n  <b>1</b>\n  <b>2</b>\n  t\n  \n</a>"
+        );
+    }
+
+    #[test]
+    fn groups() {
+        let xml = crate::document("<a>t<b>1</b><c/><b>2</b></a>").unwrap();
+        let groups = xml.children_grouped();
+        assert_eq!(groups.keys().copied().collect::<Vec<_>>(), ["b", "c"]);
+        assert_eq!(groups["b"][1].inner_xml(), "2");
+        let (b, rest) = xml.partition(|child| child.name() == Some("b"));
+        assert_eq!((b.len(), rest.len()), (2, 1));
+        assert!(crate::Xml::text("x").children_grouped().is_empty());
+    }
+
+    #[test]
+    fn items() {
+        let options = crate::ParseOptions::new().with_comments(true);
+        let xml = crate::document_with("<p>a<!-- c --><b/>c</p>", &options).unwrap();
+        let items = xml.items().collect::<Vec<_>>();
+        assert!(matches!(
+            items[..],
+            [
+                (0, ContentItem::Text("a")),
+                (2, ContentItem::Element(_)),
+                (3, ContentItem::Text("c"))
+            ]
+        ));
+    }
+}
@@ -0,0 +1,187 @@
+//! Semantic comparison of document trees.
+
+use crate::{
+    Element, Xml,
+    escape::{attr_text, unescape_text},
+};
+use std::borrow::Cow;
+
+/// Options for [`Xml::semantic_eq`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::EqOptions;
+/// let options = EqOptions::new().with_ignore_whitespace(true);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EqOptions {
+    ignore_whitespace: bool,
+    normalize_text: bool,
+    ignore_comments: bool,
+}
+impl EqOptions {
+    /// Create options comparing text exactly.
+    pub fn new() -> Self {
+        EqOptions::default()
+    }
+
+    /// Skip text that only contains whitespace, such as indentation between elements.
+    pub fn with_ignore_whitespace(mut self, ignore_whitespace: bool) -> Self {
+        self.ignore_whitespace = ignore_whitespace;
+        self
+    }
+    /// Skip comments, such as those kept by
+    /// [`ParseOptions::with_comments`](crate::ParseOptions::with_comments), so the text
+    /// around a comment is compared as one.
+    pub fn with_ignore_comments(mut self, ignore_comments: bool) -> Self {
+        self.ignore_comments = ignore_comments;
+        self
+    }
+    /// Compare text with leading and trailing whitespace removed and inner runs of whitespace
+    /// collapsed to a single space.
+    pub fn with_normalize_text(mut self, normalize_text: bool) -> Self {
+        self.normalize_text = normalize_text;
+        self
+    }
+}
+
+impl Xml<'_> {
+    /// Compare two trees by meaning rather than by representation.
+    ///
+    /// Unlike `==`, attribute values and text are compared unescaped, without quotes and with
+    /// CDATA sections unwrapped, and adjacent text nodes are compared as one. Attribute order never matters. Comments are compared like
+    /// other raw nodes when the trees keep them, and handling of comments and whitespace is
+    /// controlled by the [`EqOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{EqOptions, Xml};
+    /// let parsed = xmlite::document("<a y='2' x=\"1\">\n  <b>hi<!-- there --></b>\n</a>").unwrap();
+    /// let built = Xml::element("a")
+    ///     .with_attr("x", "1")
+    ///     .with_attr("y", "2")
+    ///     .with_child(Xml::element("b").with_child(Xml::text("hi")));
+    /// assert_ne!(parsed, built);
+    /// assert!(!parsed.semantic_eq(&built, EqOptions::new()));
+    /// assert!(parsed.semantic_eq(&built, EqOptions::new().with_ignore_whitespace(true)));
+    /// ```
+    pub fn semantic_eq(&self, other: &Xml<'_>, options: EqOptions) -> bool {
+        match (self, other) {
+            (
+                Xml::Element(Element {
+                    name,
+                    attrs,
+                    children,
+                }),
+                Xml::Element(Element {
+                    name: other_name,
+                    attrs: other_attrs,
+                    children: other_children,
+                }),
+            ) => {
+                name == other_name
+                    && attrs.len() == other_attrs.len()
+                    && attrs.iter().all(|(k, v)| {
+                        other_attrs
+                            .get(k)
+                            .is_some_and(|w| attr_text(v) == attr_text(w))
+                    })
+                    && {
+                        let a = merge(children, options);
+                        let b = merge(other_children, options);
+                        a.len() == b.len() && a.iter().zip(&b).all(|(a, b)| a.eq(b, options))
+                    }
+            }
+            (Xml::Text(a), Xml::Text(b)) => {
+                text(&unescape_text(a), options) == text(&unescape_text(b), options)
+            }
+            (Xml::Raw(a), Xml::Raw(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Child node with adjacent text merged.
+enum Child<'x, 'a> {
+    Node(&'x Xml<'a>),
+    Text(Cow<'x, str>),
+}
+impl Child<'_, '_> {
+    fn eq(&self, other: &Self, options: EqOptions) -> bool {
+        match (self, other) {
+            (Child::Node(a), Child::Node(b)) => a.semantic_eq(b, options),
+            (Child::Text(a), Child::Text(b)) => text(a, options) == text(b, options),
+            _ => false,
+        }
+    }
+}
+
+fn merge<'x, 'a>(children: &'x [Xml<'a>], options: EqOptions) -> Vec<Child<'x, 'a>> {
+    let mut merged: Vec<Child> = vec![];
+    for child in children {
+        match (child, merged.last_mut()) {
+            (Xml::Raw(raw), _) if options.ignore_comments && raw.starts_with("<!--") => {}
+            (Xml::Text(t), Some(Child::Text(last))) => last.to_mut().push_str(&unescape_text(t)),
+            (Xml::Text(t), _) => merged.push(Child::Text(unescape_text(t))),
+            (node, _) => merged.push(Child::Node(node)),
+        }
+    }
+    if options.ignore_whitespace {
+        merged.retain(|c| !matches!(c, Child::Text(t) if t.trim().is_empty()));
+    }
+    merged
+}
+
+fn text(text: &str, options: EqOptions) -> Cow<'_, str> {
+    if options.normalize_text {
+        Cow::Owned(text.split_whitespace().collect::<Vec<_>>().join(" "))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semantic_eq() {
+        let a = crate::document("<a x='1'><b>one  two</b>\n<c/></a>").unwrap();
+        let b = crate::document("<a x=\"1\"><b> one two </b><c/></a>").unwrap();
+        let loose = EqOptions::new()
+            .with_ignore_whitespace(true)
+            .with_normalize_text(true);
+        assert!(a.semantic_eq(&b, loose));
+        assert!(!a.semantic_eq(&b, loose.with_normalize_text(false)));
+        assert!(!a.semantic_eq(&b, loose.with_ignore_whitespace(false)));
+
+        let c = crate::document("<a x='2'><b>one two</b><c/></a>").unwrap();
+        assert!(!a.semantic_eq(&c, loose));
+        let d = crate::document("<a x='1'><b>one two</b><c/><c/></a>").unwrap();
+        assert!(!a.semantic_eq(&d, loose));
+    }
+
+    #[test]
+    fn unescaped() {
+        let a = crate::document("<a x='&lt;'>&lt;b<![CDATA[&]]></a>").unwrap();
+        let b = crate::document("<a x=\"&#60;\">&#60;b&amp;</a>").unwrap();
+        assert!(a.semantic_eq(&b, EqOptions::new()));
+        let built = Xml::element("a")
+            .with_attr("x", "<")
+            .with_child(Xml::text("&lt;b&amp;"));
+        assert!(a.semantic_eq(&built, EqOptions::new()));
+        let c = crate::document("<a x='&lt;'>&amp;lt;b&amp;</a>").unwrap();
+        assert!(!a.semantic_eq(&c, EqOptions::new()));
+    }
+
+    #[test]
+    fn comments() {
+        let options = crate::ParseOptions::new().with_comments(true);
+        let a = crate::document_with("<a>one<!-- x --> two<!-- y --></a>", &options).unwrap();
+        let b = crate::document("<a>one two</a>").unwrap();
+        assert!(!a.semantic_eq(&b, EqOptions::new()));
+        assert!(a.semantic_eq(&b, EqOptions::new().with_ignore_comments(true)));
+    }
+}
@@ -0,0 +1,265 @@
+//! Running the W3C XML conformance test suite.
+
+use crate::{Error, ParseOptions, Xml, escape::attr_text, resolve_uri};
+use std::collections::HashMap;
+
+/// Kind of a conformance test, from the `TYPE` attribute of a test in the suite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TestKind {
+    /// Valid document, which must be accepted.
+    Valid,
+    /// Well-formed document which is invalid against its DTD. Since this is a non-validating
+    /// parser, it must be accepted.
+    Invalid,
+    /// Document which is not well-formed, and must be rejected.
+    NotWellFormed,
+    /// Document with an optional error, which may be accepted or rejected.
+    Error,
+}
+
+/// Outcome of a conformance test, see [`TestCase::run`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// The document was handled as expected.
+    Pass,
+    /// A document which must be accepted was rejected.
+    Rejected,
+    /// A document which is not well-formed was accepted.
+    Accepted,
+    /// The document was accepted, but writing it and parsing the output gave a different tree.
+    RoundTrip,
+}
+impl Outcome {
+    /// Check if the test passed.
+    pub fn is_pass(self) -> bool {
+        self == Outcome::Pass
+    }
+}
+
+/// Test from a catalog of the W3C XML conformance test suite.
+///
+/// The suite is organized as catalogs of `TEST` elements, grouped in `TESTCASES` elements
+/// whose `xml:base` attributes locate the test documents, e.g. `xmltest/xmltest.xml`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::{Outcome, ParseOptions, TestCase, TestKind};
+/// let catalog = xmlite::document(r#"<TESTCASES xml:base="xmltest/">
+///     <TEST TYPE="valid" ID="v-1" URI="valid/sa/001.xml">Empty element</TEST>
+///     <TEST TYPE="not-wf" ID="nwf-1" URI="not-wf/sa/001.xml">Mismatched tag</TEST>
+/// </TESTCASES>"#)?;
+/// let cases = TestCase::catalog(&catalog);
+/// assert_eq!(cases[1].uri, "xmltest/not-wf/sa/001.xml");
+///
+/// let options = ParseOptions::new().with_strict(true);
+/// let results = [
+///     cases[0].run(b"<doc/>", &options),
+///     cases[1].run(b"<doc></dog>", &options),
+/// ];
+/// assert!(results.iter().all(|result| result.outcome.is_pass()));
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    /// Identifier of the test.
+    pub id: String,
+    /// Location of the test document, resolved against the `xml:base` attributes in scope.
+    pub uri: String,
+    /// Kind of the test.
+    pub kind: TestKind,
+    /// Sections of the specification which the test covers.
+    pub sections: String,
+    /// Description of the test.
+    pub description: String,
+}
+impl TestCase {
+    /// Read the tests from a catalog, in document order.
+    ///
+    /// Tests with an unknown `TYPE` are skipped. Catalogs which include others through
+    /// external entities, such as `xmlconf.xml`, must be read one included catalog at a time.
+    pub fn catalog(catalog: &Xml) -> Vec<TestCase> {
+        let mut cases = vec![];
+        collect(catalog, "", &mut cases);
+        cases
+    }
+
+    /// Run the test on the content of its document, parsing it with options and checking
+    /// that accepted documents can be written and parsed again unchanged.
+    ///
+    /// Use strict options to reject more documents which are not well-formed. Documents are
+    /// decoded as with [`document_bytes`](crate::document_bytes), so tests in encodings other
+    /// than UTF-8 are rejected.
+    pub fn run(&self, input: &[u8], options: &ParseOptions) -> TestResult {
+        let parsed = crate::utf8_text(input).and_then(|text| crate::document_with(text, options));
+        let (outcome, error) = match (self.kind, parsed) {
+            (TestKind::NotWellFormed, Ok(_)) => (Outcome::Accepted, None),
+            (TestKind::NotWellFormed | TestKind::Error, Err(error)) => (Outcome::Pass, Some(error)),
+            (_, Err(error)) => (Outcome::Rejected, Some(error)),
+            (_, Ok(xml)) => match crate::document_with(&xml.to_string(), options) {
+                Ok(written) if written == xml => (Outcome::Pass, None),
+                Ok(_) => (Outcome::RoundTrip, None),
+                Err(error) => (Outcome::RoundTrip, Some(error)),
+            },
+        };
+        TestResult {
+            id: self.id.clone(),
+            kind: self.kind,
+            outcome,
+            error,
+        }
+    }
+}
+
+/// Collect the tests of a catalog element, resolving locations against `base`.
+fn collect(node: &Xml, base: &str, cases: &mut Vec<TestCase>) {
+    let attr = |key| node.attr(key).map(|value| attr_text(value).into_owned());
+    let base = match attr("xml:base") {
+        Some(relative) => resolve_uri(base, &relative),
+        None => base.to_owned(),
+    };
+    if node.name() == Some("TEST") {
+        let kind = match attr("TYPE").as_deref() {
+            Some("valid") => TestKind::Valid,
+            Some("invalid") => TestKind::Invalid,
+            Some("not-wf") => TestKind::NotWellFormed,
+            Some("error") => TestKind::Error,
+            _ => return,
+        };
+        cases.push(TestCase {
+            id: attr("ID").unwrap_or_default(),
+            uri: resolve_uri(&base, &attr("URI").unwrap_or_default()),
+            kind,
+            sections: attr("SECTIONS").unwrap_or_default(),
+            description: node.text_content().trim().to_owned(),
+        });
+        return;
+    }
+    for child in node.children() {
+        collect(child, &base, cases);
+    }
+}
+
+/// Result of running a [`TestCase`].
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    /// Identifier of the test.
+    pub id: String,
+    /// Kind of the test.
+    pub kind: TestKind,
+    /// Outcome of the test.
+    pub outcome: Outcome,
+    /// Error from parsing the document, if it was rejected.
+    pub error: Option<Error>,
+}
+
+/// Summary of conformance test results, counting the outcomes of each kind of test.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::{ConformanceReport, Outcome, ParseOptions, TestCase, TestKind};
+/// let case = |kind, id: &str| TestCase {
+///     id: id.to_owned(),
+///     uri: format!("{id}.xml"),
+///     kind,
+///     sections: "2.1".to_owned(),
+///     description: String::new(),
+/// };
+/// let options = ParseOptions::new();
+/// let report = [
+///     case(TestKind::Valid, "a").run(b"<a/>", &options),
+///     case(TestKind::Valid, "b").run(b"<a>", &options),
+///     case(TestKind::NotWellFormed, "c").run(b"", &options),
+/// ]
+/// .into_iter()
+/// .collect::<ConformanceReport>();
+/// assert_eq!(report.count(TestKind::Valid, Outcome::Rejected), 1);
+/// assert_eq!((report.passed(), report.total()), (2, 3));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    counts: HashMap<(TestKind, Outcome), usize>,
+    failures: Vec<TestResult>,
+}
+impl ConformanceReport {
+    /// Create an empty report.
+    pub fn new() -> Self {
+        ConformanceReport::default()
+    }
+    /// Add a test result to the report.
+    pub fn add(&mut self, result: TestResult) {
+        *self
+            .counts
+            .entry((result.kind, result.outcome))
+            .or_default() += 1;
+        if !result.outcome.is_pass() {
+            self.failures.push(result);
+        }
+    }
+
+    /// Get the number of tests of a kind with an outcome.
+    pub fn count(&self, kind: TestKind, outcome: Outcome) -> usize {
+        self.counts.get(&(kind, outcome)).copied().unwrap_or(0)
+    }
+    /// Get the number of tests which passed.
+    pub fn passed(&self) -> usize {
+        self.total() - self.failures.len()
+    }
+    /// Get the number of tests.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+    /// Get the results of the tests which failed, in the order they were added.
+    pub fn failures(&self) -> &[TestResult] {
+        &self.failures
+    }
+}
+impl FromIterator<TestResult> for ConformanceReport {
+    fn from_iter<I: IntoIterator<Item = TestResult>>(iter: I) -> Self {
+        let mut report = ConformanceReport::new();
+        iter.into_iter().for_each(|result| report.add(result));
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcomes() {
+        let catalog = crate::document(
+            r#"<TESTSUITE><TESTCASES xml:base="a/"><TESTCASES xml:base="b/">
+                <TEST TYPE="not-wf" ID="1" URI="1.xml"/>
+                <TEST TYPE="error" ID="2" URI="../2.xml"/>
+                <TEST TYPE="other" ID="3" URI="3.xml"/>
+                <TEST TYPE="invalid" ID="4" URI="4.xml"/>
+            </TESTCASES></TESTCASES></TESTSUITE>"#,
+        )
+        .unwrap();
+        let cases = TestCase::catalog(&catalog);
+        let uris = cases
+            .iter()
+            .map(|case| case.uri.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(uris, ["a/b/1.xml", "a/2.xml", "a/b/4.xml"]);
+
+        let options = ParseOptions::new().with_strict(true);
+        let report = [
+            cases[0].run(b"<a>&#1;</a>", &options),
+            cases[0].run(b"<a/>", &options),
+            cases[1].run(b"<a/>", &options),
+            cases[2].run(b"\xFF\xFE<\0a\0/\0>\0", &options),
+        ]
+        .into_iter()
+        .collect::<ConformanceReport>();
+        assert_eq!(report.count(TestKind::NotWellFormed, Outcome::Pass), 1);
+        assert_eq!(report.count(TestKind::NotWellFormed, Outcome::Accepted), 1);
+        assert_eq!(report.count(TestKind::Invalid, Outcome::Rejected), 1);
+        assert!(matches!(
+            report.failures()[1].error,
+            Some(Error::Encoding { .. })
+        ));
+    }
+}
@@ -0,0 +1,263 @@
+//! Typed decoding of text content.
+
+use crate::{
+    Element, Error, Xml,
+    escape::{attr_text, unescape_text},
+    validate::child_path,
+};
+#[cfg(feature = "base64")]
+use base64::{Engine, engine::general_purpose::STANDARD};
+use std::{borrow::Cow, collections::HashMap, slice};
+
+impl Xml<'_> {
+    /// Decode the base64 text content of an element, e.g. binary payloads in SOAP messages.
+    ///
+    /// The text of all descendants is concatenated, and whitespace, such as the line breaks
+    /// of wrapped base64, is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the content is not valid base64.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<data>\n  aGVsbG8g\n  d29ybGQ=\n</data>")?;
+    /// assert_eq!(xml.content_base64()?, b"hello world");
+    /// assert!(xmlite::document("<data>a!</data>")?.content_base64().is_err());
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    #[cfg(feature = "base64")]
+    pub fn content_base64(&self) -> Result<Vec<u8>, Error> {
+        let text = self.text_content();
+        let text = text.split_ascii_whitespace().collect::<String>();
+        STANDARD
+            .decode(text)
+            .map_err(|error| self.invalid(format!("invalid base64: {error}")))
+    }
+
+    /// Decode the hexadecimal text content of an element, as in `xs:hexBinary`.
+    ///
+    /// The text of all descendants is concatenated. As in XML Schema, leading and trailing
+    /// whitespace is ignored, while whitespace between digits is an error; use
+    /// [`Xml::content_hex_lenient`] to ignore all whitespace. Digits can be in either case.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the content is not an even number of hexadecimal
+    /// digits.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<key> 0FB7 </key>")?;
+    /// assert_eq!(xml.content_hex()?, [0x0f, 0xb7]);
+    /// assert!(xmlite::document("<key>0FB</key>")?.content_hex().is_err());
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn content_hex(&self) -> Result<Vec<u8>, Error> {
+        self.hex(self.text_content().trim_matches([' ', '\t', '\r', '\n']))
+    }
+
+    /// Decode the hexadecimal text content of an element, ignoring all whitespace.
+    ///
+    /// This accepts hex dumps broken into lines or groups, which [`Xml::content_hex`] rejects.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the content is not an even number of hexadecimal
+    /// digits.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<key>\n  de ad\n  BE EF\n</key>")?;
+    /// assert!(xml.content_hex().is_err());
+    /// assert_eq!(xml.content_hex_lenient()?, [0xde, 0xad, 0xbe, 0xef]);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn content_hex_lenient(&self) -> Result<Vec<u8>, Error> {
+        self.hex(
+            &self
+                .text_content()
+                .split_ascii_whitespace()
+                .collect::<String>(),
+        )
+    }
+
+    fn hex(&self, text: &str) -> Result<Vec<u8>, Error> {
+        if !text.len().is_multiple_of(2) {
+            return Err(self.invalid("odd number of hexadecimal digits".to_owned()));
+        }
+        let digit = |c: u8| match c {
+            b'0'..=b'9' => Some(c - b'0'),
+            b'a'..=b'f' => Some(c - b'a' + 10),
+            b'A'..=b'F' => Some(c - b'A' + 10),
+            _ => None,
+        };
+        text.as_bytes()
+            .chunks(2)
+            .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+            .collect::<Option<_>>()
+            .ok_or_else(|| self.invalid("invalid hexadecimal digit".to_owned()))
+    }
+
+    /// Get the unescaped text of this node and its descendants, with CDATA sections unwrapped.
+    pub(crate) fn text_content(&self) -> String {
+        match self.content() {
+            Some(content) => unescape_text(content).into_owned(),
+            None => self
+                .descendants()
+                .filter_map(Xml::content)
+                .map(unescape_text)
+                .collect(),
+        }
+    }
+
+    /// Get the text content of a text node with whitespace collapsed: leading and trailing
+    /// whitespace is removed, and each run of whitespace inside is replaced by a space.
+    ///
+    /// As with [`Xml::content`], the text is not unescaped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<name>\n      wl_display\n    </name>")?;
+    /// let text = xml.children().next().unwrap();
+    /// assert_eq!(text.content_trimmed().as_deref(), Some("wl_display"));
+    /// assert_eq!(xml.content_trimmed(), None);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn content_trimmed(&self) -> Option<Cow<'_, str>> {
+        self.content().map(collapse)
+    }
+    /// Get the unescaped text of this node and its descendants with whitespace collapsed, as
+    /// in [`Xml::content_trimmed`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<description>\n  The core global object.\n  This is a\n  <em>special</em> singleton &amp; more.\n</description>")?;
+    /// assert_eq!(xml.text_content_trimmed(), "The core global object. This is a special singleton & more.");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn text_content_trimmed(&self) -> String {
+        collapse(&self.text_content()).into_owned()
+    }
+
+    /// Flatten an element into a map from names to unescaped values, for flat configuration
+    /// elements.
+    ///
+    /// The map holds the attributes of the element, and the text of child elements without
+    /// element children. Attributes and children of child elements are included with their
+    /// names joined by a dot, e.g. `db.port`. If a name occurs more than once, the first value
+    /// is kept, and attributes come before children. Use [`Xml::to_map_with`] to join names
+    /// with another separator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<config env='prod'><name>app &amp; co</name><db port='5432'><host>localhost</host></db></config>")?;
+    /// let map = xml.to_map();
+    /// assert_eq!(map["env"], "prod");
+    /// assert_eq!(map["name"], "app & co");
+    /// assert_eq!(map["db.port"], "5432");
+    /// assert_eq!(map["db.host"], "localhost");
+    /// assert_eq!(map.len(), 4);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.to_map_with(".")
+    }
+    /// Flatten an element into a map, as in [`Xml::to_map`], joining the names of nested
+    /// elements with a separator.
+    pub fn to_map_with(&self, separator: &str) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        flatten(self, "", separator, &mut map);
+        map
+    }
+
+    /// Build a validation error for the content of this node.
+    pub(crate) fn invalid(&self, message: String) -> Error {
+        Error::Validation {
+            message,
+            path: child_path("", slice::from_ref(self), 0),
+        }
+    }
+}
+
+/// Add the attributes and children of an element to a map, with names under a prefix.
+fn flatten(node: &Xml, prefix: &str, separator: &str, map: &mut HashMap<String, String>) {
+    let Xml::Element(Element {
+        attrs, children, ..
+    }) = node
+    else {
+        return;
+    };
+    let mut attrs = attrs.iter().collect::<Vec<_>>();
+    attrs.sort();
+    for (key, value) in attrs {
+        let value = attr_text(value).into_owned();
+        map.entry(format!("{prefix}{key}")).or_insert(value);
+    }
+    for child in children {
+        let Some(name) = child.name() else {
+            continue;
+        };
+        let key = format!("{prefix}{name}");
+        if !child.children().any(Xml::is_element) {
+            map.entry(key.clone())
+                .or_insert_with(|| child.text_content());
+        }
+        flatten(child, &format!("{key}{separator}"), separator, map);
+    }
+}
+
+/// Trim whitespace and replace each run of whitespace inside with a space.
+fn collapse(text: &str) -> Cow<'_, str> {
+    let is_space = |c| matches!(c, ' ' | '\t' | '\r' | '\n');
+    let trimmed = text.trim_matches(is_space);
+    if !trimmed.contains(['\t', '\r', '\n']) && !trimmed.contains("  ") {
+        return Cow::Borrowed(trimmed);
+    }
+    let mut words = trimmed.split(is_space).filter(|word| !word.is_empty());
+    let mut collapsed = String::with_capacity(trimmed.len());
+    collapsed.extend(words.next());
+    for word in words {
+        collapsed.push(' ');
+        collapsed.push_str(word);
+    }
+    Cow::Owned(collapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_content() {
+        let xml = crate::document("<a>x &amp; <b><![CDATA[<y>]]></b>z</a>").unwrap();
+        assert_eq!(xml.text_content(), "x & <y>z");
+        assert_eq!(Xml::text("&lt;").text_content(), "<");
+    }
+
+    #[test]
+    fn collapse() {
+        assert!(matches!(super::collapse(" a b\n"), Cow::Borrowed("a b")));
+        assert_eq!(super::collapse("\ta \r\n  b  c"), "a b c");
+        assert_eq!(super::collapse(" \n "), "");
+        let xml = crate::document("<a> x<![CDATA[ \n y]]> </a>").unwrap();
+        assert_eq!(xml.text_content_trimmed(), "x y");
+    }
+
+    #[test]
+    fn to_map() {
+        let text = "<a k='1'><k>2</k><b><c x='3'/><c>4</c>5</b><b><c>6</c></b><d/></a>";
+        let map = crate::document(text).unwrap().to_map_with("/");
+        let mut entries = map.into_iter().collect::<Vec<_>>();
+        entries.sort();
+        let expected = [("b/c", ""), ("b/c/x", "3"), ("d", ""), ("k", "1")];
+        let expected = expected.map(|(key, value)| (key.to_owned(), value.to_owned()));
+        assert_eq!(entries, expected);
+    }
+}
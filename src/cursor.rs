@@ -0,0 +1,237 @@
+//! Tree navigation with parent context.
+
+use crate::{
+    Xml,
+    escape::attr_text,
+    uri,
+    validate::{child_path, unquote},
+};
+
+/// Cursor over a document tree, tracking the ancestors of the current node.
+///
+/// Nodes do not store references to their parents, so inherited properties such as the
+/// base URI and language are computed through a cursor.
+///
+/// # Examples
+///
+/// ```rust
+/// let xml = xmlite::document("<a><b/><c><d/></c></a>").unwrap();
+/// let mut cursor = xml.cursor();
+/// assert!(cursor.first_child());
+/// assert!(cursor.next_sibling());
+/// assert!(cursor.first_child());
+/// assert_eq!(cursor.node().name(), Some("d"));
+/// assert_eq!(cursor.path(), "/a/c/d");
+/// assert!(cursor.parent());
+/// assert_eq!(cursor.node().name(), Some("c"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cursor<'x, 'a> {
+    // nodes from the root to the current node, with their index within their parent
+    stack: Vec<(&'x Xml<'a>, usize)>,
+}
+impl<'x, 'a> Cursor<'x, 'a> {
+    /// Create a cursor at the root of a tree.
+    pub fn new(root: &'x Xml<'a>) -> Self {
+        Cursor {
+            stack: vec![(root, 0)],
+        }
+    }
+
+    /// Get the current node.
+    pub fn node(&self) -> &'x Xml<'a> {
+        self.stack[self.stack.len() - 1].0
+    }
+    /// Get the parent of the current node.
+    pub fn parent_node(&self) -> Option<&'x Xml<'a>> {
+        let len = self.stack.len();
+        (len > 1).then(|| self.stack[len - 2].0)
+    }
+    /// Iterate over the ancestors of the current node, from the parent up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = &'x Xml<'a>> + '_ {
+        self.stack.iter().rev().skip(1).map(|(node, _)| *node)
+    }
+    /// Get the depth of the current node, where the root is at depth 0.
+    pub fn depth(&self) -> usize {
+        self.stack.len() - 1
+    }
+    /// Get the path of the current node, e.g. `/a/b[2]`.
+    pub fn path(&self) -> String {
+        let mut path = child_path("", std::slice::from_ref(self.stack[0].0), 0);
+        for window in self.stack.windows(2) {
+            let siblings = window[0].0.children().as_slice();
+            path = child_path(&path, siblings, window[1].1);
+        }
+        path
+    }
+
+    /// Move to the parent node. Returns `false` if already at the root.
+    pub fn parent(&mut self) -> bool {
+        if self.stack.len() > 1 {
+            self.stack.pop();
+            true
+        } else {
+            false
+        }
+    }
+    /// Move to the first child of the current node. Returns `false` if it has no children.
+    pub fn first_child(&mut self) -> bool {
+        match self.node().children().next() {
+            Some(child) => {
+                self.stack.push((child, 0));
+                true
+            }
+            None => false,
+        }
+    }
+    /// Move to the next sibling of the current node. Returns `false` if there is none.
+    pub fn next_sibling(&mut self) -> bool {
+        let index = self.stack[self.stack.len() - 1].1 + 1;
+        match self.parent_node().and_then(|p| p.children().nth(index)) {
+            Some(sibling) => {
+                *self.stack.last_mut().unwrap() = (sibling, index);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Move to the previous sibling of the current node. Returns `false` if there is none.
+    pub fn prev_sibling(&mut self) -> bool {
+        let index = self.stack[self.stack.len() - 1].1;
+        match index.checked_sub(1).zip(self.parent_node()) {
+            Some((index, parent)) => {
+                let sibling = parent.children().nth(index).unwrap();
+                *self.stack.last_mut().unwrap() = (sibling, index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the effective base URI of the current node.
+    ///
+    /// Resolves the `xml:base` attributes of the node and its ancestors against each other.
+    /// Returns `None` if no `xml:base` is in scope. The result is relative if the outermost
+    /// `xml:base` is relative, in which case it should be resolved against the document URI
+    /// with [`resolve_uri`](crate::resolve_uri).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let text = r#"<a xml:base="http://example.org/docs/"><b xml:base="guide/"><c/></b></a>"#;
+    /// let xml = xmlite::document(text).unwrap();
+    /// let mut cursor = xml.cursor();
+    /// cursor.first_child();
+    /// cursor.first_child();
+    /// assert_eq!(cursor.base_uri().as_deref(), Some("http://example.org/docs/guide/"));
+    /// ```
+    pub fn base_uri(&self) -> Option<String> {
+        self.stack
+            .iter()
+            .filter_map(|(node, _)| node.attr("xml:base"))
+            .map(attr_text)
+            .fold(None, |outer, base| match outer {
+                Some(outer) => Some(uri::resolve_uri(&outer, &base)),
+                None => Some(base.into_owned()),
+            })
+    }
+
+    /// Get the effective language of the current node.
+    ///
+    /// Returns the nearest `xml:lang` value on the node or its ancestors, or `None` if there
+    /// is none or the nearest one is empty, which marks the language as unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document(r#"<a xml:lang="en"><b><c xml:lang="da"/></b></a>"#).unwrap();
+    /// let mut cursor = xml.cursor();
+    /// cursor.first_child();
+    /// assert_eq!(cursor.lang(), Some("en"));
+    /// cursor.first_child();
+    /// assert_eq!(cursor.lang(), Some("da"));
+    /// ```
+    pub fn lang(&self) -> Option<&'x str> {
+        self.stack
+            .iter()
+            .rev()
+            .find_map(|(node, _)| node.attr("xml:lang"))
+            .map(unquote)
+            .filter(|lang| !lang.is_empty())
+    }
+}
+
+impl<'a> Xml<'a> {
+    /// Create a cursor with this node as the root.
+    pub fn cursor(&self) -> Cursor<'_, 'a> {
+        Cursor::new(self)
+    }
+
+    /// Get the base URI declared on this node with `xml:base`.
+    ///
+    /// Since nodes do not know their ancestors, inherited base URIs are only taken into
+    /// account through [`Cursor::base_uri`].
+    pub fn base_uri(&self) -> Option<&str> {
+        self.attr("xml:base").map(unquote)
+    }
+
+    /// Get the language declared on this node with `xml:lang`.
+    ///
+    /// Since nodes do not know their ancestors, inherited languages are only taken into
+    /// account through [`Cursor::lang`].
+    pub fn lang(&self) -> Option<&str> {
+        self.attr("xml:lang")
+            .map(unquote)
+            .filter(|lang| !lang.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn navigate() {
+        let xml = crate::document("<a><b/><b><c/></b>text</a>").unwrap();
+        let mut cursor = xml.cursor();
+        assert!(!cursor.parent());
+        assert!(!cursor.next_sibling());
+        assert!(cursor.first_child());
+        assert!(!cursor.prev_sibling());
+        assert!(cursor.next_sibling());
+        assert_eq!(cursor.path(), "/a/b[2]");
+        assert!(cursor.first_child());
+        assert_eq!(cursor.depth(), 2);
+        assert_eq!(cursor.ancestors().count(), 2);
+        assert!(cursor.parent());
+        assert!(cursor.next_sibling());
+        assert_eq!(cursor.node().content(), Some("text"));
+        assert!(cursor.prev_sibling());
+        assert_eq!(cursor.path(), "/a/b[2]");
+    }
+
+    #[test]
+    fn base_uri() {
+        let text = r#"<a xml:base="/root/"><b xml:base="../other/x.xml"/><c/></a>"#;
+        let xml = crate::document(text).unwrap();
+        let mut cursor = xml.cursor();
+        assert_eq!(xml.base_uri(), Some("/root/"));
+        cursor.first_child();
+        assert_eq!(cursor.base_uri().as_deref(), Some("/other/x.xml"));
+        cursor.next_sibling();
+        assert_eq!(cursor.base_uri().as_deref(), Some("/root/"));
+    }
+
+    #[test]
+    fn lang() {
+        let text = r#"<a xml:lang="en-GB"><b xml:lang=""><c/></b><c/></a>"#;
+        let xml = crate::document(text).unwrap();
+        assert_eq!(xml.lang(), Some("en-GB"));
+        let mut cursor = xml.cursor();
+        cursor.first_child();
+        assert_eq!(cursor.node().lang(), None);
+        cursor.first_child();
+        assert_eq!(cursor.lang(), None);
+        cursor.parent();
+        cursor.next_sibling();
+        assert_eq!(cursor.lang(), Some("en-GB"));
+    }
+}
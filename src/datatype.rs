@@ -0,0 +1,39 @@
+//! Built-in datatypes shared by the schema validators.
+
+use crate::chars;
+
+/// Check a value against a built-in XML Schema datatype, by local name.
+///
+/// Unknown datatypes accept any value.
+pub(crate) fn is_valid(name: &str, value: &str) -> bool {
+    let trimmed = value.trim();
+    let integer = |min: i128, max: i128| {
+        trimmed
+            .parse::<i128>()
+            .is_ok_and(|n| (min..=max).contains(&n))
+    };
+    match name {
+        "boolean" => matches!(trimmed, "true" | "false" | "1" | "0"),
+        "integer" => integer(i128::MIN, i128::MAX),
+        "long" => integer(i64::MIN.into(), i64::MAX.into()),
+        "int" => integer(i32::MIN.into(), i32::MAX.into()),
+        "short" => integer(i16::MIN.into(), i16::MAX.into()),
+        "byte" => integer(i8::MIN.into(), i8::MAX.into()),
+        "unsignedLong" => integer(0, u64::MAX.into()),
+        "unsignedInt" => integer(0, u32::MAX.into()),
+        "unsignedShort" => integer(0, u16::MAX.into()),
+        "unsignedByte" => integer(0, u8::MAX.into()),
+        "nonNegativeInteger" => integer(0, i128::MAX),
+        "positiveInteger" => integer(1, i128::MAX),
+        "nonPositiveInteger" => integer(i128::MIN, 0),
+        "negativeInteger" => integer(i128::MIN, -1),
+        "decimal" => !trimmed.contains(['e', 'E', 'I', 'N']) && trimmed.parse::<f64>().is_ok(),
+        "float" | "double" => {
+            matches!(trimmed, "INF" | "-INF" | "NaN") || trimmed.parse::<f64>().is_ok()
+        }
+        "Name" | "ID" | "IDREF" | "ENTITY" => chars::is_name(trimmed),
+        "NCName" => chars::is_name(trimmed) && !trimmed.contains(':'),
+        "NMTOKEN" => !trimmed.is_empty() && trimmed.chars().all(chars::is_name_char),
+        _ => true,
+    }
+}
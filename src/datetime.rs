@@ -0,0 +1,250 @@
+//! Date and time values of content and attributes.
+
+use crate::{Error, Xml, escape::attr_text, validate::child_path};
+use std::slice;
+
+/// Date or time type that can be read from `xs:dateTime`, `xs:date` and RFC 3339 values.
+///
+/// Implemented for the types of `chrono` and `time` when their features are enabled. Types
+/// with an offset require the value to have one, while types without an offset accept values
+/// with and without one, and ignore it.
+pub trait XmlDateTime: Sized {
+    /// Parse a value, returning `None` if it is invalid or lacks a required part.
+    fn parse_xml(text: &str) -> Option<Self>;
+}
+
+/// Parts of a date and time value.
+struct Parts {
+    year: i32,
+    month: u8,
+    day: u8,
+    // hour, minute, second and nanosecond
+    time: Option<(u8, u8, u8, u32)>,
+    // offset from UTC in seconds
+    offset: Option<i32>,
+}
+
+/// Parse `-?YYYY-MM-DD`, optionally followed by `Thh:mm:ss(.s+)?`, and an optional offset
+/// `Z` or `(+|-)hh:mm`. As in RFC 3339, the separators may be lowercase or a space.
+fn parse(text: &str) -> Option<Parts> {
+    let mut rest = text.trim_matches([' ', '\t', '\r', '\n']);
+    let digits = |n: usize, rest: &mut &str| {
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let ok = if n == 4 { len >= 4 } else { len == n };
+        let (number, after) = rest.split_at(len);
+        *rest = after;
+        ok.then(|| number.parse::<u32>().ok()).flatten()
+    };
+    let sign = |rest: &mut &str, c: char| match rest.strip_prefix(c) {
+        Some(after) => {
+            *rest = after;
+            true
+        }
+        None => false,
+    };
+
+    let negative = sign(&mut rest, '-');
+    let year = i32::try_from(digits(4, &mut rest)?).ok()?;
+    let year = if negative { -year } else { year };
+    sign(&mut rest, '-').then_some(())?;
+    let month = digits(2, &mut rest)? as u8;
+    sign(&mut rest, '-').then_some(())?;
+    let day = digits(2, &mut rest)? as u8;
+
+    let time = if sign(&mut rest, 'T') || sign(&mut rest, 't') || sign(&mut rest, ' ') {
+        let hour = digits(2, &mut rest)? as u8;
+        sign(&mut rest, ':').then_some(())?;
+        let minute = digits(2, &mut rest)? as u8;
+        sign(&mut rest, ':').then_some(())?;
+        let second = digits(2, &mut rest)? as u8;
+        let mut nanos = 0;
+        if sign(&mut rest, '.') {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let fraction = &rest[..len.min(9)];
+            if len == 0 {
+                return None;
+            }
+            nanos = fraction.parse::<u32>().ok()? * 10u32.pow(9 - fraction.len() as u32);
+            rest = &rest[len..];
+        }
+        Some((hour, minute, second, nanos))
+    } else {
+        None
+    };
+
+    let offset = match rest {
+        "" => None,
+        "Z" | "z" => Some(0),
+        _ => {
+            let negative = sign(&mut rest, '-');
+            if !negative {
+                sign(&mut rest, '+').then_some(())?;
+            }
+            let hours = digits(2, &mut rest)? as i32;
+            sign(&mut rest, ':').then_some(())?;
+            let minutes = digits(2, &mut rest)? as i32;
+            if !rest.is_empty() || hours > 14 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            Some(if negative { -offset } else { offset })
+        }
+    };
+    Some(Parts {
+        year,
+        month,
+        day,
+        time,
+        offset,
+    })
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use super::{XmlDateTime, parse};
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+
+    impl XmlDateTime for NaiveDate {
+        fn parse_xml(text: &str) -> Option<Self> {
+            let parts = parse(text).filter(|parts| parts.time.is_none())?;
+            NaiveDate::from_ymd_opt(parts.year, parts.month.into(), parts.day.into())
+        }
+    }
+    impl XmlDateTime for NaiveDateTime {
+        fn parse_xml(text: &str) -> Option<Self> {
+            let parts = parse(text)?;
+            let (hour, minute, second, nanos) = parts.time?;
+            NaiveDate::from_ymd_opt(parts.year, parts.month.into(), parts.day.into())?
+                .and_hms_nano_opt(hour.into(), minute.into(), second.into(), nanos)
+        }
+    }
+    impl XmlDateTime for DateTime<FixedOffset> {
+        fn parse_xml(text: &str) -> Option<Self> {
+            let offset = FixedOffset::east_opt(parse(text)?.offset?)?;
+            let local = NaiveDateTime::parse_xml(text)?;
+            local.and_local_timezone(offset).single()
+        }
+    }
+    impl XmlDateTime for DateTime<Utc> {
+        fn parse_xml(text: &str) -> Option<Self> {
+            DateTime::<FixedOffset>::parse_xml(text).map(|date| date.to_utc())
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_impls {
+    use super::{XmlDateTime, parse};
+    use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+    impl XmlDateTime for Date {
+        fn parse_xml(text: &str) -> Option<Self> {
+            let parts = parse(text).filter(|parts| parts.time.is_none())?;
+            let month = Month::try_from(parts.month).ok()?;
+            Date::from_calendar_date(parts.year, month, parts.day).ok()
+        }
+    }
+    impl XmlDateTime for PrimitiveDateTime {
+        fn parse_xml(text: &str) -> Option<Self> {
+            let parts = parse(text)?;
+            let (hour, minute, second, nanos) = parts.time?;
+            let month = Month::try_from(parts.month).ok()?;
+            let date = Date::from_calendar_date(parts.year, month, parts.day).ok()?;
+            let time = Time::from_hms_nano(hour, minute, second, nanos).ok()?;
+            Some(PrimitiveDateTime::new(date, time))
+        }
+    }
+    impl XmlDateTime for OffsetDateTime {
+        fn parse_xml(text: &str) -> Option<Self> {
+            let offset = UtcOffset::from_whole_seconds(parse(text)?.offset?).ok()?;
+            Some(PrimitiveDateTime::parse_xml(text)?.assume_offset(offset))
+        }
+    }
+}
+
+impl Xml<'_> {
+    /// Parse the text content of an element as a date or time.
+    ///
+    /// The text of all descendants is concatenated, and leading and trailing whitespace is
+    /// ignored. See [`XmlDateTime`] for the supported types.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the content is not a valid value of the type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "chrono")] {
+    /// use chrono::{DateTime, FixedOffset, NaiveDateTime, Timelike};
+    ///
+    /// let xml = xmlite::document("<updated>2003-12-13T18:30:02.25+01:00</updated>")?;
+    /// let updated = xml.content_datetime::<DateTime<FixedOffset>>()?;
+    /// assert_eq!((updated.hour(), updated.offset().local_minus_utc()), (18, 3600));
+    /// assert_eq!(updated.to_utc().hour(), 17);
+    ///
+    /// let local = xmlite::document("<at>2003-12-13T18:30:02</at>")?;
+    /// assert!(local.content_datetime::<DateTime<FixedOffset>>().is_err());
+    /// assert!(local.content_datetime::<NaiveDateTime>().is_ok());
+    /// # }
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn content_datetime<T: XmlDateTime>(&self) -> Result<T, Error> {
+        T::parse_xml(&self.text_content())
+            .ok_or_else(|| self.invalid("invalid date or time".to_owned()))
+    }
+
+    /// Parse an attribute as a date or time, as with [`Xml::content_datetime`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the value is not a valid value of the type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "time")] {
+    /// use time::{Date, Month};
+    ///
+    /// let xml = xmlite::document("<invoice due='2024-02-29Z'/>")?;
+    /// let due = xml.attr_datetime::<Date>("due").unwrap()?;
+    /// assert_eq!((due.month(), due.day()), (Month::February, 29));
+    /// assert!(xml.attr_datetime::<Date>("paid").is_none());
+    /// # }
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn attr_datetime<T: XmlDateTime>(&self, key: &str) -> Option<Result<T, Error>> {
+        let value = attr_text(self.attr(key)?);
+        Some(T::parse_xml(&value).ok_or_else(|| Error::Validation {
+            message: format!("invalid date or time in attribute \"{key}\""),
+            path: format!("{}/@{key}", child_path("", slice::from_ref(self), 0)),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lexical() {
+        let parts = parse(" -0044-03-15t12:00:00.123456789123-05:30 ").unwrap();
+        assert_eq!((parts.year, parts.month, parts.day), (-44, 3, 15));
+        assert_eq!(parts.time, Some((12, 0, 0, 123_456_789)));
+        assert_eq!(parts.offset, Some(-(5 * 3600 + 30 * 60)));
+        let parts = parse("12345-01-01").unwrap();
+        assert_eq!((parts.year, parts.time, parts.offset), (12345, None, None));
+        for invalid in [
+            "2024-1-01",
+            "2024-01-01T10:00",
+            "2024-01-01+1:00",
+            "2024-01-01T10:00:00.",
+        ] {
+            assert!(parse(invalid).is_none(), "{invalid}");
+        }
+    }
+}
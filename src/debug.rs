@@ -0,0 +1,142 @@
+//! Debugging views of document trees.
+
+use crate::{Element, Xml, validate::unquote};
+use std::fmt::{self, Write};
+
+impl Xml<'_> {
+    /// Render the structure of the tree as a Graphviz DOT graph.
+    ///
+    /// Elements are labelled with their name, followed by their `id` or `xml:id` attribute if
+    /// they have one. Text nodes are labelled with their text, shortened to 20 characters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<a><b id='x'>text</b></a>")?;
+    /// assert_eq!(xml.to_dot(), r#"digraph {
+    ///     n0 [label="a"];
+    ///     n1 [label="b#x"];
+    ///     n0 -> n1;
+    ///     n2 [label="text", shape=plaintext];
+    ///     n1 -> n2;
+    /// }
+    /// "#);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n");
+        dot(self, None, &mut 0, &mut out);
+        out.push_str("}\n");
+        out
+    }
+
+    /// Display the tree compactly, with one indented line per node.
+    ///
+    /// Elements are shown with their name and up to three attributes, and text is quoted and
+    /// shortened to 40 characters. Text containing only whitespace is left out. This is much
+    /// easier to read than the derived [`Debug`] output when inspecting large trees.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<a x='1'>\n  <b>some text</b>\n  <c/>\n</a>")?;
+    /// assert_eq!(xml.dump().to_string(), "a x='1'\n  b\n    \"some text\"\n  c\n");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn dump(&self) -> impl fmt::Display + '_ {
+        Dump(self)
+    }
+}
+
+struct Dump<'x, 'a>(&'x Xml<'a>);
+impl fmt::Display for Dump<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        dump(f, self.0, 0)
+    }
+}
+
+fn dump(f: &mut fmt::Formatter, node: &Xml, depth: usize) -> fmt::Result {
+    let indent = "  ".repeat(depth);
+    match node {
+        Xml::Element(Element {
+            name,
+            attrs,
+            children,
+        }) => {
+            write!(f, "{indent}{name}")?;
+            let mut attrs = attrs.iter().collect::<Vec<_>>();
+            attrs.sort();
+            for (key, value) in attrs.iter().take(3) {
+                write!(f, " {key}={}", truncated(value, 20))?;
+            }
+            if attrs.len() > 3 {
+                write!(f, " (+{} more)", attrs.len() - 3)?;
+            }
+            writeln!(f)?;
+            for child in children {
+                dump(f, child, depth + 1)?;
+            }
+            Ok(())
+        }
+        Xml::Text(text) if text.trim().is_empty() => Ok(()),
+        Xml::Text(text) => writeln!(f, "{indent}{:?}", truncated(text.trim(), 40)),
+        Xml::Raw(raw) => writeln!(f, "{indent}raw {:?}", truncated(raw, 40)),
+    }
+}
+
+fn dot(node: &Xml, parent: Option<usize>, next: &mut usize, out: &mut String) {
+    let id = *next;
+    *next += 1;
+    let (label, shape) = match node {
+        Xml::Element(Element { name, .. }) => match node.attr("id").or(node.attr("xml:id")) {
+            Some(id) => (format!("{name}#{}", unquote(id)), ""),
+            None => (name.to_string(), ""),
+        },
+        Xml::Text(text) => (truncated(text.trim(), 20), ", shape=plaintext"),
+        Xml::Raw(raw) => (truncated(raw, 20), ", shape=box"),
+    };
+    let label = label.replace('\\', "\\\\").replace('"', "\\\"");
+    writeln!(out, "    n{id} [label=\"{label}\"{shape}];")
+        .expect("writing to a string cannot fail");
+    if let Some(parent) = parent {
+        writeln!(out, "    n{parent} -> n{id};").expect("writing to a string cannot fail");
+    }
+    for child in node.children() {
+        dot(child, Some(id), next, out);
+    }
+}
+
+/// Shorten text to a number of characters, marking where it was cut.
+fn truncated(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((i, _)) => format!("{}…", &text[..i]),
+        None => text.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_labels() {
+        let xml = Xml::element("a")
+            .with_attr("xml:id", "\"q\"")
+            .with_child(Xml::text("say \"hello\" to everyone in the room"));
+        let dot = xml.to_dot();
+        assert!(dot.contains(r#"n0 [label="a#q"];"#));
+        assert!(dot.contains(r#"n1 [label="say \"hello\" to every…", shape=plaintext];"#));
+    }
+
+    #[test]
+    fn dump_attrs() {
+        let xml = Xml::element("a")
+            .with_attr("d", "4")
+            .with_attr("c", "3")
+            .with_attr("b", "2")
+            .with_attr("a", "1")
+            .with_child(Xml::raw("<!-- x -->"));
+        let expected = "a a=1 b=2 c=3 (+1 more)\n  raw \"<!-- x -->\"\n";
+        assert_eq!(xml.dump().to_string(), expected);
+    }
+}
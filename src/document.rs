@@ -1,9 +1,47 @@
-use crate::{Error, Tag, Tags};
-use std::{borrow::Cow, collections::HashMap, fmt, iter, slice};
+use crate::{
+    Dtd, Edit, Element, Error, Interner, Symbol, Tag, Tags, WriteOptions,
+    escape::{attr_text, escape},
+    validate::{child_path, unquote},
+    write::{Formatted, FormattedDocument},
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    iter, mem,
+    ops::{Index, IndexMut},
+    slice,
+    str::FromStr,
+};
+
+/// Prolog of a document: the XML declaration and the processing instructions and comments
+/// before the root element.
+pub(crate) type Prolog<'a> = (Option<Cow<'a, str>>, Vec<Xml<'a>>);
+
+/// Parse the prolog of a document, stopping before the root element.
+///
+/// Whitespace between prolog items is dropped. Comments are only kept if the tags include them.
+pub(crate) fn prolog<'a>(tags: &mut Tags<'a>) -> Result<Prolog<'a>, Error> {
+    let (mut declaration, mut items) = (None, vec![]);
+    loop {
+        match tags.peek().ok_or(Error::Eof)? {
+            Tag::Text(text) if text.trim().is_empty() => {}
+            Tag::Declaration { name: "xml", .. } => {
+                declaration = tags.peek().map(|tag| tag.to_string().into());
+            }
+            tag @ (Tag::Declaration { .. } | Tag::Comment(_) | Tag::Doctype(_)) => {
+                items.push(Xml::Raw(tag.to_string().into()));
+            }
+            _ => return Ok((declaration, items)),
+        }
+        tags.next();
+    }
+}
 
 pub(crate) fn element<'a>(tags: &mut Tags<'a>) -> Result<Xml<'a>, Error> {
     let (name, attrs, kind) = match tags.next().ok_or(Error::Eof)? {
-        Tag::Declaration { .. } => return element(tags),
+        Tag::Declaration { .. } | Tag::Comment(_) | Tag::Doctype(_) => return element(tags),
         Tag::Text(text) => return Ok(Xml::Text(text.into())),
         Tag::Tag { name, attrs, kind } => (name, attrs, kind),
     };
@@ -12,8 +50,10 @@ pub(crate) fn element<'a>(tags: &mut Tags<'a>) -> Result<Xml<'a>, Error> {
             expected: "any opening tag".to_owned(),
             found: name.to_owned(),
             span: tags.report(),
+            opened: None,
         });
     }
+    let opened = tags.span();
 
     // convert attrs to cow
     let attrs = attrs
@@ -24,21 +64,27 @@ pub(crate) fn element<'a>(tags: &mut Tags<'a>) -> Result<Xml<'a>, Error> {
 
     // return immediately if self-closing
     if kind.is_self_closing() {
-        return Ok(Xml::Element {
+        return Ok(Xml::Element(Element {
             name: name.into(),
             attrs,
             children,
-        });
+        }));
     }
     // parse children until we find the matching closing tag.
     while let Some(tag) = tags.peek() {
+        if let Tag::Comment(_) = tag {
+            // comments are only returned by tags when they are to be kept
+            children.push(Xml::Raw(tag.to_string().into()));
+            tags.next();
+            continue;
+        }
         if tag.is_closing() && tag.name() == Some(name) {
             tags.next();
-            return Ok(Xml::Element {
+            return Ok(Xml::Element(Element {
                 name: name.into(),
                 attrs,
                 children,
-            });
+            }));
         }
         if !tag.is_closing() {
             children.push(element(tags)?);
@@ -47,6 +93,7 @@ pub(crate) fn element<'a>(tags: &mut Tags<'a>) -> Result<Xml<'a>, Error> {
                 expected: name.to_owned(),
                 found: tag.name().unwrap_or("").to_owned(),
                 span: tags.report(),
+                opened: Some(opened),
             });
         }
     }
@@ -55,20 +102,138 @@ pub(crate) fn element<'a>(tags: &mut Tags<'a>) -> Result<Xml<'a>, Error> {
     Err(Error::Eof)
 }
 
+/// Parse an element, repairing mismatched and missing end tags instead of failing.
+///
+/// An end tag closes the matching open element along with any elements opened within it,
+/// end tags without a matching open element are skipped, and elements still open at the end
+/// of the input are closed. Each repair is recorded in the diagnostics of the tags.
+pub(crate) fn element_lenient<'a>(tags: &mut Tags<'a>) -> Result<Xml<'a>, Error> {
+    // open elements with the spans of their start tags
+    let mut stack: Vec<(Xml<'a>, (usize, usize))> = vec![];
+    let mismatched = |tags: &Tags, open: &(Xml, _), found: &str| Error::Mismatched {
+        expected: open.0.name().unwrap_or_default().to_owned(),
+        found: found.to_owned(),
+        span: tags.span(),
+        opened: Some(open.1),
+    };
+    loop {
+        let Some(tag) = tags.next() else {
+            let Some(open) = stack.last() else {
+                return Err(Error::Eof);
+            };
+            let error = Error::Mismatched {
+                expected: open.0.name().unwrap_or_default().to_owned(),
+                found: "end of file".to_owned(),
+                span: tags.report(),
+                opened: Some(open.1),
+            };
+            tags.diags.push(error);
+            while stack.len() > 1 {
+                close(&mut stack);
+            }
+            return Ok(stack.pop().unwrap().0);
+        };
+        let node = match tag {
+            Tag::Declaration { .. } | Tag::Comment(_) | Tag::Doctype(_) => continue,
+            Tag::Text(text) => Xml::Text(text.into()),
+            Tag::Tag { name, attrs, kind } if !kind.is_closing() => {
+                let element = Xml::Element(Element {
+                    name: name.into(),
+                    attrs: attrs
+                        .into_iter()
+                        .map(|(k, v)| (Cow::Borrowed(k), Cow::Borrowed(v)))
+                        .collect(),
+                    children: vec![],
+                });
+                if kind.is_opening() {
+                    stack.push((element, tags.span()));
+                    continue;
+                }
+                element
+            }
+            Tag::Tag { name, .. } => {
+                let Some(i) = stack
+                    .iter()
+                    .rposition(|(open, _)| open.name() == Some(name))
+                else {
+                    let error = match stack.last() {
+                        Some(open) => mismatched(tags, open, name),
+                        None => Error::Mismatched {
+                            expected: "any opening tag".to_owned(),
+                            found: name.to_owned(),
+                            span: tags.span(),
+                            opened: None,
+                        },
+                    };
+                    tags.diags.push(error);
+                    continue;
+                };
+                while stack.len() > i + 1 {
+                    let error = mismatched(tags, stack.last().unwrap(), name);
+                    tags.diags.push(error);
+                    close(&mut stack);
+                }
+                match stack.len() {
+                    1 => return Ok(stack.pop().unwrap().0),
+                    _ => close(&mut stack),
+                }
+                continue;
+            }
+        };
+        match stack.last_mut() {
+            Some((Xml::Element(Element { children, .. }), _)) => children.push(node),
+            _ => return Ok(node),
+        }
+    }
+}
+
+/// Close the innermost open element, adding it to its parent.
+fn close<'a>(stack: &mut Vec<(Xml<'a>, (usize, usize))>) {
+    let (element, _) = stack.pop().expect("an element is open");
+    if let Some((Xml::Element(Element { children, .. }), _)) = stack.last_mut() {
+        children.push(element);
+    }
+}
+
+/// Parse a sequence of elements and text, such as the content of an element.
+pub(crate) fn fragment<'a>(tags: &mut Tags<'a>) -> Result<Vec<Xml<'a>>, Error> {
+    let mut nodes = vec![];
+    while let Some(tag) = tags.peek() {
+        match tag {
+            Tag::Declaration { .. } | Tag::Comment(_) | Tag::Doctype(_) => {
+                tags.next();
+            }
+            tag if tag.is_closing() => {
+                return Err(Error::Mismatched {
+                    expected: "any opening tag".to_owned(),
+                    found: tag.name().unwrap_or("").to_owned(),
+                    span: tags.report(),
+                    opened: None,
+                });
+            }
+            _ => nodes.push(element(tags)?),
+        }
+    }
+    Ok(nodes)
+}
+
 /// XML node.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Nodes can be compared and hashed, so whole subtrees can be used as keys in maps and sets.
+/// Attribute order does not affect either.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Xml<'a> {
     /// XML element.
-    Element {
-        /// Element name.
-        name: Cow<'a, str>,
-        /// Element attributes.
-        attrs: HashMap<Cow<'a, str>, Cow<'a, str>>,
-        /// Element children.
-        children: Vec<Xml<'a>>,
-    },
+    Element(Element<'a>),
     /// XML text content.
     Text(Cow<'a, str>),
+    /// Pre-serialized markup, written verbatim.
+    ///
+    /// Raw nodes are only produced by parsing for comments kept with
+    /// [`ParseOptions::with_comments`](crate::ParseOptions::with_comments), and are opaque to
+    /// queries and validation.
+    Raw(Cow<'a, str>),
 }
 impl<'a> Xml<'a> {
     /// Create a new text node.
@@ -84,6 +249,23 @@ impl<'a> Xml<'a> {
         Xml::Text(text.into())
     }
 
+    /// Create a new raw node, whose content is written as-is when serializing.
+    ///
+    /// This injects a pre-serialized fragment, e.g. from a template, without parsing or
+    /// escaping it again. The fragment is not checked for well-formedness.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use xmlite::Xml;
+    /// let fragment = "<b>bold</b> &amp; <i>italic</i>";
+    /// let xml = Xml::element("p").with_child(Xml::raw(fragment));
+    /// assert_eq!(xml.to_string(), "<p><b>bold</b> &amp; <i>italic</i></p>");
+    /// ```
+    pub fn raw(raw: impl Into<Cow<'a, str>>) -> Self {
+        Xml::Raw(raw.into())
+    }
+
     /// Create a new element node.
     ///
     /// # Examples
@@ -94,11 +276,87 @@ impl<'a> Xml<'a> {
     /// assert_eq!(node.name(), Some("div"));
     /// ```
     pub fn element(name: impl Into<Cow<'a, str>>) -> Self {
-        Xml::Element {
+        Xml::Element(Element {
             name: name.into(),
             attrs: HashMap::new(),
             children: vec![],
+        })
+    }
+
+    /// Build a tree from a stream of tags, such as the output of a streaming transform.
+    ///
+    /// The tags are assembled as when parsing a document: declarations and document type
+    /// declarations are skipped, comments inside the root element are kept as raw nodes, and
+    /// the tree is returned once the root element is closed, ignoring any further tags.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MismatchedTag`] with the indices of the tags in the stream if an end tag
+    /// does not match the open element, and [`Error::Eof`] if the tags end before the root
+    /// element does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{Tag, Xml};
+    /// let tags = xmlite::tags("<a><secret>1</secret><b>2</b></a>");
+    /// let mut depth = 0;
+    /// let public = tags.filter(|tag| {
+    ///     let secret = tag.name() == Some("secret");
+    ///     depth += usize::from(secret && tag.is_opening());
+    ///     let keep = depth == 0;
+    ///     depth -= usize::from(secret && tag.is_closing());
+    ///     keep
+    /// });
+    /// let xml = Xml::from_tags(public)?;
+    /// assert_eq!(xml.to_string(), "<a><b>2</b></a>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn from_tags(tags: impl IntoIterator<Item = Tag<'a>>) -> Result<Xml<'a>, Error> {
+        // open elements with the indices of their start tags
+        let mut stack: Vec<(Element<'a>, usize)> = vec![];
+        for (i, tag) in tags.into_iter().enumerate() {
+            let node = match tag {
+                Tag::Comment(_) if !stack.is_empty() => Xml::Raw(tag.to_string().into()),
+                Tag::Declaration { .. } | Tag::Comment(_) | Tag::Doctype(_) => continue,
+                Tag::Text(text) if stack.is_empty() && text.trim().is_empty() => continue,
+                Tag::Text(text) => Xml::Text(text.into()),
+                Tag::Tag { name, attrs, kind } if !kind.is_closing() => {
+                    let element = Element {
+                        name: name.into(),
+                        attrs: attrs
+                            .into_iter()
+                            .map(|(k, v)| (Cow::Borrowed(k), Cow::Borrowed(v)))
+                            .collect(),
+                        children: vec![],
+                    };
+                    if kind.is_opening() {
+                        stack.push((element, i));
+                        continue;
+                    }
+                    Xml::Element(element)
+                }
+                Tag::Tag { name, .. } => match stack.pop() {
+                    Some((element, _)) if element.name == name => Xml::Element(element),
+                    open => {
+                        return Err(Error::MismatchedTag {
+                            expected: open
+                                .as_ref()
+                                .map_or("any opening tag", |(e, _)| &e.name)
+                                .to_owned(),
+                            found: name.to_owned(),
+                            index: i,
+                            opened: open.map(|(_, start)| start),
+                        });
+                    }
+                },
+            };
+            match stack.last_mut() {
+                Some((parent, _)) => parent.children.push(node),
+                None => return Ok(node),
+            }
         }
+        Err(Error::Eof)
     }
 
     /// Check if the node is a text node.
@@ -107,12 +365,16 @@ impl<'a> Xml<'a> {
     }
     /// Check if the node is an element.
     pub fn is_element(&self) -> bool {
-        matches!(self, Xml::Element { .. })
+        matches!(self, Xml::Element(_))
+    }
+    /// Check if the node is a raw node.
+    pub fn is_raw(&self) -> bool {
+        matches!(self, Xml::Raw(_))
     }
 
     /// Get element name.
     pub fn name(&self) -> Option<&str> {
-        if let Xml::Element { ref name, .. } = *self {
+        if let Xml::Element(Element { ref name, .. }) = *self {
             Some(name.as_ref())
         } else {
             None
@@ -120,20 +382,106 @@ impl<'a> Xml<'a> {
     }
     /// Get element attribute.
     pub fn attr(&self, key: &str) -> Option<&str> {
-        if let Xml::Element { attrs, .. } = self {
+        if let Xml::Element(Element { attrs, .. }) = self {
             attrs.get(key).map(|s| s.as_ref())
         } else {
             None
         }
     }
+    /// Get element attribute, comparing names case-insensitively.
+    ///
+    /// Only ASCII letters are folded, which covers HTML and most legacy formats. If several
+    /// attributes match, which one is returned is unspecified.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<IMG SRC='a.png'/>")?;
+    /// assert_eq!(xml.attr("src"), None);
+    /// assert_eq!(xml.attr_ci("src"), Some("'a.png'"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn attr_ci(&self, key: &str) -> Option<&str> {
+        if let Xml::Element(Element { attrs, .. }) = self {
+            attrs
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.as_ref())
+        } else {
+            None
+        }
+    }
+    /// Get element attribute without its quotes, with entity and character references resolved.
+    ///
+    /// Unknown or malformed references are kept as they are. Values without quotes, as set
+    /// with [`Xml::with_attr_value`], are text already and are returned as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Xml;
+    /// let xml = xmlite::document("<a href='/search?q=x&amp;page=2' title=\"&#34;x&#34;\" t=\"'hi'\"/>")?;
+    /// assert_eq!(xml.attr("href"), Some("'/search?q=x&amp;page=2'"));
+    /// assert_eq!(xml.attr_unescaped("href").as_deref(), Some("/search?q=x&page=2"));
+    /// assert_eq!(xml.attr_unescaped("title").as_deref(), Some("\"x\""));
+    /// assert_eq!(xml.attr_unescaped("t").as_deref(), Some("'hi'"));
+    ///
+    /// // the value is the same after a round trip through text
+    /// let built = Xml::element("a").with_attr("k", "x &amp; y");
+    /// let text = built.to_string();
+    /// let parsed = xmlite::document(&text)?;
+    /// assert_eq!(built.attr_unescaped("k").as_deref(), Some("x &amp; y"));
+    /// assert_eq!(parsed.attr_unescaped("k"), built.attr_unescaped("k"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn attr_unescaped(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.attr(key).map(attr_text)
+    }
     /// Get mutable reference to element attribute.
     pub fn attr_mut(&mut self, key: &str) -> Option<&mut String> {
-        if let Xml::Element { attrs, .. } = self {
+        if let Xml::Element(Element { attrs, .. }) = self {
             attrs.get_mut(key).map(|c| c.to_mut())
         } else {
             None
         }
     }
+    /// Get the items of a list-valued attribute, e.g. `class="a b"` or `viewBox="0,0 10,10"`.
+    ///
+    /// Items are separated by XML whitespace (space, tab, carriage return and line feed) and
+    /// commas, and empty items are skipped. Items are not unescaped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<div class=' card\tlarge\n'/>")?;
+    /// assert_eq!(xml.attr_list("class"), Some(vec!["card", "large"]));
+    /// assert_eq!(xml.attr_list("id"), None);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn attr_list(&self, key: &str) -> Option<Vec<&str>> {
+        let list = unquote(self.attr(key)?)
+            .split([' ', '\t', '\r', '\n', ','])
+            .filter(|item| !item.is_empty());
+        Some(list.collect())
+    }
+    /// Parse the items of a list-valued attribute, as split by [`Xml::attr_list`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first item that fails to parse.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<svg viewBox='0 0, 24 24' width='x'/>")?;
+    /// assert_eq!(xml.attr_list_as::<f64>("viewBox"), Some(Ok(vec![0.0, 0.0, 24.0, 24.0])));
+    /// assert!(matches!(xml.attr_list_as::<f64>("width"), Some(Err(_))));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn attr_list_as<T: FromStr>(&self, key: &str) -> Option<Result<Vec<T>, T::Err>> {
+        let list = self.attr_list(key)?;
+        Some(list.into_iter().map(str::parse).collect())
+    }
 
     /// Get text content.
     pub fn content(&self) -> Option<&str> {
@@ -146,6 +494,10 @@ impl<'a> Xml<'a> {
 
     /// Add attribute to element.
     ///
+    /// Values wrapped in quotes are taken as parsed values, which are quoted and escaped
+    /// already, and are written as they are. Other values are escaped when written. Use
+    /// [`Xml::with_attr_value`] for values which may start and end with a quote.
+    ///
     /// # Examples
     ///
     /// ```
@@ -162,12 +514,26 @@ impl<'a> Xml<'a> {
         key: impl Into<Cow<'a, str>>,
         value: impl Into<Cow<'a, str>>,
     ) -> Self {
-        if let Xml::Element { attrs, .. } = &mut self {
+        if let Xml::Element(Element { attrs, .. }) = &mut self {
             attrs.insert(key.into(), value.into());
         }
         self
     }
 
+    /// Add attribute to element, quoting and escaping the value so it is kept exactly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use xmlite::Xml;
+    /// let element = Xml::element("q").with_attr_value("text", "'quoted' & <b>");
+    /// assert_eq!(element.attr("text"), Some("\"&apos;quoted&apos; &amp; &lt;b&gt;\""));
+    /// assert_eq!(element.attr_unescaped("text").as_deref(), Some("'quoted' & <b>"));
+    /// ```
+    pub fn with_attr_value(self, key: impl Into<Cow<'a, str>>, value: &str) -> Self {
+        self.with_attr(key, format!("\"{}\"", escape(value)))
+    }
+
     /// Add child to element.
     ///
     /// # Examples
@@ -181,7 +547,7 @@ impl<'a> Xml<'a> {
     /// assert_eq!(element.children().next().unwrap().content(), Some("Hello, world!"));
     /// ```
     pub fn with_child(mut self, child: Self) -> Self {
-        if let Xml::Element { children, .. } = &mut self {
+        if let Xml::Element(Element { children, .. }) = &mut self {
             children.push(child);
         }
         self
@@ -197,56 +563,1029 @@ impl<'a> Xml<'a> {
     /// let child = xml.children().find(|e| e.name() == Some("c"));
     /// assert_eq!(child, Some(&Xml::element("c")));
     /// ```
-    pub fn children(&self) -> slice::Iter<'_, Xml> {
-        if let Xml::Element { children, .. } = self {
+    pub fn children(&self) -> slice::Iter<'_, Xml<'a>> {
+        if let Xml::Element(Element { children, .. }) = self {
             children.iter()
         } else {
             [].iter()
         }
     }
+    /// Iterate over child elements with a name, compared case-insensitively.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let xml = xmlite::document("<ul><LI>a</LI><li>b</li><Item/></ul>").unwrap();
+    /// assert_eq!(xml.children_named_ci("li").count(), 2);
+    /// ```
+    pub fn children_named_ci(&self, name: &str) -> impl Iterator<Item = &Xml<'a>> {
+        self.children()
+            .filter(move |child| child.name().is_some_and(|n| n.eq_ignore_ascii_case(name)))
+    }
     /// Iterate over direct children, mutably.
     pub fn children_mut<'b>(&'b mut self) -> slice::IterMut<'b, Xml<'a>> {
-        if let Xml::Element { children, .. } = self {
+        if let Xml::Element(Element { children, .. }) = self {
             children.iter_mut()
         } else {
             [].iter_mut()
         }
     }
-    /// Iterate over descendants of this node (excludes self).
-    pub fn descendants(&self) -> impl Iterator<Item = &Xml> {
-        let mut stack: Vec<&Xml> = self.children().collect();
+    /// Get the first child element with a name.
+    ///
+    /// This is the non-panicking version of indexing with a name, e.g. `xml["b"]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let xml = xmlite::document("<a>text<b>1</b><b>2</b></a>").unwrap();
+    /// assert_eq!(xml.child("b").unwrap().inner_xml(), "1");
+    /// assert_eq!(xml["b"][0].content(), Some("1"));
+    /// assert!(xml.child("c").is_none());
+    /// ```
+    pub fn child(&self, name: &str) -> Option<&Xml<'a>> {
+        self.children().find(|child| child.name() == Some(name))
+    }
+    /// Get the first child element with a name, mutably.
+    pub fn child_mut(&mut self, name: &str) -> Option<&mut Xml<'a>> {
+        self.children_mut().find(|child| child.name() == Some(name))
+    }
+    /// Get the child node at an index, counting text as well as elements.
+    ///
+    /// This is the non-panicking version of indexing with a number, e.g. `xml[0]`.
+    pub fn child_at(&self, index: usize) -> Option<&Xml<'a>> {
+        self.children().nth(index)
+    }
+    /// Get the child node at an index, mutably.
+    pub fn child_at_mut(&mut self, index: usize) -> Option<&mut Xml<'a>> {
+        self.children_mut().nth(index)
+    }
+    /// Iterate over descendants of this node (excludes self) in document order.
+    pub fn descendants(&self) -> impl Iterator<Item = &Xml<'a>> {
+        let mut stack: Vec<&Xml<'a>> = self.children().rev().collect();
         iter::from_fn(move || {
             let current = stack.pop()?;
             stack.extend(current.children().rev());
             Some(current)
         })
     }
+
+    /// Copy all borrowed strings, detaching the tree from the text it was parsed from.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::XmlOwned;
+    /// fn load() -> Result<XmlOwned, xmlite::Error> {
+    ///     let text = String::from("<a><b/></a>");
+    ///     Ok(xmlite::document(&text)?.into_owned())
+    /// }
+    /// assert_eq!(load()?.children().count(), 1);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn into_owned(self) -> Xml<'static> {
+        match self {
+            Xml::Element(element) => Xml::Element(element.into_owned()),
+            Xml::Text(text) => Xml::Text(Cow::Owned(text.into_owned())),
+            Xml::Raw(raw) => Xml::Raw(Cow::Owned(raw.into_owned())),
+        }
+    }
+}
+
+/// XML node which owns all of its strings, for storing documents independently of the text
+/// they were parsed from.
+///
+/// Owned trees are produced by parsing with [`str::parse`], or by converting a borrowed tree
+/// with [`Xml::into_owned`], and can be built with the usual constructors.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::{Xml, XmlOwned};
+/// struct Config {
+///     root: XmlOwned,
+/// }
+/// let mut config = Config { root: "<config/>".parse()? };
+/// let name = format!("entry-{}", 1);
+/// if let Some(element) = config.root.as_element_mut() {
+///     element.children.push(Xml::element(name));
+/// }
+/// assert_eq!(config.root.to_string(), "<config><entry-1/></config>");
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+pub type XmlOwned = Xml<'static>;
+
+impl Hash for Xml<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Xml::Element(element) => element.hash(state),
+            Xml::Text(text) | Xml::Raw(text) => text.hash(state),
+        }
+    }
 }
 
 impl<'a> fmt::Display for Xml<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Xml::Element {
-                name,
-                attrs,
-                children,
-            } => {
-                write!(f, "<{name}")?;
-                for (k, v) in attrs {
-                    write!(f, " {k}={v:?}")?;
+        Formatted(self, &WriteOptions::new()).fmt(f)
+    }
+}
+impl<'a> Index<&str> for Xml<'a> {
+    type Output = Xml<'a>;
+
+    /// Get the first child element with a name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such child, see [`Xml::child`] for a non-panicking version.
+    fn index(&self, name: &str) -> &Self::Output {
+        self.child(name)
+            .unwrap_or_else(|| panic!("no child element named {name:?}"))
+    }
+}
+impl IndexMut<&str> for Xml<'_> {
+    fn index_mut(&mut self, name: &str) -> &mut Self::Output {
+        self.child_mut(name)
+            .unwrap_or_else(|| panic!("no child element named {name:?}"))
+    }
+}
+impl<'a> Index<usize> for Xml<'a> {
+    type Output = Xml<'a>;
+
+    /// Get the child node at an index, counting text as well as elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds, see [`Xml::child_at`] for a non-panicking version.
+    fn index(&self, index: usize) -> &Self::Output {
+        let len = self.children().len();
+        self.child_at(index)
+            .unwrap_or_else(|| panic!("index {index} out of bounds for {len} children"))
+    }
+}
+impl IndexMut<usize> for Xml<'_> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        let len = self.children().len();
+        self.child_at_mut(index)
+            .unwrap_or_else(|| panic!("index {index} out of bounds for {len} children"))
+    }
+}
+impl FromStr for Xml<'static> {
+    type Err = Error;
+
+    /// Parse a document into a tree which owns its strings, as with
+    /// [`document`](crate::document).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Xml;
+    /// let xml = String::from("<a><b/></a>").parse::<Xml>()?;
+    /// assert_eq!(xml.children().count(), 1);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    fn from_str(text: &str) -> Result<Self, Error> {
+        crate::document(text).map(Xml::into_owned)
+    }
+}
+impl<'a> TryFrom<&'a str> for Xml<'a> {
+    type Error = Error;
+
+    /// Parse a document into a tree borrowing from the text, as with
+    /// [`document`](crate::document).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Xml;
+    /// let xml = Xml::try_from("<a>text</a>")?;
+    /// assert_eq!(xml.name(), Some("a"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    fn try_from(text: &'a str) -> Result<Self, Error> {
+        crate::document(text)
+    }
+}
+impl fmt::Display for Document<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        FormattedDocument(self, &WriteOptions::new()).fmt(f)
+    }
+}
+
+/// Parsed document with indices for fast lookups.
+///
+/// Elements are identified by their `xml:id` attribute, and optionally by other attributes
+/// declared as IDs, e.g. in a DTD. An index of element names can be added for read-heavy
+/// workloads with [`Document::with_name_index`]. The tree can only be changed through
+/// [`Document::apply`], which keeps the indices up to date; use [`Document::into_root`] to
+/// edit it freely.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::Document;
+/// let doc = Document::parse(r#"<a><b xml:id="first"/><c xml:id="second">hi</c></a>"#)?;
+/// let c = doc.element_by_id("second").unwrap();
+/// assert_eq!(c.name(), Some("c"));
+/// assert!(doc.element_by_id("third").is_none());
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Document<'a> {
+    declaration: Option<Cow<'a, str>>,
+    prolog: Vec<Xml<'a>>,
+    root: Xml<'a>,
+    id_attrs: Vec<String>,
+    idref_attrs: Vec<String>,
+    // child indices leading from the root to each element with an ID, and the index of the ID
+    // attribute, in document order so the first one is found and the others are duplicates
+    ids: HashMap<String, Vec<(Vec<usize>, usize)>>,
+    // child indices of each element with an IDREF attribute, and the index of the attribute
+    refs: Vec<(Vec<usize>, usize)>,
+    names: Option<HashMap<Symbol, Vec<Vec<usize>>>>,
+    interner: Interner,
+    diags: Vec<Error>,
+    changes: Option<Vec<Edit<'a>>>,
+}
+impl<'a> Document<'a> {
+    /// Create a document from a tree, indexing its `xml:id` attributes.
+    pub fn new(root: Xml<'a>) -> Self {
+        let mut document = Document {
+            declaration: None,
+            prolog: vec![],
+            root,
+            id_attrs: vec!["xml:id".to_owned()],
+            idref_attrs: vec![],
+            ids: HashMap::new(),
+            refs: vec![],
+            names: None,
+            interner: Interner::new(),
+            diags: vec![],
+            changes: None,
+        };
+        document.index();
+        document
+    }
+
+    /// Parse a document and index its `xml:id` attributes.
+    ///
+    /// The XML declaration, processing instructions and comments before the root element are
+    /// kept, so they are written back when serializing the document.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Document;
+    /// let text = "<?xml version=\"1.0\"?>\n<!-- generated -->\n<a/>";
+    /// let doc = Document::parse(text)?;
+    /// assert_eq!(doc.declaration(), Some("<?xml version=\"1.0\"?>"));
+    /// assert_eq!(doc.prolog()[0].to_string(), "<!-- generated -->");
+    /// assert_eq!(doc.to_string(), text);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn parse(text: &'a str) -> Result<Self, Error> {
+        let mut tags = Tags::new(text).with_comments(true);
+        let (declaration, prolog) = self::prolog(&mut tags)?;
+        tags.lexer.comments = false;
+        let root = element(&mut tags)?;
+        Ok(Document {
+            declaration,
+            prolog,
+            ..Document::new(root)
+        })
+    }
+
+    /// Parse a document, repairing improperly nested tags instead of failing.
+    ///
+    /// When an end tag does not match the innermost open element, the elements opened since
+    /// the matching element are closed, and end tags without a matching element are skipped.
+    /// Elements left open at the end of the input are closed. Each repair, along with any
+    /// syntax error recovered from, is recorded in [`Document::diags`], so slightly broken
+    /// machine-generated files still yield a usable tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document has no root element or an invalid prolog.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{Document, Error};
+    /// let doc = Document::parse_lenient("<a><b><c>text</b></x><d>")?;
+    /// assert_eq!(doc.root().to_string(), "<a><b><c>text</c></b><d/></a>");
+    /// assert_eq!(doc.diags().len(), 3);
+    /// assert!(matches!(&doc.diags()[0], Error::Mismatched { expected, .. } if expected == "c"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn parse_lenient(text: &'a str) -> Result<Self, Error> {
+        let mut tags = Tags::new(text).with_comments(true);
+        let (declaration, prolog) = self::prolog(&mut tags)?;
+        tags.lexer.comments = false;
+        let root = element_lenient(&mut tags)?;
+        let mut document = Document {
+            declaration,
+            prolog,
+            ..Document::new(root)
+        };
+        document.diags.splice(0..0, tags.diags);
+        Ok(document)
+    }
+
+    /// Parse a document which does not borrow from the text, with diagnostics recorded before
+    /// parsing.
+    pub(crate) fn parse_owned(text: &str, diags: Vec<Error>) -> Result<Document<'static>, Error> {
+        let parsed = Document::parse(text)?;
+        let mut document = Document {
+            declaration: parsed.declaration.map(|d| Cow::Owned(d.into_owned())),
+            prolog: parsed.prolog.into_iter().map(Xml::into_owned).collect(),
+            ..Document::new(parsed.root.into_owned())
+        };
+        document.diags.splice(0..0, diags);
+        Ok(document)
+    }
+
+    /// Treat another attribute as an ID, in addition to `xml:id`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Document;
+    /// let doc = Document::parse(r#"<a><b id="x"/></a>"#)?.with_id_attr("id");
+    /// assert_eq!(doc.element_by_id("x").and_then(|b| b.name()), Some("b"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn with_id_attr(mut self, name: impl Into<String>) -> Self {
+        self.id_attrs.push(name.into());
+        self.index();
+        self
+    }
+
+    /// Treat an attribute as an IDREF or IDREFS, whose whitespace-separated values refer to
+    /// IDs in the document.
+    ///
+    /// References to IDs that do not exist are recorded in [`Document::diags`] with the path of
+    /// the referring attribute, which [`Error::locate`] turns into the span of its element.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{Document, Error};
+    /// let text = "<a>\n  <b id=\"x\"/>\n  <c refs=\"x y\"/>\n</a>";
+    /// let doc = Document::parse(text)?.with_id_attr("id").with_idref_attr("refs");
+    /// let Error::Validation { message, path } = &doc.diags()[0] else { unreachable!() };
+    /// assert_eq!((message.as_str(), path.as_str()), (r#"dangling IDREF "y""#, "/a/c/@refs"));
+    /// assert_eq!(doc.diags()[0].locate(text), Some((3, 3)));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn with_idref_attr(mut self, name: impl Into<String>) -> Self {
+        self.idref_attrs.push(name.into());
+        self.index();
+        self
+    }
+
+    /// Resolve the IDREF or IDREFS attribute of an element to the elements it refers to.
+    ///
+    /// References to IDs that do not exist are skipped. The attribute does not need to be
+    /// registered with [`Document::with_idref_attr`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Document;
+    /// let doc = Document::parse(r#"<a><b xml:id="x"/><c xml:id="y"/><d for="y x z"/></a>"#)?;
+    /// let d = doc.root().children().nth(2).unwrap();
+    /// let targets = doc.resolve_idref(d, "for");
+    /// assert_eq!(targets.iter().filter_map(|e| e.name()).collect::<Vec<_>>(), ["c", "b"]);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn resolve_idref(&self, element: &Xml, attr: &str) -> Vec<&Xml<'a>> {
+        let refs = element.attr(attr).map(unquote).unwrap_or_default();
+        refs.split_ascii_whitespace()
+            .filter_map(|id| self.element_by_id(id))
+            .collect()
+    }
+
+    /// Get the XML declaration, e.g. `<?xml version="1.0"?>`, if the document has one.
+    pub fn declaration(&self) -> Option<&str> {
+        self.declaration.as_deref()
+    }
+    /// Get the document type declaration without its delimiters, if the document has one.
+    pub fn doctype(&self) -> Option<&str> {
+        self.prolog.iter().find_map(|item| {
+            let Xml::Raw(raw) = item else {
+                return None;
+            };
+            let doctype = raw.strip_prefix("<!DOCTYPE ")?.strip_suffix('>')?;
+            Some(doctype)
+        })
+    }
+    /// Add the default attribute values declared in the document type declaration to the
+    /// elements that omit them.
+    ///
+    /// Problems with the declarations are recorded in [`Document::diags`], and the defaults
+    /// from the valid declarations are still applied. See [`Dtd`] for what is supported.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Document;
+    /// let text = r#"<!DOCTYPE a [<!ATTLIST b kind (x|y) "x">]><a><b/><b kind="y"/></a>"#;
+    /// let doc = Document::parse(text)?.with_dtd_defaults();
+    /// let kinds = doc.root().children().filter_map(|b| b.attr("kind")).collect::<Vec<_>>();
+    /// assert_eq!(kinds, ["\"x\"", "\"y\""]);
+    /// assert_eq!(doc.doctype(), Some(r#"a [<!ATTLIST b kind (x|y) "x">]"#));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn with_dtd_defaults(mut self) -> Self {
+        let Some(doctype) = self.doctype() else {
+            return self;
+        };
+        match Dtd::parse(doctype) {
+            Ok(dtd) => dtd.apply(&mut self.root),
+            Err(error) => self.diags.push(error),
+        }
+        self.index();
+        self
+    }
+    /// Get the processing instructions, comments and document type declaration before the root
+    /// element, as raw nodes.
+    pub fn prolog(&self) -> &[Xml<'a>] {
+        &self.prolog
+    }
+
+    /// Get the root node.
+    pub fn root(&self) -> &Xml<'a> {
+        &self.root
+    }
+    /// Take the root node out of the document, discarding the index.
+    pub fn into_root(self) -> Xml<'a> {
+        self.root
+    }
+
+    /// Find the element with the given ID.
+    pub fn element_by_id(&self, id: &str) -> Option<&Xml<'a>> {
+        let (indices, _) = self.ids.get(id)?.first()?;
+        Some(self.node(indices))
+    }
+
+    /// Build an index of element names, speeding up [`Document::elements_by_name`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Document;
+    /// let doc = Document::parse("<a><b>1</b><c><b>2</b></c></a>")?.with_name_index();
+    /// let bs = doc.elements_by_name("b").collect::<Vec<_>>();
+    /// assert_eq!(bs.len(), 2);
+    /// assert_eq!(bs[1].children().next().and_then(|t| t.content()), Some("2"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn with_name_index(mut self) -> Self {
+        self.names = Some(HashMap::new());
+        self.index();
+        self
+    }
+
+    /// Iterate over the elements with the given name, including the root, in document order.
+    ///
+    /// Without [`Document::with_name_index`], this scans the whole tree.
+    pub fn elements_by_name<'d>(&'d self, name: &'d str) -> impl Iterator<Item = &'d Xml<'a>> {
+        let (indexed, scanned) = match &self.names {
+            Some(names) => {
+                let indices = self.interner.get(name).and_then(|s| names.get(&s));
+                (indices.map(|v| v.as_slice()), None)
+            }
+            None => {
+                let nodes = iter::once(&self.root).chain(self.root.descendants());
+                (
+                    None,
+                    Some(nodes.filter(move |node| node.name() == Some(name))),
+                )
+            }
+        };
+        let indexed = indexed.unwrap_or_default().iter();
+        let indexed = indexed.map(|indices| self.node(indices));
+        indexed.chain(scanned.into_iter().flatten())
+    }
+
+    /// Record the edits applied with [`Document::apply`], e.g. for dirty tracking or syncing
+    /// edits to an external store.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{Document, Edit};
+    /// let mut doc = Document::parse("<a><b/></a>")?.with_change_log();
+    /// doc.apply(Edit::set_attr(vec![0], "x", "1"))?;
+    /// doc.apply(Edit::remove(vec![0]))?;
+    /// assert_eq!(doc.changes().len(), 2);
+    /// assert_eq!(doc.changes()[0].path(), [0]);
+    ///
+    /// let synced = doc.take_changes();
+    /// assert_eq!(synced[1], Edit::remove(vec![0]));
+    /// assert!(doc.changes().is_empty());
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn with_change_log(mut self) -> Self {
+        self.changes.get_or_insert_with(Vec::new);
+        self
+    }
+    /// Get the edits recorded since the change log was enabled or last taken, in the order
+    /// they were applied.
+    ///
+    /// This is empty unless the change log is enabled with [`Document::with_change_log`].
+    pub fn changes(&self) -> &[Edit<'a>] {
+        self.changes.as_deref().unwrap_or_default()
+    }
+    /// Take the recorded edits, leaving the change log empty.
+    pub fn take_changes(&mut self) -> Vec<Edit<'a>> {
+        self.changes.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    /// Apply an edit to the tree, updating the indices and recording the edit in the change log
+    /// if it is enabled.
+    ///
+    /// Only the entries of the edited nodes and the paths of the nodes after them are updated,
+    /// so edits do not rebuild the indices from the whole tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the edit does not apply, in which case the document
+    /// is left unchanged.
+    pub fn apply(&mut self, edit: Edit<'a>) -> Result<(), Error> {
+        let logged = self.changes.is_some().then(|| edit.clone());
+        let inverse = edit.apply(&mut self.root)?;
+        if let (Some(changes), Some(edit)) = (&mut self.changes, logged) {
+            changes.push(edit);
+        }
+        let changed = match &inverse {
+            Edit::SetAttr { path, key, value } => self.reindex_attr(path, key, value.as_deref()),
+            Edit::Rename { path, name } => {
+                self.reindex_name(path, name);
+                false
+            }
+            // the inverse of an insertion is a removal, and the other way around
+            Edit::Remove { path } => {
+                self.shift(path, true);
+                self.index_subtree(path)
+            }
+            Edit::Insert { path, .. } => {
+                let removed = self.unindex_subtree(path);
+                self.shift(path, false);
+                removed
+            }
+            Edit::Replace { path, .. } => self.unindex_subtree(path) | self.index_subtree(path),
+        };
+        // problems are located by path, so they also change when nodes move
+        let moved = matches!(inverse, Edit::Insert { .. } | Edit::Remove { .. });
+        let problems = self
+            .diags
+            .iter()
+            .any(|e| matches!(e, Error::Validation { .. }));
+        if changed || moved && problems {
+            self.check_ids();
+        }
+        Ok(())
+    }
+
+    /// Get the interner holding the element names of the name index.
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    /// Return problems found while indexing, such as IDs used on more than one element or
+    /// references to missing IDs, and errors recovered from by [`Document::parse_lenient`].
+    ///
+    /// Problems found while indexing are located by node path, which [`Error::locate`] turns
+    /// into a span in the parsed text.
+    pub fn diags(&self) -> &[Error] {
+        &self.diags
+    }
+
+    fn node(&self, indices: &[usize]) -> &Xml<'a> {
+        indices
+            .iter()
+            .fold(&self.root, |node, &i| &node.children().as_slice()[i])
+    }
+
+    fn path(&self, indices: &[usize]) -> String {
+        let mut node = &self.root;
+        let mut path = child_path("", slice::from_ref(node), 0);
+        for &i in indices {
+            path = child_path(&path, node.children().as_slice(), i);
+            node = &node.children().as_slice()[i];
+        }
+        path
+    }
+
+    /// Rebuild the indices from the whole tree.
+    fn index(&mut self) {
+        self.ids.clear();
+        self.refs.clear();
+        if let Some(names) = &mut self.names {
+            names.clear();
+        }
+        self.index_subtree(&[]);
+        self.check_ids();
+    }
+
+    /// Add the entries of the subtree at a path to the indices, returning whether it has any
+    /// IDs or references.
+    fn index_subtree(&mut self, path: &[usize]) -> bool {
+        let Document {
+            root,
+            id_attrs,
+            idref_attrs,
+            ids,
+            refs,
+            names,
+            interner,
+            ..
+        } = self;
+        let node = path
+            .iter()
+            .fold(&*root, |node, &i| &node.children().as_slice()[i]);
+        let mut found = false;
+        walk(node, &mut path.to_vec(), &mut |node, indices| {
+            for (k, name) in idref_attrs.iter().enumerate() {
+                if node.attr(name).is_some() {
+                    insert_sorted(refs, (indices.to_vec(), k));
+                    found = true;
                 }
-                if children.is_empty() {
-                    write!(f, "/>")?;
-                } else {
-                    write!(f, ">")?;
-                    for child in children {
-                        write!(f, "{child}")?;
-                    }
-                    write!(f, "</{name}>")?;
+            }
+            for (k, name) in id_attrs.iter().enumerate() {
+                if let Some(id) = node.attr(name).map(|id| unquote(id).trim()) {
+                    let holders = ids.entry(id.to_owned()).or_default();
+                    insert_sorted(holders, (indices.to_vec(), k));
+                    found = true;
                 }
-                Ok(())
             }
-            Xml::Text(text) => f.write_str(text),
+            if let (Some(names), Some(name)) = (names.as_mut(), node.name()) {
+                let symbol = interner.intern(name);
+                insert_sorted(names.entry(symbol).or_default(), indices.to_vec());
+            }
+        });
+        found
+    }
+
+    /// Remove the entries of the subtree at a path from the indices, returning whether it had
+    /// any IDs or references.
+    fn unindex_subtree(&mut self, path: &[usize]) -> bool {
+        let mut found = false;
+        self.ids.retain(|_, holders| {
+            let len = holders.len();
+            holders.retain(|(indices, _)| !indices.starts_with(path));
+            found |= holders.len() != len;
+            !holders.is_empty()
+        });
+        let refs = self.refs.len();
+        self.refs.retain(|(indices, _)| !indices.starts_with(path));
+        found |= self.refs.len() != refs;
+        for holders in self.names.iter_mut().flat_map(|names| names.values_mut()) {
+            holders.retain(|indices| !indices.starts_with(path));
+        }
+        found
+    }
+
+    /// Shift the entries of the siblings after a node which was inserted or removed, along
+    /// with their descendants.
+    fn shift(&mut self, path: &[usize], inserted: bool) {
+        let Some((&i, parent)) = path.split_last() else {
+            return;
+        };
+        let depth = parent.len();
+        let shift = |indices: &mut Vec<usize>| {
+            if indices.len() <= depth || !indices.starts_with(parent) {
+                return;
+            }
+            match inserted {
+                true if indices[depth] >= i => indices[depth] += 1,
+                false if indices[depth] > i => indices[depth] -= 1,
+                _ => {}
+            }
+        };
+        let ids = self.ids.values_mut().flatten().map(|(indices, _)| indices);
+        let refs = self.refs.iter_mut().map(|(indices, _)| indices);
+        let names = self.names.iter_mut().flat_map(|names| names.values_mut());
+        ids.chain(refs).chain(names.flatten()).for_each(shift);
+    }
+
+    /// Update the ID and reference entries of an element after an attribute was changed,
+    /// returning whether the attribute is an ID or reference.
+    fn reindex_attr(&mut self, path: &[usize], key: &str, old: Option<&str>) -> bool {
+        let value = self
+            .node(path)
+            .attr(key)
+            .map(|v| unquote(v).trim().to_owned());
+        let mut found = false;
+        for k in (0..self.id_attrs.len()).filter(|&k| self.id_attrs[k] == key) {
+            let entry = (path.to_vec(), k);
+            if let Some(old) = old.map(|id| unquote(id).trim())
+                && let Some(holders) = self.ids.get_mut(old)
+            {
+                holders.retain(|holder| *holder != entry);
+                if holders.is_empty() {
+                    self.ids.remove(old);
+                }
+            }
+            if let Some(id) = &value {
+                insert_sorted(self.ids.entry(id.clone()).or_default(), entry);
+            }
+            found = true;
+        }
+        for k in (0..self.idref_attrs.len()).filter(|&k| self.idref_attrs[k] == key) {
+            let entry = (path.to_vec(), k);
+            self.refs.retain(|holder| *holder != entry);
+            if value.is_some() {
+                insert_sorted(&mut self.refs, entry);
+            }
+            found = true;
         }
+        found
+    }
+
+    /// Move the name index entry of an element after it was renamed.
+    fn reindex_name(&mut self, path: &[usize], old: &str) {
+        let name = self.node(path).name().unwrap_or_default().to_owned();
+        let Some(names) = &mut self.names else {
+            return;
+        };
+        if let Some(holders) = self.interner.get(old).and_then(|old| names.get_mut(&old))
+            && let Ok(i) = holders.binary_search_by(|indices| indices.as_slice().cmp(path))
+        {
+            holders.remove(i);
+        }
+        let symbol = self.interner.intern(&name);
+        insert_sorted(names.entry(symbol).or_default(), path.to_vec());
+    }
+
+    /// Rebuild the problems found with IDs and references from the indices, keeping other
+    /// diagnostics such as parse errors.
+    fn check_ids(&mut self) {
+        let mut duplicates = self
+            .ids
+            .iter()
+            .flat_map(|(id, holders)| holders[1..].iter().map(move |(i, k)| (i, *k, id)))
+            .collect::<Vec<_>>();
+        duplicates.sort_unstable();
+        let duplicates = duplicates
+            .into_iter()
+            .map(|(indices, k, id)| Error::Validation {
+                message: format!("duplicate ID {id:?}"),
+                path: format!("{}/@{}", self.path(indices), self.id_attrs[k]),
+            })
+            .collect::<Vec<_>>();
+        let dangling = self
+            .refs
+            .iter()
+            .flat_map(|(indices, k)| {
+                let name = &self.idref_attrs[*k];
+                let value = self.node(indices).attr(name).map(unquote);
+                let dangling = value
+                    .unwrap_or_default()
+                    .split_ascii_whitespace()
+                    .filter(|id| !self.ids.contains_key(*id));
+                let path = format!("{}/@{name}", self.path(indices));
+                dangling.map(move |id| Error::Validation {
+                    message: format!("dangling IDREF {id:?}"),
+                    path: path.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        self.diags
+            .retain(|error| !matches!(error, Error::Validation { .. }));
+        self.diags.extend(duplicates);
+        self.diags.extend(dangling);
+    }
+}
+
+/// Insert an item into a sorted list.
+fn insert_sorted<T: Ord>(items: &mut Vec<T>, item: T) {
+    let (Ok(i) | Err(i)) = items.binary_search(&item);
+    items.insert(i, item);
+}
+
+/// Documents are serialized as their prolog, root, ID and IDREF attributes; indices are
+/// rebuilt when deserializing, except for the name index.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Document<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Document", 5)?;
+        state.serialize_field("declaration", &self.declaration)?;
+        state.serialize_field("prolog", &self.prolog)?;
+        state.serialize_field("root", &self.root)?;
+        state.serialize_field("id_attrs", &self.id_attrs)?;
+        state.serialize_field("idref_attrs", &self.idref_attrs)?;
+        state.end()
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Document<'_> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Repr<'a> {
+            #[serde(default)]
+            declaration: Option<Cow<'a, str>>,
+            #[serde(default)]
+            prolog: Vec<Xml<'a>>,
+            root: Xml<'a>,
+            id_attrs: Vec<String>,
+            #[serde(default)]
+            idref_attrs: Vec<String>,
+        }
+        let Repr {
+            declaration,
+            prolog,
+            root,
+            id_attrs,
+            idref_attrs,
+        } = Repr::deserialize(deserializer)?;
+        let mut document = Document {
+            declaration,
+            prolog,
+            ..Document::new(root)
+        };
+        document.id_attrs = id_attrs;
+        document.idref_attrs = idref_attrs;
+        document.index();
+        Ok(document)
+    }
+}
+
+/// Visit every node in document order, along with the child indices leading to it.
+pub(crate) fn walk<'x, 'a>(
+    node: &'x Xml<'a>,
+    indices: &mut Vec<usize>,
+    f: &mut impl FnMut(&'x Xml<'a>, &[usize]),
+) {
+    f(node, indices);
+    for (i, child) in node.children().enumerate() {
+        indices.push(i);
+        walk(child, indices, f);
+        indices.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index() {
+        let mut xml = crate::document("<a><b><c>1</c></b>text</a>").unwrap();
+        assert_eq!(xml["b"]["c"][0], Xml::text("1"));
+        xml["b"]["c"][0] = Xml::text("2");
+        assert_eq!(xml[1].content(), Some("text"));
+        assert_eq!(xml.to_string(), "<a><b><c>2</c></b>text</a>");
+        assert!(xml.child_at(2).is_none() && xml[1].child("b").is_none());
+    }
+
+    #[test]
+    fn from_tags() {
+        let text = "<?xml version='1.0'?> <a x='1'>t<b/><c>u</c></a><d/>";
+        let tags = crate::tags(text).with_comments(true);
+        assert_eq!(
+            Xml::from_tags(tags).unwrap(),
+            crate::document(text).unwrap()
+        );
+        let error = Xml::from_tags(crate::tags("<a><b></a>")).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::MismatchedTag {
+                index: 2,
+                opened: Some(1),
+                ..
+            }
+        ));
+        assert!(matches!(
+            Xml::from_tags(crate::tags("<a><b/>")),
+            Err(Error::Eof)
+        ));
+    }
+
+    #[test]
+    fn ids() {
+        let text = r#"<a xml:id="root"><b xml:id=" x "/><b><c xml:id="y">c</c></b></a>"#;
+        let doc = Document::parse(text).unwrap();
+        assert_eq!(doc.element_by_id("root"), Some(doc.root()));
+        assert_eq!(doc.element_by_id("x").and_then(|b| b.name()), Some("b"));
+        assert_eq!(doc.element_by_id("y").and_then(|c| c.name()), Some("c"));
+        assert!(doc.diags().is_empty());
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let text = r#"<a><b xml:id="x" id="y"/><b xml:id="y"/><c id="x"/></a>"#;
+        let doc = Document::parse(text).unwrap().with_id_attr("id");
+        let errors = doc
+            .diags()
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                r#"duplicate ID "y" at /a/b[2]/@xml:id"#,
+                r#"duplicate ID "x" at /a/c/@id"#
+            ]
+        );
+        assert_eq!(doc.element_by_id("y"), doc.root().children().next());
+    }
+
+    #[test]
+    fn name_index() {
+        let text = "<a><b/><c><b>x</b><a/></c>text</a>";
+        let doc = Document::parse(text).unwrap();
+        let indexed = doc.clone().with_name_index();
+        let scanned = doc.elements_by_name("a").collect::<Vec<_>>();
+        assert_eq!(indexed.elements_by_name("a").collect::<Vec<_>>(), scanned);
+        assert_eq!(scanned.len(), 2);
+        let doc = indexed;
+        assert_eq!(scanned[1], &Xml::element("a"));
+        assert_eq!(doc.elements_by_name("b").count(), 2);
+        assert_eq!(doc.elements_by_name("d").count(), 0);
+    }
+
+    #[test]
+    fn incremental() {
+        let text = r#"<a><b id="x"/><c><b id="y" ref="x z"/></c><d ref="y"/></a>"#;
+        let mut doc = Document::parse(text)
+            .unwrap()
+            .with_id_attr("id")
+            .with_idref_attr("ref")
+            .with_name_index();
+        let edits = [
+            Edit::insert(vec![0], Xml::element("b").with_attr("id", "\"z\"")),
+            Edit::rename(vec![2], "b"),
+            Edit::set_attr(vec![1], "id", "\"y\""),
+            Edit::remove(vec![2, 0]),
+            Edit::replace(vec![3], Xml::element("e").with_attr("ref", "\"w\"")),
+            Edit::set_attr(vec![0], "id", "\"x\""),
+            Edit::remove_attr(vec![1], "id"),
+        ];
+        for edit in edits {
+            doc.apply(edit).unwrap();
+            let fresh = Document::new(doc.root().clone())
+                .with_id_attr("id")
+                .with_idref_attr("ref")
+                .with_name_index();
+            assert_eq!(doc.ids, fresh.ids);
+            assert_eq!(doc.refs, fresh.refs);
+            let diags = |doc: &Document| {
+                doc.diags()
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(diags(&doc), diags(&fresh));
+            for name in ["a", "b", "c", "d", "e"] {
+                let indexed = doc.elements_by_name(name).collect::<Vec<_>>();
+                assert_eq!(indexed, fresh.elements_by_name(name).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn attr_list() {
+        let xml = Xml::element("a")
+            .with_attr("points", ",1,,2 ")
+            .with_attr("empty", "");
+        assert_eq!(xml.attr_list("points"), Some(vec!["1", "2"]));
+        assert_eq!(xml.attr_list_as::<u8>("empty"), Some(Ok(vec![])));
+    }
+
+    #[test]
+    fn lenient() {
+        let doc = Document::parse_lenient("<a xml:id='x'></b><c xml:id='x'/>").unwrap();
+        assert_eq!(doc.root().to_string(), "<a xml:id='x'><c xml:id='x'/></a>");
+        let doc = doc.with_id_attr("id");
+        assert_eq!(doc.diags().len(), 3);
+        assert!(matches!(doc.diags()[2], Error::Validation { .. }));
+        assert!(Document::parse_lenient("</a>").is_err());
+    }
+
+    #[test]
+    fn hash() {
+        use std::collections::HashSet;
+        let a = Xml::element("a").with_attr("x", "1").with_attr("y", "2");
+        let b = Xml::element("a").with_attr("y", "2").with_attr("x", "1");
+        let c = Xml::element("a").with_attr("x", "2").with_attr("y", "1");
+        let set = HashSet::from([a.clone(), b, c, Xml::text("a")]);
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&a));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let text = r#"<a x="1"><b id="b">text</b></a>"#;
+        let doc = Document::parse(text).unwrap().with_id_attr("id");
+        let json = serde_json::to_string(&doc).unwrap();
+        let copy: Document = serde_json::from_str(&json).unwrap();
+        assert_eq!(copy.root(), doc.root());
+        assert_eq!(copy.element_by_id("b"), doc.element_by_id("b"));
+
+        let json = serde_json::to_string(doc.root()).unwrap();
+        assert_eq!(serde_json::from_str::<Xml>(&json).unwrap(), *doc.root());
     }
 }
@@ -0,0 +1,198 @@
+//! Attribute defaults declared in document type declarations.
+
+use crate::{Element, Error, Xml};
+use std::{borrow::Cow, collections::HashMap};
+
+/// Attribute list declarations of a DTD, used to fill in default attribute values.
+///
+/// Only the internal subset of the document type declaration is read. External subsets are
+/// not fetched, and parameter entity references are not expanded.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::Dtd;
+/// let dtd = Dtd::parse(r#"note [<!ATTLIST note lang CDATA "en" id ID #IMPLIED>]"#)?;
+/// let mut xml = xmlite::document("<note>hi</note>")?;
+/// dtd.apply(&mut xml);
+/// assert_eq!(xml.attr("lang"), Some("\"en\""));
+/// assert_eq!(xml.attr("id"), None);
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dtd {
+    // element name to attribute names and quoted default values, in declaration order
+    defaults: HashMap<String, Vec<(String, String)>>,
+}
+impl Dtd {
+    /// Parse the attribute list declarations in a document type declaration, as returned by
+    /// [`Tag::Doctype`](crate::Tag::Doctype) or [`Document::doctype`](crate::Document::doctype).
+    ///
+    /// Other markup declarations are skipped. As in XML, the first declaration of an attribute
+    /// is the one that counts.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Syntax`] for malformed attribute list declarations, with the span
+    /// relative to the start of the text.
+    pub fn parse(text: &str) -> Result<Dtd, Error> {
+        let mut dtd = Dtd::default();
+        let Some(open) = text.find('[') else {
+            return Ok(dtd);
+        };
+        let mut i = open + 1;
+        while let Some(start) = text[i..].find('<').map(|start| i + start) {
+            let rest = &text[start..];
+            if rest.starts_with("<!--") || rest.starts_with("<?") {
+                let close = if rest.starts_with("<?") { "?>" } else { "-->" };
+                i = rest
+                    .find(close)
+                    .map_or(text.len(), |end| start + end + close.len());
+                continue;
+            }
+            let (tokens, len) = tokens(rest);
+            i = start + len;
+            if rest.starts_with("<!ATTLIST") {
+                dtd.attlist(&tokens, text, start)?;
+            }
+        }
+        Ok(dtd)
+    }
+
+    /// Add the defaults of an attribute list declaration.
+    fn attlist(&mut self, tokens: &[(&str, usize)], text: &str, start: usize) -> Result<(), Error> {
+        let error = |(token, offset): (&str, usize)| Error::Syntax {
+            token: token.to_owned(),
+            span: position(text, start + offset),
+        };
+        let quoted = |token: &str| token.starts_with(['"', '\'']);
+        let Some(&(element, _)) = tokens.first().filter(|(t, _)| !quoted(t) && *t != ">") else {
+            return Err(error(tokens.first().copied().unwrap_or(("", 0))));
+        };
+        let mut rest = &tokens[1..];
+        loop {
+            let (name, kind) = match rest {
+                [(">", _)] => return Ok(()),
+                [(name, _), (kind, _), ..] if !quoted(name) && *name != ">" && *kind != ">" => {
+                    (*name, *kind)
+                }
+                [token, ..] => return Err(error(*token)),
+                [] => return Err(error(("", text.len() - start))),
+            };
+            rest = &rest[2..];
+            if kind == "NOTATION" {
+                rest = rest
+                    .get(1..)
+                    .ok_or_else(|| error(("", text.len() - start)))?;
+            }
+            let default = match rest {
+                [("#REQUIRED" | "#IMPLIED", _), ..] => None,
+                [("#FIXED", _), (value, _), ..] if quoted(value) => {
+                    rest = &rest[1..];
+                    Some(*value)
+                }
+                [(value, _), ..] if quoted(value) => Some(*value),
+                [token, ..] => return Err(error(*token)),
+                [] => return Err(error(("", text.len() - start))),
+            };
+            rest = &rest[1..];
+            let Some(value) = default else {
+                continue;
+            };
+            let defaults = self.defaults.entry(element.to_owned()).or_default();
+            if !defaults.iter().any(|(n, _)| n == name) {
+                defaults.push((name.to_owned(), value.to_owned()));
+            }
+        }
+    }
+
+    /// Iterate over the attributes with default values declared for an element, with their
+    /// quoted values.
+    pub fn defaults(&self, element: &str) -> impl Iterator<Item = (&str, &str)> {
+        let defaults = self.defaults.get(element).map_or(&[][..], Vec::as_slice);
+        defaults.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Add default values to the elements of a tree that omit declared attributes.
+    pub fn apply(&self, xml: &mut Xml) {
+        let Xml::Element(Element {
+            name,
+            attrs,
+            children,
+        }) = xml
+        else {
+            return;
+        };
+        for (key, value) in self.defaults(name) {
+            if !attrs.contains_key(key) {
+                attrs.insert(Cow::Owned(key.to_owned()), Cow::Owned(value.to_owned()));
+            }
+        }
+        for child in children {
+            self.apply(child);
+        }
+    }
+}
+
+/// Split a markup declaration into tokens with their offsets, up to and including its `>`.
+///
+/// Quoted strings and parenthesized groups are single tokens. Returns the tokens after the
+/// declaration keyword and the length of the declaration.
+fn tokens(src: &str) -> (Vec<(&str, usize)>, usize) {
+    let mut tokens = vec![];
+    let mut i = src
+        .find(char::is_whitespace)
+        .unwrap_or(src.len())
+        .min(src.find('>').unwrap_or(src.len()));
+    while let Some(c) = src[i..].chars().next() {
+        let end = match c {
+            c if c.is_whitespace() => {
+                i += c.len_utf8();
+                continue;
+            }
+            '>' => {
+                tokens.push((">", i));
+                return (tokens, i + 1);
+            }
+            '"' | '\'' => src[i + 1..].find(c).map(|end| i + end + 2),
+            '(' => src[i..].find(')').map(|end| i + end + 1),
+            _ => src[i..]
+                .find(|c: char| c.is_whitespace() || matches!(c, '>' | '"' | '\'' | '('))
+                .map(|end| i + end),
+        };
+        let end = end.unwrap_or(src.len());
+        tokens.push((&src[i..end], i));
+        i = end;
+    }
+    (tokens, src.len())
+}
+
+/// Get the (line, column) of a byte offset.
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declarations() {
+        let text = "a [\n<!-- <!ATTLIST a x CDATA 'no'> -->\n<!ELEMENT a (b)*>\
+            <!ATTLIST a x (p|q) 'p' x CDATA 'q' n NOTATION (g) #FIXED \"g\">\
+            <!ATTLIST b y CDATA #REQUIRED>]";
+        let dtd = Dtd::parse(text).unwrap();
+        let defaults = dtd.defaults("a").collect::<Vec<_>>();
+        assert_eq!(defaults, [("x", "'p'"), ("n", "\"g\"")]);
+        assert_eq!(dtd.defaults("b").count(), 0);
+
+        let Err(Error::Syntax { token, span }) = Dtd::parse("a [\n<!ATTLIST a x CDATA #BAD>]")
+        else {
+            panic!("malformed declaration was accepted");
+        };
+        assert_eq!((token.as_str(), span), ("#BAD", (2, 21)));
+    }
+}
@@ -0,0 +1,423 @@
+//! Edits of trees that can be recorded and reversed.
+
+use crate::{Element, Error, Xml, validate::child_path};
+use std::{borrow::Cow, mem, slice};
+
+/// Edit of a tree, addressing nodes by the child indices leading to them from the root.
+///
+/// Applying an edit returns its inverse, which restores the tree when applied in turn. This
+/// is the basis of change logs, undo histories and transactions.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::{Edit, Xml};
+/// let mut xml = xmlite::document("<a><b/></a>")?;
+/// let inverse = Edit::insert(vec![0], Xml::element("c")).apply(&mut xml)?;
+/// Edit::set_attr(vec![1], "x", "1").apply(&mut xml)?;
+/// assert_eq!(xml.to_string(), r#"<a><c/><b x="1"/></a>"#);
+///
+/// assert_eq!(inverse, Edit::remove(vec![0]));
+/// let err = Edit::remove(vec![2]).apply(&mut xml).unwrap_err();
+/// assert!(matches!(err, xmlite::Error::Validation { .. }));
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit<'a> {
+    /// Set or remove an attribute of an element.
+    SetAttr {
+        /// Child indices of the element.
+        path: Vec<usize>,
+        /// Attribute name.
+        key: Cow<'a, str>,
+        /// New value, or `None` to remove the attribute.
+        value: Option<Cow<'a, str>>,
+    },
+    /// Rename an element.
+    Rename {
+        /// Child indices of the element.
+        path: Vec<usize>,
+        /// New name.
+        name: Cow<'a, str>,
+    },
+    /// Insert a node, shifting the following siblings.
+    Insert {
+        /// Child indices the node has after insertion.
+        path: Vec<usize>,
+        /// Inserted node.
+        node: Xml<'a>,
+    },
+    /// Remove a node.
+    Remove {
+        /// Child indices of the node.
+        path: Vec<usize>,
+    },
+    /// Replace a node, or the root if the path is empty.
+    Replace {
+        /// Child indices of the node.
+        path: Vec<usize>,
+        /// New node.
+        node: Xml<'a>,
+    },
+}
+impl<'a> Edit<'a> {
+    /// Set an attribute of the element at a path.
+    pub fn set_attr(
+        path: Vec<usize>,
+        key: impl Into<Cow<'a, str>>,
+        value: impl Into<Cow<'a, str>>,
+    ) -> Self {
+        Edit::SetAttr {
+            path,
+            key: key.into(),
+            value: Some(value.into()),
+        }
+    }
+    /// Remove an attribute of the element at a path.
+    pub fn remove_attr(path: Vec<usize>, key: impl Into<Cow<'a, str>>) -> Self {
+        Edit::SetAttr {
+            path,
+            key: key.into(),
+            value: None,
+        }
+    }
+    /// Rename the element at a path.
+    pub fn rename(path: Vec<usize>, name: impl Into<Cow<'a, str>>) -> Self {
+        Edit::Rename {
+            path,
+            name: name.into(),
+        }
+    }
+    /// Insert a node at a path.
+    pub fn insert(path: Vec<usize>, node: Xml<'a>) -> Self {
+        Edit::Insert { path, node }
+    }
+    /// Remove the node at a path.
+    pub fn remove(path: Vec<usize>) -> Self {
+        Edit::Remove { path }
+    }
+    /// Replace the node at a path.
+    pub fn replace(path: Vec<usize>, node: Xml<'a>) -> Self {
+        Edit::Replace { path, node }
+    }
+
+    /// Get the child indices of the node the edit applies to.
+    pub fn path(&self) -> &[usize] {
+        match self {
+            Edit::SetAttr { path, .. }
+            | Edit::Rename { path, .. }
+            | Edit::Insert { path, .. }
+            | Edit::Remove { path }
+            | Edit::Replace { path, .. } => path,
+        }
+    }
+
+    /// Apply the edit to a tree, returning the edit that reverses it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the path does not lead to a suitable node, in which
+    /// case the tree is left unchanged.
+    pub fn apply(self, root: &mut Xml<'a>) -> Result<Edit<'a>, Error> {
+        let inverse = match self {
+            Edit::SetAttr { path, key, value } => {
+                let Some(Xml::Element(Element { attrs, .. })) = node_mut(root, &path) else {
+                    return Err(invalid(root, "expected an element", &path));
+                };
+                let old = match value {
+                    Some(value) => attrs.insert(key.clone(), value),
+                    None => attrs.remove(&key),
+                };
+                Edit::SetAttr {
+                    path,
+                    key,
+                    value: old,
+                }
+            }
+            Edit::Rename { path, name } => {
+                let Some(Xml::Element(Element { name: old, .. })) = node_mut(root, &path) else {
+                    return Err(invalid(root, "expected an element", &path));
+                };
+                let old = mem::replace(old, name);
+                Edit::Rename { path, name: old }
+            }
+            Edit::Insert { path, node } => {
+                let Some((children, i)) = siblings(root, &path).filter(|(c, i)| *i <= c.len())
+                else {
+                    return Err(invalid(root, "cannot insert a node here", &path));
+                };
+                children.insert(i, node);
+                Edit::Remove { path }
+            }
+            Edit::Remove { path } => {
+                let Some((children, i)) = siblings(root, &path).filter(|(c, i)| *i < c.len())
+                else {
+                    return Err(invalid(root, "expected a node", &path));
+                };
+                let node = children.remove(i);
+                Edit::Insert { path, node }
+            }
+            Edit::Replace { path, node } => {
+                let Some(old) = node_mut(root, &path) else {
+                    return Err(invalid(root, "expected a node", &path));
+                };
+                let old = mem::replace(old, node);
+                Edit::Replace { path, node: old }
+            }
+        };
+        Ok(inverse)
+    }
+}
+
+/// History of edits to a tree, supporting undo and redo.
+///
+/// Only the inverse of each edit is kept, so editors do not need to clone the whole tree
+/// for every step. Applying a new edit discards the edits that could be redone.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::{Edit, EditHistory, Xml};
+/// let mut xml = Xml::element("a");
+/// let mut history = EditHistory::new();
+/// history.apply(&mut xml, Edit::set_attr(vec![], "x", "1"))?;
+/// history.apply(&mut xml, Edit::insert(vec![0], Xml::element("b")))?;
+/// assert_eq!(xml.to_string(), r#"<a x="1"><b/></a>"#);
+///
+/// assert!(history.undo(&mut xml)?);
+/// assert!(history.undo(&mut xml)?);
+/// assert!(!history.undo(&mut xml)?);
+/// assert_eq!(xml.to_string(), "<a/>");
+///
+/// assert!(history.redo(&mut xml)?);
+/// assert_eq!(xml.to_string(), r#"<a x="1"/>"#);
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditHistory<'a> {
+    undo: Vec<Edit<'a>>,
+    redo: Vec<Edit<'a>>,
+}
+impl<'a> EditHistory<'a> {
+    /// Create an empty history.
+    pub fn new() -> Self {
+        EditHistory::default()
+    }
+
+    /// Apply an edit to a tree and record it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the edit does not apply, in which case neither the
+    /// tree nor the history is changed.
+    pub fn apply(&mut self, root: &mut Xml<'a>, edit: Edit<'a>) -> Result<(), Error> {
+        let inverse = edit.apply(root)?;
+        self.undo.push(inverse);
+        self.redo.clear();
+        Ok(())
+    }
+
+    /// Reverse the last applied edit, returning `false` if there is nothing to undo.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the tree was changed outside of the history so the
+    /// edit no longer applies, in which case the edit stays in the history.
+    pub fn undo(&mut self, root: &mut Xml<'a>) -> Result<bool, Error> {
+        step(root, &mut self.undo, &mut self.redo)
+    }
+    /// Reapply the last undone edit, returning `false` if there is nothing to redo.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the tree was changed outside of the history so the
+    /// edit no longer applies, in which case the edit stays in the history.
+    pub fn redo(&mut self, root: &mut Xml<'a>) -> Result<bool, Error> {
+        step(root, &mut self.redo, &mut self.undo)
+    }
+
+    /// Check whether there are edits to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    /// Check whether there are edits to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    /// Forget all edits.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// Edits applied within [`Xml::edit`], which are rolled back unless all of them succeed.
+#[derive(Debug)]
+pub struct Transaction<'x, 'a> {
+    root: &'x mut Xml<'a>,
+    undo: Vec<Edit<'a>>,
+}
+impl<'a> Transaction<'_, 'a> {
+    /// Apply an edit as part of the transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the edit does not apply. The edits applied so far
+    /// are only rolled back if the error is returned from the transaction.
+    pub fn apply(&mut self, edit: Edit<'a>) -> Result<(), Error> {
+        let inverse = edit.apply(self.root)?;
+        self.undo.push(inverse);
+        Ok(())
+    }
+    /// Get the tree, with the edits applied so far.
+    pub fn root(&self) -> &Xml<'a> {
+        self.root
+    }
+}
+
+impl<'a> Xml<'a> {
+    /// Apply several edits atomically, rolling all of them back if the closure fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the closure, after restoring the tree to its state before the
+    /// transaction.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{Edit, Xml};
+    /// let mut xml = xmlite::document("<a><b/></a>")?;
+    /// let result = xml.edit(|tx| {
+    ///     tx.apply(Edit::rename(vec![0], "c"))?;
+    ///     tx.apply(Edit::remove(vec![1]))
+    /// });
+    /// assert!(result.is_err());
+    /// assert_eq!(xml.to_string(), "<a><b/></a>");
+    ///
+    /// let count = xml.edit(|tx| {
+    ///     tx.apply(Edit::insert(vec![1], Xml::element("c")))?;
+    ///     Ok::<_, xmlite::Error>(tx.root().children().count())
+    /// })?;
+    /// assert_eq!((count, xml.to_string().as_str()), (2, "<a><b/><c/></a>"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn edit<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut Transaction<'_, 'a>) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut tx = Transaction {
+            root: self,
+            undo: vec![],
+        };
+        let result = f(&mut tx);
+        if result.is_err() {
+            for inverse in tx.undo.into_iter().rev() {
+                inverse
+                    .apply(tx.root)
+                    .expect("inverse edits apply in reverse order");
+            }
+        }
+        result
+    }
+}
+
+/// Apply the last edit of one stack, pushing its inverse onto the other.
+fn step<'a>(
+    root: &mut Xml<'a>,
+    from: &mut Vec<Edit<'a>>,
+    to: &mut Vec<Edit<'a>>,
+) -> Result<bool, Error> {
+    let Some(edit) = from.last() else {
+        return Ok(false);
+    };
+    let inverse = edit.clone().apply(root)?;
+    from.pop();
+    to.push(inverse);
+    Ok(true)
+}
+
+/// Get the node at a path.
+fn node_mut<'x, 'a>(root: &'x mut Xml<'a>, path: &[usize]) -> Option<&'x mut Xml<'a>> {
+    path.iter().try_fold(root, |node, &i| match node {
+        Xml::Element(Element { children, .. }) => children.get_mut(i),
+        _ => None,
+    })
+}
+
+/// Get the children of the parent of a path, along with the last index.
+fn siblings<'x, 'a>(
+    root: &'x mut Xml<'a>,
+    path: &[usize],
+) -> Option<(&'x mut Vec<Xml<'a>>, usize)> {
+    let (&i, parent) = path.split_last()?;
+    match node_mut(root, parent)? {
+        Xml::Element(Element { children, .. }) => Some((children, i)),
+        _ => None,
+    }
+}
+
+fn invalid(root: &Xml, message: &str, path: &[usize]) -> Error {
+    Error::Validation {
+        message: message.to_owned(),
+        path: render(root, path),
+    }
+}
+
+/// Describe a path, naming the nodes as far as they exist.
+pub(crate) fn render(root: &Xml, path: &[usize]) -> String {
+    let mut node = root;
+    let mut out = child_path("", slice::from_ref(root), 0);
+    for (depth, &i) in path.iter().enumerate() {
+        let children = node.children().as_slice();
+        if i >= children.len() {
+            for &i in &path[depth..] {
+                out.push_str(&format!("/node()[{}]", i + 1));
+            }
+            break;
+        }
+        out = child_path(&out, children, i);
+        node = &children[i];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverses() {
+        let text = "<a x='1'><b/>text</a>";
+        let original = crate::document(text).unwrap();
+        let mut xml = original.clone();
+        let edits = [
+            Edit::set_attr(vec![], "x", "2"),
+            Edit::remove_attr(vec![], "y"),
+            Edit::rename(vec![0], "c"),
+            Edit::replace(vec![1], Xml::text("other")),
+            Edit::remove(vec![0]),
+            Edit::insert(vec![1], Xml::element("d")),
+            Edit::replace(vec![], Xml::element("e")),
+        ];
+        let mut inverses = vec![];
+        for edit in edits {
+            inverses.push(edit.apply(&mut xml).unwrap());
+        }
+        assert_eq!(xml, Xml::element("e"));
+        for inverse in inverses.into_iter().rev() {
+            inverse.apply(&mut xml).unwrap();
+        }
+        assert_eq!(xml, original);
+
+        let Err(Error::Validation { path, .. }) = Edit::rename(vec![1], "x").apply(&mut xml) else {
+            panic!("renamed a text node");
+        };
+        assert_eq!(path, "/a/text()");
+        assert!(
+            Edit::insert(vec![], Xml::element("f"))
+                .apply(&mut xml)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Element nodes.
+
+use crate::Xml;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+/// XML element, the content of an [`Xml::Element`] node.
+///
+/// Elements can be destructured and passed around on their own, and turned back into nodes
+/// with [`Xml::from`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::{Element, Xml};
+/// let mut xml = xmlite::document(r#"<a x="1"><b/>text</a>"#)?;
+/// let Element { name, children, .. } = xml.as_element_mut().unwrap();
+/// *name = "c".into();
+/// children.pop();
+/// assert_eq!(xml.to_string(), r#"<c x="1"><b/></c>"#);
+///
+/// let element = Element::new("d").with_child(Xml::text("hi"));
+/// assert_eq!(Xml::from(element).to_string(), "<d>hi</d>");
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Element<'a> {
+    /// Element name.
+    pub name: Cow<'a, str>,
+    /// Element attributes.
+    pub attrs: HashMap<Cow<'a, str>, Cow<'a, str>>,
+    /// Element children.
+    pub children: Vec<Xml<'a>>,
+}
+impl<'a> Element<'a> {
+    /// Create an element without attributes and children.
+    pub fn new(name: impl Into<Cow<'a, str>>) -> Self {
+        Element {
+            name: name.into(),
+            ..Element::default()
+        }
+    }
+
+    /// Add an attribute, returning the element.
+    pub fn with_attr(
+        mut self,
+        key: impl Into<Cow<'a, str>>,
+        value: impl Into<Cow<'a, str>>,
+    ) -> Self {
+        self.attrs.insert(key.into(), value.into());
+        self
+    }
+    /// Add a child, returning the element.
+    pub fn with_child(mut self, child: impl Into<Xml<'a>>) -> Self {
+        self.children.push(child.into());
+        self
+    }
+
+    /// Get an attribute value, as stored. Parsed values keep their quotes.
+    pub fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs.get(key).map(|value| value.as_ref())
+    }
+    /// Iterate over the child elements, skipping text.
+    pub fn elements(&self) -> impl Iterator<Item = &Element<'a>> {
+        self.children.iter().filter_map(Xml::as_element)
+    }
+
+    /// Copy all borrowed strings, as with [`Xml::into_owned`].
+    pub fn into_owned(self) -> Element<'static> {
+        let owned = |s: Cow<str>| Cow::Owned(s.into_owned());
+        Element {
+            name: owned(self.name),
+            attrs: (self.attrs.into_iter())
+                .map(|(k, v)| (owned(k), owned(v)))
+                .collect(),
+            children: self.children.into_iter().map(Xml::into_owned).collect(),
+        }
+    }
+}
+/// Element which owns all of its strings, see [`XmlOwned`](crate::XmlOwned).
+pub type ElementOwned = Element<'static>;
+
+impl Hash for Element<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        // hash attributes in a fixed order, since map iteration order is arbitrary
+        let mut attrs = self.attrs.iter().collect::<Vec<_>>();
+        attrs.sort_unstable();
+        attrs.hash(state);
+        self.children.hash(state);
+    }
+}
+impl<'a> From<Element<'a>> for Xml<'a> {
+    fn from(element: Element<'a>) -> Self {
+        Xml::Element(element)
+    }
+}
+
+impl<'a> Xml<'a> {
+    /// Get the element of an element node.
+    pub fn as_element(&self) -> Option<&Element<'a>> {
+        match self {
+            Xml::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+    /// Get the element of an element node, mutably.
+    pub fn as_element_mut(&mut self) -> Option<&mut Element<'a>> {
+        match self {
+            Xml::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+    /// Convert an element node into its element.
+    pub fn into_element(self) -> Option<Element<'a>> {
+        match self {
+            Xml::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_ignores_attr_order() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |element: &Element| {
+            let mut hasher = DefaultHasher::new();
+            element.hash(&mut hasher);
+            hasher.finish()
+        };
+        let a = Element::new("a").with_attr("x", "1").with_attr("y", "2");
+        let b = Element::new("a").with_attr("y", "2").with_attr("x", "1");
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(a.elements().count(), 0);
+    }
+}
@@ -0,0 +1,186 @@
+//! Encoding detection and declaration checks.
+
+use crate::{Error, Tag, tag::unquote};
+use std::io;
+
+/// Character encoding of written documents, see
+/// [`WriteOptions::with_encoding`](crate::WriteOptions::with_encoding).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// UTF-8.
+    Utf8,
+    /// UTF-16, little endian, with a byte order mark.
+    Utf16Le,
+    /// UTF-16, big endian, with a byte order mark.
+    Utf16Be,
+    /// ISO-8859-1, also known as Latin-1.
+    Latin1,
+    /// US-ASCII.
+    Ascii,
+}
+impl Encoding {
+    /// Look up an encoding by the name used in XML declarations, ignoring case.
+    ///
+    /// Common aliases such as `latin1` and `ascii` are recognized too. `UTF-16` is taken to be
+    /// little endian, as is common on Windows.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Encoding;
+    /// assert_eq!(Encoding::from_name("iso-8859-1"), Some(Encoding::Latin1));
+    /// assert_eq!(Encoding::from_name("UTF-16"), Some(Encoding::Utf16Le));
+    /// assert_eq!(Encoding::from_name("EBCDIC"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "UTF-8" | "UTF8" => Some(Encoding::Utf8),
+            "UTF-16" | "UTF-16LE" => Some(Encoding::Utf16Le),
+            "UTF-16BE" => Some(Encoding::Utf16Be),
+            "ISO-8859-1" | "ISO_8859-1" | "ISO8859-1" | "LATIN1" | "LATIN-1" | "ISO-LATIN-1"
+            | "L1" | "CP819" | "IBM819" => Some(Encoding::Latin1),
+            "US-ASCII" | "ASCII" | "ISO646-US" | "ANSI_X3.4-1968" | "CP367" | "IBM367" => {
+                Some(Encoding::Ascii)
+            }
+            _ => None,
+        }
+    }
+    /// Get the name of the encoding for XML declarations.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le | Encoding::Utf16Be => "UTF-16",
+            Encoding::Latin1 => "ISO-8859-1",
+            Encoding::Ascii => "US-ASCII",
+        }
+    }
+
+    /// Get the highest character which can be encoded, or `None` if all can.
+    pub(crate) fn max_char(self) -> Option<char> {
+        match self {
+            Encoding::Latin1 => Some('\u{FF}'),
+            Encoding::Ascii => Some('\u{7F}'),
+            _ => None,
+        }
+    }
+    /// Encode text to a writer.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error for characters which cannot be encoded.
+    pub(crate) fn encode(self, text: &str, mut writer: impl io::Write) -> io::Result<()> {
+        // UTF-16 starts with a byte order mark
+        let units = |f: fn(u16) -> [u8; 2]| {
+            let units = [0xFEFF].into_iter().chain(text.encode_utf16());
+            units.flat_map(f).collect::<Vec<_>>()
+        };
+        let bytes = match self {
+            Encoding::Utf8 => return writer.write_all(text.as_bytes()),
+            Encoding::Utf16Le => units(u16::to_le_bytes),
+            Encoding::Utf16Be => units(u16::to_be_bytes),
+            Encoding::Latin1 | Encoding::Ascii => {
+                let max = self.max_char().unwrap_or(char::MAX);
+                let bytes = text.chars().map(|c| match c <= max {
+                    true => Ok(c as u8),
+                    false => Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("character {c:?} cannot be encoded in {}", self.name()),
+                    )),
+                });
+                bytes.collect::<io::Result<Vec<_>>>()?
+            }
+        };
+        writer.write_all(&bytes)
+    }
+}
+
+/// Detect the encoding of a byte stream from its byte order mark or leading bytes.
+///
+/// See appendix F of the XML specification.
+pub(crate) fn detect(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => "UTF-8",
+        [0xFE, 0xFF, ..] | [0x00, b'<', 0x00, b'?', ..] => "UTF-16BE",
+        [0xFF, 0xFE, ..] | [b'<', 0x00, b'?', 0x00, ..] => "UTF-16LE",
+        _ => "UTF-8",
+    }
+}
+
+/// Read the encoding named in the XML declaration at the start of a byte stream.
+///
+/// Declarations are ASCII, so zero bytes are skipped to read UTF-16 declarations as well.
+pub(crate) fn declared(bytes: &[u8]) -> Option<String> {
+    let head = bytes.iter().take(512).filter(|&&b| b != 0 && b.is_ascii());
+    let head = String::from_utf8(head.copied().collect()).ok()?;
+    let end = head.find("?>")? + 2;
+    match crate::tags(&head[..end]).next()? {
+        Tag::Declaration { name: "xml", attrs } => Some(unquote(attrs.get("encoding")?).to_owned()),
+        _ => None,
+    }
+}
+
+/// Check that the declared encoding is consistent with UTF-8 encoded bytes.
+///
+/// Encodings are named as for [`Encoding::from_name`], and the supported ASCII-compatible
+/// single-byte encodings are accepted as long as the bytes are pure ASCII.
+pub(crate) fn check(declared: &str, bytes: &[u8]) -> Result<(), Error> {
+    // ASCII-compatible encodings agree with UTF-8 as long as the bytes are ASCII
+    let consistent = match Encoding::from_name(declared) {
+        Some(Encoding::Utf8) => true,
+        Some(Encoding::Latin1 | Encoding::Ascii) => bytes.is_ascii(),
+        _ => false,
+    };
+    if consistent {
+        Ok(())
+    } else {
+        Err(Error::Encoding {
+            declared: declared.to_owned(),
+            detected: "UTF-8".to_owned(),
+            span: (1, 1),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_bom() {
+        assert_eq!(detect(b"\xEF\xBB\xBF<a/>"), "UTF-8");
+        assert_eq!(detect(b"\xFF\xFE<\x00"), "UTF-16LE");
+        assert_eq!(detect(b"\x00<\x00?"), "UTF-16BE");
+        assert_eq!(detect(b"<a/>"), "UTF-8");
+    }
+
+    #[test]
+    fn encode() {
+        let mut output = vec![];
+        Encoding::Utf16Be.encode("<é/>", &mut output).unwrap();
+        assert_eq!(output, b"\xFE\xFF\x00<\x00\xE9\x00/\x00>");
+        output.clear();
+        Encoding::Latin1.encode("<é/>", &mut output).unwrap();
+        assert_eq!(output, b"<\xE9/>");
+        assert!(Encoding::Ascii.encode("é", &mut output).is_err());
+    }
+
+    #[test]
+    fn declared() {
+        assert!(check("utf-8", "<a>æ</a>".as_bytes()).is_ok());
+        assert!(check("ISO-8859-1", b"<a>a</a>").is_ok());
+        assert!(check("ISO-8859-1", "<a>æ</a>".as_bytes()).is_err());
+        for name in [
+            "utf8",
+            "latin1",
+            "ISO_8859-1",
+            "cp819",
+            "ascii",
+            "ISO646-US",
+        ] {
+            assert!(Encoding::from_name(name).is_some());
+            assert!(check(name, b"<a/>").is_ok());
+        }
+        assert!(check("UTF-16", b"<a/>").is_err());
+        let utf16 = b"\xFF\xFE<\0?\0x\0m\0l\0 \0e\0n\0c\0o\0d\0i\0n\0g\0=\0'\0U\0T\0F\0-\x001\x006\0'\0?\0>\0";
+        assert_eq!(super::declared(utf16).as_deref(), Some("UTF-16"));
+        assert_eq!(super::declared(b"<a/>"), None);
+    }
+}
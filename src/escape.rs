@@ -0,0 +1,129 @@
+//! Escaping and unescaping of text and attribute values.
+
+use crate::tag::parsed_quote;
+use std::borrow::Cow;
+
+/// Get the text of an attribute value.
+///
+/// Parsed values have their quotes stripped and their references replaced, while values
+/// without quotes, as set with [`Xml::with_attr_value`](crate::Xml::with_attr_value), are
+/// text already and are escaped when written.
+pub(crate) fn attr_text(value: &str) -> Cow<'_, str> {
+    match parsed_quote(value) {
+        Some(_) => unescape(&value[1..value.len() - 1]),
+        None => Cow::Borrowed(value),
+    }
+}
+/// Get the escaped form of an attribute value as it is written, without quotes.
+pub(crate) fn attr_escaped(value: &str) -> Cow<'_, str> {
+    match parsed_quote(value) {
+        Some(_) => Cow::Borrowed(&value[1..value.len() - 1]),
+        None => escape(value),
+    }
+}
+
+/// Get the text of a text node, with a CDATA section unwrapped and references replaced
+/// otherwise.
+pub(crate) fn unescape_text(text: &str) -> Cow<'_, str> {
+    match text.strip_prefix("<![CDATA[") {
+        Some(cdata) => Cow::Borrowed(cdata.strip_suffix("]]>").unwrap_or(cdata)),
+        None => unescape(text),
+    }
+}
+
+/// Escape markup characters in text or an attribute value.
+pub(crate) fn escape(text: &str) -> Cow<'_, str> {
+    let Some(start) = find_markup(text) else {
+        return Cow::Borrowed(text);
+    };
+    let mut escaped = String::with_capacity(text.len() + 8);
+    escaped.push_str(&text[..start]);
+    for c in text[start..].chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Find the byte offset of the first character which must be escaped.
+#[cfg(feature = "simd")]
+fn find_markup(text: &str) -> Option<usize> {
+    crate::bulk::find_any(text.as_bytes(), b"<>&\"'")
+}
+/// Find the byte offset of the first character which must be escaped.
+#[cfg(not(feature = "simd"))]
+fn find_markup(text: &str) -> Option<usize> {
+    text.find(['<', '>', '&', '"', '\''])
+}
+
+/// Replace the predefined entities and character references in text or an attribute value.
+///
+/// Unknown or malformed references are kept as they are.
+pub(crate) fn unescape(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        unescaped.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let c = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            r => r
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| r.strip_prefix('#').map(|dec| dec.parse()))
+                .and_then(|n| n.ok())
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    Cow::Owned(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        let text = r#"a < b && "c" > 'd'"#;
+        assert_eq!(
+            escape(text),
+            "a &lt; b &amp;&amp; &quot;c&quot; &gt; &apos;d&apos;"
+        );
+        assert!(matches!(escape("plain"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn unescaping() {
+        let text = "a &lt; b &amp;&amp; &quot;c&quot; &gt; &apos;d&apos;";
+        assert_eq!(unescape(text), r#"a < b && "c" > 'd'"#);
+        assert_eq!(unescape("&#65;&#x42;&amp;c"), "AB&c");
+        assert_eq!(unescape("&nbsp; & &#xZZ; &"), "&nbsp; & &#xZZ; &");
+    }
+}
@@ -0,0 +1,244 @@
+//! RSS 2.0 and Atom feeds.
+
+use crate::{
+    Element, Error, Xml,
+    escape::{attr_text, unescape_text},
+    validate::{local, unquote},
+};
+
+/// Syndication feed, read from either RSS 2.0 or Atom.
+///
+/// Dates are kept as they are written in the feed, which is RFC 822 for RSS (e.g.
+/// `Sat, 07 Sep 2002 00:00:01 GMT`) and RFC 3339 for Atom (e.g. `2003-12-13T18:30:02Z`).
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::Feed;
+/// let rss = r#"<rss version="2.0"><channel>
+///     <title>News</title>
+///     <link>https://example.com/</link>
+///     <item><title>First</title><link>https://example.com/1</link><pubDate>Sat, 07 Sep 2002 00:00:01 GMT</pubDate></item>
+/// </channel></rss>"#;
+/// let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+///     <title>News</title>
+///     <link rel="self" href="https://example.com/feed"/><link href="https://example.com/"/>
+///     <entry><title>First</title><link href="https://example.com/1"/><published>2002-09-07T00:00:01Z</published></entry>
+/// </feed>"#;
+/// for text in [rss, atom] {
+///     let feed = Feed::parse(text)?;
+///     assert_eq!(feed.title, "News");
+///     assert_eq!(feed.link.as_deref(), Some("https://example.com/"));
+///     assert_eq!(feed.entries[0].link.as_deref(), Some("https://example.com/1"));
+///     assert!(feed.entries[0].published.is_some());
+/// }
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Feed {
+    /// Title of the feed.
+    pub title: String,
+    /// Link to the website of the feed.
+    pub link: Option<String>,
+    /// Description of the feed.
+    pub description: Option<String>,
+    /// When the feed was last updated.
+    pub updated: Option<String>,
+    /// Entries of the feed, in document order.
+    pub entries: Vec<Entry>,
+}
+
+/// Entry of a [`Feed`], i.e. an RSS item or an Atom entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Entry {
+    /// Unique identifier of the entry, i.e. the RSS `guid` or Atom `id`.
+    pub id: Option<String>,
+    /// Title of the entry.
+    pub title: Option<String>,
+    /// Link to the content of the entry.
+    pub link: Option<String>,
+    /// Summary or description of the entry.
+    pub summary: Option<String>,
+    /// When the entry was published.
+    pub published: Option<String>,
+    /// When the entry was last updated.
+    pub updated: Option<String>,
+}
+
+impl Feed {
+    /// Parse an RSS 2.0 or Atom feed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document is malformed, or [`Error::Validation`] if its root
+    /// element is neither `rss` nor `feed`.
+    pub fn parse(text: &str) -> Result<Feed, Error> {
+        Feed::from_xml(&crate::document(text)?)
+    }
+
+    /// Read a feed from a parsed document.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the root element is neither `rss` nor `feed`.
+    pub fn from_xml(xml: &Xml) -> Result<Feed, Error> {
+        match xml.name().map(local) {
+            Some("rss") => Ok(rss(xml)),
+            Some("feed") => Ok(atom(xml)),
+            _ => Err(Error::Validation {
+                message: "expected an rss or feed root element".to_owned(),
+                path: "/".to_owned(),
+            }),
+        }
+    }
+}
+
+/// Namespaces of RSS elements, for feeds which prefix them.
+const RSS: [&str; 2] = [
+    "http://backend.userland.com/rss2",
+    "http://purl.org/rss/1.0/",
+];
+
+fn rss(xml: &Xml) -> Feed {
+    // elements from extensions share local names with RSS elements, e.g. `atom:link`, so only
+    // unprefixed elements and those with a prefix bound to an RSS namespace are read
+    let mut prefixes = vec![""];
+    rss_prefixes(xml, &mut prefixes);
+    let channel = rss_child(xml, "channel", &prefixes).unwrap_or(xml);
+    rss_prefixes(channel, &mut prefixes);
+    let children = |xml, name| rss_children(xml, name, &prefixes);
+    let text = |xml, name| content(rss_child(xml, name, &prefixes)?);
+    Feed {
+        title: text(channel, "title").unwrap_or_default(),
+        link: text(channel, "link"),
+        description: text(channel, "description"),
+        updated: text(channel, "lastBuildDate").or_else(|| text(channel, "pubDate")),
+        entries: children(channel, "item")
+            .map(|item| Entry {
+                id: text(item, "guid"),
+                title: text(item, "title"),
+                link: text(item, "link"),
+                summary: text(item, "description"),
+                published: text(item, "pubDate"),
+                updated: None,
+            })
+            .collect(),
+    }
+}
+
+fn atom(xml: &Xml) -> Feed {
+    Feed {
+        title: text(xml, "title").unwrap_or_default(),
+        link: link(xml),
+        description: text(xml, "subtitle"),
+        updated: text(xml, "updated"),
+        entries: children(xml, "entry")
+            .map(|entry| Entry {
+                id: text(entry, "id"),
+                title: text(entry, "title"),
+                link: link(entry),
+                summary: text(entry, "summary").or_else(|| text(entry, "content")),
+                published: text(entry, "published"),
+                updated: text(entry, "updated"),
+            })
+            .collect(),
+    }
+}
+
+/// Add the prefixes an element binds to RSS namespaces.
+fn rss_prefixes<'x>(xml: &'x Xml, prefixes: &mut Vec<&'x str>) {
+    let Xml::Element(Element { attrs, .. }) = xml else {
+        return;
+    };
+    for (key, value) in attrs {
+        if let Some(prefix) = key.strip_prefix("xmlns:")
+            && RSS.contains(&unquote(value))
+        {
+            prefixes.push(prefix);
+        }
+    }
+}
+
+/// Iterate over the child elements with a local name and one of the prefixes.
+fn rss_children<'x, 'a, 'p>(
+    xml: &'x Xml<'a>,
+    name: &'p str,
+    prefixes: &'p [&str],
+) -> impl Iterator<Item = &'x Xml<'a>> + 'p
+where
+    'x: 'p,
+{
+    xml.children().filter(move |child| {
+        let qname = child.name().unwrap_or_default();
+        let (prefix, local) = qname.split_once(':').unwrap_or(("", qname));
+        child.is_element() && local == name && prefixes.contains(&prefix)
+    })
+}
+
+fn rss_child<'x, 'a>(xml: &'x Xml<'a>, name: &str, prefixes: &[&str]) -> Option<&'x Xml<'a>> {
+    rss_children(xml, name, prefixes).next()
+}
+
+/// Iterate over the child elements with a local name.
+fn children<'x, 'a>(xml: &'x Xml<'a>, name: &str) -> impl Iterator<Item = &'x Xml<'a>> {
+    xml.children()
+        .filter(move |child| child.name().map(local) == Some(name))
+}
+
+fn child<'x, 'a>(xml: &'x Xml<'a>, name: &str) -> Option<&'x Xml<'a>> {
+    children(xml, name).next()
+}
+
+/// Get the trimmed text of the first child element with a local name, if it is not empty.
+fn text(xml: &Xml, name: &str) -> Option<String> {
+    content(child(xml, name)?)
+}
+
+/// Get the trimmed text of an element, if it is not empty.
+fn content(xml: &Xml) -> Option<String> {
+    let text = xml
+        .children()
+        .filter_map(Xml::content)
+        .map(unescape_text)
+        .collect::<String>();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_owned())
+}
+
+/// Get the Atom link to an alternate version of an element.
+fn link(xml: &Xml) -> Option<String> {
+    let href = children(xml, "link")
+        .find(|link| {
+            link.attr("rel")
+                .is_none_or(|rel| unquote(rel) == "alternate")
+        })?
+        .attr("href")?;
+    Some(attr_text(href).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaped_content() {
+        let text = "<rss><channel><title>A &amp; B</title><item><description><![CDATA[<p>hi</p>]]></description></item></channel></rss>";
+        let feed = Feed::parse(text).unwrap();
+        assert_eq!(feed.title, "A & B");
+        assert_eq!(feed.entries[0].summary.as_deref(), Some("<p>hi</p>"));
+        assert_eq!(feed.entries[0].title, None);
+        assert!(Feed::parse("<html/>").is_err());
+    }
+
+    #[test]
+    fn extensions() {
+        let text = r#"<rss xmlns:atom="http://www.w3.org/2005/Atom" xmlns:r="http://backend.userland.com/rss2"><r:channel>
+            <atom:link href="https://example.com/feed" rel="self"/>
+            <link>https://example.com/</link>
+            <atom:title>Other</atom:title><r:title>News</r:title>
+        </r:channel></rss>"#;
+        let feed = Feed::parse(text).unwrap();
+        assert_eq!(feed.link.as_deref(), Some("https://example.com/"));
+        assert_eq!(feed.title, "News");
+    }
+}
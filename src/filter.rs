@@ -0,0 +1,239 @@
+//! Adaptors for filtering tag streams.
+
+use crate::{Select, StreamPath, Tag, TagKind};
+use std::collections::BTreeMap;
+
+/// Adaptors for iterators over tags, such as [`Tags`](crate::Tags).
+///
+/// The adaptors track element nesting, so they compose like regular iterator adaptors to
+/// extract parts of a document without building a tree.
+///
+/// # Examples
+///
+/// ```rust
+/// use xmlite::{Tag, TagsExt};
+/// let text = "<rss><channel><title>News</title><item><title>A</title></item></channel></rss>";
+/// let titles = xmlite::tags(text)
+///     .within("item")
+///     .within("title")
+///     .filter_map(|tag| if let Tag::Text(text) = tag { Some(text) } else { None })
+///     .collect::<Vec<_>>();
+/// assert_eq!(titles, ["A"]);
+/// ```
+pub trait TagsExt<'a>: Iterator<Item = Tag<'a>> + Sized {
+    /// Keep only the elements with a given name, including their start and end tags and
+    /// everything in between.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use xmlite::TagsExt;
+    /// let text = "<list><item>1</item><other/><item/></list>";
+    /// let items = xmlite::tags(text).elements_named("item").map(|tag| tag.to_string());
+    /// assert_eq!(items.collect::<String>(), "<item>1</item><item/>");
+    /// ```
+    fn elements_named(self, name: &str) -> Within<'_, Self> {
+        Within::new(self, name, true)
+    }
+
+    /// Keep only the content of elements with a given name, excluding their start and end tags.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use xmlite::TagsExt;
+    /// let text = "<a><b>x<c/></b>y<b>z</b></a>";
+    /// let content = xmlite::tags(text).within("b").map(|tag| tag.to_string());
+    /// assert_eq!(content.collect::<String>(), "x<c/>z");
+    /// ```
+    fn within(self, name: &str) -> Within<'_, Self> {
+        Within::new(self, name, false)
+    }
+
+    /// Select from the tags with a path expression, reporting matches as they stream past.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use xmlite::{PathEvent, StreamPath, TagsExt};
+    /// let text = "<feed><entry id='1'><link/></entry><entry id='2'/></feed>";
+    /// let path = StreamPath::new("//entry/@id")?;
+    /// let ids = xmlite::tags(text).select(&path).filter_map(|event| match event {
+    ///     PathEvent::Attr(value) => Some(value),
+    ///     _ => None,
+    /// });
+    /// assert_eq!(ids.collect::<Vec<_>>(), ["'1'", "'2'"]);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    fn select(self, path: &StreamPath) -> Select<'a, '_, Self> {
+        Select::new(self, path)
+    }
+
+    /// Count the elements with a given name, consuming the tags.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use xmlite::TagsExt;
+    /// let text = "<items><item>1</item><item/><other><item/></other></items>";
+    /// assert_eq!(xmlite::tags(text).count_elements("item"), 3);
+    /// ```
+    fn count_elements(self, name: &str) -> usize {
+        self.filter(|tag| !tag.is_closing() && tag.name() == Some(name))
+            .count()
+    }
+
+    /// Count the elements with each name, consuming the tags.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use xmlite::TagsExt;
+    /// let names = xmlite::tags("<a><b/><c><b/></c></a>").element_names();
+    /// assert_eq!(names.into_iter().collect::<Vec<_>>(), [("a", 1), ("b", 2), ("c", 1)]);
+    /// ```
+    fn element_names(self) -> BTreeMap<&'a str, usize> {
+        let mut names = BTreeMap::new();
+        for tag in self {
+            if let Tag::Tag { name, kind, .. } = tag
+                && !kind.is_closing()
+            {
+                *names.entry(name).or_default() += 1;
+            }
+        }
+        names
+    }
+
+    /// Get the deepest nesting of elements, consuming the tags. A lone root element has
+    /// depth 1.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use xmlite::TagsExt;
+    /// assert_eq!(xmlite::tags("<a><b><c/></b><b/></a>").max_depth(), 3);
+    /// ```
+    fn max_depth(self) -> usize {
+        let (mut depth, mut max) = (0usize, 0);
+        for tag in self {
+            match tag {
+                Tag::Tag {
+                    kind: TagKind::Opening,
+                    ..
+                } => depth += 1,
+                Tag::Tag {
+                    kind: TagKind::Closing,
+                    ..
+                } => depth = depth.saturating_sub(1),
+                Tag::Tag { .. } => max = max.max(depth + 1),
+                _ => {}
+            }
+            max = max.max(depth);
+        }
+        max
+    }
+
+    /// Skip comments, e.g. from [`Tags::with_comments`](crate::Tags::with_comments).
+    fn skip_comments(self) -> SkipComments<Self> {
+        SkipComments { iter: self }
+    }
+}
+impl<'a, I: Iterator<Item = Tag<'a>>> TagsExt<'a> for I {}
+
+/// Iterator returned by [`TagsExt::within`] and [`TagsExt::elements_named`].
+#[derive(Debug, Clone)]
+pub struct Within<'n, I> {
+    iter: I,
+    name: &'n str,
+    inclusive: bool,
+    // nesting depth inside the current matching element
+    depth: usize,
+}
+impl<'n, I> Within<'n, I> {
+    fn new(iter: I, name: &'n str, inclusive: bool) -> Self {
+        Within {
+            iter,
+            name,
+            inclusive,
+            depth: 0,
+        }
+    }
+}
+impl<'a, I: Iterator<Item = Tag<'a>>> Iterator for Within<'_, I> {
+    type Item = Tag<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let tag = self.iter.next()?;
+            let kind = match tag {
+                Tag::Tag { kind, .. } => Some(kind),
+                _ => None,
+            };
+            if self.depth == 0 {
+                if tag.name() != Some(self.name) {
+                    continue;
+                }
+                if kind == Some(TagKind::Opening) {
+                    self.depth = 1;
+                }
+                if self.inclusive && kind != Some(TagKind::Closing) {
+                    return Some(tag);
+                }
+                continue;
+            }
+            match kind {
+                Some(TagKind::Opening) => self.depth += 1,
+                Some(TagKind::Closing) => self.depth -= 1,
+                _ => {}
+            }
+            if self.depth > 0 || self.inclusive {
+                return Some(tag);
+            }
+        }
+    }
+}
+
+/// Iterator returned by [`TagsExt::skip_comments`].
+#[derive(Debug, Clone)]
+pub struct SkipComments<I> {
+    iter: I,
+}
+impl<'a, I: Iterator<Item = Tag<'a>>> Iterator for SkipComments<I> {
+    type Item = Tag<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.find(|tag| !matches!(tag, Tag::Comment(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested() {
+        let text = "<a><b><b>1</b></b><c><b>2</b></c></a>";
+        let tags = crate::tags(text).elements_named("b").collect::<Vec<_>>();
+        assert_eq!(tags.len(), 8);
+        let inner = crate::tags(text)
+            .within("c")
+            .within("b")
+            .map(|tag| tag.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(inner, ["2"]);
+    }
+
+    #[test]
+    fn folds() {
+        let text = "<a><b><b/>x</b></b><c/></a>";
+        assert_eq!(crate::tags(text).count_elements("b"), 2);
+        assert_eq!(crate::tags(text).element_names()["c"], 1);
+        assert_eq!(crate::tags(text).max_depth(), 3);
+    }
+
+    #[test]
+    fn comments() {
+        let tags = crate::Tags::new("<a><!--x--></a>").with_comments(true);
+        assert_eq!(tags.skip_comments().count(), 2);
+    }
+}
@@ -0,0 +1,387 @@
+//! Reformatting of documents without building a tree.
+
+use crate::{
+    Attrs, Error, Tag, TagKind, Tags, WriteOptions,
+    validate::unquote,
+    write::{DECLARATION, quoted, write_line, write_start, write_text},
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::{self, Write},
+};
+
+/// Reformat a document with consistent indentation, like `xmllint --format`.
+///
+/// The document is re-emitted tag by tag, so comments, processing instructions and the order
+/// of attributes are preserved. Elements containing only other elements, comments and
+/// processing instructions are indented according to the [`WriteOptions`], while elements
+/// with text content or within `xml:space="preserve"` are written as they are, since their
+/// whitespace is significant. Empty elements are written as `<a/>` unless configured
+/// otherwise.
+///
+/// # Errors
+///
+/// Returns the first syntax error in the document, or [`Error::Mismatched`] if its tags are
+/// not properly nested.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::WriteOptions;
+/// let text = "<?xml version=\"1.0\"?><a z='1' y='2'><!-- items --><b>some <i>text</i></b><c></c></a>";
+/// let formatted = xmlite::format(text, &WriteOptions::new().with_indent("  "))?;
+/// assert_eq!(formatted, "<?xml version=\"1.0\"?>
+/// <a z='1' y='2'>
+///   <!-- items -->
+///   <b>some <i>text</i></b>
+///   <c/>
+/// </a>");
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+pub fn format(text: &str, options: &WriteOptions) -> Result<String, Error> {
+    let all = checked(Tags::new(text).with_comments(true))?;
+    let elements = elements(&all)?;
+
+    let mut output = String::with_capacity(text.len());
+    write_tags(&mut output, &all, &elements, options).expect("writing to a string cannot fail");
+    if text.ends_with('\n') {
+        output.push_str(&options.newline);
+    }
+    Ok(output)
+}
+
+/// Write a checked tag list with the layout of its elements.
+fn write_tags(
+    out: &mut String,
+    tags: &[Spanned],
+    elements: &[Element],
+    options: &WriteOptions,
+) -> fmt::Result {
+    if options.declaration == Some(true)
+        && !matches!(
+            tags.first(),
+            Some((Tag::Declaration { name: "xml", .. }, _))
+        )
+    {
+        out.push_str(DECLARATION);
+    }
+
+    // for each open element: whether it is written inline, and whether whitespace is preserved
+    let mut stack: Vec<(bool, bool)> = vec![];
+    let mut root = false;
+    let mut i = 0;
+    while i < tags.len() {
+        let tag = &tags[i].0;
+        let depth = stack.len();
+        let (inline, preserve) = stack.last().copied().unwrap_or_default();
+        i += 1;
+
+        // skip whitespace between indented tags, and omitted prolog items
+        let prolog = !root && depth == 0;
+        let skip = match tag {
+            Tag::Text(text) => !inline && text.trim().is_empty(),
+            Tag::Declaration { name: "xml", .. } => options.declaration == Some(false),
+            Tag::Declaration { .. } | Tag::Comment(_) | Tag::Doctype(_) => {
+                prolog && !options.prolog
+            }
+            _ => false,
+        };
+        if skip {
+            continue;
+        }
+        if !inline && !tag.is_closing() && !out.is_empty() {
+            write_line(out, options, depth)?;
+        }
+
+        let Tag::Tag { name, attrs, kind } = tag else {
+            match tag {
+                Tag::Text(text) => write_text(out, text, options)?,
+                tag => write!(out, "{tag}")?,
+            }
+            continue;
+        };
+        if kind.is_closing() {
+            if !stack.pop().is_none_or(|(inline, _)| inline) {
+                write_line(out, options, depth - 1)?;
+            }
+            write!(out, "</{name}>")?;
+            continue;
+        }
+
+        root = true;
+        let preserve = match attrs.get("xml:space").map(unquote) {
+            Some("preserve") => true,
+            Some("default") => false,
+            _ => preserve,
+        };
+        let attrs = attrs.iter().map(|(key, value)| (key, quoted_parsed(value)));
+        write_start(out, name, attrs.collect(), options, depth)?;
+        let element = elements[i - 1];
+        let empty = element.empty && (!preserve || element.end == i);
+        if kind.is_opening() && !empty {
+            out.push('>');
+            stack.push((inline || element.mixed || preserve, preserve));
+            continue;
+        }
+        if kind.is_opening() {
+            i = element.end + 1;
+        }
+        if options.expands(name) {
+            write!(out, "></{name}>")?;
+        } else {
+            out.push_str(options.empty_end());
+        }
+    }
+    Ok(())
+}
+
+/// Minify a document for size-sensitive delivery.
+///
+/// Comments, whitespace between elements and XML declarations which only state the defaults
+/// are removed, as are namespace declarations repeating a binding already in scope. Empty
+/// elements are written as `<a/>`. Text in mixed content, CDATA sections and everything
+/// within `xml:space="preserve"` are kept as they are.
+///
+/// # Errors
+///
+/// Returns the first syntax error in the document, or [`Error::Mismatched`] if its tags are
+/// not properly nested.
+///
+/// # Examples
+///
+/// ```rust
+/// let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+/// <svg xmlns="http://www.w3.org/2000/svg">
+///   <!-- icon -->
+///   <g xmlns="http://www.w3.org/2000/svg">
+///     <path d="M0 0"></path>
+///   </g>
+///   <text xml:space="preserve"> <tspan>a</tspan> </text>
+/// </svg>
+/// "#;
+/// let minified = xmlite::minify(text)?;
+/// assert_eq!(minified, r#"<svg xmlns="http://www.w3.org/2000/svg"><g><path d="M0 0"/></g><text xml:space="preserve"> <tspan>a</tspan> </text></svg>"#);
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+pub fn minify(text: &str) -> Result<String, Error> {
+    let tags = checked(Tags::new(text))?;
+    let elements = elements(&tags)?;
+
+    let mut out = String::with_capacity(text.len());
+    let mut scope = HashMap::from([("", "")]);
+    // for each open element: whether whitespace is preserved, whether it has mixed content,
+    // and the namespace bindings it shadows
+    let mut stack: Vec<(bool, bool, Shadowed)> = vec![];
+    let mut i = 0;
+    while i < tags.len() {
+        let tag = &tags[i].0;
+        i += 1;
+        let (preserve, mixed) = stack.last().map_or((false, false), |(p, m, _)| (*p, *m));
+        let (name, attrs, kind) = match tag {
+            Tag::Text(text) if !preserve && !mixed && text.trim().is_empty() => continue,
+            Tag::Declaration { name: "xml", attrs } if defaults(attrs) => continue,
+            Tag::Tag { name, attrs, kind } => (*name, attrs, *kind),
+            tag => {
+                write!(out, "{tag}").expect("writing to a string cannot fail");
+                continue;
+            }
+        };
+        if kind.is_closing() {
+            let (.., shadowed) = stack.pop().unwrap_or_default();
+            unbind(&mut scope, shadowed);
+            out.push_str(&format!("</{name}>"));
+            continue;
+        }
+
+        // drop namespace declarations repeating the binding in scope
+        let mut kept = Attrs::new();
+        let mut shadowed = vec![];
+        for (key, value) in attrs {
+            let declared = match key {
+                "xmlns" => Some(""),
+                key => key.strip_prefix("xmlns:"),
+            };
+            if let Some(prefix) = declared {
+                let uri = unquote(value);
+                if scope.get(prefix) == Some(&uri) {
+                    continue;
+                }
+                shadowed.push((prefix, scope.insert(prefix, uri)));
+            }
+            kept.insert(key, value);
+        }
+
+        let preserve = match attrs.get("xml:space").map(unquote) {
+            Some("preserve") => true,
+            Some("default") => false,
+            _ => preserve,
+        };
+        let element = elements[i - 1];
+        let opening = kind.is_opening();
+        let empty = !opening || element.end == i || element.empty && !preserve;
+        let kind = if empty {
+            TagKind::SelfClosing
+        } else {
+            TagKind::Opening
+        };
+        let tag = Tag::Tag {
+            name,
+            attrs: kept,
+            kind,
+        };
+        write!(out, "{tag}").expect("writing to a string cannot fail");
+        if !empty {
+            stack.push((preserve, element.mixed, shadowed));
+            continue;
+        }
+        unbind(&mut scope, shadowed);
+        if opening {
+            i = element.end + 1;
+        }
+    }
+    Ok(out)
+}
+
+/// Check whether an XML declaration only states the default version and encoding.
+fn defaults(attrs: &Attrs) -> bool {
+    attrs.iter().all(|(key, value)| match key {
+        "version" => unquote(value) == "1.0",
+        "encoding" => unquote(value).eq_ignore_ascii_case("UTF-8"),
+        _ => false,
+    })
+}
+
+/// Restore the namespace bindings shadowed by an element.
+fn unbind<'a>(scope: &mut HashMap<&'a str, &'a str>, shadowed: Shadowed<'a>) {
+    for (prefix, outer) in shadowed.into_iter().rev() {
+        match outer {
+            Some(outer) => scope.insert(prefix, outer),
+            None => scope.remove(prefix),
+        };
+    }
+}
+
+/// Collect tags along with their spans, failing on the first syntax error.
+fn checked(mut tags: Tags) -> Result<Vec<Spanned>, Error> {
+    let mut all = vec![];
+    while let Some(tag) = tags.next() {
+        all.push((tag, tags.span()));
+    }
+    match tags.diags.first() {
+        Some(error) => Err(error.clone()),
+        None => Ok(all),
+    }
+}
+
+/// Tag with the (line, column) it starts at.
+type Spanned<'a> = (Tag<'a>, (usize, usize));
+
+/// Namespace bindings shadowed by an element, with the bindings they replaced.
+type Shadowed<'a> = Vec<(&'a str, Option<&'a str>)>;
+
+/// Quote a parsed attribute value, keeping attributes without values bare.
+fn quoted_parsed(value: &str) -> Cow<'_, str> {
+    match value {
+        "" => Cow::Borrowed(""),
+        value => quoted(value),
+    }
+}
+
+/// Layout of an element in a tag list.
+#[derive(Debug, Clone, Copy, Default)]
+struct Element {
+    // index of the closing tag
+    end: usize,
+    // whether the element directly contains text
+    mixed: bool,
+    // whether the element contains nothing but whitespace
+    empty: bool,
+}
+
+/// Find the layout of the element opened at each index, checking that tags are nested.
+fn elements(tags: &[Spanned]) -> Result<Vec<Element>, Error> {
+    let mut elements = vec![Element::default(); tags.len()];
+    let mut open: Vec<usize> = vec![];
+    for (i, (tag, span)) in tags.iter().enumerate() {
+        if let Some(&parent) = open.last() {
+            let element = &mut elements[parent];
+            match tag {
+                Tag::Text(text) if text.trim().is_empty() => {}
+                Tag::Text(_) => (element.mixed, element.empty) = (true, false),
+                Tag::Tag {
+                    kind: TagKind::Closing,
+                    ..
+                } => {}
+                _ => element.empty = false,
+            }
+        }
+        let Tag::Tag { name, kind, .. } = tag else {
+            continue;
+        };
+        match kind {
+            TagKind::Opening => {
+                elements[i].empty = true;
+                open.push(i);
+            }
+            TagKind::Closing => {
+                let opened = open.pop();
+                let expected = opened.and_then(|o| tags[o].0.name());
+                if expected != Some(name) {
+                    return Err(Error::Mismatched {
+                        expected: expected.unwrap_or("any opening tag").to_owned(),
+                        found: (*name).to_owned(),
+                        span: *span,
+                        opened: opened.map(|o| tags[o].1),
+                    });
+                }
+                elements[opened.unwrap()].end = i;
+            }
+            TagKind::SelfClosing => {}
+        }
+    }
+    match open.is_empty() {
+        true => Ok(elements),
+        false => Err(Error::Eof),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout() {
+        let text = "<a>\n<b x='1'>\n\n</b><!-- c --><d>text</d>\n</a>\n";
+        let options = WriteOptions::new()
+            .with_indent("\t")
+            .with_expand_empty_for("b", true);
+        let expected = "<a>\n\t<b x='1'></b>\n\t<!-- c -->\n\t<d>text</d>\n</a>\n";
+        assert_eq!(format(text, &options).unwrap(), expected);
+    }
+
+    #[test]
+    fn errors() {
+        let options = WriteOptions::new();
+        assert!(matches!(
+            format("<a><b></a>", &options),
+            Err(Error::Mismatched { .. })
+        ));
+        assert!(matches!(format("<a><b/>", &options), Err(Error::Eof)));
+    }
+
+    #[test]
+    fn preserve() {
+        let text = "<a><p xml:space='preserve'>\n <b/> <c>\n</c></p><q xml:space='preserve'> </q>\n<r> </r><s xml:space='preserve'></s></a>";
+        let options = WriteOptions::new().with_indent("  ");
+        let expected = "<a>\n  <p xml:space='preserve'>\n <b/> <c>\n</c></p>\n  <q xml:space='preserve'> </q>\n  <r/>\n  <s xml:space='preserve'/>\n</a>";
+        assert_eq!(format(text, &options).unwrap(), expected);
+    }
+
+    #[test]
+    fn minify_content() {
+        let text = "<?xml version='1.1'?>\n<a>\n  <b>x <i>y</i> z</b>\n  <c><![CDATA[ ]]></c>\n  <d xml:space='preserve'>\n</d>\n</a>";
+        let expected = "<?xml version='1.1'?><a><b>x <i>y</i> z</b><c><![CDATA[ ]]></c><d xml:space='preserve'>\n</d></a>";
+        assert_eq!(minify(text).unwrap(), expected);
+    }
+}
@@ -0,0 +1,139 @@
+//! Markup fragments within document trees.
+
+use crate::{Element, Error, Tags, Xml, document};
+
+impl<'a> Xml<'a> {
+    /// Parse an XML fragment and insert the resulting nodes as children at an index.
+    ///
+    /// A fragment is any content allowed within an element, i.e. a sequence of elements and
+    /// text. The parsed nodes are copied, so the fragment text does not need to outlive the
+    /// tree. Nothing is inserted into text nodes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fragment is not well-formed, in which case the tree is left
+    /// unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the number of children.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut xml = xmlite::document("<ul><li>first</li></ul>")?;
+    /// let snippet = String::from("<li>second</li><li>third</li>");
+    /// xml.insert_fragment(1, &snippet)?;
+    /// drop(snippet);
+    /// assert_eq!(xml.to_string(), "<ul><li>first</li><li>second</li><li>third</li></ul>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn insert_fragment(&mut self, index: usize, text: &str) -> Result<(), Error> {
+        let Xml::Element(Element { children, .. }) = self else {
+            return Ok(());
+        };
+        assert!(index <= children.len(), "index out of bounds");
+        let nodes = document::fragment(&mut Tags::new(text))?;
+        children.splice(
+            index..index,
+            nodes
+                .into_iter()
+                .map(|node| -> Xml<'a> { node.into_owned() }),
+        );
+        Ok(())
+    }
+
+    /// Serialize the children of an element, without the element itself.
+    ///
+    /// Text and raw nodes have no children, so their markup is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<p>some <b>bold</b> text</p>")?;
+    /// assert_eq!(xml.inner_xml(), "some <b>bold</b> text");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn inner_xml(&self) -> String {
+        self.children().map(Xml::to_string).collect()
+    }
+
+    /// Parse an XML fragment and replace the children of an element with the resulting nodes.
+    ///
+    /// Like [`Xml::insert_fragment`], the parsed nodes are copied, and text nodes are left
+    /// unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fragment is not well-formed, in which case the tree is left
+    /// unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut xml = xmlite::document("<p>old <b>text</b></p>")?;
+    /// xml.set_inner_xml("new <i>text</i>")?;
+    /// assert_eq!(xml.to_string(), "<p>new <i>text</i></p>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn set_inner_xml(&mut self, text: &str) -> Result<(), Error> {
+        let Xml::Element(Element { children, .. }) = self else {
+            return Ok(());
+        };
+        let nodes = document::fragment(&mut Tags::new(text))?;
+        *children = nodes
+            .into_iter()
+            .map(|node| -> Xml<'a> { node.into_owned() })
+            .collect();
+        Ok(())
+    }
+
+    /// Serialize a node, including the element itself.
+    ///
+    /// This is the same as the [`Display`](std::fmt::Display) implementation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<p>some <b>bold</b> text</p>")?;
+    /// assert_eq!(xml.children().nth(1).unwrap().outer_xml(), "<b>bold</b>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn outer_xml(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_fragment() {
+        let mut xml = Xml::element("a");
+        xml.insert_fragment(0, "text <b/>").unwrap();
+        assert_eq!(xml.to_string(), "<a>text <b/></a>");
+        assert!(xml.insert_fragment(0, "<c></d>").is_err());
+        assert!(xml.insert_fragment(0, "</a>").is_err());
+        assert!(xml.insert_fragment(0, "<c>").is_err());
+        assert_eq!(xml.children().count(), 2);
+    }
+
+    #[test]
+    fn inner_xml() {
+        let xml = crate::document("<a><b/>x &amp; y<c>z</c></a>").unwrap();
+        assert_eq!(xml.inner_xml(), "<b/>x &amp; y<c>z</c>");
+        assert_eq!(xml.outer_xml(), format!("<a>{}</a>", xml.inner_xml()));
+        assert_eq!(Xml::text("t").inner_xml(), "");
+    }
+
+    #[test]
+    fn set_inner_xml() {
+        let mut xml = crate::document("<a><b/></a>").unwrap();
+        let inner = xml.inner_xml();
+        assert!(xml.set_inner_xml("<c>").is_err());
+        assert_eq!(xml.inner_xml(), inner);
+        xml.set_inner_xml("").unwrap();
+        assert_eq!(xml.to_string(), "<a/>");
+    }
+}
@@ -0,0 +1,387 @@
+//! Lenient parsing of HTML.
+
+use crate::{
+    Element, Error, Xml, chars,
+    escape::{escape, unescape},
+};
+use std::{borrow::Cow, collections::HashMap};
+
+/// Elements which never have content or end tags.
+pub(crate) const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose content is text up to their end tag.
+const RAW_TEXT: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Elements which end an open paragraph.
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "dl",
+    "fieldset",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// Parse an HTML document leniently into a tree.
+///
+/// This is not a full HTML5 parser, but handles the most common deviations from XML:
+///
+/// - Void elements like `<br>` and `<img>` need no end tag.
+/// - Attribute values may be unquoted, and attributes may have no value. Such values are
+///   stored quoted like parsed values, e.g. `class=menu` as `"menu"` and `disabled` as `""`.
+/// - End tags are implied where HTML allows them to be omitted, e.g. for `<p>` and `<li>`.
+///   End tags without a matching start tag are ignored, and unclosed elements are closed at
+///   the end of the document.
+/// - Doctypes, comments and processing instructions are skipped, and the content of
+///   elements such as `<script>` and `<style>` is read as text.
+///
+/// Element and attribute names are converted to lowercase, and element names end at the first
+/// character not allowed in names. Stray `&` and `<` in text and attribute values are
+/// escaped, while other references are kept as they are written, including HTML named
+/// references like `&nbsp;`. If the document does not have a single root element, its
+/// content is wrapped in an `html` element.
+///
+/// # Errors
+///
+/// Returns [`Error::Eof`] if the document contains no elements.
+///
+/// # Examples
+///
+/// ```rust
+/// let text = "<!DOCTYPE html><ul class=menu><li>One<li>Two<br></ul><p>A & B";
+/// let xml = xmlite::html(text)?;
+/// assert_eq!(xml.to_string(), r#"<html><ul class="menu"><li>One</li><li>Two<br/></li></ul><p>A &amp; B</p></html>"#);
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+pub fn html(text: &str) -> Result<Xml<'_>, Error> {
+    let mut stack = vec![Xml::element("html")];
+    let mut src = text;
+    while !src.is_empty() {
+        let Some(start) = src.strip_prefix('<').filter(|_| markup(src) == Some(0)) else {
+            let end = markup(src).unwrap_or(src.len());
+            push(&mut stack, Xml::Text(normalized(&src[..end])));
+            src = &src[end..];
+            continue;
+        };
+
+        // skip doctypes, comments and processing instructions
+        if let Some(rest) = start.strip_prefix("!--") {
+            src = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        if start.starts_with(['!', '?']) {
+            src = start.find('>').map_or("", |end| &start[end + 1..]);
+            continue;
+        }
+
+        // end tags close the matching open element and everything within it
+        if let Some(rest) = start.strip_prefix('/') {
+            let name = rest[..name_len(rest)].to_ascii_lowercase();
+            src = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            if let Some(i) = stack.iter().rposition(|open| open.name() == Some(&*name))
+                && i > 0
+            {
+                while stack.len() > i {
+                    close(&mut stack);
+                }
+            }
+            continue;
+        }
+
+        let (name, attrs, rest, self_closing) = tag(start);
+        src = rest;
+        implied_ends(&mut stack, &name);
+        let raw = RAW_TEXT.contains(&name.as_str()) && !self_closing;
+        let void = VOID.contains(&name.as_str()) || self_closing;
+        let element = Xml::Element(Element {
+            name: name.clone().into(),
+            attrs,
+            children: vec![],
+        });
+        if void {
+            push(&mut stack, element);
+            continue;
+        }
+        stack.push(element);
+        if raw {
+            // raw text runs until the matching end tag, with references decoded in the escapable
+            // raw text of titles and text areas
+            let end = find_ci(src, &format!("</{name}")).unwrap_or(src.len());
+            let text = match name.as_str() {
+                "title" | "textarea" => Cow::Owned(escape(&unescape(&src[..end])).into_owned()),
+                _ => escape(&src[..end]),
+            };
+            if end > 0 {
+                push(&mut stack, Xml::Text(text));
+            }
+            src = &src[end..];
+        }
+    }
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+
+    let mut root = stack.pop().expect("root is never closed");
+    let Xml::Element(Element { children, .. }) = &mut root else {
+        unreachable!("root is an element");
+    };
+    let whitespace = |node: &Xml| node.content().is_some_and(|t| t.trim().is_empty());
+    let mut elements = children.iter().filter(|node| !whitespace(node));
+    match (elements.next(), elements.next()) {
+        (None, _) => Err(Error::Eof),
+        (Some(Xml::Element(_)), None) => Ok(children.drain(..).find(Xml::is_element).unwrap()),
+        _ => Ok(root),
+    }
+}
+
+/// Parse a start tag after its `<`, returning its name, attributes, the rest of the input and
+/// whether it is self-closing.
+fn tag(src: &str) -> (String, HashMap<Cow<'_, str>, Cow<'_, str>>, &str, bool) {
+    let len = name_len(src);
+    let name = src[..len].to_ascii_lowercase();
+    let mut rest = &src[len..];
+    let mut attrs = HashMap::new();
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            return (name, attrs, after, true);
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            return (name, attrs, after, false);
+        }
+        if rest.is_empty() {
+            return (name, attrs, rest, false);
+        }
+
+        // attribute name, skipping stray characters
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))
+            .unwrap_or(rest.len())
+            .max(1);
+        let key = &rest[..len];
+        rest = rest[len..].trim_start();
+        let Some(after) = rest.strip_prefix('=') else {
+            if chars::is_name(key) {
+                attrs.insert(Cow::Owned(key.to_ascii_lowercase()), Cow::Borrowed("\"\""));
+            }
+            continue;
+        };
+
+        // quoted or unquoted value
+        rest = after.trim_start();
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = rest[1..].find(quote).map_or(rest.len(), |i| i + 2);
+                let value = &rest[..end];
+                rest = &rest[end..];
+                let inner = match value.len() >= 2 && value.ends_with(quote) {
+                    true => &value[1..value.len() - 1],
+                    false => &value[1..],
+                };
+                match normalized(inner) {
+                    Cow::Borrowed(_) if value.len() >= 2 && value.ends_with(quote) => {
+                        Cow::Borrowed(value)
+                    }
+                    inner => Cow::Owned(format!("{quote}{inner}{quote}")),
+                }
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || c == '>')
+                    .unwrap_or(rest.len());
+                let value = &rest[..end];
+                rest = &rest[end..];
+                Cow::Owned(format!("\"{}\"", normalized(value).replace('"', "&quot;")))
+            }
+        };
+        if chars::is_name(key) {
+            attrs.insert(Cow::Owned(key.to_ascii_lowercase()), value);
+        }
+    }
+}
+
+/// Close the open elements whose end tags are implied by a start tag.
+fn implied_ends(stack: &mut Vec<Xml>, name: &str) {
+    loop {
+        let current = stack.last().and_then(Xml::name).unwrap_or_default();
+        let implied = match current {
+            "p" => BLOCKS.contains(&name),
+            "li" => name == "li",
+            "dt" | "dd" => matches!(name, "dt" | "dd"),
+            "option" => matches!(name, "option" | "optgroup"),
+            "td" | "th" => matches!(name, "td" | "th" | "tr" | "tbody" | "thead" | "tfoot"),
+            "tr" => matches!(name, "tr" | "tbody" | "thead" | "tfoot"),
+            "thead" | "tbody" => matches!(name, "tbody" | "tfoot"),
+            _ => false,
+        };
+        if !implied || stack.len() == 1 {
+            return;
+        }
+        close(stack);
+    }
+}
+
+/// Pop the current element and add it to its parent.
+fn close(stack: &mut Vec<Xml>) {
+    let element = stack.pop().expect("an element is open");
+    push(stack, element);
+}
+
+/// Add a node to the current element.
+fn push<'a>(stack: &mut [Xml<'a>], node: Xml<'a>) {
+    if let Some(Xml::Element(Element { children, .. })) = stack.last_mut() {
+        children.push(node);
+    }
+}
+
+/// Find the start of the next tag or markup declaration, ignoring stray `<`.
+fn markup(src: &str) -> Option<usize> {
+    src.match_indices('<').map(|(i, _)| i).find(|&i| {
+        let next = src[i + 1..].chars().next();
+        let name = |c: char| c.is_alphabetic() && chars::is_name_start_char(c);
+        next.is_some_and(|c| name(c) || matches!(c, '/' | '!' | '?'))
+    })
+}
+
+/// Get the length of the tag name at the start of the input, which ends at the first
+/// character not allowed in names, so malformed names like `a=b` are cut short.
+fn name_len(src: &str) -> usize {
+    src.find(|c: char| !chars::is_name_char(c))
+        .unwrap_or(src.len())
+}
+
+/// Find a string case-insensitively.
+fn find_ci(haystack: &str, needle: &str) -> Option<usize> {
+    let haystack = haystack.as_bytes();
+    let needle = needle.as_bytes();
+    haystack
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle))
+}
+
+/// Escape stray `<` and `&` in text, keeping well-formed references.
+fn normalized(text: &str) -> Cow<'_, str> {
+    let reference = |rest: &str| {
+        let end = rest.find(';')?;
+        let name = &rest[1..end];
+        let valid = match name.strip_prefix('#') {
+            Some(n) => n.strip_prefix(['x', 'X']).map_or(
+                !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()),
+                |h| !h.is_empty() && h.chars().all(|c| c.is_ascii_hexdigit()),
+            ),
+            None => chars::is_name(name),
+        };
+        valid.then_some(())
+    };
+    let stray = text
+        .match_indices(['&', '<'])
+        .any(|(i, c)| c == "<" || reference(&text[i..]).is_none());
+    if !stray {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len() + 8);
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '&' if reference(&text[i..]).is_none() => out.push_str("&amp;"),
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn implied_structure() {
+        let text = "<HTML><Body><table><tr><td>1<td>2<tr><td>3</table><p>x <b>y</p>z\
+            <script>if (a < b && c) {}</SCRIPT><input type=checkbox checked /></body></html>";
+        let xml = html(text).unwrap();
+        let body = xml.children().next().unwrap();
+        let table = body.children().next().unwrap();
+        assert_eq!(
+            table.inner_xml(),
+            "<tr><td>1</td><td>2</td></tr><tr><td>3</td></tr>"
+        );
+        let p = body.children().nth(1).unwrap();
+        assert_eq!(p.inner_xml(), "x <b>y</b>");
+        let script = body.children().nth(3).unwrap();
+        assert_eq!(script.inner_xml(), "if (a &lt; b &amp;&amp; c) {}");
+        let input = body.children().nth(4).unwrap();
+        assert_eq!(input.attr("type"), Some("\"checkbox\""));
+        assert_eq!(input.attr("checked"), Some("\"\""));
+    }
+
+    #[test]
+    fn empty() {
+        assert!(matches!(html("<!-- nothing --> "), Err(Error::Eof)));
+        assert_eq!(html("text").unwrap().to_string(), "<html>text</html>");
+        assert_eq!(html("<a x='1>").unwrap().attr("x"), Some("'1>'"));
+    }
+
+    #[test]
+    fn stray() {
+        assert_eq!(html("<>").unwrap().to_string(), "<html>&lt;></html>");
+        assert_eq!(html("< a").unwrap().to_string(), "<html>&lt; a</html>");
+        let xml = html("<p>1 < 2 <> 3</p>").unwrap();
+        assert_eq!(xml.to_string(), "<p>1 &lt; 2 &lt;> 3</p>");
+    }
+
+    #[test]
+    fn well_formed() {
+        let cases = [
+            (
+                "<p title='x & y <z>'>t</p>",
+                "<p title='x &amp; y &lt;z>'>t</p>",
+            ),
+            (
+                "<a href=?a=1&b=2<c>x</a>",
+                "<a href=\"?a=1&amp;b=2&lt;c\">x</a>",
+            ),
+            ("<a=b>x</a=b>", "<a b=\"\">x</a>"),
+            ("<b\"c>x</b>", "<b>x</b>"),
+            (
+                "<title>A &amp; B < C</title>",
+                "<title>A &amp; B &lt; C</title>",
+            ),
+            (
+                "<textarea>&lt;&#38;</textarea>",
+                "<textarea>&lt;&amp;</textarea>",
+            ),
+            ("<p class='&amp;'>&nbsp;</p>", "<p class='&amp;'>&nbsp;</p>"),
+        ];
+        for (text, expected) in cases {
+            let output = html(text).unwrap().to_string();
+            assert_eq!(output, expected);
+            if !output.contains("&nbsp;") {
+                assert!(crate::document_strict(&output).is_ok(), "{output}");
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Memoized queries over mostly-static documents.
+
+use crate::{Error, Path, Xml, document::walk};
+use std::{cell::RefCell, collections::HashMap};
+
+/// Tree with a cache of query results, for applications that evaluate the same path
+/// expressions many times.
+///
+/// Results are stored as the child indices leading to the selected nodes, so repeated queries
+/// skip evaluating the expression. Any mutable access to the tree clears the cache.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::{Element, IndexedDocument, Xml};
+/// let xml = xmlite::document("<a><b>1</b><c><b>2</b></c></a>")?;
+/// let mut doc = IndexedDocument::new(xml);
+/// assert_eq!(doc.select("//b")?.len(), 2);
+/// assert!(doc.is_cached("//b"));
+///
+/// if let Xml::Element(Element { children, .. }) = doc.root_mut() {
+///     children.pop();
+/// }
+/// assert!(!doc.is_cached("//b"));
+/// assert_eq!(doc.select("//b")?.len(), 1);
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndexedDocument<'a> {
+    root: Xml<'a>,
+    cache: RefCell<HashMap<String, Vec<Vec<usize>>>>,
+}
+impl<'a> IndexedDocument<'a> {
+    /// Wrap a tree with an empty cache.
+    pub fn new(root: Xml<'a>) -> Self {
+        IndexedDocument {
+            root,
+            cache: RefCell::default(),
+        }
+    }
+
+    /// Get the root node.
+    pub fn root(&self) -> &Xml<'a> {
+        &self.root
+    }
+    /// Get mutable access to the root node, clearing the cache.
+    pub fn root_mut(&mut self) -> &mut Xml<'a> {
+        self.cache.get_mut().clear();
+        &mut self.root
+    }
+    /// Take the root node out of the wrapper, discarding the cache.
+    pub fn into_root(self) -> Xml<'a> {
+        self.root
+    }
+
+    /// Select the nodes matching a path expression, reusing the result of earlier calls with
+    /// the same expression.
+    ///
+    /// See [`Path`] for the supported syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Syntax`] for malformed expressions, which are not cached.
+    pub fn select(&self, expr: &str) -> Result<Vec<&Xml<'a>>, Error> {
+        if let Some(indices) = self.cache.borrow().get(expr) {
+            return Ok(indices.iter().map(|indices| self.node(indices)).collect());
+        }
+        let selected = Path::new(expr)?.select(&self.root);
+
+        // locate the selected nodes by identity
+        let mut locations = HashMap::new();
+        walk(&self.root, &mut vec![], &mut |node, indices| {
+            locations.insert(node as *const Xml, indices.to_vec());
+        });
+        let indices = selected
+            .iter()
+            .map(|&node| locations[&(node as *const Xml)].clone())
+            .collect();
+        self.cache.borrow_mut().insert(expr.to_owned(), indices);
+        Ok(selected)
+    }
+
+    /// Check whether the result of an expression is cached.
+    pub fn is_cached(&self, expr: &str) -> bool {
+        self.cache.borrow().contains_key(expr)
+    }
+    /// Clear the cache.
+    pub fn clear_cache(&mut self) {
+        self.cache.get_mut().clear();
+    }
+
+    fn node(&self, indices: &[usize]) -> &Xml<'a> {
+        indices
+            .iter()
+            .fold(&self.root, |node, &i| &node.children().as_slice()[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached() {
+        let xml = crate::document("<a><b n='1'/><b n='2'/></a>").unwrap();
+        let mut doc = IndexedDocument::new(xml);
+        let names = |doc: &IndexedDocument| {
+            let b = doc.select("b[@n='2']").unwrap();
+            b.iter()
+                .filter_map(|b| b.attr("n"))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        assert_eq!(names(&doc), "'2'");
+        assert_eq!(names(&doc), "'2'");
+        assert!(doc.select("b[").is_err());
+        assert!(!doc.is_cached("b["));
+
+        *doc.root_mut() = crate::document("<a><b n='2'/></a>").unwrap();
+        assert_eq!(doc.select("b").unwrap().len(), 1);
+        assert_eq!(names(&doc), "'2'");
+    }
+}
@@ -0,0 +1,392 @@
+//! Inference of structural summaries from example documents.
+
+use crate::{
+    Element, Xml, datatype,
+    escape::{attr_text, unescape},
+};
+use std::collections::BTreeMap;
+
+/// Structural summary of one or more documents, see [`Xml::infer`].
+///
+/// Elements are summarized by name, wherever they occur, so elements with the same name in
+/// different contexts are merged.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::{Inference, SimpleKind};
+/// let mut inference = Inference::new();
+/// for text in [
+///     r#"<feed><entry id="1"><title>A</title><tag>x</tag><tag>y</tag></entry></feed>"#,
+///     r#"<feed><entry id="2" draft="true"><title>B</title></entry><entry id="3.5"/></feed>"#,
+/// ] {
+///     inference.add(&xmlite::document(text)?);
+/// }
+/// let entry = &inference.elements["entry"];
+/// assert_eq!(entry.count, 3);
+/// let tag = entry.children.iter().find(|child| child.name == "tag").unwrap();
+/// assert_eq!((tag.min, tag.max), (0, 2));
+/// assert_eq!(entry.attrs["id"].kind, SimpleKind::Decimal);
+/// assert_eq!(entry.attrs["draft"].count, 1);
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inference {
+    /// Names of the root elements, with the number of documents they were the root of.
+    pub roots: BTreeMap<String, usize>,
+    /// Summaries of the elements, by name.
+    pub elements: BTreeMap<String, ElementShape>,
+}
+
+/// Summary of all occurrences of an element name, part of an [`Inference`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElementShape {
+    /// Number of occurrences.
+    pub count: usize,
+    /// Child elements in the order they were first seen.
+    pub children: Vec<ChildShape>,
+    /// Attributes, by name.
+    pub attrs: BTreeMap<String, AttrShape>,
+    /// Type of the text content, if any occurrence has text other than whitespace.
+    pub text: Option<SimpleKind>,
+    /// Whether the element has both text and child elements, in the same occurrence or in
+    /// different ones.
+    pub mixed: bool,
+    /// Whether any occurrence has neither text nor child elements.
+    pub empty: bool,
+}
+
+/// Cardinality of a child element within its parent, part of an [`ElementShape`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChildShape {
+    /// Name of the child element.
+    pub name: String,
+    /// Fewest occurrences within a single parent.
+    pub min: usize,
+    /// Most occurrences within a single parent.
+    pub max: usize,
+}
+
+/// Summary of the values of an attribute, part of an [`ElementShape`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttrShape {
+    /// Number of elements which have the attribute. The attribute is always present if this
+    /// equals the count of the element.
+    pub count: usize,
+    /// Type of the values.
+    pub kind: SimpleKind,
+}
+
+/// Type inferred from text values, the most specific one which fits all values seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimpleKind {
+    /// `true` or `false`.
+    Boolean,
+    /// Integer, such as `-12`.
+    Integer,
+    /// Decimal number, such as `3.5`. Also fits integers.
+    Decimal,
+    /// Date, such as `2024-02-29`.
+    Date,
+    /// Date and time, such as `2024-02-29T12:00:00Z`.
+    DateTime,
+    /// Any text.
+    String,
+}
+impl SimpleKind {
+    /// Infer the type of a single value.
+    pub fn of(value: &str) -> Self {
+        let value = value.trim();
+        let digits = |s: &str, n: usize| s.len() == n && s.bytes().all(|b| b.is_ascii_digit());
+        let date = |s: &str| {
+            let mut parts = s.splitn(3, '-');
+            digits(parts.next().unwrap_or(""), 4)
+                && digits(parts.next().unwrap_or(""), 2)
+                && digits(parts.next().unwrap_or(""), 2)
+        };
+        match value {
+            "true" | "false" => SimpleKind::Boolean,
+            _ if datatype::is_valid("integer", value) => SimpleKind::Integer,
+            _ if datatype::is_valid("decimal", value) => SimpleKind::Decimal,
+            _ if value.len() == 10 && date(value) => SimpleKind::Date,
+            _ if value.len() >= 19
+                && value.is_char_boundary(10)
+                && date(&value[..10])
+                && value[10..].starts_with('T') =>
+            {
+                SimpleKind::DateTime
+            }
+            _ => SimpleKind::String,
+        }
+    }
+    /// Get the most specific type which fits the values of both types.
+    pub fn join(self, other: Self) -> Self {
+        use SimpleKind::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Integer, Decimal) | (Decimal, Integer) => Decimal,
+            _ => String,
+        }
+    }
+    /// Get the name of the corresponding built-in XML Schema type, e.g. `xs:integer`.
+    pub fn xsd_name(self) -> &'static str {
+        match self {
+            SimpleKind::Boolean => "xs:boolean",
+            SimpleKind::Integer => "xs:integer",
+            SimpleKind::Decimal => "xs:decimal",
+            SimpleKind::Date => "xs:date",
+            SimpleKind::DateTime => "xs:dateTime",
+            SimpleKind::String => "xs:string",
+        }
+    }
+}
+
+impl Inference {
+    /// Create an empty summary.
+    pub fn new() -> Self {
+        Inference::default()
+    }
+
+    /// Add a document to the summary.
+    pub fn add(&mut self, xml: &Xml) {
+        if let Some(name) = xml.name() {
+            *self.roots.entry(name.to_owned()).or_default() += 1;
+        }
+        self.element(xml);
+    }
+    fn element(&mut self, xml: &Xml) {
+        let Xml::Element(Element {
+            name,
+            attrs,
+            children,
+        }) = xml
+        else {
+            return;
+        };
+        let seen = self
+            .elements
+            .get(name.as_ref())
+            .map_or(0, |shape| shape.count);
+
+        // occurrences of each child name within this element, in order of appearance
+        let mut counts: Vec<(&str, usize)> = vec![];
+        let mut text = String::new();
+        for child in children {
+            match child {
+                Xml::Element(child) => match counts.iter_mut().find(|(n, _)| *n == child.name) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((&child.name, 1)),
+                },
+                Xml::Text(content) => text.push_str(&unescape(content)),
+                Xml::Raw(_) => {}
+            }
+        }
+
+        let shape = self.elements.entry(name.to_string()).or_default();
+        shape.count += 1;
+        for child in &mut shape.children {
+            if !counts.iter().any(|(name, _)| *name == child.name) {
+                child.min = 0;
+            }
+        }
+        for &(name, count) in &counts {
+            match shape.children.iter_mut().find(|child| child.name == name) {
+                Some(child) => {
+                    (child.min, child.max) = (child.min.min(count), child.max.max(count))
+                }
+                // children first seen after other occurrences were missing from those
+                None => shape.children.push(ChildShape {
+                    name: name.to_owned(),
+                    min: if seen == 0 { count } else { 0 },
+                    max: count,
+                }),
+            }
+        }
+        for (key, value) in attrs {
+            let kind = SimpleKind::of(&attr_text(value));
+            shape
+                .attrs
+                .entry(key.to_string())
+                .and_modify(|attr| (attr.count, attr.kind) = (attr.count + 1, attr.kind.join(kind)))
+                .or_insert(AttrShape { count: 1, kind });
+        }
+        if !text.trim().is_empty() {
+            let kind = SimpleKind::of(&text);
+            shape.text = Some(shape.text.map_or(kind, |text| text.join(kind)));
+        } else if counts.is_empty() {
+            shape.empty = true;
+        }
+        shape.mixed = shape.text.is_some() && !shape.children.is_empty();
+
+        for child in children {
+            self.element(child);
+        }
+    }
+
+    /// Build an XML Schema skeleton from the summary, with a global declaration for each
+    /// element and references between them.
+    ///
+    /// Child elements are declared in a sequence in the order they were first seen, which may
+    /// be too strict for documents which order them differently. Text content which is
+    /// missing from some occurrences is declared as `xs:string`, since other types do not
+    /// accept empty values. The skeleton is meant as a starting point to be edited by hand.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::WriteOptions;
+    /// let xml = xmlite::document(r#"<a n="1"><b>x</b><b>y</b></a>"#)?;
+    /// let options = WriteOptions::new().with_sorted_attrs(true);
+    /// let xsd = xml.infer().to_xsd().to_string_with(&options);
+    /// assert!(xsd.contains(r#"<xs:element maxOccurs="unbounded" minOccurs="2" ref="b"/>"#));
+    /// assert!(xsd.contains(r#"<xs:attribute name="n" type="xs:integer" use="required"/>"#));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn to_xsd(&self) -> Xml<'static> {
+        let mut schema =
+            Xml::element("xs:schema").with_attr("xmlns:xs", "\"http://www.w3.org/2001/XMLSchema\"");
+        for (name, shape) in &self.elements {
+            let quoted = |value: &str| format!("\"{value}\"");
+            let mut element = Xml::element("xs:element").with_attr("name", quoted(name));
+            let attrs = shape.attrs.iter().map(|(key, attr)| {
+                let decl = Xml::element("xs:attribute")
+                    .with_attr("name", quoted(key))
+                    .with_attr("type", quoted(attr.kind.xsd_name()));
+                match attr.count == shape.count {
+                    true => decl.with_attr("use", "\"required\""),
+                    false => decl,
+                }
+            });
+            let attrs = attrs.collect::<Vec<_>>();
+            let text = match shape.empty {
+                true => SimpleKind::String,
+                false => shape.text.unwrap_or(SimpleKind::String),
+            };
+            let text = text.xsd_name();
+
+            element = match (shape.children.is_empty(), attrs.is_empty()) {
+                (true, true) if shape.text.is_some() => element.with_attr("type", quoted(text)),
+                (true, true) => element.with_child(Xml::element("xs:complexType")),
+                (true, false) => {
+                    let mut extension =
+                        Xml::element("xs:extension").with_attr("base", quoted(text));
+                    for attr in attrs {
+                        extension = extension.with_child(attr);
+                    }
+                    let content = Xml::element("xs:simpleContent").with_child(extension);
+                    element.with_child(Xml::element("xs:complexType").with_child(content))
+                }
+                (false, _) => {
+                    let mut sequence = Xml::element("xs:sequence");
+                    for child in &shape.children {
+                        let mut decl =
+                            Xml::element("xs:element").with_attr("ref", quoted(&child.name));
+                        if child.min != 1 {
+                            decl = decl.with_attr("minOccurs", quoted(&child.min.to_string()));
+                        }
+                        if child.max > 1 {
+                            decl = decl.with_attr("maxOccurs", "\"unbounded\"");
+                        }
+                        sequence = sequence.with_child(decl);
+                    }
+                    let mut complex = Xml::element("xs:complexType").with_child(sequence);
+                    if shape.mixed {
+                        complex = complex.with_attr("mixed", "\"true\"");
+                    }
+                    for attr in attrs {
+                        complex = complex.with_child(attr);
+                    }
+                    element.with_child(complex)
+                }
+            };
+            schema = schema.with_child(element);
+        }
+        schema
+    }
+}
+
+impl Xml<'_> {
+    /// Infer a structural summary of the document: which children and attributes each
+    /// element has, how often, and the types of their values.
+    ///
+    /// Use [`Inference::add`] to summarize several documents together.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::SimpleKind;
+    /// let xml = xmlite::document("<log><at>2024-01-01T10:00:00Z</at><n>1</n><n>2.5</n></log>")?;
+    /// let inference = xml.infer();
+    /// assert_eq!(inference.elements["at"].text, Some(SimpleKind::DateTime));
+    /// assert_eq!(inference.elements["n"].text, Some(SimpleKind::Decimal));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn infer(&self) -> Inference {
+        let mut inference = Inference::new();
+        inference.add(self);
+        inference
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds() {
+        let kinds = [
+            "true",
+            " 42 ",
+            "-1.5",
+            "2024-02-29",
+            "2024-02-29T12:00:00",
+            "é",
+        ];
+        let kinds = kinds.map(SimpleKind::of);
+        assert_eq!(
+            kinds,
+            [
+                SimpleKind::Boolean,
+                SimpleKind::Integer,
+                SimpleKind::Decimal,
+                SimpleKind::Date,
+                SimpleKind::DateTime,
+                SimpleKind::String
+            ]
+        );
+        assert_eq!(
+            SimpleKind::Date.join(SimpleKind::Integer),
+            SimpleKind::String
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "xsd")]
+    fn skeleton_validates() {
+        let documents = [
+            r#"<a v="1"><b>x</b>text<c><d/></c></a>"#,
+            r#"<a v="2.5" w="z"><b>y</b><b>z</b></a>"#,
+        ]
+        .map(|text| crate::document(text).unwrap());
+        let mut inference = Inference::new();
+        documents.iter().for_each(|xml| inference.add(xml));
+        assert_eq!(inference.elements["a"].children[1].min, 0);
+        let xsd = inference.to_xsd().to_string();
+        let schema = crate::Schema::new(&crate::document(&xsd).unwrap()).unwrap();
+        for xml in &documents {
+            assert!(schema.validate(xml).is_empty(), "{xsd}");
+        }
+
+        // text and children, or text and nothing, in different occurrences
+        for pair in [["<a><b/></a>", "<a>x</a>"], ["<n>1</n>", "<n/>"]] {
+            let documents = pair.map(|text| crate::document(text).unwrap());
+            let mut inference = Inference::new();
+            documents.iter().for_each(|xml| inference.add(xml));
+            let xsd = inference.to_xsd().to_string();
+            let schema = crate::Schema::new(&crate::document(&xsd).unwrap()).unwrap();
+            for xml in &documents {
+                assert!(schema.validate(xml).is_empty(), "{xsd}");
+            }
+        }
+    }
+}
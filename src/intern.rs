@@ -0,0 +1,98 @@
+//! Name interning.
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+/// Small handle for an interned name.
+///
+/// Symbols from the same [`Interner`] are equal exactly when their names are equal, so they
+/// can be compared and hashed without touching the names themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+/// Table of interned names.
+///
+/// Documents such as protocol descriptions repeat the same handful of element and attribute
+/// names thousands of times. Interning stores each distinct name once and hands out
+/// [`Symbol`]s in its place.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::Interner;
+/// let mut interner = Interner::new();
+/// let a = interner.intern("interface");
+/// let b = interner.intern("request");
+/// assert_eq!(interner.intern("interface"), a);
+/// assert_ne!(a, b);
+/// assert_eq!(interner.resolve(b), "request");
+/// assert_eq!(interner.get("event"), None);
+/// ```
+#[derive(Default, Clone)]
+pub struct Interner {
+    // each name is allocated once and shared by both, atomically so documents stay `Send`
+    symbols: HashMap<Arc<str>, Symbol>,
+    names: Vec<Arc<str>>,
+}
+impl Interner {
+    /// Create an empty interner.
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// Intern a name, returning its symbol.
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(name) {
+            return symbol;
+        }
+        let symbol = Symbol(self.names.len() as u32);
+        let name = Arc::<str>::from(name);
+        self.names.push(name.clone());
+        self.symbols.insert(name, symbol);
+        symbol
+    }
+    /// Get the symbol of a name without interning it.
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).copied()
+    }
+    /// Look up the name of a symbol.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the symbol was created by a different interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.0 as usize]
+    }
+
+    /// Get the number of interned names.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+    /// Check if no names have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+impl fmt::Debug for Interner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.names).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern() {
+        let mut interner = Interner::new();
+        let names = ["arg", "request", "arg", "event", "request"];
+        let symbols = names.map(|name| interner.intern(name));
+        assert_eq!(interner.len(), 3);
+        assert_eq!(symbols[0], symbols[2]);
+        assert_eq!(symbols[1], symbols[4]);
+        for (name, symbol) in names.iter().zip(symbols) {
+            assert_eq!(interner.resolve(symbol), *name);
+            assert_eq!(interner.get(name), Some(symbol));
+        }
+    }
+}
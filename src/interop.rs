@@ -0,0 +1,341 @@
+//! Conversions to and from other XML libraries.
+
+#[cfg(feature = "minidom")]
+mod minidom {
+    use crate::{
+        Xml,
+        escape::{attr_text, unescape_text},
+    };
+    use minidom::{Element, Node};
+
+    impl Xml<'_> {
+        /// Convert an element into a minidom element. Returns `None` for text nodes, and for
+        /// elements containing raw nodes since minidom cannot represent unparsed markup.
+        ///
+        /// Attribute values and text are unescaped and CDATA sections unwrapped, since minidom
+        /// escapes them when writing. Namespace declarations for the prefix of an element
+        /// become its namespace; other declarations are kept as attributes.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// let xml = xmlite::document(r#"<a xmlns="urn:a" b="1 &amp; 2">c</a>"#).unwrap();
+        /// let element = xml.to_minidom().unwrap();
+        /// assert_eq!(element.ns().as_deref(), Some("urn:a"));
+        /// assert_eq!(element.attr("b"), Some("1 & 2"));
+        /// assert_eq!(element.text(), "c");
+        /// ```
+        pub fn to_minidom(&self) -> Option<Element> {
+            let Xml::Element(crate::Element {
+                name,
+                attrs,
+                children,
+            }) = self
+            else {
+                return None;
+            };
+            let prefix = name.split_once(':').map(|(prefix, _)| prefix);
+            let mut builder = Element::builder(name);
+            for (key, value) in attrs {
+                let value = attr_text(value).into_owned();
+                let declared = match key.split_once(':') {
+                    Some(("xmlns", declared)) => Some(Some(declared)),
+                    None if key == "xmlns" => Some(None),
+                    _ => None,
+                };
+                builder = match declared {
+                    Some(declared) if declared == prefix => builder.ns(value),
+                    _ => builder.attr(key.as_ref(), value),
+                };
+            }
+            for child in children {
+                builder = match child {
+                    Xml::Text(text) => builder.append(Node::Text(unescape_text(text).into_owned())),
+                    element => builder.append(element.to_minidom()?),
+                };
+            }
+            Some(builder.build())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #[test]
+        fn to_minidom() {
+            let text = r#"<x:a xmlns:x="urn:x" xmlns:y="urn:y"><b y:c='&lt;'>d &#65;<![CDATA[<f>]]></b>e</x:a>"#;
+            let element = crate::document(text).unwrap().to_minidom().unwrap();
+            assert_eq!(element.name(), "a");
+            assert_eq!(element.ns().as_deref(), Some("urn:x"));
+            let b = element.children().next().unwrap();
+            assert_eq!(b.attr("y:c"), Some("<"));
+            assert_eq!(b.text(), "d A<f>");
+            assert_eq!(element.text(), "e");
+            assert!(crate::Xml::text("a").to_minidom().is_none());
+        }
+    }
+}
+
+#[cfg(feature = "quick-xml")]
+mod quick {
+    use crate::{Attrs, Tag, TagKind, Tags};
+    use quick_xml::events::{BytesDecl, BytesEnd, BytesPI, BytesStart, BytesText, Event};
+    use std::{borrow::Cow, str};
+
+    impl<'a> Tag<'a> {
+        /// Convert a quick-xml event into a tag, borrowing from the event.
+        ///
+        /// Returns `None` for events without a tag equivalent: CDATA sections, entity
+        /// references and the end of file.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// # use xmlite::Tag;
+        /// use quick_xml::{events::Event, Reader};
+        ///
+        /// let mut reader = Reader::from_str(r#"<a b="c">d</a>"#);
+        /// let event = reader.read_event().unwrap();
+        /// let tag = Tag::from_event(&event).unwrap();
+        /// assert_eq!(tag.name(), Some("a"));
+        /// assert_eq!(tag.attr("b"), Some("\"c\""));
+        /// ```
+        pub fn from_event(event: &'a Event<'_>) -> Option<Tag<'a>> {
+            let (raw, kind) = match event {
+                Event::Start(start) => (&**start, TagKind::Opening),
+                Event::Empty(start) => (&**start, TagKind::SelfClosing),
+                Event::End(end) => (&**end, TagKind::Closing),
+                Event::Text(text) => return str::from_utf8(text).ok().map(Tag::Text),
+                Event::Comment(comment) => return str::from_utf8(comment).ok().map(Tag::Comment),
+                Event::DocType(doctype) => return str::from_utf8(doctype).ok().map(Tag::Doctype),
+                Event::Decl(decl) => {
+                    let (name, attrs) = parts(decl)?;
+                    return Some(Tag::Declaration { name, attrs });
+                }
+                Event::PI(pi) => {
+                    let (name, attrs) = parts(pi)?;
+                    return Some(Tag::Declaration { name, attrs });
+                }
+                _ => return None,
+            };
+            let (name, attrs) = parts(raw)?;
+            Some(Tag::Tag { name, attrs, kind })
+        }
+    }
+
+    impl<'a> From<Tag<'a>> for Event<'a> {
+        fn from(tag: Tag<'a>) -> Self {
+            match tag {
+                Tag::Tag {
+                    name,
+                    kind: TagKind::Closing,
+                    ..
+                } => Event::End(BytesEnd::new(name)),
+                Tag::Tag { name, attrs, kind } => {
+                    let start = BytesStart::from_content(content(name, &attrs), name.len());
+                    match kind {
+                        TagKind::SelfClosing => Event::Empty(start),
+                        _ => Event::Start(start),
+                    }
+                }
+                Tag::Text(text) => Event::Text(BytesText::from_escaped(text)),
+                Tag::Comment(comment) => Event::Comment(BytesText::from_escaped(comment)),
+                Tag::Doctype(doctype) => Event::DocType(BytesText::from_escaped(doctype)),
+                Tag::Declaration { name: "xml", attrs } => {
+                    let start = BytesStart::from_content(content("xml", &attrs), 3);
+                    Event::Decl(BytesDecl::from_start(start))
+                }
+                Tag::Declaration { name, attrs } => Event::PI(BytesPI::new(content(name, &attrs))),
+            }
+        }
+    }
+
+    impl<'a> Tags<'a> {
+        /// Convert the tags into quick-xml events.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use quick_xml::Writer;
+        ///
+        /// let mut writer = Writer::new(Vec::new());
+        /// for event in xmlite::tags("<a b='c'>d<e/></a>").events() {
+        ///     writer.write_event(event).unwrap();
+        /// }
+        /// assert_eq!(writer.into_inner(), b"<a b='c'>d<e/></a>");
+        /// ```
+        pub fn events(self) -> impl Iterator<Item = Event<'a>> {
+            self.map(Event::from)
+        }
+    }
+
+    /// Write a tag name and attributes as the content of a quick-xml tag.
+    fn content<'a>(name: &'a str, attrs: &Attrs<'a>) -> Cow<'a, str> {
+        if attrs.is_empty() {
+            return Cow::Borrowed(name);
+        }
+        let mut content = name.to_owned();
+        for (key, value) in attrs {
+            content.push(' ');
+            content.push_str(key);
+            if !value.is_empty() {
+                content.push('=');
+                content.push_str(value);
+            }
+        }
+        Cow::Owned(content)
+    }
+
+    /// Split the content of a quick-xml tag into a name and attributes, keeping value quotes.
+    fn parts(raw: &[u8]) -> Option<(&str, Attrs<'_>)> {
+        let raw = str::from_utf8(raw).ok()?;
+        let (name, mut rest) = raw.split_once(char::is_whitespace).unwrap_or((raw, ""));
+        let mut attrs = Attrs::new();
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                return Some((name, attrs));
+            }
+            let end = rest
+                .find(|c: char| c == '=' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            let (key, tail) = rest.split_at(end);
+            let tail = tail.trim_start();
+            match tail.strip_prefix('=').map(str::trim_start) {
+                Some(value) => {
+                    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+                    let end = value[1..].find(quote)? + 2;
+                    attrs.insert(key, &value[..end]);
+                    rest = &value[end..];
+                }
+                None => {
+                    attrs.insert(key, "");
+                    rest = tail;
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use quick_xml::Reader;
+
+        #[test]
+        fn round_trip() {
+            let text = r#"<?xml version="1.0"?><a x="1" y = '2'>text<b/><?pi z="3"?></a>"#;
+            let mut reader = Reader::from_str(text);
+            let mut events = vec![];
+            loop {
+                match reader.read_event().unwrap() {
+                    Event::Eof => break,
+                    event => events.push(event),
+                }
+            }
+            let tags = events
+                .iter()
+                .filter_map(Tag::from_event)
+                .collect::<Vec<_>>();
+            let expected = crate::tags(text).collect::<Vec<_>>();
+            assert_eq!(format!("{tags:?}"), format!("{expected:?}"));
+
+            let events = crate::tags(text).events().collect::<Vec<_>>();
+            assert!(matches!(&events[0], Event::Decl(decl) if decl.version().is_ok()));
+            assert!(matches!(&events[2], Event::Text(t) if &**t == b"text"));
+            assert!(matches!(&events[5], Event::End(end) if &**end == b"a"));
+        }
+    }
+}
+
+#[cfg(feature = "roxmltree")]
+mod roxmltree {
+    use crate::{Element, Xml, escape::escape};
+    use roxmltree::{Document, Node};
+    use std::{borrow::Cow, collections::HashMap};
+
+    impl<'input> From<Node<'_, 'input>> for Xml<'input> {
+        /// Convert a roxmltree node into a tree.
+        ///
+        /// Text and attribute values are escaped and attribute values quoted, as in parsed
+        /// trees. Namespaced names get the prefix in scope for their namespace, and namespace
+        /// declarations are added to the elements that introduce them. Comments and
+        /// processing instructions are skipped.
+        fn from(node: Node<'_, 'input>) -> Self {
+            if !node.is_element() {
+                let text = node.text().unwrap_or_default();
+                return Xml::Text(escape(text).into_owned().into());
+            }
+            let name = node.tag_name();
+            let prefix = name
+                .namespace()
+                .filter(|&uri| node.lookup_namespace_uri(None) != Some(uri))
+                .and_then(|uri| node.lookup_prefix(uri));
+            let mut attrs = HashMap::new();
+            let inherited = node.parent_element().map(|p| p.namespaces());
+            let inherited = inherited.into_iter().flatten().collect::<Vec<_>>();
+            for ns in node.namespaces().filter(|ns| !inherited.contains(ns)) {
+                let key = match ns.name() {
+                    Some(prefix) => Cow::Owned(format!("xmlns:{prefix}")),
+                    None => Cow::Borrowed("xmlns"),
+                };
+                attrs.insert(key, quote(ns.uri()));
+            }
+            for attr in node.attributes() {
+                let prefix = attr.namespace().and_then(|uri| {
+                    node.namespaces()
+                        .find(|ns| ns.uri() == uri && ns.name().is_some())
+                        .and_then(|ns| ns.name())
+                        .or(node.lookup_prefix(uri))
+                });
+                attrs.insert(qualify(prefix, attr.name()), quote(attr.value()));
+            }
+            let children = node
+                .children()
+                .filter(|c| c.is_element() || c.is_text())
+                .map(Xml::from)
+                .collect();
+            Xml::Element(Element {
+                name: qualify(prefix, name.name()),
+                attrs,
+                children,
+            })
+        }
+    }
+    impl<'input> From<&Document<'input>> for Xml<'input> {
+        /// Convert the root element of a roxmltree document into a tree.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// # use xmlite::Xml;
+        /// let text = r#"<a xmlns:x="urn:x"><x:b x:c="1 &amp; 2">d</x:b></a>"#;
+        /// let doc = roxmltree::Document::parse(text).unwrap();
+        /// assert_eq!(Xml::from(&doc), xmlite::document(text).unwrap());
+        /// ```
+        fn from(document: &Document<'input>) -> Self {
+            Xml::from(document.root_element())
+        }
+    }
+
+    fn qualify<'a>(prefix: Option<&str>, name: &'a str) -> Cow<'a, str> {
+        match prefix {
+            Some(prefix) => Cow::Owned(format!("{prefix}:{name}")),
+            None => Cow::Borrowed(name),
+        }
+    }
+
+    fn quote(value: &str) -> Cow<'static, str> {
+        Cow::Owned(format!("\"{}\"", escape(value)))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn from_roxmltree() {
+            let text = r#"<a xmlns="urn:a" xmlns:x="urn:x" b="&lt;"><!-- c --><x:d xmlns:y="urn:y" y:e="f">g &amp; h</x:d><i/></a>"#;
+            let doc = Document::parse(text).unwrap();
+            assert_eq!(Xml::from(&doc), crate::document(text).unwrap());
+        }
+    }
+}
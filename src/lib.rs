@@ -13,16 +13,182 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
-#![doc = include_str!("../readme.md")]
+#![cfg_attr(feature = "tree", doc = include_str!("../readme.md"))]
+#![cfg_attr(
+    not(feature = "tree"),
+    doc = "XML 1.0 tokenizer, built without the document tree."
+)]
 #![deny(unsafe_code, missing_docs)]
 #![warn(clippy::all)]
 
+#[cfg(feature = "tree")]
+mod borrowed;
+#[cfg(feature = "simd")]
+mod bulk;
+mod chars;
+#[cfg(feature = "tree")]
+mod children;
+#[cfg(feature = "tree")]
+mod compare;
+#[cfg(feature = "tree")]
+mod conformance;
+#[cfg(feature = "tree")]
+mod content;
+#[cfg(feature = "tree")]
+mod cursor;
+#[cfg(feature = "tree")]
+mod datatype;
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod datetime;
+#[cfg(feature = "tree")]
+mod debug;
+#[cfg(feature = "tree")]
 mod document;
+#[cfg(feature = "tree")]
+mod dtd;
+#[cfg(feature = "tree")]
+mod edit;
+#[cfg(feature = "tree")]
+mod element;
+#[cfg(feature = "tree")]
+mod encoding;
+#[cfg(feature = "tree")]
+mod escape;
+#[cfg(feature = "feed")]
+mod feed;
+mod filter;
+#[cfg(feature = "tree")]
+mod format;
+#[cfg(feature = "tree")]
+mod fragment;
+#[cfg(feature = "arbitrary")]
+mod generate;
+#[cfg(feature = "tree")]
+mod html;
+#[cfg(feature = "tree")]
+mod indexed;
+#[cfg(feature = "tree")]
+mod infer;
+mod intern;
+#[cfg(any(feature = "minidom", feature = "quick-xml", feature = "roxmltree"))]
+mod interop;
+#[cfg(feature = "tree")]
+mod merge;
+#[cfg(feature = "tree")]
+mod namespace;
+#[cfg(feature = "nfc")]
+mod nfc;
+mod options;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "tree")]
+mod path;
+#[cfg(feature = "regex")]
+mod replace;
+mod rewrite;
+#[cfg(feature = "rnc")]
+mod rnc;
+#[cfg(feature = "tree")]
+mod rules;
+#[cfg(feature = "tree")]
+mod search;
+#[cfg(feature = "tree")]
+mod shared;
+#[cfg(feature = "soap")]
+mod soap;
+mod split;
+#[cfg(feature = "tree")]
+mod stats;
+mod stream;
+#[cfg(feature = "tree")]
+mod table;
 mod tag;
 mod token;
+mod uri;
+#[cfg(feature = "tree")]
+mod validate;
+#[cfg(feature = "tree")]
+mod write;
+#[cfg(feature = "xmldsig")]
+mod xmldsig;
+#[cfg(feature = "xmlrpc")]
+mod xmlrpc;
+#[cfg(feature = "xsd")]
+mod xsd;
+#[cfg(feature = "tree")]
+mod xsi;
+#[cfg(feature = "yaml")]
+mod yaml;
 
+#[cfg(feature = "tree")]
+pub use borrowed::*;
+#[cfg(feature = "tree")]
+pub use children::*;
+#[cfg(feature = "tree")]
+pub use compare::*;
+#[cfg(feature = "tree")]
+pub use conformance::*;
+#[cfg(feature = "tree")]
+pub use cursor::*;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub use datetime::*;
+#[cfg(feature = "tree")]
 pub use document::*;
+#[cfg(feature = "tree")]
+pub use dtd::*;
+#[cfg(feature = "tree")]
+pub use edit::*;
+#[cfg(feature = "tree")]
+pub use element::*;
+#[cfg(feature = "tree")]
+pub use encoding::*;
+#[cfg(feature = "feed")]
+pub use feed::*;
+pub use filter::*;
+#[cfg(feature = "tree")]
+pub use format::*;
+#[cfg(feature = "arbitrary")]
+pub use generate::*;
+#[cfg(feature = "tree")]
+pub use html::*;
+#[cfg(feature = "tree")]
+pub use indexed::*;
+#[cfg(feature = "tree")]
+pub use infer::*;
+pub use intern::*;
+#[cfg(feature = "tree")]
+pub use merge::*;
+pub use options::*;
+#[cfg(feature = "rayon")]
+pub use par::*;
+#[cfg(feature = "tree")]
+pub use path::*;
+pub use rewrite::*;
+#[cfg(feature = "rnc")]
+pub use rnc::*;
+#[cfg(feature = "tree")]
+pub use rules::*;
+#[cfg(feature = "tree")]
+pub use shared::*;
+#[cfg(feature = "soap")]
+pub use soap::*;
+pub use split::*;
+#[cfg(feature = "tree")]
+pub use stats::*;
+pub use stream::*;
+#[cfg(feature = "tree")]
+pub use table::*;
 pub use tag::*;
+pub use token::*;
+pub use uri::*;
+#[cfg(feature = "tree")]
+pub use write::*;
+#[cfg(feature = "xmldsig")]
+pub use xmldsig::*;
+#[cfg(feature = "xmlrpc")]
+pub use xmlrpc::*;
+#[cfg(feature = "xsd")]
+pub use xsd::*;
 
 use std::{error, fmt};
 
@@ -51,11 +217,168 @@ use std::{error, fmt};
 /// let attr = xml.children_mut().find(|e| e.name() == Some("pastry")).unwrap().attr_mut("kind");
 /// *attr.unwrap() = "berliner".to_owned();
 /// ```
-pub fn document(text: &str) -> Result<Xml, Error> {
+#[cfg(feature = "tree")]
+pub fn document(text: &str) -> Result<Xml<'_>, Error> {
     let mut tags = Tags::new(text);
+    document::prolog(&mut tags)?;
     document::element(&mut tags)
 }
 
+/// Parse an XML document with options.
+///
+/// See [`ParseOptions`] for the available options. With the default options, this is the
+/// same as [`document`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::ParseOptions;
+/// let options = ParseOptions::new().with_namespaces(true);
+/// assert!(xmlite::document_with("<x:a xmlns:x='urn:x'/>", &options).is_ok());
+/// assert!(xmlite::document_with("<x:a/>", &options).is_err());
+/// ```
+#[cfg(feature = "tree")]
+pub fn document_with<'a>(text: &'a str, options: &ParseOptions) -> Result<Xml<'a>, Error> {
+    let mut tags = Tags::new(text).with_options(options);
+    document::prolog(&mut tags)?;
+    let xml = document::element(&mut tags)?;
+    if options.strict
+        && let Some(error) = tags.diags.first()
+    {
+        return Err(error.clone());
+    }
+    if options.namespaces
+        && let Some(error) = xml.check_well_formed().into_iter().next()
+    {
+        return Err(error);
+    }
+    Ok(xml)
+}
+
+/// Parse an XML document, rejecting characters and names that are illegal in XML.
+///
+/// Control characters and character references to code points outside the `Char` production are
+/// reported as [`Error::InvalidChar`], and names outside the `Name` production as
+/// [`Error::InvalidName`], instead of being passed through.
+///
+/// # Examples
+///
+/// ```rust
+/// assert!(xmlite::document_strict("<a>ok</a>").is_ok());
+/// assert!(xmlite::document_strict("<a>&#x1;</a>").is_err());
+/// ```
+#[cfg(feature = "tree")]
+pub fn document_strict(text: &str) -> Result<Xml<'_>, Error> {
+    let mut tags = Tags::new(text).with_strict(true);
+    document::prolog(&mut tags)?;
+    let xml = document::element(&mut tags)?;
+    match tags.diags.first() {
+        Some(e) => Err(e.clone()),
+        None => Ok(xml),
+    }
+}
+
+/// Parse an XML document from raw bytes.
+///
+/// The encoding is detected from the byte order mark and leading bytes, and checked against the
+/// encoding named in the XML declaration. Only UTF-8 content is supported; anything else is
+/// reported as an [`Error::Encoding`] instead of being parsed into garbled text.
+///
+/// # Examples
+///
+/// ```rust
+/// let xml = xmlite::document_bytes(b"\xEF\xBB\xBF<?xml encoding='UTF-8'?><a/>").unwrap();
+/// assert_eq!(xml.name(), Some("a"));
+///
+/// let err = xmlite::document_bytes(b"<?xml encoding='ISO-8859-1'?><a>\xC3\xA6</a>");
+/// assert!(matches!(err, Err(xmlite::Error::Encoding { .. })));
+/// ```
+#[cfg(feature = "tree")]
+pub fn document_bytes(bytes: &[u8]) -> Result<Xml<'_>, Error> {
+    document(utf8_text(bytes)?)
+}
+
+/// Parse an XML document from bytes, replacing invalid UTF-8 instead of failing.
+///
+/// Each invalid sequence is replaced with U+FFFD and recorded in [`Document::diags`] as an
+/// [`Error::Encoding`], with the position of the replacement character. Valid input is
+/// parsed without copying, as with [`Document::parse`], while repaired input is copied into
+/// a document which owns its text.
+///
+/// # Errors
+///
+/// Returns an error if the document is malformed, or if it is not encoded as UTF-8, as with
+/// [`document_bytes`].
+///
+/// # Examples
+///
+/// ```rust
+/// let doc = xmlite::document_bytes_lossy(b"<a>caf\xE9 \xFF</a>")?;
+/// assert_eq!(doc.root().to_string(), "<a>caf\u{FFFD} \u{FFFD}</a>");
+/// assert_eq!(doc.diags().len(), 2);
+/// assert_eq!(doc.diags()[1].span(), Some((1, 9)));
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[cfg(feature = "tree")]
+pub fn document_bytes_lossy(bytes: &[u8]) -> Result<Document<'_>, Error> {
+    let bytes = utf8_bytes(bytes)?;
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Document::parse(text);
+    }
+    let declared = encoding::declared(bytes).unwrap_or_default();
+    let (mut text, mut diags) = (String::with_capacity(bytes.len()), vec![]);
+    let mut lexer = token::Lexer::new("");
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        lexer.advance(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            diags.push(Error::Encoding {
+                declared: declared.clone(),
+                detected: "invalid UTF-8".to_owned(),
+                span: lexer.report(),
+            });
+            text.push(char::REPLACEMENT_CHARACTER);
+            lexer.advance("\u{FFFD}");
+        }
+    }
+    Document::parse_owned(&text, diags)
+}
+
+/// Decode bytes as UTF-8 text, reporting other encodings and invalid sequences.
+#[cfg(feature = "tree")]
+pub(crate) fn utf8_text(bytes: &[u8]) -> Result<&str, Error> {
+    let bytes = utf8_bytes(bytes)?;
+    std::str::from_utf8(bytes).map_err(|e| {
+        let mut lexer = token::Lexer::new("");
+        lexer.advance(std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default());
+        Error::Encoding {
+            declared: encoding::declared(bytes).unwrap_or_default(),
+            detected: "invalid UTF-8".to_owned(),
+            span: lexer.report(),
+        }
+    })
+}
+
+/// Check that bytes are meant to be UTF-8, and that the encoding named in their declaration
+/// agrees, stripping the byte order mark.
+#[cfg(feature = "tree")]
+fn utf8_bytes(bytes: &[u8]) -> Result<&[u8], Error> {
+    let declared = encoding::declared(bytes);
+    let detected = encoding::detect(bytes);
+    if detected != "UTF-8" {
+        return Err(Error::Encoding {
+            declared: declared.unwrap_or_default(),
+            detected: detected.to_owned(),
+            span: (1, 1),
+        });
+    }
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    if let Some(declared) = declared {
+        encoding::check(&declared, bytes)?;
+    }
+    Ok(bytes)
+}
+
 /// Parse XML tags as an iterator.
 ///
 /// UTF-8 encoding only.
@@ -66,12 +389,16 @@ pub fn document(text: &str) -> Result<Xml, Error> {
 /// let text = r#"<a><b/><c/></a>"#;
 /// let tags = xmlite::tags(text);
 /// ```
-pub fn tags(text: &str) -> Tags {
+pub fn tags(text: &str) -> Tags<'_> {
     Tags::new(text)
 }
 
 /// Errors produced when encountering malformed XML.
+///
+/// Positions are reported as 1-based (line, column) pairs, which can be converted to byte
+/// offsets with [`Error::offset`]. Use [`Error::kind`] to match on the category of an error.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Error {
     /// Syntax errors.
     Syntax {
@@ -88,6 +415,77 @@ pub enum Error {
         found: String,
         /// Location of the error.
         span: (usize, usize),
+        /// Location of the opening tag that was not closed, if any.
+        opened: Option<(usize, usize)>,
+    },
+    /// Mismatched tag in a stream of tags which need not come from text, located by the
+    /// index of the tag in the stream instead of a (line, column) position.
+    MismatchedTag {
+        /// What the parser expected to find.
+        expected: String,
+        /// What the parser actually found.
+        found: String,
+        /// Index of the offending tag.
+        index: usize,
+        /// Index of the opening tag that was not closed, if any.
+        opened: Option<usize>,
+    },
+    /// Declared encoding does not match the actual content.
+    Encoding {
+        /// Encoding named in the XML declaration, empty if it could not be read.
+        declared: String,
+        /// Encoding detected from the content.
+        detected: String,
+        /// Location of the error.
+        span: (usize, usize),
+    },
+    /// Character that is not allowed in XML.
+    InvalidChar {
+        /// The offending code point.
+        char: u32,
+        /// Location of the error.
+        span: (usize, usize),
+    },
+    /// Element or attribute name that does not match the `Name` production.
+    InvalidName {
+        /// The offending name.
+        name: String,
+        /// Location of the error.
+        span: (usize, usize),
+    },
+    /// Attribute specified more than once on the same tag.
+    DuplicateAttr {
+        /// The repeated attribute name.
+        name: String,
+        /// Location of the error.
+        span: (usize, usize),
+    },
+    /// Markup, such as another tag or a comment, started inside a tag.
+    MarkupInTag {
+        /// Location of the error.
+        span: (usize, usize),
+    },
+    /// Parsing was given up after too many errors, see
+    /// [`ParseOptions::with_max_errors`].
+    TooManyErrors {
+        /// The maximum number of errors.
+        limit: usize,
+        /// Location where parsing stopped.
+        span: (usize, usize),
+    },
+    /// Input in another format, such as YAML, which could not be converted to or from a tree.
+    Conversion {
+        /// Description of the error.
+        message: String,
+        /// Location of the error in the input, if known.
+        span: Option<(usize, usize)>,
+    },
+    /// Document tree violates a constraint.
+    Validation {
+        /// Description of the violated constraint.
+        message: String,
+        /// Path of the offending node, e.g. `/a/b[2]/@c`.
+        path: String,
     },
     /// End of file.
     Eof,
@@ -103,17 +501,218 @@ impl fmt::Display for Error {
                 expected,
                 found,
                 span: (line, col),
+                ..
             } => write!(
                 f,
                 "mismatched tag. expected {expected}, found {found} at {line}:{col}"
             ),
+            Error::MismatchedTag {
+                expected,
+                found,
+                index,
+                ..
+            } => write!(
+                f,
+                "mismatched tag. expected {expected}, found {found} at tag {index}"
+            ),
+            Error::Encoding {
+                declared,
+                detected,
+                span: (line, col),
+            } => write!(
+                f,
+                "encoding mismatch. declared {declared:?}, detected {detected} at {line}:{col}"
+            ),
+            Error::InvalidChar {
+                char,
+                span: (line, col),
+            } => write!(f, "invalid character U+{char:04X} at {line}:{col}"),
+            Error::InvalidName {
+                name,
+                span: (line, col),
+            } => write!(f, "invalid name {name:?} at {line}:{col}"),
+            Error::DuplicateAttr {
+                name,
+                span: (line, col),
+            } => write!(f, "duplicate attribute {name:?} at {line}:{col}"),
+            Error::MarkupInTag { span: (line, col) } => {
+                write!(f, "'<' not allowed inside a tag at {line}:{col}")
+            }
+            Error::TooManyErrors {
+                limit,
+                span: (line, col),
+            } => write!(
+                f,
+                "too many errors (limit {limit}), stopped at {line}:{col}"
+            ),
+            Error::Conversion { message, .. } => f.write_str(message),
+            Error::Validation { message, path } => write!(f, "{message} at {path}"),
             Error::Eof => f.write_str("end of file"),
         }
     }
 }
 impl error::Error for Error {}
+impl Error {
+    /// Get the category of the error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::ErrorKind;
+    /// # #[cfg(feature = "tree")] {
+    /// let err = xmlite::document("<a></b>").unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::Mismatched);
+    /// assert_eq!(err.kind().code(), "E002");
+    /// # }
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Syntax { .. } => ErrorKind::Syntax,
+            Error::Mismatched { .. } | Error::MismatchedTag { .. } => ErrorKind::Mismatched,
+            Error::Encoding { .. } => ErrorKind::Encoding,
+            Error::InvalidChar { .. } => ErrorKind::InvalidChar,
+            Error::InvalidName { .. } => ErrorKind::InvalidName,
+            Error::DuplicateAttr { .. } => ErrorKind::DuplicateAttr,
+            Error::MarkupInTag { .. } => ErrorKind::MarkupInTag,
+            Error::TooManyErrors { .. } => ErrorKind::TooManyErrors,
+            Error::Conversion { .. } => ErrorKind::Conversion,
+            Error::Validation { .. } => ErrorKind::Validation,
+            Error::Eof => ErrorKind::Eof,
+        }
+    }
 
-#[cfg(test)]
+    /// Get the (line, column) of the error, if it has one.
+    pub fn span(&self) -> Option<(usize, usize)> {
+        match self {
+            Error::Syntax { span, .. }
+            | Error::Mismatched { span, .. }
+            | Error::Encoding { span, .. }
+            | Error::InvalidChar { span, .. }
+            | Error::InvalidName { span, .. }
+            | Error::DuplicateAttr { span, .. }
+            | Error::MarkupInTag { span }
+            | Error::TooManyErrors { span, .. } => Some(*span),
+            Error::Conversion { span, .. } => *span,
+            Error::MismatchedTag { .. } | Error::Validation { .. } | Error::Eof => None,
+        }
+    }
+
+    /// Get the byte offset of the error in the text it was produced from, if it has a span.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "tree")] {
+    /// let text = "<a>\n  <b></c>\n</a>";
+    /// let err = xmlite::document(text).unwrap_err();
+    /// assert_eq!(err.span(), Some((2, 10)));
+    /// assert_eq!(&text[err.offset(text).unwrap()..], "\n</a>");
+    /// # }
+    /// ```
+    pub fn offset(&self, text: &str) -> Option<usize> {
+        let (line, column) = self.span()?;
+        let start = match line {
+            1 => 0,
+            _ => text.match_indices('\n').nth(line - 2)?.0 + 1,
+        };
+        let line = &text[start..];
+        let end = line.find('\n').unwrap_or(line.len());
+        let mut columns = line[..end].char_indices().map(|(i, _)| i).chain([end]);
+        columns.nth(column.checked_sub(1)?).map(|i| start + i)
+    }
+
+    /// Get the (line, column) of the error in the text of the document it was produced from.
+    ///
+    /// Unlike [`Error::span`], this also locates [`Error::Validation`] errors, at the start of
+    /// the node their path points to. Attributes are located at the start tag of their
+    /// element.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Xml;
+    /// let text = "<a>\n  <b/>\n  <x:b/>\n</a>";
+    /// let errors = xmlite::document(text)?.check_well_formed();
+    /// assert_eq!(errors[0].span(), None);
+    /// assert_eq!(errors[0].locate(text), Some((3, 3)));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    #[cfg(feature = "tree")]
+    pub fn locate(&self, text: &str) -> Option<(usize, usize)> {
+        match self {
+            Error::Validation { path, .. } => validate::locate(text, path),
+            _ => self.span(),
+        }
+    }
+
+    /// Get a secondary location related to the error, along with a description of it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "tree")] {
+    /// let err = xmlite::document("<a>\n  <b></c>\n</a>").unwrap_err();
+    /// assert_eq!(err.related(), Some(((2, 3), "opening tag was here")));
+    /// # }
+    /// ```
+    pub fn related(&self) -> Option<((usize, usize), &'static str)> {
+        match self {
+            Error::Mismatched {
+                opened: Some(opened),
+                ..
+            } => Some((*opened, "opening tag was here")),
+            _ => None,
+        }
+    }
+}
+
+/// Category of an [`Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Unexpected token.
+    Syntax,
+    /// Mismatched tag.
+    Mismatched,
+    /// Declared encoding does not match the actual content.
+    Encoding,
+    /// Character that is not allowed in XML.
+    InvalidChar,
+    /// Name that does not match the `Name` production.
+    InvalidName,
+    /// Attribute specified more than once on the same tag.
+    DuplicateAttr,
+    /// Markup started inside a tag.
+    MarkupInTag,
+    /// Parsing was given up after too many errors.
+    TooManyErrors,
+    /// Document tree violates a constraint.
+    Validation,
+    /// Unexpected end of file.
+    Eof,
+    /// Input in another format could not be converted.
+    Conversion,
+}
+impl ErrorKind {
+    /// Get the stable code of the error kind, e.g. `E001` for syntax errors.
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::Syntax => "E001",
+            ErrorKind::Mismatched => "E002",
+            ErrorKind::Encoding => "E003",
+            ErrorKind::InvalidChar => "E004",
+            ErrorKind::InvalidName => "E005",
+            ErrorKind::DuplicateAttr => "E006",
+            ErrorKind::Validation => "E007",
+            ErrorKind::Eof => "E008",
+            ErrorKind::MarkupInTag => "E009",
+            ErrorKind::TooManyErrors => "E010",
+            ErrorKind::Conversion => "E011",
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tree"))]
 mod tests {
     use super::*;
 
@@ -144,4 +743,37 @@ mod tests {
 
         *attr.unwrap() = "berliner".to_owned();
     }
+
+    #[test]
+    fn prolog_whitespace() {
+        let text = "<?xml version=\"1.0\"?>\n<!-- c -->\n<a>\n<!-- d --></a>\n";
+        let xml = document(text).unwrap();
+        assert_eq!(xml.name(), Some("a"));
+        assert_eq!(xml.children().count(), 1);
+    }
+
+    #[test]
+    fn parse_options() {
+        let options = ParseOptions::new().with_strict(true).with_lazy_attrs(true);
+        let xml = document_with("<a x='1'><b/></a>", &options).unwrap();
+        assert_eq!(xml.attr("x"), Some("'1'"));
+        assert!(document_with("<a>\u{1}</a>", &options).is_err());
+        assert!(document_with("<a>\u{1}</a>", &ParseOptions::new()).is_ok());
+    }
+
+    #[test]
+    fn encoding_mismatch() {
+        let text = r#"<?xml version="1.0" encoding="US-ASCII"?><a>blåbær</a>"#;
+        assert!(matches!(
+            document_bytes(text.as_bytes()),
+            Err(Error::Encoding { declared, .. }) if declared == "US-ASCII"
+        ));
+        assert!(document(text).is_ok());
+        let text = r#"<?xml version="1.0" encoding="utf-8"?><a>blåbær</a>"#;
+        assert!(document(text).is_ok());
+        assert!(matches!(
+            document_bytes(b"<a>\xFF</a>"),
+            Err(Error::Encoding { span: (1, 4), .. })
+        ));
+    }
 }
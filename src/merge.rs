@@ -0,0 +1,121 @@
+//! Merging of document trees.
+
+use crate::{Element, Xml, validate::unquote};
+
+/// Options for [`Xml::merge`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::MergeOptions;
+/// let options = MergeOptions::new().with_key("id").with_key("name");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeOptions {
+    keys: Vec<String>,
+}
+impl MergeOptions {
+    /// Create options matching children by element name only.
+    pub fn new() -> Self {
+        MergeOptions::default()
+    }
+
+    /// Also match children by an attribute, e.g. `id`.
+    ///
+    /// When several keys are added, the first one present on an element is used.
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.keys.push(key.into());
+        self
+    }
+
+    /// Get the key of an element, as the key attribute and its value.
+    fn key<'x>(&self, node: &'x Xml) -> Option<(&str, &'x str)> {
+        self.keys
+            .iter()
+            .find_map(|key| Some((key.as_str(), unquote(node.attr(key)?))))
+    }
+}
+
+impl<'a> Xml<'a> {
+    /// Merge another tree into this one, e.g. to apply an override configuration to a base
+    /// configuration.
+    ///
+    /// Attributes of the other element replace those of this element. Each child element of
+    /// the other element is merged into the first unmatched child of this element with the
+    /// same name and key, or appended if there is none. Text in the other element replaces
+    /// the text of this element, while text containing only whitespace is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{MergeOptions, WriteOptions};
+    /// let mut base = xmlite::document(r#"<config><server name="a" port="80"/><server name="b"/><log>info</log></config>"#)?;
+    /// let over = xmlite::document(r#"<config><server name="a" port="8080"/><server name="c"/><log>debug</log></config>"#)?;
+    /// base.merge(&over, &MergeOptions::new().with_key("name"));
+    /// let sorted = WriteOptions::new().with_sorted_attrs(true);
+    /// assert_eq!(base.to_string_with(&sorted), r#"<config><server name="a" port="8080"/><server name="b"/><log>debug</log><server name="c"/></config>"#);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn merge(&mut self, other: &Xml<'a>, options: &MergeOptions) {
+        let (
+            Xml::Element(Element {
+                attrs, children, ..
+            }),
+            Xml::Element(Element {
+                attrs: other_attrs,
+                children: other_children,
+                ..
+            }),
+        ) = (&mut *self, other)
+        else {
+            return;
+        };
+        attrs.extend(other_attrs.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        if other_children.iter().any(is_content) {
+            children.retain(|child| !child.is_text());
+        }
+        let mut matched = vec![false; children.len()];
+        for child in other_children {
+            if !child.is_element() {
+                if is_content(child) {
+                    children.push(child.clone());
+                }
+                continue;
+            }
+            let key = options.key(child);
+            let found = (0..matched.len()).find(|&i| {
+                !matched[i]
+                    && children[i].name() == child.name()
+                    && options.key(&children[i]) == key
+            });
+            match found {
+                Some(i) => {
+                    matched[i] = true;
+                    children[i].merge(child, options);
+                }
+                None => children.push(child.clone()),
+            }
+        }
+    }
+}
+
+/// Check whether a node is text with something other than whitespace.
+fn is_content(node: &Xml) -> bool {
+    node.content().is_some_and(|text| !text.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unkeyed() {
+        let mut base = crate::document("<a>\n  <b x='1'><c/></b>\n  <b/>\n</a>").unwrap();
+        let over = crate::document("<a><b y='2'><d/></b><b>text</b><b/></a>").unwrap();
+        base.merge(&over, &MergeOptions::new());
+        let expected = "<a>\n  <b x='1' y='2'><c/><d/></b>\n  <b>text</b>\n<b/></a>";
+        let sorted = crate::WriteOptions::new().with_sorted_attrs(true);
+        assert_eq!(base.to_string_with(&sorted), expected);
+    }
+}
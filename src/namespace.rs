@@ -0,0 +1,273 @@
+//! Namespace prefix rewriting and declaration cleanup.
+
+use crate::{Element, Xml, chars, tag::parsed_quote, validate::unquote};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    mem,
+};
+
+impl<'a> Xml<'a> {
+    /// Rewrite namespace prefixes to a chosen mapping from namespace names to prefixes.
+    ///
+    /// Element names, attribute names and namespace declarations bound to a mapped namespace
+    /// get the new prefix, as do attribute values which are qualified names, e.g.
+    /// `xsi:type="ns0:string"`, as in [`Xml::drop_unused_namespaces`]. Empty prefixes are
+    /// ignored, since unprefixed attributes are not in any namespace. The mapping should not reuse prefixes bound to other namespaces.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut xml = xmlite::document(r#"<ns0:schema xmlns:ns0="urn:s"><ns0:element/></ns0:schema>"#)?;
+    /// xml.rename_prefixes(&[("urn:s", "xs")]);
+    /// assert_eq!(xml.name(), Some("xs:schema"));
+    /// assert_eq!(xml.attr("xmlns:xs"), Some("\"urn:s\""));
+    /// assert_eq!(xml.children().next().unwrap().name(), Some("xs:element"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn rename_prefixes(&mut self, prefixes: &[(&str, &str)]) {
+        rename(self, prefixes, &mut HashMap::new());
+    }
+
+    /// Remove namespace declarations that are not used by the element or its descendants.
+    ///
+    /// Attribute values which are qualified names, e.g. `xsi:type="xs:string"`, count as uses
+    /// of their prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut xml = xmlite::document(r#"<a xmlns:x="urn:x" xmlns:y="urn:y"><x:b/></a>"#)?;
+    /// xml.drop_unused_namespaces();
+    /// assert!(xml.attr("xmlns:x").is_some());
+    /// assert!(xml.attr("xmlns:y").is_none());
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn drop_unused_namespaces(&mut self) {
+        prune(self);
+    }
+
+    /// Move prefixed namespace declarations repeated on descendants to this element.
+    ///
+    /// A prefix is only hoisted if it is bound to the same namespace everywhere in the tree,
+    /// so that moving its declaration does not change the meaning of any name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let text = r#"<a><x:b xmlns:x="urn:x"/><x:c xmlns:x="urn:x"/></a>"#;
+    /// let mut xml = xmlite::document(text)?;
+    /// xml.hoist_namespaces();
+    /// assert_eq!(xml.attr("xmlns:x"), Some("\"urn:x\""));
+    /// assert!(xml.children().all(|child| child.attr("xmlns:x").is_none()));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn hoist_namespaces(&mut self) {
+        // count the bindings of each prefix, and whether they agree
+        let mut bindings: HashMap<String, (Cow<'a, str>, usize, bool)> = HashMap::new();
+        let mut bind = |node: &Xml<'a>, count: usize| {
+            let Xml::Element(Element { attrs, .. }) = node else {
+                return;
+            };
+            for (key, value) in attrs {
+                let Some(prefix) = key.strip_prefix("xmlns:") else {
+                    continue;
+                };
+                let (uri, n, same) =
+                    bindings
+                        .entry(prefix.to_owned())
+                        .or_insert((value.clone(), 0, true));
+                *same &= unquote(uri) == unquote(value);
+                *n += count;
+            }
+        };
+        bind(self, 2);
+        self.descendants().for_each(|node| bind(node, 1));
+
+        let hoisted = bindings
+            .into_iter()
+            .filter(|(_, (_, n, same))| *same && *n >= 2)
+            .collect::<HashMap<_, _>>();
+        let Xml::Element(Element {
+            attrs, children, ..
+        }) = self
+        else {
+            return;
+        };
+        for (prefix, (uri, ..)) in &hoisted {
+            attrs
+                .entry(format!("xmlns:{prefix}").into())
+                .or_insert_with(|| uri.clone());
+        }
+        for child in children {
+            undeclare(child, &hoisted);
+        }
+    }
+}
+
+fn rename(node: &mut Xml, prefixes: &[(&str, &str)], scope: &mut HashMap<String, String>) {
+    let Xml::Element(Element {
+        name,
+        attrs,
+        children,
+    }) = node
+    else {
+        return;
+    };
+
+    // bind namespace declarations on this element
+    let mut shadowed = vec![];
+    for (key, value) in attrs.iter() {
+        if let Some(prefix) = key.strip_prefix("xmlns:") {
+            let outer = scope.insert(prefix.to_owned(), unquote(value).to_owned());
+            shadowed.push((prefix.to_owned(), outer));
+        }
+    }
+
+    let prefix_for = |uri: &str| {
+        let (_, prefix) = prefixes.iter().find(|(u, p)| *u == uri && !p.is_empty())?;
+        Some(*prefix)
+    };
+    let renamed = |qname: &str| {
+        let (prefix, local) = qname.split_once(':')?;
+        let prefix = prefix_for(scope.get(prefix)?)?;
+        Some(format!("{prefix}:{local}"))
+    };
+    if let Some(new) = renamed(name) {
+        *name = new.into();
+    }
+    *attrs = mem::take(attrs)
+        .into_iter()
+        .map(|(key, value)| {
+            let (new, renamed_value) = match key.strip_prefix("xmlns:") {
+                Some(_) => {
+                    let new = prefix_for(unquote(&value)).map(|prefix| format!("xmlns:{prefix}"));
+                    (new, None)
+                }
+                None => {
+                    let renamed_value = qname(&value).and_then(|_| renamed(unquote(&value)));
+                    (renamed(&key), renamed_value)
+                }
+            };
+            let value = match (renamed_value, parsed_quote(&value)) {
+                (Some(new), Some(quote)) => Cow::Owned(format!("{quote}{new}{quote}")),
+                (Some(new), None) => Cow::Owned(new),
+                (None, _) => value,
+            };
+            (new.map_or(key, Cow::Owned), value)
+        })
+        .collect();
+
+    for child in children {
+        rename(child, prefixes, scope);
+    }
+
+    // restore outer namespace scope
+    for (prefix, outer) in shadowed.into_iter().rev() {
+        match outer {
+            Some(outer) => scope.insert(prefix, outer),
+            None => scope.remove(&prefix),
+        };
+    }
+}
+
+/// Remove unused declarations from a subtree, returning the prefixes it uses without
+/// declaring them. The default namespace is represented by the empty prefix.
+fn prune(node: &mut Xml) -> HashSet<String> {
+    let Xml::Element(Element {
+        name,
+        attrs,
+        children,
+    }) = node
+    else {
+        return HashSet::new();
+    };
+
+    let mut used = children.iter_mut().flat_map(prune).collect::<HashSet<_>>();
+    used.insert(name.split_once(':').map_or("", |(p, _)| p).to_owned());
+    for (key, value) in attrs.iter() {
+        if declared(key).is_some() {
+            continue;
+        }
+        if let Some((prefix, _)) = key.split_once(':') {
+            used.insert(prefix.to_owned());
+        }
+        if let Some((prefix, _)) = qname(value) {
+            used.insert(prefix.to_owned());
+        }
+    }
+
+    attrs.retain(|key, _| declared(key).is_none_or(|prefix| used.contains(prefix)));
+    for prefix in attrs.keys().filter_map(|key| declared(key)) {
+        used.remove(prefix);
+    }
+    used
+}
+
+/// Split an attribute value into prefix and local name, if it is a qualified name.
+fn qname(value: &str) -> Option<(&str, &str)> {
+    let (prefix, local) = unquote(value).split_once(':')?;
+    (chars::is_name(prefix) && chars::is_name(local)).then_some((prefix, local))
+}
+
+/// Get the prefix declared by an attribute, if it is a namespace declaration.
+fn declared(key: &str) -> Option<&str> {
+    match key {
+        "xmlns" => Some(""),
+        _ => key.strip_prefix("xmlns:"),
+    }
+}
+
+fn undeclare(node: &mut Xml, prefixes: &HashMap<String, (Cow<str>, usize, bool)>) {
+    if let Xml::Element(Element {
+        attrs, children, ..
+    }) = node
+    {
+        attrs.retain(|key, _| {
+            key.strip_prefix("xmlns:")
+                .is_none_or(|prefix| !prefixes.contains_key(prefix))
+        });
+        for child in children {
+            undeclare(child, prefixes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn tidy() {
+        let text = r#"<r xmlns:u="urn:unused"><ns1:a xmlns:ns1="urn:a" xmlns:ns2="urn:b"><ns2:b/></ns1:a><ns1:a xmlns:ns1="urn:a"/></r>"#;
+        let mut xml = crate::document(text).unwrap();
+        xml.rename_prefixes(&[("urn:a", "a"), ("urn:b", "b")]);
+        xml.drop_unused_namespaces();
+        xml.hoist_namespaces();
+        assert_eq!(xml.attr("xmlns:a"), Some("\"urn:a\""));
+        assert_eq!(xml.attr("xmlns:u"), None);
+        let a = xml.children().next().unwrap();
+        assert_eq!(a.name(), Some("a:a"));
+        assert_eq!(a.attr("xmlns:a"), None);
+        assert_eq!(a.attr("xmlns:b"), Some("\"urn:b\""));
+        assert!(xml.check_well_formed().is_empty());
+    }
+
+    #[test]
+    fn conflicting_bindings() {
+        let text = r#"<r><x:a xmlns:x="urn:1"/><x:a xmlns:x="urn:2"/><x:a xmlns:x="urn:2"/></r>"#;
+        let mut xml = crate::document(text).unwrap();
+        xml.hoist_namespaces();
+        assert_eq!(xml.attr("xmlns:x"), None);
+        assert!(xml.children().all(|a| a.attr("xmlns:x").is_some()));
+    }
+
+    #[test]
+    fn qname_values() {
+        let text = r#"<a xmlns:ns0="urn:s" xmlns:xsi="urn:i" xsi:type='ns0:string' k="ns1:x"/>"#;
+        let mut xml = crate::document(text).unwrap();
+        xml.rename_prefixes(&[("urn:s", "xs")]);
+        assert_eq!(xml.attr("xsi:type"), Some("'xs:string'"));
+        assert_eq!(xml.attr("k"), Some("\"ns1:x\""));
+        xml.drop_unused_namespaces();
+        assert_eq!(xml.attr("xmlns:xs"), Some("\"urn:s\""));
+    }
+}
@@ -0,0 +1,70 @@
+//! Unicode normalization of text and attribute values.
+
+use crate::{Element, Xml};
+use std::borrow::Cow;
+use unicode_normalization::{UnicodeNormalization, is_nfc};
+
+impl Xml<'_> {
+    /// Normalize the text and attribute values of the tree to Unicode normalization form C,
+    /// returning whether anything changed.
+    ///
+    /// Documents written on some platforms, such as file names from macOS, use decomposed
+    /// characters, e.g. `e` followed by a combining acute accent instead of `é`, so equal
+    /// text compares unequal until normalized. Names, raw nodes and character references are
+    /// left as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut xml = xmlite::document("<file name='cafe\u{301}.txt'>Cafe\u{301}</file>")?;
+    /// assert!(xml.normalize_nfc());
+    /// assert_eq!(xml, xmlite::document("<file name='café.txt'>Café</file>")?);
+    /// assert!(!xml.normalize_nfc());
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn normalize_nfc(&mut self) -> bool {
+        match self {
+            Xml::Text(text) => normalize(text),
+            Xml::Element(Element {
+                attrs, children, ..
+            }) => {
+                let mut changed = false;
+                for value in attrs.values_mut() {
+                    changed |= normalize(value);
+                }
+                for child in children {
+                    changed |= child.normalize_nfc();
+                }
+                changed
+            }
+            Xml::Raw(_) => false,
+        }
+    }
+}
+
+/// Normalize text to NFC in place, returning whether it changed.
+fn normalize(text: &mut Cow<'_, str>) -> bool {
+    if is_nfc(text) {
+        return false;
+    }
+    *text = Cow::Owned(text.nfc().collect());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Xml;
+
+    #[test]
+    fn normalize() {
+        let mut xml = Xml::element("a")
+            .with_attr("k", "'A\u{30A}'")
+            .with_child(Xml::text("\u{212B} &#x301;"));
+        assert!(xml.normalize_nfc());
+        assert_eq!(xml.attr("k"), Some("'\u{C5}'"));
+        assert_eq!(
+            xml.children().next().unwrap().content(),
+            Some("\u{C5} &#x301;")
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Options for parsing.
+
+/// Strategy for continuing after a malformed tag.
+///
+/// Every malformed tag is recorded in the diagnostics, whichever strategy is used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Recovery {
+    /// Skip the malformed tag and continue at the next tag or text.
+    #[default]
+    SkipTag,
+    /// Skip the rest of the content of the element containing the malformed tag, continuing
+    /// at its end tag or that of an ancestor. At the top level, only the malformed tag is
+    /// skipped.
+    SkipElement,
+    /// Stop at the first malformed tag, as if the input ended there.
+    Stop,
+}
+
+/// Options for parsing a document with [`document_with`](crate::document_with), or tags with
+/// [`Tags::with_options`](crate::Tags::with_options).
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "tree")] {
+/// # use xmlite::ParseOptions;
+/// let options = ParseOptions::new().with_comments(true).with_ignore_whitespace(true);
+/// let xml = xmlite::document_with("<a>\n  <!-- note -->\n  <b/>\n</a>", &options)?;
+/// assert_eq!(xml.to_string(), "<a><!-- note --><b/></a>");
+/// # }
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+///
+/// ```rust
+/// # use xmlite::{ParseOptions, Recovery, Tags};
+/// let options = ParseOptions::new().with_recovery(Recovery::SkipElement);
+/// let tags = Tags::new("<a><b><c x=></c>text</b><d/></a>").with_options(&options);
+/// let text = tags.map(|tag| tag.to_string()).collect::<String>();
+/// assert_eq!(text, "<a><b></b><d/></a>");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub(crate) recovery: Recovery,
+    pub(crate) max_errors: Option<usize>,
+    pub(crate) strict: bool,
+    pub(crate) comments: bool,
+    pub(crate) ignore_whitespace: bool,
+    pub(crate) lazy_attrs: bool,
+    pub(crate) namespaces: bool,
+}
+impl ParseOptions {
+    /// Create options with the default behavior of [`Tags::new`](crate::Tags::new).
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// Set the strategy for continuing after a malformed tag.
+    pub fn with_recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Give up after a number of errors, or never with `None`, which is the default.
+    ///
+    /// Once the limit is reached, an [`Error::TooManyErrors`](crate::Error::TooManyErrors) is
+    /// recorded after the errors and the input is treated as if it ended there. This bounds
+    /// the work and diagnostics spent on input which is not XML at all.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{Error, ParseOptions, Tags};
+    /// let options = ParseOptions::new().with_max_errors(Some(100));
+    /// let text = "<a =>".repeat(10_000);
+    /// let mut tags = Tags::new(&text).with_options(&options);
+    /// tags.by_ref().for_each(drop);
+    /// assert_eq!(tags.diags().len(), 101);
+    /// assert!(matches!(tags.diags()[100], Error::TooManyErrors { limit: 100, .. }));
+    /// ```
+    pub fn with_max_errors(mut self, limit: Option<usize>) -> Self {
+        self.max_errors = limit;
+        self
+    }
+
+    /// Enable or disable strict checking, see [`Tags::with_strict`](crate::Tags::with_strict).
+    ///
+    /// When parsing a document, the first problem found is returned as an error, as with
+    /// [`document_strict`](crate::document_strict).
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Enable or disable keeping comments, see [`Tags::with_comments`](crate::Tags::with_comments).
+    ///
+    /// When parsing a document, comments inside the root element are kept as raw nodes.
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Enable or disable dropping text which consists only of whitespace, such as indentation.
+    ///
+    /// Whitespace is kept within elements marked with `xml:space="preserve"`, up to a
+    /// descendant marked with `xml:space="default"`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{ParseOptions, Tags};
+    /// let options = ParseOptions::new().with_ignore_whitespace(true);
+    /// let text = "<a> <pre xml:space='preserve'> <b/> </pre> </a>";
+    /// let tags = Tags::new(text).with_options(&options);
+    /// let text = tags.map(|tag| tag.to_string()).collect::<String>();
+    /// assert_eq!(text, "<a><pre xml:space='preserve'> <b/> </pre></a>");
+    /// ```
+    pub fn with_ignore_whitespace(mut self, ignore_whitespace: bool) -> Self {
+        self.ignore_whitespace = ignore_whitespace;
+        self
+    }
+
+    /// Enable or disable lazily parsed attributes, see
+    /// [`Tags::with_lazy_attrs`](crate::Tags::with_lazy_attrs).
+    pub fn with_lazy_attrs(mut self, lazy: bool) -> Self {
+        self.lazy_attrs = lazy;
+        self
+    }
+
+    /// Enable or disable checking the namespace constraints of a parsed document, namely that
+    /// prefixes are declared before use and reserved prefixes are not rebound.
+    ///
+    /// The tree is checked with [`Xml::check_well_formed`](crate::Xml::check_well_formed), so
+    /// its other constraints, such as valid names and characters and escaped markup, are
+    /// checked as well. The first violation is returned as an error. Only applies when parsing
+    /// a document.
+    pub fn with_namespaces(mut self, namespaces: bool) -> Self {
+        self.namespaces = namespaces;
+        self
+    }
+}
@@ -0,0 +1,214 @@
+//! Parallel parsing and tree iteration.
+
+use crate::{Element, Error, Tag, TagKind, Tags, Xml, document, token::Lexer};
+use rayon::prelude::*;
+use std::borrow::Cow;
+
+/// Parse an XML document, parsing the children of the root element in parallel.
+///
+/// Meant for documents that are a flat list of large sibling records. The content of the root
+/// element is split at the boundaries between its children with a quick structural scan, and
+/// the pieces are parsed on separate threads. The result is the same as with
+/// [`document`](crate::document). The prolog before the root element is skipped. Documents
+/// that cannot be split, e.g. because they are malformed, are parsed sequentially.
+///
+/// # Examples
+///
+/// ```rust
+/// let text = r#"<?xml version="1.0"?><log><entry n="1">a</entry><entry n="2"/>b<entry/></log>"#;
+/// let xml = xmlite::document_par(text).unwrap();
+/// assert_eq!(xml, xmlite::document(text).unwrap());
+/// assert_eq!(xml.children().count(), 4);
+/// ```
+pub fn document_par(text: &str) -> Result<Xml<'_>, Error> {
+    parse_split(text).unwrap_or_else(|| crate::document(text))
+}
+
+/// Parse a document split between threads, or return `None` if it cannot be split.
+fn parse_split(text: &str) -> Option<Result<Xml<'_>, Error>> {
+    let mut tags = Tags::new(text);
+    document::prolog(&mut tags).ok()?;
+    let Some(Tag::Tag {
+        name,
+        attrs,
+        kind: TagKind::Opening,
+    }) = tags.next()
+    else {
+        return None;
+    };
+    let offset = text.len() - tags.lexer.src.len();
+    let (ends, close) = split(&text[offset..])?;
+    let close = offset + close;
+
+    // the closing tag must match, otherwise the sequential parser reports the error
+    let mut closing = Tags::new(&text[close..]);
+    if !closing
+        .next()
+        .is_some_and(|tag| tag.is_closing() && tag.name() == Some(name))
+    {
+        return None;
+    }
+
+    // group the children into a few chunks per thread
+    let target = (close - offset) / (rayon::current_num_threads() * 4) + 1;
+    let mut chunks = vec![];
+    let mut lexer = Lexer::new(text);
+    let mut start = offset;
+    lexer.advance(&text[..offset]);
+    for end in ends.into_iter().map(|end| offset + end) {
+        if end - start >= target {
+            chunks.push((start..end, lexer.report()));
+            lexer.advance(&text[start..end]);
+            start = end;
+        }
+    }
+    chunks.push((start..close, lexer.report()));
+
+    let children = chunks
+        .into_par_iter()
+        .map(|(range, (line, column))| {
+            let mut tags = Tags::new(&text[range]);
+            (tags.lexer.line, tags.lexer.column) = (line, column);
+            let mut children = vec![];
+            while tags.peek().is_some() {
+                children.push(document::element(&mut tags)?);
+            }
+            Ok(children)
+        })
+        .collect::<Result<Vec<_>, Error>>();
+    Some(children.map(|children| {
+        Xml::Element(Element {
+            name: name.into(),
+            attrs: attrs
+                .into_iter()
+                .map(|(k, v)| (Cow::Borrowed(k), Cow::Borrowed(v)))
+                .collect(),
+            children: children.into_iter().flatten().collect(),
+        })
+    }))
+}
+
+/// Find the ends of the top-level elements in the content of an element, and the start of the
+/// closing tag of the element.
+fn split(content: &str) -> Option<(Vec<usize>, usize)> {
+    let mut ends = vec![];
+    let mut depth = 0usize;
+    let mut i = 0;
+    while let Some(at) = content[i..].find('<').map(|at| i + at) {
+        let rest = &content[at..];
+        let skip = |delimiter: &str| rest.find(delimiter).map(|end| at + end + delimiter.len());
+        i = if rest.starts_with("<!--") {
+            skip("-->")?
+        } else if rest.starts_with("<![CDATA[") {
+            skip("]]>")?
+        } else if rest.starts_with("<?") {
+            skip("?>")?
+        } else if rest.starts_with("<!") {
+            skip(">")?
+        } else if rest.starts_with("</") {
+            if depth == 0 {
+                return Some((ends, at));
+            }
+            depth -= 1;
+            skip(">")?
+        } else {
+            let end = at + tag_end(rest)?;
+            if !content[..end].ends_with("/>") {
+                depth += 1;
+            }
+            end
+        };
+        if depth == 0 {
+            ends.push(i);
+        }
+    }
+    None
+}
+
+/// Find the end of a start tag, skipping over quoted attribute values.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, b) in tag.bytes().enumerate() {
+        match (quote, b) {
+            (None, b'"' | b'\'') => quote = Some(b),
+            (Some(q), b) if q == b => quote = None,
+            (None, b'>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+impl<'a> Xml<'a> {
+    /// Iterate over descendants of this node (excludes self) in parallel.
+    ///
+    /// Subtrees are split between threads as they are visited, so per-node work on large
+    /// trees can use all cores. Collecting the iterator yields nodes in the same order as
+    /// [`Xml::descendants`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rayon::prelude::*;
+    ///
+    /// let xml = xmlite::document("<a><b>1</b><b>2</b><c><b>3</b></c></a>").unwrap();
+    /// let sum = xml
+    ///     .par_descendants()
+    ///     .filter_map(|node| node.content()?.parse::<u32>().ok())
+    ///     .sum::<u32>();
+    /// assert_eq!(sum, 6);
+    /// ```
+    pub fn par_descendants(&self) -> impl ParallelIterator<Item = &Xml<'a>> {
+        self.children()
+            .as_slice()
+            .par_iter()
+            .flat_map(|child| rayon::iter::walk_tree_prefix(child, |node| node.children()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let text =
+            "<?xml?>\n<r a='1'>\n  <x b='>'>1<y/></x>\n  <!-- </r> --><x/><?pi?>text<x>2</x>\n</r>";
+        assert_eq!(document_par(text).unwrap(), crate::document(text).unwrap());
+        let records = "<record><field>value</field></record>\n".repeat(1000);
+        let text = format!("<records>{records}</records>");
+        assert_eq!(
+            document_par(&text).unwrap(),
+            crate::document(&text).unwrap()
+        );
+
+        // the prolog is skipped rather than falling back to the sequential parser
+        let text = format!("<?xml version='1.0'?>\n<!-- c -->\n<!DOCTYPE records>\n{text}");
+        let split = parse_split(&text).expect("document should be split");
+        assert_eq!(split.unwrap(), crate::document(&text).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        let text = format!("<r>{}<a>\n</b></r>", "<a></a>\n".repeat(100));
+        let Err(Error::Mismatched { span, .. }) = document_par(&text) else {
+            panic!("expected a mismatched tag");
+        };
+        assert_eq!(span, (102, 5));
+        assert!(document_par("<r><a></a></s>").is_err());
+        assert!(document_par("<r><a></a>").is_err());
+    }
+
+    #[test]
+    fn order() {
+        let xml = crate::document("<a><b><c/>d</b><e><f><g/></f></e>h</a>").unwrap();
+        let parallel = xml.par_descendants().collect::<Vec<_>>();
+        let sequential = xml.descendants().collect::<Vec<_>>();
+        assert_eq!(parallel, sequential);
+        let names = parallel
+            .iter()
+            .map(|n| n.name().unwrap_or("#"))
+            .collect::<String>();
+        assert_eq!(names, "bc#efg#");
+    }
+}
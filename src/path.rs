@@ -0,0 +1,484 @@
+//! Path expressions for selecting nodes.
+
+use crate::{Error, Xml, escape::attr_escaped, validate::child_path};
+use std::{borrow::Cow, slice};
+
+/// Compiled path expression, a small subset of XPath.
+///
+/// Supported syntax:
+/// - `/a/b` absolute and `a/b` relative paths, `//b` and `a//b` descendant steps.
+/// - `*` any element, `text()` text nodes, `.` the context node, `@c` attributes.
+/// - Predicates: `[2]` position, `[@c]` or `[b]` existence, `[@c='v']` and `[b!='v']`
+///   comparison against a string value, and `[not(...)]` negation.
+///
+/// Unlike XPath, positions after a descendant step count across all matched descendants,
+/// so `//b[1]` is the first `b` in the document.
+///
+/// Names are compared exactly, unless the path is made case-insensitive with
+/// [`Path::with_ignore_case`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::Path;
+/// let xml = xmlite::document(r#"<a><b c="1"/><b c="2">x</b><d><b/></d></a>"#).unwrap();
+///
+/// let path = Path::new("/a/b[@c='2']").unwrap();
+/// assert_eq!(path.select(&xml).len(), 1);
+/// assert_eq!(Path::new("//b").unwrap().select(&xml).len(), 3);
+/// assert_eq!(Path::new("b/@c").unwrap().values(&xml), ["1", "2"]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    absolute: bool,
+    steps: Vec<Step>,
+    ignore_case: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    descendant: bool,
+    test: NodeTest,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NodeTest {
+    Name(String),
+    Any,
+    Text,
+    Context,
+    Attribute(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Predicate {
+    Position(usize),
+    Exists(Path),
+    Compare(Path, String, bool),
+    Not(Box<Predicate>),
+}
+
+/// Node or attribute selected by a path, with its location.
+#[derive(Debug, Clone)]
+pub(crate) enum Item<'x, 'a> {
+    Node(&'x Xml<'a>, Cow<'x, str>),
+    Attr(Cow<'x, str>, String),
+}
+impl<'x, 'a> Item<'x, 'a> {
+    /// Get the string value of the item.
+    pub(crate) fn value(&self) -> Cow<'x, str> {
+        match self {
+            Item::Node(node, _) => string_value(node),
+            Item::Attr(value, _) => value.clone(),
+        }
+    }
+    /// Get the path of the item.
+    pub(crate) fn path(&self) -> &str {
+        match self {
+            Item::Node(_, path) => path,
+            Item::Attr(_, path) => path,
+        }
+    }
+}
+
+impl Path {
+    /// Compile a path expression.
+    ///
+    /// Returns an [`Error::Syntax`] locating the offending token for malformed expressions.
+    pub fn new(expr: &str) -> Result<Self, Error> {
+        let mut parser = Parser { expr, pos: 0 };
+        let path = parser.path()?;
+        match parser.rest() {
+            "" => Ok(path),
+            rest => Err(parser.error(rest)),
+        }
+    }
+
+    /// Compare element and attribute names case-insensitively, e.g. for HTML-ish content.
+    ///
+    /// Only ASCII letters are folded. Values in predicates are still compared exactly.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Path;
+    /// let xml = xmlite::document("<HTML><Body><A HREF='x'/></Body></HTML>").unwrap();
+    /// let path = Path::new("/html/body/a/@href").unwrap();
+    /// assert!(path.values(&xml).is_empty());
+    /// assert_eq!(path.with_ignore_case(true).values(&xml), ["x"]);
+    /// ```
+    pub fn with_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.set_ignore_case(ignore_case);
+        self
+    }
+    fn set_ignore_case(&mut self, ignore_case: bool) {
+        self.ignore_case = ignore_case;
+        for predicate in self.steps.iter_mut().flat_map(|step| &mut step.predicates) {
+            predicate.set_ignore_case(ignore_case);
+        }
+    }
+
+    /// Select the nodes matched by this path, in document order.
+    ///
+    /// The document root is the parent of `xml`, so absolute paths start with its name.
+    /// Relative paths are evaluated with `xml` as the context node.
+    pub fn select<'x, 'a>(&self, xml: &'x Xml<'a>) -> Vec<&'x Xml<'a>> {
+        let path = child_path("", slice::from_ref(xml), 0);
+        self.items(xml, xml, &path)
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::Node(node, _) => Some(node),
+                Item::Attr(..) => None,
+            })
+            .collect()
+    }
+
+    /// Select the string values of the nodes and attributes matched by this path.
+    ///
+    /// Elements have the concatenation of their descendant text as their value.
+    pub fn values<'x>(&self, xml: &'x Xml) -> Vec<Cow<'x, str>> {
+        let path = child_path("", slice::from_ref(xml), 0);
+        self.items(xml, xml, &path)
+            .iter()
+            .map(Item::value)
+            .collect()
+    }
+
+    /// Evaluate the path against a context node within a document.
+    pub(crate) fn items<'x, 'a>(
+        &self,
+        root: &'x Xml<'a>,
+        context: &'x Xml<'a>,
+        path: &str,
+    ) -> Vec<Item<'x, 'a>> {
+        let root_path = child_path("", slice::from_ref(root), 0);
+        let mut items = match self.absolute {
+            true => vec![],
+            false => vec![Item::Node(context, Cow::Owned(path.to_owned()))],
+        };
+        for (i, step) in self.steps.iter().enumerate() {
+            let mut next = vec![];
+            if self.absolute && i == 0 {
+                let document = Item::Node(root, Cow::Owned(root_path.clone()));
+                let candidates = match (&step.test, step.descendant) {
+                    (NodeTest::Attribute(_), true) => step.candidates(&document, self.ignore_case),
+                    (NodeTest::Attribute(_), false) => vec![],
+                    (_, true) => descendants(&document, true),
+                    (_, false) => vec![document],
+                };
+                next.extend(step.filter(root, candidates, self.ignore_case));
+            } else {
+                for item in &items {
+                    let candidates = step.candidates(item, self.ignore_case);
+                    next.extend(step.filter(root, candidates, self.ignore_case));
+                }
+            }
+            items = next;
+        }
+        items
+    }
+}
+
+impl Step {
+    /// Collect the items on this step's axis from a context item.
+    fn candidates<'x, 'a>(&self, item: &Item<'x, 'a>, ignore_case: bool) -> Vec<Item<'x, 'a>> {
+        let Item::Node(node, path) = item else {
+            return vec![];
+        };
+        let attr = |node: &'x Xml, name: &str| match ignore_case {
+            true => node.attr_ci(name),
+            false => node.attr(name),
+        };
+        match &self.test {
+            NodeTest::Context => return vec![item.clone()],
+            NodeTest::Attribute(name) if !self.descendant => {
+                return match attr(node, name) {
+                    Some(value) => vec![Item::Attr(attr_escaped(value), format!("{path}/@{name}"))],
+                    None => vec![],
+                };
+            }
+            NodeTest::Attribute(name) => {
+                return descendants(item, true)
+                    .into_iter()
+                    .filter_map(|item| match item {
+                        Item::Node(node, path) => attr(node, name).map(|value| {
+                            Item::Attr(attr_escaped(value), format!("{path}/@{name}"))
+                        }),
+                        _ => None,
+                    })
+                    .collect();
+            }
+            _ => {}
+        }
+        if self.descendant {
+            return descendants(item, false);
+        }
+        let children = node.children().as_slice();
+        (0..children.len())
+            .map(|i| Item::Node(&children[i], child_path(path, children, i).into()))
+            .collect()
+    }
+
+    /// Apply the node test and predicates to candidate items.
+    fn filter<'x, 'a>(
+        &self,
+        root: &'x Xml<'a>,
+        candidates: Vec<Item<'x, 'a>>,
+        ignore_case: bool,
+    ) -> Vec<Item<'x, 'a>> {
+        let named = |node: &Xml, name: &str| match ignore_case {
+            true => node.name().is_some_and(|n| n.eq_ignore_ascii_case(name)),
+            false => node.name() == Some(name),
+        };
+        let mut items = candidates
+            .into_iter()
+            .filter(|item| match (&self.test, item) {
+                (NodeTest::Name(name), Item::Node(node, _)) => named(node, name),
+                (NodeTest::Any, Item::Node(node, _)) => node.is_element(),
+                (NodeTest::Text, Item::Node(node, _)) => node.is_text(),
+                (NodeTest::Context | NodeTest::Attribute(_), _) => true,
+                _ => false,
+            })
+            .collect::<Vec<_>>();
+        for predicate in &self.predicates {
+            items = items
+                .into_iter()
+                .enumerate()
+                .filter(|(i, item)| predicate.matches(root, item, i + 1))
+                .map(|(_, item)| item)
+                .collect();
+        }
+        items
+    }
+}
+
+impl Predicate {
+    fn set_ignore_case(&mut self, ignore_case: bool) {
+        match self {
+            Predicate::Position(_) => {}
+            Predicate::Exists(p) | Predicate::Compare(p, ..) => p.set_ignore_case(ignore_case),
+            Predicate::Not(p) => p.set_ignore_case(ignore_case),
+        }
+    }
+
+    fn matches(&self, root: &Xml, item: &Item, position: usize) -> bool {
+        let Item::Node(node, path) = item else {
+            return false;
+        };
+        match self {
+            Predicate::Position(n) => position == *n,
+            Predicate::Exists(p) => !p.items(root, node, path).is_empty(),
+            Predicate::Compare(p, value, equal) => p
+                .items(root, node, path)
+                .iter()
+                .any(|item| (item.value() == value.as_str()) == *equal),
+            Predicate::Not(p) => !p.matches(root, item, position),
+        }
+    }
+}
+
+/// Collect descendants of an item in document order, optionally including itself.
+fn descendants<'x, 'a>(item: &Item<'x, 'a>, include_self: bool) -> Vec<Item<'x, 'a>> {
+    let mut items = vec![];
+    if include_self {
+        items.push(item.clone());
+    }
+    if let Item::Node(node, path) = item {
+        let children = node.children().as_slice();
+        for i in 0..children.len() {
+            let child = Item::Node(&children[i], child_path(path, children, i).into());
+            items.extend(descendants(&child, true));
+        }
+    }
+    items
+}
+
+/// Get the string value of a node, the concatenation of its descendant text.
+fn string_value<'x>(node: &'x Xml) -> Cow<'x, str> {
+    match node.content() {
+        Some(text) => Cow::Borrowed(text),
+        None => node
+            .descendants()
+            .filter_map(Xml::content)
+            .collect::<String>()
+            .into(),
+    }
+}
+
+struct Parser<'e> {
+    expr: &'e str,
+    pos: usize,
+}
+impl<'e> Parser<'e> {
+    fn rest(&self) -> &'e str {
+        &self.expr[self.pos..]
+    }
+    fn eat(&mut self, s: &str) -> bool {
+        let rest = self.rest().trim_start();
+        if rest.starts_with(s) {
+            self.pos = self.expr.len() - rest.len() + s.len();
+            true
+        } else {
+            false
+        }
+    }
+    fn error(&self, token: &str) -> Error {
+        Error::Syntax {
+            token: token.chars().take(1).collect(),
+            span: (1, self.expr[..self.pos].chars().count() + 1),
+        }
+    }
+    fn name(&mut self) -> Result<&'e str, Error> {
+        let rest = self.rest().trim_start();
+        let len = rest
+            .find(|c| !crate::chars::is_name_char(c))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(rest));
+        }
+        self.pos = self.expr.len() - rest.len() + len;
+        Ok(&rest[..len])
+    }
+
+    fn path(&mut self) -> Result<Path, Error> {
+        let mut steps = vec![];
+        let mut descendant = self.eat("//");
+        let absolute = descendant || self.eat("/");
+        loop {
+            steps.push(self.step(descendant)?);
+            descendant = self.eat("//");
+            if !descendant && !self.eat("/") {
+                break;
+            }
+        }
+        Ok(Path {
+            absolute,
+            steps,
+            ignore_case: false,
+        })
+    }
+
+    fn step(&mut self, descendant: bool) -> Result<Step, Error> {
+        let test = if self.eat("text()") {
+            NodeTest::Text
+        } else if self.eat("*") {
+            NodeTest::Any
+        } else if self.eat(".") {
+            NodeTest::Context
+        } else if self.eat("@") {
+            NodeTest::Attribute(self.name()?.to_owned())
+        } else {
+            NodeTest::Name(self.name()?.to_owned())
+        };
+        let mut predicates = vec![];
+        while self.eat("[") {
+            predicates.push(self.predicate()?);
+            if !self.eat("]") {
+                return Err(self.error(self.rest()));
+            }
+        }
+        Ok(Step {
+            descendant,
+            test,
+            predicates,
+        })
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, Error> {
+        let rest = self.rest().trim_start();
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(0);
+        if digits > 0 {
+            self.pos = self.expr.len() - rest.len() + digits;
+            return rest[..digits]
+                .parse()
+                .map(Predicate::Position)
+                .map_err(|_| self.error(rest));
+        }
+        if self.eat("not(") {
+            let predicate = self.predicate()?;
+            if !self.eat(")") {
+                return Err(self.error(self.rest()));
+            }
+            return Ok(Predicate::Not(predicate.into()));
+        }
+        let path = self.path()?;
+        let equal = if self.eat("!=") {
+            false
+        } else if self.eat("=") {
+            true
+        } else {
+            return Ok(Predicate::Exists(path));
+        };
+        let rest = self.rest().trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'');
+        let Some(end) = quote.and_then(|q| rest[1..].find(q)) else {
+            return Err(self.error(rest));
+        };
+        self.pos = self.expr.len() - rest.len() + end + 2;
+        Ok(Predicate::Compare(path, rest[1..end + 1].to_owned(), equal))
+    }
+}
+
+impl<'a> Xml<'a> {
+    /// Select nodes matching a path expression.
+    ///
+    /// See [`Path`] for the supported syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document(r#"<a><b><c/></b><b/></a>"#).unwrap();
+    /// assert_eq!(xml.select("b[c]").unwrap().len(), 1);
+    /// ```
+    pub fn select<'x>(&'x self, expr: &str) -> Result<Vec<&'x Xml<'a>>, Error> {
+        Ok(Path::new(expr)?.select(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select() {
+        let text = r#"<a><b c="1">x</b><b c="2"><d>y</d></b><e><b/></e></a>"#;
+        let xml = crate::document(text).unwrap();
+        let names = |expr| {
+            Path::new(expr)
+                .unwrap()
+                .items(&xml, &xml, "/a")
+                .iter()
+                .map(|i| i.path().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("/a"), ["/a"]);
+        assert_eq!(names("/a/b[2]/d"), ["/a/b[2]/d"]);
+        assert_eq!(names("//b"), ["/a/b[1]", "/a/b[2]", "/a/e/b"]);
+        assert_eq!(names("b[not(@c='1')]"), ["/a/b[2]"]);
+        assert_eq!(names("*[d!='z']"), ["/a/b[2]"]);
+        assert_eq!(names("b/text()"), ["/a/b[1]/text()"]);
+        assert_eq!(names("//@c"), ["/a/b[1]/@c", "/a/b[2]/@c"]);
+        assert_eq!(names("e/b/."), ["/a/e/b"]);
+    }
+
+    #[test]
+    fn ignore_case() {
+        let xml = crate::document("<A><B C='1'/><b><D/></b></A>").unwrap();
+        let path = Path::new("//b[not(@c)][d]").unwrap().with_ignore_case(true);
+        assert_eq!(path.select(&xml).len(), 1);
+        let path = Path::new("/a/b[@c='1']").unwrap();
+        assert!(path.select(&xml).is_empty());
+        assert_eq!(path.with_ignore_case(true).select(&xml).len(), 1);
+    }
+
+    #[test]
+    fn syntax() {
+        assert!(Path::new("a[").is_err());
+        assert!(Path::new("a[@b=c]").is_err());
+        assert!(matches!(
+            Path::new("a/?"),
+            Err(Error::Syntax { span: (1, 3), .. })
+        ));
+    }
+}
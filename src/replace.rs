@@ -0,0 +1,168 @@
+//! Regular expression replacement over document trees.
+
+use crate::{
+    Element, Xml,
+    escape::{escape, unescape},
+    tag::parsed_quote,
+};
+use regex::Regex;
+use std::borrow::Cow;
+
+impl Xml<'_> {
+    /// Replace every match of a regular expression in the text nodes of the tree, returning
+    /// the number of text nodes changed.
+    ///
+    /// Text is unescaped before matching and the replacements are escaped, so patterns and
+    /// replacements are written against the text as it reads, e.g. `&` rather than `&amp;`.
+    /// The rest of the text is kept as written. The replacement may refer to capture groups,
+    /// as in [`Regex::replace_all`]. Text containing CDATA sections or references other than
+    /// the predefined entities and character references, e.g. `&nbsp;`, is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use regex::Regex;
+    /// let mut xml = xmlite::document("<a><b>see http://old.example/x &amp; http://old.example/y</b></a>")?;
+    /// let regex = Regex::new(r"http://old\.example/(\w+)").unwrap();
+    /// assert_eq!(xml.replace_text(&regex, "https://new.example/$1"), 1);
+    /// assert_eq!(xml.to_string(), "<a><b>see https://new.example/x &amp; https://new.example/y</b></a>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn replace_text(&mut self, regex: &Regex, replacement: &str) -> usize {
+        let mut changed = 0;
+        replace(self, &mut |node| {
+            let Xml::Text(text) = node else {
+                return;
+            };
+            if text.contains("<![CDATA[") {
+                return;
+            }
+            if let Some(new) = replaced(text, regex, replacement) {
+                *text = Cow::Owned(new);
+                changed += 1;
+            }
+        });
+        changed
+    }
+
+    /// Replace every match of a regular expression in the attribute values of the tree,
+    /// returning the number of attribute values changed.
+    ///
+    /// Values are unquoted before matching and keep their quotes, and are otherwise handled
+    /// like the text in [`Xml::replace_text`]. Attribute names are left as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use regex::Regex;
+    /// let mut xml = xmlite::document(r#"<a href="http://old.example/"><img src='http://old.example/i.png'/></a>"#)?;
+    /// let regex = Regex::new(r"^http://old\.example").unwrap();
+    /// assert_eq!(xml.replace_attr_values(&regex, "https://new.example"), 2);
+    /// assert_eq!(xml.attr("href"), Some("\"https://new.example/\""));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn replace_attr_values(&mut self, regex: &Regex, replacement: &str) -> usize {
+        let mut changed = 0;
+        replace(self, &mut |node| {
+            let Xml::Element(Element { attrs, .. }) = node else {
+                return;
+            };
+            for value in attrs.values_mut() {
+                let (quote, escaped) = match parsed_quote(value) {
+                    Some(quote) => (quote, Cow::Borrowed(&value[1..value.len() - 1])),
+                    None => ('"', escape(value)),
+                };
+                if let Some(new) = replaced(&escaped, regex, replacement) {
+                    *value = Cow::Owned(format!("{quote}{new}{quote}"));
+                    changed += 1;
+                }
+            }
+        });
+        changed
+    }
+}
+
+/// Apply a function to a node and all of its descendants.
+fn replace<'a>(node: &mut Xml<'a>, f: &mut impl FnMut(&mut Xml<'a>)) {
+    f(node);
+    if let Xml::Element(Element { children, .. }) = node {
+        for child in children {
+            replace(child, f);
+        }
+    }
+}
+
+/// Replace the matches in escaped text, or `None` if there are none or the text has unknown
+/// references.
+fn replaced(text: &str, regex: &Regex, replacement: &str) -> Option<String> {
+    // unescaped text, with the offsets where each character starts in both texts
+    let (mut unescaped, mut offsets) = (String::with_capacity(text.len()), vec![]);
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let len = match c {
+            '&' => text[i..].find(';')? + 1,
+            c => c.len_utf8(),
+        };
+        let unit = &text[i..i + len];
+        let resolved = unescape(unit);
+        if c == '&' && resolved == unit {
+            return None;
+        }
+        offsets.push((unescaped.len(), i));
+        unescaped.push_str(&resolved);
+        i += len;
+    }
+    offsets.push((unescaped.len(), text.len()));
+    let original = |at| {
+        let found = offsets.binary_search_by_key(&at, |&(unescaped, _)| unescaped);
+        offsets[found.expect("matches end at character boundaries")].1
+    };
+
+    let (mut new, mut last) = (String::with_capacity(text.len()), None);
+    for captures in regex.captures_iter(&unescaped) {
+        let found = captures.get_match();
+        new.push_str(&text[last.unwrap_or(0)..original(found.start())]);
+        let mut expanded = String::new();
+        captures.expand(replacement, &mut expanded);
+        new.push_str(&escape(&expanded));
+        last = Some(original(found.end()));
+    }
+    new.push_str(&text[last?..]);
+    Some(new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaped() {
+        let mut xml = crate::document("<a x='1 &lt; 2'>a &lt; b<![CDATA[a < b]]></a>").unwrap();
+        let regex = Regex::new("<").unwrap();
+        assert_eq!(xml.replace_text(&regex, "&"), 1);
+        assert_eq!(xml.replace_attr_values(&regex, "<="), 1);
+        assert_eq!(xml.attr("x"), Some("'1 &lt;= 2'"));
+        assert_eq!(
+            xml.to_string(),
+            "<a x='1 &lt;= 2'>a &amp; b<![CDATA[a < b]]></a>"
+        );
+        assert_eq!(xml.replace_text(&Regex::new("z").unwrap(), ""), 0);
+    }
+
+    #[test]
+    fn kept() {
+        let mut xml =
+            crate::document(r#"<a x='say "hi" &amp; &#33;'>"q" x &#x21; &amp;</a>"#).unwrap();
+        let regex = Regex::new("[x!]").unwrap();
+        assert_eq!(xml.replace_text(&regex, "<$0>"), 1);
+        assert_eq!(xml.replace_attr_values(&regex, "'"), 1);
+        assert_eq!(
+            xml.to_string(),
+            r#"<a x='say "hi" &amp; &apos;'>"q" &lt;x&gt; &lt;!&gt; &amp;</a>"#
+        );
+
+        let mut xml = crate::document("<a>x &nbsp;</a>").unwrap();
+        assert_eq!(xml.replace_text(&regex, "y"), 0);
+        assert_eq!(xml.to_string(), "<a>x &nbsp;</a>");
+    }
+}
@@ -0,0 +1,164 @@
+//! Streaming tag rewriting.
+
+use crate::{Tag, Tags};
+use std::{borrow::Cow, io};
+
+/// Replacement for a tag in [`rewrite`].
+#[derive(Debug, Clone)]
+pub enum Rewrite<'a> {
+    /// Write a tag.
+    Tag(Tag<'a>),
+    /// Write raw markup as-is.
+    Raw(Cow<'a, str>),
+    /// Write nothing.
+    Drop,
+}
+impl<'a> From<Tag<'a>> for Rewrite<'a> {
+    fn from(tag: Tag<'a>) -> Self {
+        Rewrite::Tag(tag)
+    }
+}
+impl<'a> From<Option<Tag<'a>>> for Rewrite<'a> {
+    fn from(tag: Option<Tag<'a>>) -> Self {
+        tag.map_or(Rewrite::Drop, Rewrite::Tag)
+    }
+}
+impl From<String> for Rewrite<'_> {
+    fn from(raw: String) -> Self {
+        Rewrite::Raw(Cow::Owned(raw))
+    }
+}
+
+/// Stream the tags of a document through a transform and serialize the result.
+///
+/// The transform is called once per tag, in document order, and returns a [`Tag`], an
+/// `Option<Tag>` where `None` drops the tag, or any other [`Rewrite`]. Comments are passed
+/// through as [`Tag::Comment`]. No tree is built, so memory use does not grow with the input.
+///
+/// Attribute values keep their quotes as in the input, and whitespace inside tags is
+/// normalized to single spaces.
+///
+/// # Examples
+///
+/// Renaming elements:
+///
+/// ```rust
+/// # use xmlite::Tag;
+/// let text = r#"<list><item id="1"/><item id="2">two</item></list>"#;
+/// let output = xmlite::rewrite(text, |tag| match tag {
+///     Tag::Tag { name: "item", attrs, kind } => Tag::Tag { name: "entry", attrs, kind },
+///     tag => tag,
+/// });
+/// assert_eq!(output, r#"<list><entry id="1"/><entry id="2">two</entry></list>"#);
+/// ```
+///
+/// Stripping elements with all of their content:
+///
+/// ```rust
+/// # use xmlite::{Tag, TagKind};
+/// let mut depth = 0;
+/// let output = xmlite::rewrite("<a><secret><b/>x</secret><c/></a>", |tag| {
+///     match tag {
+///         Tag::Tag { name: "secret", kind: TagKind::Opening, .. } => depth += 1,
+///         Tag::Tag { name: "secret", kind: TagKind::Closing, .. } => depth -= 1,
+///         _ if depth == 0 => return Some(tag),
+///         _ => {}
+///     }
+///     None
+/// });
+/// assert_eq!(output, "<a><c/></a>");
+/// ```
+pub fn rewrite<'a, R: Into<Rewrite<'a>>>(input: &'a str, f: impl FnMut(Tag<'a>) -> R) -> String {
+    let mut output = Vec::with_capacity(input.len());
+    rewrite_to(input, &mut output, f).expect("writing to a vector cannot fail");
+    String::from_utf8(output).expect("output is built from string slices")
+}
+
+/// Stream the tags of a document through a transform, writing the result to a writer.
+///
+/// See [`rewrite`] for details. The writer is not buffered, so wrap it in a
+/// [`BufWriter`](io::BufWriter) when writing to a file.
+///
+/// # Examples
+///
+/// Redacting attributes:
+///
+/// ```rust
+/// # use xmlite::Tag;
+/// let mut output = vec![];
+/// let text = r#"<user name="alice" password="hunter2"/>"#;
+/// xmlite::rewrite_to(text, &mut output, |tag| match tag {
+///     Tag::Tag { name, attrs, kind } => {
+///         let attrs = attrs
+///             .into_iter()
+///             .map(|(k, v)| (k, if k == "password" { "\"***\"" } else { v }))
+///             .collect();
+///         Tag::Tag { name, attrs, kind }
+///     }
+///     tag => tag,
+/// })?;
+/// assert_eq!(output, br#"<user name="alice" password="***"/>"#);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn rewrite_to<'a, W: io::Write, R: Into<Rewrite<'a>>>(
+    input: &'a str,
+    mut writer: W,
+    mut f: impl FnMut(Tag<'a>) -> R,
+) -> io::Result<()> {
+    for tag in Tags::new(input).with_comments(true) {
+        match f(tag).into() {
+            Rewrite::Tag(tag) => write!(writer, "{tag}")?,
+            Rewrite::Raw(raw) => writer.write_all(raw.as_bytes())?,
+            Rewrite::Drop => {}
+        }
+    }
+    Ok(())
+}
+
+/// Transform for [`rewrite`] and [`rewrite_to`] which removes comments and processing
+/// instructions, e.g. to sanitize documents before hashing or transmitting them.
+///
+/// The XML declaration and document type declaration are kept. Text around the removed
+/// markup, such as the line breaks between prolog items, is kept as well.
+///
+/// # Examples
+///
+/// ```rust
+/// let text = "<?xml version='1.0'?><?style href='a.css'?><a><!-- x --><?pi?>b</a>";
+/// let output = xmlite::rewrite(text, xmlite::strip_comments_and_pis);
+/// assert_eq!(output, "<?xml version='1.0'?><a>b</a>");
+/// ```
+pub fn strip_comments_and_pis(tag: Tag<'_>) -> Option<Tag<'_>> {
+    match tag {
+        Tag::Comment(_) => None,
+        Tag::Declaration { name, .. } if name != "xml" => None,
+        tag => Some(tag),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity() {
+        let text = "<?xml version=\"1.0\"?>\n<a x='1'><!-- note --><b/>text</a>";
+        assert_eq!(rewrite(text, |tag| tag), text);
+    }
+
+    #[test]
+    fn raw() {
+        let output = rewrite("<a><b/></a>", |tag| match tag {
+            Tag::Tag { name: "b", .. } => Rewrite::Raw("<![CDATA[b]]>".into()),
+            tag => tag.into(),
+        });
+        assert_eq!(output, "<a><![CDATA[b]]></a>");
+    }
+
+    #[test]
+    fn strip() {
+        let text = "<!DOCTYPE a><!--c--><a><?xml-stylesheet x?><!----><b/></a><?end?>";
+        let output = rewrite(text, strip_comments_and_pis);
+        assert_eq!(output, "<!DOCTYPE a><a><b/></a>");
+    }
+}
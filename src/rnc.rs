@@ -0,0 +1,890 @@
+//! RELAX NG compact syntax validation.
+
+use crate::{
+    Element, Error, Xml, datatype,
+    token::Lexer,
+    validate::{child_path, local, unquote},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    slice,
+};
+
+/// RELAX NG schema written in the compact syntax.
+///
+/// Supports grammars with `start` and named definitions (including `|=` and `&=`
+/// combination), `element`, `attribute`, `text`, `empty`, `notAllowed`, `mixed`, `list`,
+/// groups, choices, interleaves, the `?`, `*` and `+` operators, literal values and the XML
+/// Schema datatypes with numeric and length parameters. Annotations are skipped.
+///
+/// Names are compared by local name, namespaces are not taken into account.
+/// Validation uses the derivative algorithm, so interleaves and ambiguous choices are
+/// handled exactly.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::RelaxNg;
+/// let rnc = r#"
+///     start = element protocol {
+///         attribute name { xsd:NCName },
+///         element copyright { text }?,
+///         element interface { attribute version { xsd:positiveInteger }, empty }+
+///     }
+/// "#;
+/// let schema = RelaxNg::new(rnc).unwrap();
+///
+/// let xml = xmlite::document(r#"<protocol name="wl"><interface version="1"/></protocol>"#);
+/// assert!(schema.validate(&xml.unwrap()).is_empty());
+///
+/// let xml = xmlite::document(r#"<protocol name="wl"><interface/></protocol>"#);
+/// assert_eq!(schema.validate(&xml.unwrap()).len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RelaxNg {
+    start: Rc<Pattern>,
+    defines: HashMap<String, Rc<Pattern>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Pattern {
+    Empty,
+    NotAllowed,
+    Text,
+    Choice(Rc<Pattern>, Rc<Pattern>),
+    Interleave(Rc<Pattern>, Rc<Pattern>),
+    Group(Rc<Pattern>, Rc<Pattern>),
+    OneOrMore(Rc<Pattern>),
+    Element(NameClass, Rc<Pattern>),
+    Attribute(NameClass, Rc<Pattern>),
+    Data(String, Vec<(String, String)>),
+    Value(String),
+    List(Rc<Pattern>),
+    Ref(String),
+    After(Rc<Pattern>, Rc<Pattern>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NameClass {
+    Name(String),
+    Any,
+    Choice(Vec<NameClass>),
+}
+impl NameClass {
+    fn contains(&self, name: &str) -> bool {
+        match self {
+            NameClass::Name(n) => n == local(name),
+            NameClass::Any => true,
+            NameClass::Choice(ncs) => ncs.iter().any(|nc| nc.contains(name)),
+        }
+    }
+}
+
+impl RelaxNg {
+    /// Read a schema from RELAX NG compact syntax.
+    ///
+    /// Returns an [`Error::Syntax`] for malformed or unsupported schemas, including
+    /// definitions which refer to themselves outside of an element.
+    pub fn new(text: &str) -> Result<Self, Error> {
+        let mut parser = Parser {
+            lexer: Lexer::new(text),
+            peek: None,
+        };
+        parser.schema()
+    }
+
+    /// Validate a document against the schema.
+    ///
+    /// Returns every violation found as an [`Error::Validation`] located by node path.
+    pub fn validate(&self, xml: &Xml) -> Vec<Error> {
+        let mut diags = vec![];
+        let path = child_path("", slice::from_ref(xml), 0);
+        if let Some(p) = self.child(self.start.clone(), xml, &path, &mut diags)
+            && !self.nullable(&p)
+        {
+            diags.push(invalid("document does not match start pattern", &path));
+        }
+        diags
+    }
+
+    /// Compute the derivative of a pattern with respect to a node, reporting errors.
+    ///
+    /// Returns `None` if the node did not match, after an error has been reported.
+    fn child(
+        &self,
+        p: Rc<Pattern>,
+        node: &Xml,
+        path: &str,
+        diags: &mut Vec<Error>,
+    ) -> Option<Rc<Pattern>> {
+        let Xml::Element(Element {
+            name,
+            attrs,
+            children,
+        }) = node
+        else {
+            let text = node.content().unwrap_or_default();
+            let d = self.text(&p, text);
+            if *d == Pattern::NotAllowed {
+                diags.push(invalid("text not allowed", path));
+                return None;
+            }
+            return Some(d);
+        };
+
+        let mut p = self.start_tag_open(&p, name);
+        if *p == Pattern::NotAllowed {
+            diags.push(invalid("unexpected element", path));
+            return None;
+        }
+        for (key, value) in attrs {
+            if key.starts_with("xmlns") {
+                continue;
+            }
+            let path = format!("{path}/@{key}");
+            let d = self.attribute(&p, key, unquote(value), false);
+            if *d != Pattern::NotAllowed {
+                p = d;
+                continue;
+            }
+            let d = self.attribute(&p, key, unquote(value), true);
+            if *d == Pattern::NotAllowed {
+                diags.push(invalid("unexpected attribute", &path));
+            } else {
+                diags.push(invalid("invalid attribute value", &path));
+                p = d;
+            }
+        }
+        let close = self.start_tag_close(&p, false);
+        p = if *close == Pattern::NotAllowed {
+            diags.push(invalid("missing required attribute", path));
+            self.start_tag_close(&p, true)
+        } else {
+            close
+        };
+
+        // children, skipping whitespace unless it is the only content
+        let significant = children
+            .iter()
+            .any(|c| c.is_element() || c.content().is_some_and(|t| !t.trim().is_empty()));
+        if !significant {
+            let text = children.iter().filter_map(Xml::content).collect::<String>();
+            p = choice(p.clone(), self.text(&p, &text));
+        } else {
+            for (i, child) in children.iter().enumerate() {
+                if child.content().is_some_and(|t| t.trim().is_empty()) {
+                    continue;
+                }
+                let path = child_path(path, children, i);
+                if let Some(d) = self.child(p.clone(), child, &path, diags) {
+                    p = d;
+                }
+            }
+        }
+
+        let end = self.end_tag(&p, false);
+        if *end == Pattern::NotAllowed {
+            diags.push(invalid("missing required content", path));
+            return Some(self.end_tag(&p, true));
+        }
+        Some(end)
+    }
+
+    fn resolve(&self, p: &Rc<Pattern>) -> Rc<Pattern> {
+        let mut p = p.clone();
+        while let Pattern::Ref(name) = &*p {
+            p = match self.defines.get(name) {
+                Some(d) => d.clone(),
+                None => Rc::new(Pattern::NotAllowed),
+            };
+        }
+        p
+    }
+
+    fn nullable(&self, p: &Rc<Pattern>) -> bool {
+        match &*self.resolve(p) {
+            Pattern::Group(p1, p2) | Pattern::Interleave(p1, p2) => {
+                self.nullable(p1) && self.nullable(p2)
+            }
+            Pattern::Choice(p1, p2) => self.nullable(p1) || self.nullable(p2),
+            Pattern::OneOrMore(p) => self.nullable(p),
+            Pattern::Empty | Pattern::Text => true,
+            _ => false,
+        }
+    }
+
+    fn text(&self, p: &Rc<Pattern>, text: &str) -> Rc<Pattern> {
+        match &*self.resolve(p) {
+            Pattern::Choice(p1, p2) => choice(self.text(p1, text), self.text(p2, text)),
+            Pattern::Interleave(p1, p2) => choice(
+                interleave(self.text(p1, text), p2.clone()),
+                interleave(p1.clone(), self.text(p2, text)),
+            ),
+            Pattern::Group(p1, p2) => {
+                let d = group(self.text(p1, text), p2.clone());
+                if self.nullable(p1) {
+                    choice(d, self.text(p2, text))
+                } else {
+                    d
+                }
+            }
+            Pattern::After(p1, p2) => after(self.text(p1, text), p2.clone()),
+            Pattern::OneOrMore(p) => group(
+                self.text(p, text),
+                choice(Rc::new(Pattern::OneOrMore(p.clone())), empty()),
+            ),
+            Pattern::Text => Rc::new(Pattern::Text),
+            Pattern::Value(value) if normalize(value) == normalize(text) => empty(),
+            Pattern::Data(name, params) if data(name, params, text) => empty(),
+            Pattern::List(p) => {
+                let d = text
+                    .split_whitespace()
+                    .fold(p.clone(), |p, word| self.text(&p, word));
+                if self.nullable(&d) {
+                    empty()
+                } else {
+                    not_allowed()
+                }
+            }
+            _ => not_allowed(),
+        }
+    }
+
+    fn start_tag_open(&self, p: &Rc<Pattern>, name: &str) -> Rc<Pattern> {
+        match &*self.resolve(p) {
+            Pattern::Choice(p1, p2) => {
+                choice(self.start_tag_open(p1, name), self.start_tag_open(p2, name))
+            }
+            Pattern::Element(nc, p) if nc.contains(name) => after(p.clone(), empty()),
+            Pattern::Interleave(p1, p2) => choice(
+                apply_after(&self.start_tag_open(p1, name), &|d| {
+                    interleave(d, p2.clone())
+                }),
+                apply_after(&self.start_tag_open(p2, name), &|d| {
+                    interleave(p1.clone(), d)
+                }),
+            ),
+            Pattern::OneOrMore(p1) => {
+                let rest = choice(Rc::new(Pattern::OneOrMore(p1.clone())), empty());
+                apply_after(&self.start_tag_open(p1, name), &|d| group(d, rest.clone()))
+            }
+            Pattern::Group(p1, p2) => {
+                let d = apply_after(&self.start_tag_open(p1, name), &|d| group(d, p2.clone()));
+                if self.nullable(p1) {
+                    choice(d, self.start_tag_open(p2, name))
+                } else {
+                    d
+                }
+            }
+            Pattern::After(p1, p2) => {
+                apply_after(&self.start_tag_open(p1, name), &|d| after(d, p2.clone()))
+            }
+            _ => not_allowed(),
+        }
+    }
+
+    /// Compute the derivative for an attribute, any value is allowed if `lenient`.
+    fn attribute(&self, p: &Rc<Pattern>, name: &str, value: &str, lenient: bool) -> Rc<Pattern> {
+        let attribute = |p| self.attribute(p, name, value, lenient);
+        match &*self.resolve(p) {
+            Pattern::After(p1, p2) => after(attribute(p1), p2.clone()),
+            Pattern::Choice(p1, p2) => choice(attribute(p1), attribute(p2)),
+            Pattern::Group(p1, p2) => choice(
+                group(attribute(p1), p2.clone()),
+                group(p1.clone(), attribute(p2)),
+            ),
+            Pattern::Interleave(p1, p2) => choice(
+                interleave(attribute(p1), p2.clone()),
+                interleave(p1.clone(), attribute(p2)),
+            ),
+            Pattern::OneOrMore(p1) => group(
+                attribute(p1),
+                choice(Rc::new(Pattern::OneOrMore(p1.clone())), empty()),
+            ),
+            Pattern::Attribute(nc, p1) if nc.contains(name) => {
+                let matches = lenient
+                    || (self.nullable(p1) && value.trim().is_empty())
+                    || self.nullable(&self.text(p1, value));
+                if matches { empty() } else { not_allowed() }
+            }
+            _ => not_allowed(),
+        }
+    }
+
+    /// Close the start tag, remaining attribute patterns are disallowed unless `lenient`.
+    fn start_tag_close(&self, p: &Rc<Pattern>, lenient: bool) -> Rc<Pattern> {
+        let close = |p| self.start_tag_close(p, lenient);
+        match &*self.resolve(p) {
+            Pattern::After(p1, p2) => after(close(p1), p2.clone()),
+            Pattern::Choice(p1, p2) => choice(close(p1), close(p2)),
+            Pattern::Group(p1, p2) => group(close(p1), close(p2)),
+            Pattern::Interleave(p1, p2) => interleave(close(p1), close(p2)),
+            Pattern::OneOrMore(p1) => one_or_more(close(p1)),
+            Pattern::Attribute(..) if lenient => empty(),
+            Pattern::Attribute(..) => not_allowed(),
+            _ => self.resolve(p),
+        }
+    }
+
+    /// Close an element, incomplete content is disallowed unless `lenient`.
+    fn end_tag(&self, p: &Rc<Pattern>, lenient: bool) -> Rc<Pattern> {
+        match &*self.resolve(p) {
+            Pattern::Choice(p1, p2) => choice(self.end_tag(p1, lenient), self.end_tag(p2, lenient)),
+            Pattern::After(p1, p2) if lenient || self.nullable(p1) => p2.clone(),
+            _ => not_allowed(),
+        }
+    }
+}
+
+fn empty() -> Rc<Pattern> {
+    Rc::new(Pattern::Empty)
+}
+
+fn not_allowed() -> Rc<Pattern> {
+    Rc::new(Pattern::NotAllowed)
+}
+
+fn choice(p1: Rc<Pattern>, p2: Rc<Pattern>) -> Rc<Pattern> {
+    match (&*p1, &*p2) {
+        (Pattern::NotAllowed, _) => p2,
+        (_, Pattern::NotAllowed) => p1,
+        _ if Rc::ptr_eq(&p1, &p2) || p1 == p2 => p1,
+        _ => Rc::new(Pattern::Choice(p1, p2)),
+    }
+}
+
+fn group(p1: Rc<Pattern>, p2: Rc<Pattern>) -> Rc<Pattern> {
+    match (&*p1, &*p2) {
+        (Pattern::NotAllowed, _) | (_, Pattern::NotAllowed) => not_allowed(),
+        (Pattern::Empty, _) => p2,
+        (_, Pattern::Empty) => p1,
+        _ => Rc::new(Pattern::Group(p1, p2)),
+    }
+}
+
+fn interleave(p1: Rc<Pattern>, p2: Rc<Pattern>) -> Rc<Pattern> {
+    match (&*p1, &*p2) {
+        (Pattern::NotAllowed, _) | (_, Pattern::NotAllowed) => not_allowed(),
+        (Pattern::Empty, _) => p2,
+        (_, Pattern::Empty) => p1,
+        _ => Rc::new(Pattern::Interleave(p1, p2)),
+    }
+}
+
+fn after(p1: Rc<Pattern>, p2: Rc<Pattern>) -> Rc<Pattern> {
+    match (&*p1, &*p2) {
+        (Pattern::NotAllowed, _) | (_, Pattern::NotAllowed) => not_allowed(),
+        _ => Rc::new(Pattern::After(p1, p2)),
+    }
+}
+
+fn one_or_more(p: Rc<Pattern>) -> Rc<Pattern> {
+    match &*p {
+        Pattern::NotAllowed => p,
+        _ => Rc::new(Pattern::OneOrMore(p)),
+    }
+}
+
+fn apply_after(p: &Rc<Pattern>, f: &dyn Fn(Rc<Pattern>) -> Rc<Pattern>) -> Rc<Pattern> {
+    match &**p {
+        Pattern::After(p1, p2) => after(p1.clone(), f(p2.clone())),
+        Pattern::Choice(p1, p2) => choice(apply_after(p1, f), apply_after(p2, f)),
+        _ => not_allowed(),
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Check a value against a datatype with parameters.
+fn data(name: &str, params: &[(String, String)], text: &str) -> bool {
+    if !datatype::is_valid(local(name), text) {
+        return false;
+    }
+    let text = text.trim();
+    let length = text.chars().count();
+    let number = text.parse::<f64>().ok();
+    params.iter().all(|(param, value)| {
+        let limit = value.parse::<f64>().ok();
+        match (param.as_str(), number, limit) {
+            ("length", _, Some(l)) => length as f64 == l,
+            ("minLength", _, Some(l)) => length as f64 >= l,
+            ("maxLength", _, Some(l)) => length as f64 <= l,
+            ("minInclusive", Some(n), Some(l)) => n >= l,
+            ("maxInclusive", Some(n), Some(l)) => n <= l,
+            ("minExclusive", Some(n), Some(l)) => n > l,
+            ("maxExclusive", Some(n), Some(l)) => n < l,
+            _ => true,
+        }
+    })
+}
+
+/// Find a definition which refers to itself without an element in between, which would make
+/// resolving references and computing derivatives loop forever.
+fn unguarded<'p>(
+    defines: &'p HashMap<String, Rc<Pattern>>,
+    p: &'p Pattern,
+    stack: &mut Vec<&'p str>,
+    checked: &mut HashSet<&'p str>,
+) -> Option<&'p str> {
+    match p {
+        Pattern::Ref(name) if stack.contains(&name.as_str()) => Some(name),
+        Pattern::Ref(name) if checked.contains(name.as_str()) => None,
+        Pattern::Ref(name) => {
+            stack.push(name);
+            let found = unguarded(defines, defines.get(name)?, stack, checked);
+            stack.pop();
+            checked.insert(name);
+            found
+        }
+        Pattern::Choice(p1, p2)
+        | Pattern::Interleave(p1, p2)
+        | Pattern::Group(p1, p2)
+        | Pattern::After(p1, p2) => unguarded(defines, p1, stack, checked)
+            .or_else(|| unguarded(defines, p2, stack, checked)),
+        Pattern::OneOrMore(p) | Pattern::List(p) | Pattern::Attribute(_, p) => {
+            unguarded(defines, p, stack, checked)
+        }
+        _ => None,
+    }
+}
+
+fn invalid(message: &str, path: &str) -> Error {
+    Error::Validation {
+        message: message.to_owned(),
+        path: path.to_owned(),
+    }
+}
+
+/// Recursive descent parser for the compact syntax.
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peek: Option<(&'a str, (usize, usize))>,
+}
+impl<'a> Parser<'a> {
+    /// Lex the next token, skipping whitespace, comments and annotations.
+    fn token(&mut self) -> Option<(&'a str, (usize, usize))> {
+        if let Some(token) = self.peek.take() {
+            return Some(token);
+        }
+        loop {
+            let src = self.lexer.src;
+            let skip = src.len() - src.trim_start().len();
+            self.bump(skip);
+            let src = self.lexer.src;
+            if src.starts_with('#') {
+                self.bump(src.find('\n').unwrap_or(src.len()));
+                continue;
+            }
+            let span = self.lexer.report();
+            let c = src.chars().next()?;
+            let len = match c {
+                '"' | '\'' => src[1..].find(c).map(|l| l + 2).unwrap_or(src.len()),
+                '|' | '&' if src[1..].starts_with('=') => 2,
+                _ if is_ident(c) => src.find(|c| !is_ident(c)).unwrap_or(src.len()),
+                _ => c.len_utf8(),
+            };
+            let token = &src[..len];
+            self.bump(len);
+            return Some((token, span));
+        }
+    }
+    fn bump(&mut self, len: usize) {
+        let text = &self.lexer.src[..len];
+        self.lexer.advance(text);
+        self.lexer.src = &self.lexer.src[len..];
+    }
+    fn peek(&mut self) -> Option<&'a str> {
+        if self.peek.is_none() {
+            self.peek = self.token();
+        }
+        self.peek.map(|t| t.0)
+    }
+    fn next(&mut self) -> Result<&'a str, Error> {
+        let token = self.token().ok_or(Error::Eof)?;
+        if token.0 == "[" {
+            self.annotation()?;
+            return self.next();
+        }
+        Ok(token.0)
+    }
+    fn error(&self, token: &str) -> Error {
+        Error::Syntax {
+            token: token.to_owned(),
+            span: self.lexer.report(),
+        }
+    }
+    /// Get the content of a quoted literal token.
+    fn literal(&self, token: &str) -> Result<String, Error> {
+        match token.chars().next() {
+            Some(quote @ ('"' | '\'')) if token.len() > 1 && token.ends_with(quote) => {
+                Ok(token[1..token.len() - 1].to_owned())
+            }
+            _ => Err(self.error(token)),
+        }
+    }
+    fn expect(&mut self, expected: &str) -> Result<(), Error> {
+        match self.next()? {
+            t if t == expected => Ok(()),
+            t => Err(self.error(t)),
+        }
+    }
+    /// Skip a bracketed annotation.
+    fn annotation(&mut self) -> Result<(), Error> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.token().ok_or(Error::Eof)?.0 {
+                "[" => depth += 1,
+                "]" => depth -= 1,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+    fn skip_annotations(&mut self) -> Result<(), Error> {
+        while self.peek() == Some("[") {
+            self.token();
+            self.annotation()?;
+        }
+        Ok(())
+    }
+
+    fn schema(&mut self) -> Result<RelaxNg, Error> {
+        // declarations
+        loop {
+            self.skip_annotations()?;
+            match self.peek() {
+                Some("namespace" | "datatypes") => {
+                    self.next()?;
+                    self.next()?;
+                    self.expect("=")?;
+                    self.next()?;
+                }
+                Some("default") => {
+                    self.next()?;
+                    self.expect("namespace")?;
+                    if self.next()? != "=" {
+                        self.expect("=")?;
+                    }
+                    self.next()?;
+                }
+                _ => break,
+            }
+        }
+
+        // a single pattern, or grammar content
+        let mut defines = HashMap::new();
+        let mut start = None;
+        let grammar = match self.peek() {
+            Some("start" | "div" | "include") => true,
+            Some(t) if t.chars().all(is_ident) && !is_keyword(t) => {
+                let saved = (self.lexer.clone(), self.peek);
+                self.next()?;
+                let assign = matches!(self.peek(), Some("=" | "|=" | "&="));
+                (self.lexer, self.peek) = saved;
+                assign
+            }
+            _ => false,
+        };
+        if grammar {
+            self.grammar(&mut defines, &mut start, None)?;
+        } else {
+            start = Some(self.pattern()?);
+        }
+        if let Some(token) = self.peek() {
+            return Err(self.error(token));
+        }
+        let start = start.ok_or_else(|| self.error("start"))?;
+        let mut checked = HashSet::new();
+        for p in defines.values().chain([&start]) {
+            if let Some(name) = unguarded(&defines, p, &mut vec![], &mut checked) {
+                return Err(self.error(name));
+            }
+        }
+        Ok(RelaxNg { start, defines })
+    }
+
+    fn grammar(
+        &mut self,
+        defines: &mut HashMap<String, Rc<Pattern>>,
+        start: &mut Option<Rc<Pattern>>,
+        end: Option<&str>,
+    ) -> Result<(), Error> {
+        loop {
+            self.skip_annotations()?;
+            let name = match self.peek() {
+                None if end.is_none() => return Ok(()),
+                Some(t) if Some(t) == end => {
+                    self.next()?;
+                    return Ok(());
+                }
+                Some("div") => {
+                    self.next()?;
+                    self.expect("{")?;
+                    self.grammar(defines, start, Some("}"))?;
+                    continue;
+                }
+                Some("include") => return Err(self.error("include")),
+                _ => self.next()?,
+            };
+            let op = self.next()?;
+            let pattern = self.pattern()?;
+            let old = match name {
+                "start" => start.take(),
+                _ => defines.remove(name),
+            };
+            let pattern = match (old, op) {
+                (None, "=" | "|=" | "&=") => pattern,
+                (Some(old), "|=") => Rc::new(Pattern::Choice(old, pattern)),
+                (Some(old), "&=") => Rc::new(Pattern::Interleave(old, pattern)),
+                _ => return Err(self.error(name)),
+            };
+            match name {
+                "start" => *start = Some(pattern),
+                _ => drop(defines.insert(name.to_owned(), pattern)),
+            }
+        }
+    }
+
+    fn pattern(&mut self) -> Result<Rc<Pattern>, Error> {
+        let first = self.particle()?;
+        let op = match self.peek() {
+            Some(op @ ("," | "|" | "&")) => op,
+            _ => return Ok(first),
+        };
+        let mut p = first;
+        while self.peek() == Some(op) {
+            self.next()?;
+            let next = self.particle()?;
+            p = match op {
+                "," => Rc::new(Pattern::Group(p, next)),
+                "|" => Rc::new(Pattern::Choice(p, next)),
+                _ => Rc::new(Pattern::Interleave(p, next)),
+            };
+        }
+        match self.peek() {
+            Some(t @ ("," | "|" | "&")) => Err(self.error(t)),
+            _ => Ok(p),
+        }
+    }
+
+    fn particle(&mut self) -> Result<Rc<Pattern>, Error> {
+        let p = self.primary()?;
+        Ok(match self.peek() {
+            Some("?") => {
+                self.next()?;
+                Rc::new(Pattern::Choice(p, empty()))
+            }
+            Some("*") => {
+                self.next()?;
+                Rc::new(Pattern::Choice(Rc::new(Pattern::OneOrMore(p)), empty()))
+            }
+            Some("+") => {
+                self.next()?;
+                Rc::new(Pattern::OneOrMore(p))
+            }
+            _ => p,
+        })
+    }
+
+    fn primary(&mut self) -> Result<Rc<Pattern>, Error> {
+        let token = self.next()?;
+        let braced = |this: &mut Self| -> Result<Rc<Pattern>, Error> {
+            this.expect("{")?;
+            let p = this.pattern()?;
+            this.expect("}")?;
+            Ok(p)
+        };
+        Ok(Rc::new(match token {
+            "element" => {
+                let nc = self.name_class()?;
+                Pattern::Element(nc, braced(self)?)
+            }
+            "attribute" => {
+                let nc = self.name_class()?;
+                Pattern::Attribute(nc, braced(self)?)
+            }
+            "mixed" => Pattern::Interleave(Rc::new(Pattern::Text), braced(self)?),
+            "list" => Pattern::List(braced(self)?),
+            "text" => Pattern::Text,
+            "empty" => Pattern::Empty,
+            "notAllowed" => Pattern::NotAllowed,
+            "(" => {
+                let p = self.pattern()?;
+                self.expect(")")?;
+                return Ok(p);
+            }
+            t if t.starts_with(['"', '\'']) => Pattern::Value(self.literal(t)?),
+            t @ ("string" | "token") => {
+                if self.peek().is_some_and(|t| t.starts_with(['"', '\''])) {
+                    let value = self.next()?;
+                    Pattern::Value(self.literal(value)?)
+                } else {
+                    Pattern::Data(t.to_owned(), self.params()?)
+                }
+            }
+            t if t.contains(':') => {
+                if self.peek().is_some_and(|t| t.starts_with(['"', '\''])) {
+                    let value = self.next()?;
+                    Pattern::Value(self.literal(value)?)
+                } else {
+                    Pattern::Data(t.to_owned(), self.params()?)
+                }
+            }
+            t if t.chars().all(is_ident) && !is_keyword(t) => Pattern::Ref(t.to_owned()),
+            t => return Err(self.error(t)),
+        }))
+    }
+
+    fn params(&mut self) -> Result<Vec<(String, String)>, Error> {
+        let mut params = vec![];
+        if self.peek() != Some("{") {
+            return Ok(params);
+        }
+        self.next()?;
+        while self.peek() != Some("}") {
+            let name = self.next()?;
+            self.expect("=")?;
+            let value = self.next()?;
+            params.push((name.to_owned(), self.literal(value)?));
+        }
+        self.next()?;
+        Ok(params)
+    }
+
+    fn name_class(&mut self) -> Result<NameClass, Error> {
+        let mut ncs = vec![self.name_class_primary()?];
+        while self.peek() == Some("|") {
+            self.next()?;
+            ncs.push(self.name_class_primary()?);
+        }
+        Ok(match ncs.len() {
+            1 => ncs.remove(0),
+            _ => NameClass::Choice(ncs),
+        })
+    }
+
+    fn name_class_primary(&mut self) -> Result<NameClass, Error> {
+        match self.next()? {
+            "*" => Ok(NameClass::Any),
+            "(" => {
+                let nc = self.name_class()?;
+                self.expect(")")?;
+                Ok(nc)
+            }
+            t if t.ends_with(':') => {
+                self.expect("*")?;
+                Ok(NameClass::Any)
+            }
+            t if t.chars().all(is_ident) => {
+                let t = t.strip_prefix('\\').unwrap_or(t);
+                Ok(NameClass::Name(local(t).to_owned()))
+            }
+            t => Err(self.error(t)),
+        }
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    crate::chars::is_name_char(c) || c == '\\'
+}
+
+fn is_keyword(t: &str) -> bool {
+    matches!(
+        t,
+        "attribute"
+            | "default"
+            | "datatypes"
+            | "div"
+            | "element"
+            | "empty"
+            | "external"
+            | "grammar"
+            | "include"
+            | "inherit"
+            | "list"
+            | "mixed"
+            | "namespace"
+            | "notAllowed"
+            | "parent"
+            | "start"
+            | "string"
+            | "text"
+            | "token"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"
+        # wayland protocol subset
+        default namespace = ""
+        datatypes xsd = "http://www.w3.org/2001/XMLSchema-datatypes"
+
+        start = element protocol { attribute name { xsd:NCName }, interface+ }
+        interface = element interface {
+            attribute name { text },
+            attribute version { xsd:int { minInclusive = "1" } },
+            (description? & message*)
+        }
+        message |= element request { arg* }
+        message |= element event { arg* }
+        arg = element arg { attribute type { "int" | "uint" | "string" }, empty }
+        description = [ a:doc [ "ignored" ] ] element description { mixed { element b { text }* } }
+    "#;
+
+    fn validate(text: &str) -> Vec<String> {
+        let schema = RelaxNg::new(SCHEMA).unwrap();
+        let xml = crate::document(text).unwrap();
+        schema
+            .validate(&xml)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let text = r#"<protocol name="wl"><interface name="a" version="2"><request/><description>some <b>bold</b> text</description><event><arg type="int"/></event></interface></protocol>"#;
+        assert_eq!(validate(text), Vec::<String>::new());
+    }
+
+    #[test]
+    fn invalid() {
+        let text = r#"<protocol name="wl"><interface name="a" version="0"><request><arg type="float"/></request><enum/></interface><interface version="1"/></protocol>"#;
+        assert_eq!(
+            validate(text),
+            [
+                "invalid attribute value at /protocol/interface[1]/@version",
+                "invalid attribute value at /protocol/interface[1]/request/arg/@type",
+                "unexpected element at /protocol/interface[1]/enum",
+                "missing required attribute at /protocol/interface[2]",
+            ]
+        );
+        let text = r#"<protocol name="wl" version="1"/>"#;
+        assert_eq!(
+            validate(text),
+            [
+                "unexpected attribute at /protocol/@version",
+                "missing required content at /protocol"
+            ]
+        );
+    }
+
+    #[test]
+    fn syntax() {
+        assert!(RelaxNg::new("element a { text").is_err());
+        assert!(RelaxNg::new("start = element a { b } c").is_err());
+        assert!(RelaxNg::new("element a { text, empty | text }").is_err());
+        assert!(RelaxNg::new("'").is_err());
+        assert!(RelaxNg::new("element a { \"").is_err());
+        assert!(RelaxNg::new("element a { xsd:int { minInclusive = 1 } }").is_err());
+        assert!(RelaxNg::new("start = element a { b } b = c c = b").is_err());
+        assert!(RelaxNg::new("start = element a { b } b = text, b").is_err());
+        assert!(RelaxNg::new("start = b b = element a { b? }").is_ok());
+    }
+}
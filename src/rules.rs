@@ -0,0 +1,157 @@
+//! Rule-based validation of documents.
+
+use crate::{Error, Path, Xml, path::Item, validate::child_path};
+use std::{fmt, slice};
+
+/// Set of assertions evaluated over a document, in the style of Schematron.
+///
+/// Each rule selects context nodes with a [`Path`] and checks a test against every one of
+/// them. Tests are either path expressions, which pass if they select anything, or closures.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::Rules;
+/// let rules = Rules::new()
+///     .with_assert("//interface", "@version", "interfaces must be versioned")?
+///     .with_report("//request[@type='destructor']", "arg", "destructors take no arguments")?
+///     .with_check("//arg", |arg| arg.children().next().is_none(), "args must be empty")?;
+///
+/// let xml = xmlite::document(r#"<protocol><interface><arg>x</arg></interface></protocol>"#)?;
+/// let errors = rules.validate(&xml);
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors[0].to_string(), "interfaces must be versioned at /protocol/interface");
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Default)]
+pub struct Rules<'f> {
+    rules: Vec<Rule<'f>>,
+}
+
+struct Rule<'f> {
+    context: Path,
+    test: Test<'f>,
+    message: String,
+}
+
+enum Test<'f> {
+    Assert(Path),
+    Report(Path),
+    Check(Box<dyn Fn(&Xml) -> bool + 'f>),
+}
+
+impl<'f> Rules<'f> {
+    /// Create an empty rule set.
+    pub fn new() -> Self {
+        Rules { rules: vec![] }
+    }
+
+    /// Add a rule failing when `test` selects nothing from a `context` node.
+    pub fn with_assert(
+        self,
+        context: &str,
+        test: &str,
+        message: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let test = Test::Assert(Path::new(test)?);
+        self.with_rule(context, test, message.into())
+    }
+
+    /// Add a rule failing when `test` selects anything from a `context` node.
+    pub fn with_report(
+        self,
+        context: &str,
+        test: &str,
+        message: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let test = Test::Report(Path::new(test)?);
+        self.with_rule(context, test, message.into())
+    }
+
+    /// Add a rule failing when the `test` closure returns `false` for a `context` node.
+    pub fn with_check(
+        self,
+        context: &str,
+        test: impl Fn(&Xml) -> bool + 'f,
+        message: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let test = Test::Check(Box::new(test));
+        self.with_rule(context, test, message.into())
+    }
+
+    fn with_rule(mut self, context: &str, test: Test<'f>, message: String) -> Result<Self, Error> {
+        self.rules.push(Rule {
+            context: Path::new(context)?,
+            test,
+            message,
+        });
+        Ok(self)
+    }
+
+    /// Evaluate all rules over a document.
+    ///
+    /// Returns an [`Error::Validation`] with the rule message for every failing context node,
+    /// in rule order.
+    pub fn validate(&self, xml: &Xml) -> Vec<Error> {
+        let mut diags = vec![];
+        let path = child_path("", slice::from_ref(xml), 0);
+        for rule in &self.rules {
+            for item in rule.context.items(xml, xml, &path) {
+                let passed = match (&rule.test, &item) {
+                    (Test::Assert(test), Item::Node(node, path)) => {
+                        !test.items(xml, node, path).is_empty()
+                    }
+                    (Test::Report(test), Item::Node(node, path)) => {
+                        test.items(xml, node, path).is_empty()
+                    }
+                    (Test::Check(test), Item::Node(node, _)) => test(node),
+                    (_, Item::Attr(..)) => continue,
+                };
+                if !passed {
+                    diags.push(Error::Validation {
+                        message: rule.message.clone(),
+                        path: item.path().to_owned(),
+                    });
+                }
+            }
+        }
+        diags
+    }
+}
+impl fmt::Debug for Rules<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rules")
+            .field("rules", &self.rules.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules() {
+        let text = r#"<a><b id="1"><c/></b><b><c/><c/></b></a>"#;
+        let xml = crate::document(text).unwrap();
+        let rules = Rules::new()
+            .with_assert("/a/b", "@id", "missing id")
+            .unwrap()
+            .with_report("b", "c[2]", "too many c")
+            .unwrap()
+            .with_check("//c", |c| c.children().next().is_none(), "c must be empty")
+            .unwrap();
+        let errors = rules
+            .validate(&xml)
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(errors, ["missing id at /a/b[2]", "too many c at /a/b[2]"]);
+    }
+
+    #[test]
+    fn invalid_path() {
+        assert!(Rules::new().with_assert("/a[", "b", "").is_err());
+        assert!(Rules::new().with_report("/a", "", "").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! Text and attribute search across document trees.
+
+use crate::{
+    Xml,
+    escape::{attr_text, unescape},
+    validate::child_path,
+};
+use std::{iter, slice};
+
+impl<'a> Xml<'a> {
+    /// Find the text nodes containing a substring, along with their paths.
+    ///
+    /// Text is unescaped before searching, so `&amp;` matches `&`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<a><b>fish &amp; chips</b><c><b>chips</b></c></a>")?;
+    /// let found = xml.find_text("chips");
+    /// let paths = found.iter().map(|(path, _)| path.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(paths, ["/a/b/text()", "/a/c/b/text()"]);
+    /// assert_eq!(xml.find_text("& chips").len(), 1);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn find_text(&self, needle: &str) -> Vec<(String, &Xml<'a>)> {
+        self.find_text_nodes(|text| text.contains(needle))
+    }
+
+    /// Find the text nodes matching a predicate, along with their paths, in document order.
+    ///
+    /// The predicate is called with the unescaped text of each text node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<a><n>12</n><n>x</n><n> 7 </n></a>")?;
+    /// let numbers = xml.find_text_nodes(|text| text.trim().parse::<i32>().is_ok());
+    /// assert_eq!(numbers.len(), 2);
+    /// assert_eq!(numbers[1].0, "/a/n[3]/text()");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn find_text_nodes(
+        &self,
+        mut predicate: impl FnMut(&str) -> bool,
+    ) -> Vec<(String, &Xml<'a>)> {
+        let mut found = vec![];
+        let path = child_path("", slice::from_ref(self), 0);
+        search(self, path, &mut predicate, &mut found);
+        found
+    }
+
+    /// Find the elements whose attribute equals a value, including this element, in document
+    /// order.
+    ///
+    /// Attribute values are unescaped before comparing, so `&amp;` matches `&`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document(r#"<a><b type="x"/><c><b type='x' n="2"/></c><b type="y"/></a>"#)?;
+    /// let found = xml.find_by_attr("type", "x");
+    /// assert_eq!(found.len(), 2);
+    /// assert_eq!(found[1].attr("n"), Some("\"2\""));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn find_by_attr(&self, key: &str, value: &str) -> Vec<&Xml<'a>> {
+        self.find_by_attr_with(key, |v| v == value)
+    }
+
+    /// Find the elements whose attribute starts with a prefix, as with [`Xml::find_by_attr`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document(r#"<a><link href="https://a"/><link href="/b"/></a>"#)?;
+    /// assert_eq!(xml.find_by_attr_prefix("href", "https:").len(), 1);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn find_by_attr_prefix(&self, key: &str, prefix: &str) -> Vec<&Xml<'a>> {
+        self.find_by_attr_with(key, |v| v.starts_with(prefix))
+    }
+
+    /// Find the elements whose attribute contains a substring, as with [`Xml::find_by_attr`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document(r#"<a><p class="note warn"/><p class="note"/></a>"#)?;
+    /// assert_eq!(xml.find_by_attr_containing("class", "warn").len(), 1);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn find_by_attr_containing(&self, key: &str, needle: &str) -> Vec<&Xml<'a>> {
+        self.find_by_attr_with(key, |v| v.contains(needle))
+    }
+
+    /// Find the elements whose attribute matches a predicate, including this element, in
+    /// document order.
+    ///
+    /// The predicate is called with the unquoted and unescaped value of each attribute.
+    pub fn find_by_attr_with(
+        &self,
+        key: &str,
+        mut predicate: impl FnMut(&str) -> bool,
+    ) -> Vec<&Xml<'a>> {
+        iter::once(self)
+            .chain(self.descendants())
+            .filter(|node| {
+                node.attr(key)
+                    .is_some_and(|value| predicate(&attr_text(value)))
+            })
+            .collect()
+    }
+}
+
+fn search<'x, 'a>(
+    node: &'x Xml<'a>,
+    path: String,
+    predicate: &mut impl FnMut(&str) -> bool,
+    found: &mut Vec<(String, &'x Xml<'a>)>,
+) {
+    if let Some(text) = node.content() {
+        if predicate(&unescape(text)) {
+            found.push((path, node));
+        }
+        return;
+    }
+    let children = node.children().as_slice();
+    for (i, child) in children.iter().enumerate() {
+        search(child, child_path(&path, children, i), predicate, found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Xml;
+
+    #[test]
+    fn text_root() {
+        let xml = Xml::text("needle");
+        assert_eq!(xml.find_text("need")[0].0, "/text()");
+        assert!(xml.find_text("haystack").is_empty());
+        assert!(Xml::raw("needle").find_text("needle").is_empty());
+    }
+
+    #[test]
+    fn attr_root() {
+        let xml = Xml::element("a")
+            .with_attr("k", "x &amp; y")
+            .with_child(Xml::element("b").with_attr("k", "x"));
+        assert_eq!(xml.find_by_attr("k", "x &amp; y"), [&xml]);
+        let parsed = crate::document("<a k='x &amp; y'/>").unwrap();
+        assert_eq!(parsed.find_by_attr("k", "x & y"), [&parsed]);
+        assert_eq!(xml.find_by_attr_prefix("k", "x").len(), 2);
+        assert!(xml.find_by_attr("j", "x").is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+//! Persistent document trees with structural sharing.
+
+use crate::{Element, Xml};
+use std::{collections::HashMap, fmt, slice, sync::Arc};
+
+/// XML node with cheap cloning and copy-on-write edits.
+///
+/// Cloning a node only clones a reference. Editing a node through a clone first copies it,
+/// along with the nodes on the path to it, while unchanged subtrees stay shared between the
+/// versions. This makes it cheap to keep many historical versions of a document, e.g. for
+/// undo stacks or audit trails.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::SharedXml;
+/// let xml = xmlite::document("<a><b>old</b><c><d/></c></a>").unwrap();
+/// let v1 = SharedXml::from(&xml);
+/// let mut v2 = v1.clone();
+/// let b = v2.children_mut().next().unwrap();
+/// *b.children_mut().next().unwrap() = SharedXml::text("new");
+///
+/// assert_eq!(v1.to_string(), "<a><b>old</b><c><d/></c></a>");
+/// assert_eq!(v2.to_string(), "<a><b>new</b><c><d/></c></a>");
+/// // the untouched subtree is shared between versions
+/// assert!(v1.children().nth(1).unwrap().ptr_eq(v2.children().nth(1).unwrap()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedXml {
+    /// XML element.
+    Element(Arc<SharedElement>),
+    /// XML text content.
+    Text(Arc<str>),
+    /// Pre-serialized markup, written verbatim.
+    Raw(Arc<str>),
+}
+
+/// Element of a [`SharedXml`] tree.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SharedElement {
+    /// Element name.
+    pub name: String,
+    /// Element attributes.
+    pub attrs: HashMap<String, String>,
+    /// Element children.
+    pub children: Vec<SharedXml>,
+}
+
+impl SharedXml {
+    /// Create a new text node.
+    pub fn text(text: impl Into<Arc<str>>) -> Self {
+        SharedXml::Text(text.into())
+    }
+    /// Create a new element node.
+    pub fn element(name: impl Into<String>) -> Self {
+        SharedXml::Element(Arc::new(SharedElement {
+            name: name.into(),
+            ..SharedElement::default()
+        }))
+    }
+
+    /// Get element name.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            SharedXml::Element(element) => Some(&element.name),
+            SharedXml::Text(_) | SharedXml::Raw(_) => None,
+        }
+    }
+    /// Get element attribute.
+    pub fn attr(&self, key: &str) -> Option<&str> {
+        match self {
+            SharedXml::Element(element) => element.attrs.get(key).map(|s| s.as_str()),
+            SharedXml::Text(_) | SharedXml::Raw(_) => None,
+        }
+    }
+    /// Get text content.
+    pub fn content(&self) -> Option<&str> {
+        match self {
+            SharedXml::Text(text) => Some(text),
+            SharedXml::Element(_) | SharedXml::Raw(_) => None,
+        }
+    }
+    /// Iterate over direct children.
+    pub fn children(&self) -> slice::Iter<'_, SharedXml> {
+        match self {
+            SharedXml::Element(element) => element.children.iter(),
+            SharedXml::Text(_) | SharedXml::Raw(_) => [].iter(),
+        }
+    }
+
+    /// Get mutable access to the element, copying it first if it is shared.
+    pub fn element_mut(&mut self) -> Option<&mut SharedElement> {
+        match self {
+            SharedXml::Element(element) => Some(Arc::make_mut(element)),
+            SharedXml::Text(_) | SharedXml::Raw(_) => None,
+        }
+    }
+    /// Get mutable reference to element attribute, copying the element first if it is shared.
+    pub fn attr_mut(&mut self, key: &str) -> Option<&mut String> {
+        self.element_mut()?.attrs.get_mut(key)
+    }
+    /// Iterate over direct children mutably, copying the element first if it is shared.
+    pub fn children_mut(&mut self) -> slice::IterMut<'_, SharedXml> {
+        match self.element_mut() {
+            Some(element) => element.children.iter_mut(),
+            None => [].iter_mut(),
+        }
+    }
+
+    /// Add attribute to element.
+    pub fn with_attr(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        if let Some(element) = self.element_mut() {
+            element.attrs.insert(key.into(), value.into());
+        }
+        self
+    }
+    /// Add child to element.
+    pub fn with_child(mut self, child: Self) -> Self {
+        if let Some(element) = self.element_mut() {
+            element.children.push(child);
+        }
+        self
+    }
+
+    /// Check if two nodes share the same storage, i.e. one is an unedited clone of the other.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (SharedXml::Element(a), SharedXml::Element(b)) => Arc::ptr_eq(a, b),
+            (SharedXml::Text(a), SharedXml::Text(b)) => Arc::ptr_eq(a, b),
+            (SharedXml::Raw(a), SharedXml::Raw(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Convert into a regular tree, copying every node.
+    pub fn to_xml(&self) -> Xml<'static> {
+        match self {
+            SharedXml::Element(element) => Xml::Element(Element {
+                name: element.name.clone().into(),
+                attrs: element
+                    .attrs
+                    .iter()
+                    .map(|(k, v)| (k.clone().into(), v.clone().into()))
+                    .collect(),
+                children: element.children.iter().map(|c| c.to_xml()).collect(),
+            }),
+            SharedXml::Text(text) => Xml::Text(text.to_string().into()),
+            SharedXml::Raw(raw) => Xml::Raw(raw.to_string().into()),
+        }
+    }
+}
+impl From<&Xml<'_>> for SharedXml {
+    fn from(xml: &Xml<'_>) -> Self {
+        match xml {
+            Xml::Element(Element {
+                name,
+                attrs,
+                children,
+            }) => SharedXml::Element(Arc::new(SharedElement {
+                name: name.to_string(),
+                attrs: attrs
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                children: children.iter().map(SharedXml::from).collect(),
+            })),
+            Xml::Text(text) => SharedXml::Text(text.as_ref().into()),
+            Xml::Raw(raw) => SharedXml::Raw(raw.as_ref().into()),
+        }
+    }
+}
+impl fmt::Display for SharedXml {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_xml().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_on_write() {
+        let xml = crate::document(r#"<a><b c="1"><d/></b><e/></a>"#).unwrap();
+        let v1 = SharedXml::from(&xml);
+        let mut v2 = v1.clone();
+        assert!(v1.ptr_eq(&v2));
+
+        let b = v2.children_mut().next().unwrap();
+        *b.attr_mut("c").unwrap() = "2".to_owned();
+        assert!(!v1.ptr_eq(&v2));
+        assert_eq!(v1.children().next().unwrap().attr("c"), Some("\"1\""));
+        assert_eq!(v2.children().next().unwrap().attr("c"), Some("2"));
+
+        // only the edited path was copied
+        let d1 = v1.children().next().unwrap().children().next().unwrap();
+        let d2 = v2.children().next().unwrap().children().next().unwrap();
+        assert!(d1.ptr_eq(d2));
+        assert!(
+            v1.children()
+                .nth(1)
+                .unwrap()
+                .ptr_eq(v2.children().nth(1).unwrap())
+        );
+        assert_eq!(v1.to_xml(), xml);
+    }
+}
@@ -0,0 +1,264 @@
+//! SOAP 1.1 and 1.2 envelopes.
+
+use crate::{
+    Error, Xml,
+    escape::{escape, unescape_text},
+    validate::{local, unquote},
+};
+
+/// Version of SOAP, identified by the namespace of the envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoapVersion {
+    /// SOAP 1.1.
+    Soap11,
+    /// SOAP 1.2.
+    Soap12,
+}
+impl SoapVersion {
+    /// Get the namespace name of envelopes of this version.
+    pub fn namespace(self) -> &'static str {
+        match self {
+            SoapVersion::Soap11 => "http://schemas.xmlsoap.org/soap/envelope/",
+            SoapVersion::Soap12 => "http://www.w3.org/2003/05/soap-envelope",
+        }
+    }
+
+    /// Build an envelope with header blocks and body content.
+    ///
+    /// The header is left out if there are no header blocks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{SoapVersion, Xml};
+    /// let request = Xml::element("m:GetPrice").with_attr("xmlns:m", "urn:shop");
+    /// let envelope = SoapVersion::Soap12.envelope(vec![], vec![request]);
+    /// assert_eq!(
+    ///     envelope.to_string(),
+    ///     r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope"><soap:Body><m:GetPrice xmlns:m="urn:shop"/></soap:Body></soap:Envelope>"#,
+    /// );
+    /// ```
+    pub fn envelope<'a>(self, headers: Vec<Xml<'a>>, body: Vec<Xml<'a>>) -> Xml<'a> {
+        let mut envelope = Xml::element("soap:Envelope").with_attr("xmlns:soap", self.namespace());
+        if !headers.is_empty() {
+            envelope = envelope.with_child(element("soap:Header", headers));
+        }
+        envelope.with_child(element("soap:Body", body))
+    }
+
+    /// Build a fault to return in the body of an envelope.
+    ///
+    /// The code is a qualified name such as `soap:Server` for SOAP 1.1 or `soap:Receiver` for
+    /// SOAP 1.2, using the `soap` prefix of [`SoapVersion::envelope`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::SoapVersion;
+    /// let fault = SoapVersion::Soap11.fault("soap:Server", "Out of stock");
+    /// let envelope = SoapVersion::Soap11.envelope(vec![], vec![fault]);
+    /// let text = envelope.to_string();
+    /// let parsed = xmlite::document(&text)?;
+    /// let fault = parsed.soap()?.fault().unwrap();
+    /// assert_eq!((fault.code.as_str(), fault.reason.as_str()), ("soap:Server", "Out of stock"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn fault(self, code: &str, reason: &str) -> Xml<'static> {
+        let text = |text: &str| vec![Xml::text(escape(text).into_owned())];
+        match self {
+            SoapVersion::Soap11 => element(
+                "soap:Fault",
+                vec![
+                    element("faultcode", text(code)),
+                    element("faultstring", text(reason)),
+                ],
+            ),
+            SoapVersion::Soap12 => element(
+                "soap:Fault",
+                vec![
+                    element("soap:Code", vec![element("soap:Value", text(code))]),
+                    element(
+                        "soap:Reason",
+                        vec![element("soap:Text", text(reason)).with_attr("xml:lang", "en")],
+                    ),
+                ],
+            ),
+        }
+    }
+}
+
+/// Parts of a SOAP envelope, returned by [`Xml::soap`].
+#[derive(Debug, Clone, Copy)]
+pub struct Envelope<'x, 'a> {
+    /// Version of the envelope.
+    pub version: SoapVersion,
+    /// Header of the envelope, if any.
+    pub header: Option<&'x Xml<'a>>,
+    /// Body of the envelope.
+    pub body: &'x Xml<'a>,
+}
+impl<'x, 'a> Envelope<'x, 'a> {
+    /// Iterate over the header blocks.
+    pub fn headers(&self) -> impl Iterator<Item = &'x Xml<'a>> + use<'x, 'a> {
+        self.header
+            .into_iter()
+            .flat_map(|header| header.children().filter(|node| node.is_element()))
+    }
+
+    /// Get the first element in the body, i.e. the request, response or fault.
+    pub fn payload(&self) -> Option<&'x Xml<'a>> {
+        self.body.children().find(|node| node.is_element())
+    }
+
+    /// Get the fault in the body, if the envelope reports one.
+    pub fn fault(&self) -> Option<Fault> {
+        let fault = self.payload().filter(|node| is(node, "Fault"))?;
+        let fault = match self.version {
+            SoapVersion::Soap11 => Fault {
+                code: text(child(fault, "faultcode")),
+                reason: text(child(fault, "faultstring")),
+                detail: child(fault, "detail").map(Xml::inner_xml),
+            },
+            SoapVersion::Soap12 => Fault {
+                code: text(child(fault, "Code").and_then(|code| child(code, "Value"))),
+                reason: text(child(fault, "Reason").and_then(|reason| child(reason, "Text"))),
+                detail: child(fault, "Detail").map(Xml::inner_xml),
+            },
+        };
+        Some(fault)
+    }
+}
+
+/// Fault reported in a SOAP envelope.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fault {
+    /// Fault code as written, e.g. `soap:Client`.
+    pub code: String,
+    /// Human-readable explanation of the fault. For SOAP 1.2, the first reason is used.
+    pub reason: String,
+    /// Serialized content of the fault detail, if any.
+    pub detail: Option<String>,
+}
+
+impl<'a> Xml<'a> {
+    /// Dissect a SOAP envelope into its header and body.
+    ///
+    /// The version is determined from the namespace of the envelope element.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if this is not a SOAP 1.1 or 1.2 envelope with a body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::SoapVersion;
+    /// let text = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+    ///   <env:Header><t:Trace xmlns:t="urn:trace">42</t:Trace></env:Header>
+    ///   <env:Body><m:Price xmlns:m="urn:shop">9.95</m:Price></env:Body>
+    /// </env:Envelope>"#;
+    /// let xml = xmlite::document(text)?;
+    /// let envelope = xml.soap()?;
+    /// assert_eq!(envelope.version, SoapVersion::Soap12);
+    /// assert_eq!(envelope.headers().count(), 1);
+    /// assert_eq!(envelope.payload().unwrap().name(), Some("m:Price"));
+    /// assert!(envelope.fault().is_none());
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn soap(&self) -> Result<Envelope<'_, 'a>, Error> {
+        let invalid = |message: &str| Error::Validation {
+            message: message.to_owned(),
+            path: "/".to_owned(),
+        };
+        let name = self.name().ok_or_else(|| invalid("expected an element"))?;
+        let prefix = name.split_once(':').map_or("", |(prefix, _)| prefix);
+        let declaration = match prefix {
+            "" => "xmlns".to_owned(),
+            prefix => format!("xmlns:{prefix}"),
+        };
+        let namespace = self.attr(&declaration).map(unquote);
+        let version = [SoapVersion::Soap11, SoapVersion::Soap12]
+            .into_iter()
+            .find(|version| namespace == Some(version.namespace()))
+            .filter(|_| local(name) == "Envelope")
+            .ok_or_else(|| invalid("expected a SOAP envelope"))?;
+
+        // header and body are in the namespace of the envelope
+        let part = |name: &str| {
+            self.children().find(|child| {
+                child.name() == Some(&qualified(prefix, name))
+                    && child
+                        .attr(&declaration)
+                        .is_none_or(|ns| unquote(ns) == version.namespace())
+            })
+        };
+        Ok(Envelope {
+            version,
+            header: part("Header"),
+            body: part("Body").ok_or_else(|| invalid("expected a SOAP body"))?,
+        })
+    }
+}
+
+fn element<'a>(name: &'static str, children: Vec<Xml<'a>>) -> Xml<'a> {
+    children
+        .into_iter()
+        .fold(Xml::element(name), Xml::with_child)
+}
+
+fn qualified(prefix: &str, name: &str) -> String {
+    match prefix {
+        "" => name.to_owned(),
+        prefix => format!("{prefix}:{name}"),
+    }
+}
+
+/// Check whether a node is an element with a local name.
+fn is(node: &Xml, name: &str) -> bool {
+    node.name().map(local) == Some(name)
+}
+
+fn child<'x, 'a>(node: &'x Xml<'a>, name: &str) -> Option<&'x Xml<'a>> {
+    node.children().find(|child| is(child, name))
+}
+
+/// Get the unescaped and trimmed text of an element, with CDATA sections unwrapped.
+fn text(node: Option<&Xml>) -> String {
+    let text = node
+        .into_iter()
+        .flat_map(Xml::children)
+        .filter_map(Xml::content)
+        .map(unescape_text)
+        .collect::<String>();
+    text.trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soap12_fault() {
+        let fault = SoapVersion::Soap12.fault("soap:Sender", "a < b");
+        let envelope = SoapVersion::Soap12.envelope(vec![Xml::element("h")], vec![fault]);
+        let text = envelope.to_string();
+        let xml = crate::document(&text).unwrap();
+        let envelope = xml.soap().unwrap();
+        assert_eq!(envelope.headers().count(), 1);
+        let fault = envelope.fault().unwrap();
+        assert_eq!(fault.code, "soap:Sender");
+        assert_eq!(fault.reason, "a < b");
+        assert_eq!(fault.detail, None);
+
+        let text = "<s:Envelope xmlns:s='http://schemas.xmlsoap.org/soap/envelope/'><s:Body><s:Fault>\
+            <faultcode><![CDATA[s:Client]]></faultcode><faultstring>a &lt; <![CDATA[<b>]]></faultstring>\
+            </s:Fault></s:Body></s:Envelope>";
+        let xml = crate::document(text).unwrap();
+        let fault = xml.soap().unwrap().fault().unwrap();
+        assert_eq!(fault.code, "s:Client");
+        assert_eq!(fault.reason, "a < <b>");
+
+        let wrong = Xml::element("soap:Envelope").with_attr("xmlns:soap", "urn:other");
+        assert!(wrong.soap().is_err());
+    }
+}
@@ -0,0 +1,123 @@
+//! Splitting of documents into records.
+
+use crate::{Error, Tag, TagKind, Tags};
+
+/// Split a document into the serialized records with a given name, without building a tree.
+///
+/// Records are the elements with the given name directly within the root element, which is
+/// how most large exports are laid out. Each record is written as it appears in the document,
+/// including comments, so it can be parsed on its own with [`document`](crate::document)
+/// or passed to another worker.
+///
+/// # Errors
+///
+/// Yields [`Error::Mismatched`] if the tags of a record are not properly nested, or
+/// [`Error::Eof`] if a record is not closed, after which the iterator ends.
+///
+/// # Examples
+///
+/// ```rust
+/// let text = "<export><record id='1'>a</record><meta/><record id='2'><!-- b --></record></export>";
+/// let records = xmlite::split(text, "record").collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(records, ["<record id='1'>a</record>", "<record id='2'><!-- b --></record>"]);
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+pub fn split<'a, 'n>(text: &'a str, name: &'n str) -> Split<'a, 'n> {
+    Split {
+        tags: Tags::new(text).with_comments(true),
+        name,
+        depth: 0,
+        done: false,
+    }
+}
+
+/// Iterator returned by [`split`].
+#[derive(Debug, Clone)]
+pub struct Split<'a, 'n> {
+    tags: Tags<'a>,
+    name: &'n str,
+    // nesting depth outside of records
+    depth: usize,
+    done: bool,
+}
+impl Split<'_, '_> {
+    /// Serialize the rest of a record after its start tag.
+    fn record(&mut self, start: Tag) -> Result<String, Error> {
+        let mut out = start.to_string();
+        let mut open = vec![(self.name, self.tags.span())];
+        while let Some(tag) = self.tags.next() {
+            out.push_str(&tag.to_string());
+            let Tag::Tag { name, kind, .. } = tag else {
+                continue;
+            };
+            match kind {
+                TagKind::Opening => open.push((name, self.tags.span())),
+                TagKind::Closing => {
+                    let (expected, opened) = open.pop().expect("record is open");
+                    if expected != name {
+                        return Err(Error::Mismatched {
+                            expected: expected.to_owned(),
+                            found: name.to_owned(),
+                            span: self.tags.span(),
+                            opened: Some(opened),
+                        });
+                    }
+                    if open.is_empty() {
+                        return Ok(out);
+                    }
+                }
+                TagKind::SelfClosing => {}
+            }
+        }
+        Err(Error::Eof)
+    }
+}
+impl Iterator for Split<'_, '_> {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let tag = self.tags.next()?;
+            let (name, kind) = match &tag {
+                Tag::Tag { name, kind, .. } => (*name, *kind),
+                _ => continue,
+            };
+            let record = self.depth == 1 && name == self.name;
+            match kind {
+                TagKind::Opening if record => {
+                    let record = self.record(tag);
+                    self.done = record.is_err();
+                    return Some(record);
+                }
+                TagKind::SelfClosing if record => return Some(Ok(tag.to_string())),
+                TagKind::Opening => self.depth += 1,
+                TagKind::Closing => self.depth = self.depth.saturating_sub(1),
+                TagKind::SelfClosing => {}
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_records() {
+        let text = "<a><record><record/></record><b><record/></b><record>";
+        let mut records = split(text, "record");
+        assert_eq!(
+            records.next().unwrap().unwrap(),
+            "<record><record/></record>"
+        );
+        assert!(matches!(records.next(), Some(Err(Error::Eof))));
+        assert!(records.next().is_none());
+
+        let mut records = split("<a><record><b></record></a>", "record");
+        assert!(matches!(
+            records.next(),
+            Some(Err(Error::Mismatched { .. }))
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+//! Size and shape statistics of document trees.
+
+use crate::{Element, Xml};
+use std::{borrow::Cow, collections::HashMap, mem};
+
+/// Statistics about a document tree, returned by [`Xml::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of elements.
+    pub elements: usize,
+    /// Number of text nodes.
+    pub texts: usize,
+    /// Deepest nesting of elements, where a lone root element has depth 1.
+    pub max_depth: usize,
+    /// Number of elements with each name.
+    pub names: HashMap<String, usize>,
+    /// Estimated heap memory used by the tree, in bytes.
+    ///
+    /// Strings borrowed from the parsed text are not counted, since they are owned by the
+    /// input rather than the tree.
+    pub heap: usize,
+}
+
+impl Xml<'_> {
+    /// Collect statistics about the tree, e.g. to decide on limits for ingesting unknown
+    /// documents.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<feed><entry><title>A</title></entry><entry/></feed>")?;
+    /// let stats = xml.stats();
+    /// assert_eq!(stats.elements, 4);
+    /// assert_eq!(stats.texts, 1);
+    /// assert_eq!(stats.max_depth, 3);
+    /// assert_eq!(stats.names["entry"], 2);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        collect(self, 1, &mut stats);
+        stats
+    }
+}
+
+fn collect(node: &Xml, depth: usize, stats: &mut Stats) {
+    // heap memory used by a string, if it is owned
+    let owned = |s: &Cow<str>| match s {
+        Cow::Borrowed(_) => 0,
+        Cow::Owned(s) => s.capacity(),
+    };
+    match node {
+        Xml::Element(Element {
+            name,
+            attrs,
+            children,
+        }) => {
+            stats.elements += 1;
+            stats.max_depth = stats.max_depth.max(depth);
+            *stats.names.entry(name.to_string()).or_default() += 1;
+            stats.heap += owned(name)
+                + attrs.capacity() * mem::size_of::<(Cow<str>, Cow<str>)>()
+                + attrs
+                    .iter()
+                    .map(|(k, v)| owned(k) + owned(v))
+                    .sum::<usize>()
+                + children.capacity() * mem::size_of::<Xml>();
+            for child in children {
+                collect(child, depth + 1, stats);
+            }
+        }
+        Xml::Text(text) => {
+            stats.texts += 1;
+            stats.heap += owned(text);
+        }
+        Xml::Raw(raw) => stats.heap += owned(raw),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heap() {
+        let borrowed = crate::document("<a x='1'><b/>text</a>").unwrap();
+        let owned = Xml::element("a".to_owned())
+            .with_attr("x".to_owned(), "1".to_owned())
+            .with_child(Xml::element("b".to_owned()))
+            .with_child(Xml::text("text".to_owned()));
+        assert_eq!(borrowed.stats().max_depth, 2);
+        assert!(owned.stats().heap > borrowed.stats().heap);
+        assert_eq!(Xml::text("t").stats().max_depth, 0);
+    }
+}
@@ -0,0 +1,329 @@
+//! Path expressions evaluated over tag streams.
+
+use crate::{Error, Tag, TagKind, chars, tag::unquote};
+
+/// Compiled path expression for selecting from a stream of tags without building a tree,
+/// see [`TagsExt::select`](crate::TagsExt::select).
+///
+/// Supported syntax, a subset of that of [`Path`](crate::Path):
+/// - `/a/b` absolute paths and `//b` and `a//b` descendant steps. Relative paths match at any
+///   depth, as if they started with `//`.
+/// - `*` any element, and a final `text()` or `@c` step selecting text or an attribute.
+/// - Predicates on attributes: `[@c]` existence, and `[@c='v']` and `[@c!='v']` comparison
+///   against the value as written, without unescaping.
+///
+/// Matching keeps state for each open element, so memory use depends on the nesting depth
+/// of the stream and not its length.
+///
+/// # Examples
+///
+/// ```rust
+/// use xmlite::{PathEvent, StreamPath, TagsExt};
+/// let text = r#"<log><entry level="error">disk full</entry><entry level="info">ok</entry></log>"#;
+/// let path = StreamPath::new("/log/entry[@level='error']/text()")?;
+/// let errors = xmlite::tags(text).select(&path).collect::<Vec<_>>();
+/// assert!(matches!(errors[..], [PathEvent::Text("disk full")]));
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamPath {
+    steps: Vec<Step>,
+    target: Target,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    descendant: bool,
+    // element name, or `None` for any element
+    name: Option<String>,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Predicate {
+    attr: String,
+    // expected value and whether it must be equal, or `None` to check existence
+    value: Option<(String, bool)>,
+}
+
+/// What is selected from the elements matched by the steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Element,
+    // whether the final step is a descendant step
+    Text(bool),
+    Attr(String, bool),
+}
+
+/// Event produced when selecting with a [`StreamPath`].
+#[derive(Debug, Clone)]
+pub enum PathEvent<'a> {
+    /// Start of a matched element, with its start tag, or its only tag if self-closing.
+    Start(Tag<'a>),
+    /// End of a matched element, with its name. Every start is followed by an end, after
+    /// the content of the element.
+    End(&'a str),
+    /// Text selected by a final `text()` step.
+    Text(&'a str),
+    /// Value of an attribute selected by a final `@c` step, with its quotes.
+    Attr(&'a str),
+}
+
+impl StreamPath {
+    /// Compile a path expression.
+    ///
+    /// Returns an [`Error::Syntax`] locating the offending token for malformed expressions and
+    /// for syntax outside the supported subset.
+    pub fn new(expr: &str) -> Result<Self, Error> {
+        let mut parser = Parser { expr, pos: 0 };
+        let mut steps = vec![];
+        let mut descendant = !parser.eat("/") || parser.eat("/");
+        loop {
+            if parser.eat("text()") {
+                return parser.end(steps, Target::Text(descendant));
+            }
+            if parser.eat("@") {
+                let attr = parser.name()?.to_owned();
+                return parser.end(steps, Target::Attr(attr, descendant));
+            }
+            let name = match parser.eat("*") {
+                true => None,
+                false => Some(parser.name()?.to_owned()),
+            };
+            let mut predicates = vec![];
+            while parser.eat("[") {
+                if !parser.eat("@") {
+                    return Err(parser.error());
+                }
+                let attr = parser.name()?.to_owned();
+                let value = match (parser.eat("="), parser.eat("!=")) {
+                    (false, false) => None,
+                    (equal, _) => Some((parser.literal()?.to_owned(), equal)),
+                };
+                if !parser.eat("]") {
+                    return Err(parser.error());
+                }
+                predicates.push(Predicate { attr, value });
+            }
+            steps.push(Step {
+                descendant,
+                name,
+                predicates,
+            });
+            if !parser.eat("/") {
+                return parser.end(steps, Target::Element);
+            }
+            descendant = parser.eat("/");
+        }
+    }
+}
+impl Step {
+    fn matches(&self, name: &str, tag: &Tag) -> bool {
+        self.name.as_ref().is_none_or(|n| n == name)
+            && self.predicates.iter().all(|predicate| {
+                let value = tag.attr(&predicate.attr).map(unquote);
+                match (&predicate.value, value) {
+                    (None, value) => value.is_some(),
+                    (Some((expected, equal)), Some(value)) => (value == expected) == *equal,
+                    (Some(_), None) => false,
+                }
+            })
+    }
+}
+
+struct Parser<'e> {
+    expr: &'e str,
+    pos: usize,
+}
+impl<'e> Parser<'e> {
+    fn rest(&self) -> &'e str {
+        &self.expr[self.pos..]
+    }
+    fn eat(&mut self, s: &str) -> bool {
+        let rest = self.rest().trim_start();
+        if rest.starts_with(s) {
+            self.pos = self.expr.len() - rest.len() + s.len();
+            true
+        } else {
+            false
+        }
+    }
+    fn error(&self) -> Error {
+        Error::Syntax {
+            token: self.rest().trim_start().chars().take(1).collect(),
+            span: (1, self.expr[..self.pos].chars().count() + 1),
+        }
+    }
+    fn name(&mut self) -> Result<&'e str, Error> {
+        let rest = self.rest().trim_start();
+        let len = rest.find(|c| !chars::is_name_char(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error());
+        }
+        self.pos = self.expr.len() - rest.len() + len;
+        Ok(&rest[..len])
+    }
+    fn literal(&mut self) -> Result<&'e str, Error> {
+        let rest = self.rest().trim_start();
+        let Some(quote) = rest.chars().next().filter(|c| matches!(c, '"' | '\'')) else {
+            return Err(self.error());
+        };
+        let Some(len) = rest[1..].find(quote) else {
+            return Err(self.error());
+        };
+        self.pos = self.expr.len() - rest.len() + len + 2;
+        Ok(&rest[1..len + 1])
+    }
+    fn end(&self, steps: Vec<Step>, target: Target) -> Result<StreamPath, Error> {
+        match self.rest().trim() {
+            "" => Ok(StreamPath { steps, target }),
+            _ => Err(self.error()),
+        }
+    }
+}
+
+/// Iterator returned by [`TagsExt::select`](crate::TagsExt::select).
+#[derive(Debug, Clone)]
+pub struct Select<'a, 'p, I> {
+    iter: I,
+    path: &'p StreamPath,
+    // states of the open elements, i.e. the numbers of steps matched, stored back to back
+    states: Vec<usize>,
+    // start of the states of each open element, and whether its end is to be reported
+    frames: Vec<(usize, bool)>,
+    // end of a matched self-closing element
+    pending: Option<&'a str>,
+}
+impl<'p, I> Select<'_, 'p, I> {
+    pub(crate) fn new(iter: I, path: &'p StreamPath) -> Self {
+        Select {
+            iter,
+            path,
+            states: vec![0],
+            frames: vec![],
+            pending: None,
+        }
+    }
+
+    /// Add the states reached by the start tag of an element from those of its parent.
+    fn open(&mut self, parent: usize, name: &str, tag: &Tag) {
+        let start = self.states.len();
+        for i in parent..start {
+            let state = self.states[i];
+            let (next, keep) = match self.path.steps.get(state) {
+                Some(step) => (
+                    step.matches(name, tag).then_some(state + 1),
+                    step.descendant,
+                ),
+                None => (
+                    None,
+                    matches!(self.path.target, Target::Text(true) | Target::Attr(_, true)),
+                ),
+            };
+            for reached in next.into_iter().chain(keep.then_some(state)) {
+                if !self.states[start..].contains(&reached) {
+                    self.states.push(reached);
+                }
+            }
+        }
+    }
+}
+impl<'a, I: Iterator<Item = Tag<'a>>> Iterator for Select<'a, '_, I> {
+    type Item = PathEvent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(name) = self.pending.take() {
+            return Some(PathEvent::End(name));
+        }
+        let full = self.path.steps.len();
+        loop {
+            let tag = self.iter.next()?;
+            let parent = self.frames.last().map_or(0, |&(start, _)| start);
+            match tag {
+                Tag::Text(text) => {
+                    let matched = !self.frames.is_empty() && self.states[parent..].contains(&full);
+                    if matched && matches!(self.path.target, Target::Text(_)) {
+                        return Some(PathEvent::Text(text));
+                    }
+                }
+                Tag::Tag {
+                    name,
+                    kind: TagKind::Closing,
+                    ..
+                } => {
+                    if let Some((start, matched)) = self.frames.pop() {
+                        self.states.truncate(start);
+                        if matched {
+                            return Some(PathEvent::End(name));
+                        }
+                    }
+                }
+                Tag::Tag {
+                    name,
+                    ref attrs,
+                    kind,
+                } => {
+                    let start = self.states.len();
+                    self.open(parent, name, &tag);
+                    let matched = self.states[start..].contains(&full);
+                    let element = matched && self.path.target == Target::Element;
+                    if kind.is_self_closing() {
+                        self.states.truncate(start);
+                        self.pending = element.then_some(name);
+                    } else {
+                        self.frames.push((start, element));
+                    }
+                    match &self.path.target {
+                        _ if !matched => {}
+                        Target::Element => return Some(PathEvent::Start(tag)),
+                        Target::Attr(attr, _) => {
+                            if let Some(value) = attrs.get(attr) {
+                                return Some(PathEvent::Attr(value));
+                            }
+                        }
+                        Target::Text(_) => {}
+                    }
+                }
+                Tag::Comment(_) | Tag::Doctype(_) | Tag::Declaration { .. } => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TagsExt;
+
+    fn select(expr: &str, text: &str) -> Vec<String> {
+        let path = StreamPath::new(expr).unwrap();
+        let events = crate::tags(text).select(&path).map(|event| match event {
+            PathEvent::Start(tag) => tag.to_string(),
+            PathEvent::End(name) => format!("/{name}"),
+            PathEvent::Text(text) | PathEvent::Attr(text) => text.to_owned(),
+        });
+        events.collect()
+    }
+
+    #[test]
+    fn steps() {
+        let text = r#"<a><b c="1">x<b c="2"/></b><d><b>y</b></d></a>"#;
+        assert_eq!(select("/a/b", text), [r#"<b c="1">"#, "/b"]);
+        assert_eq!(select("//b", text).len(), 6);
+        assert_eq!(select("b[@c!='1']", text), [r#"<b c="2"/>"#, "/b"]);
+        assert_eq!(select("/a/*/b/text()", text), ["y"]);
+        assert_eq!(select("/a//text()", text), ["x", "y"]);
+        assert_eq!(select("//@c", text), ["\"1\"", "\"2\""]);
+        assert_eq!(select("/b", text), Vec::<String>::new());
+    }
+
+    #[test]
+    fn syntax() {
+        for expr in ["", "/a/", "a[b]", "a[@b=c]", "text()/a", "@a[@b]", "a]"] {
+            assert!(
+                matches!(StreamPath::new(expr), Err(Error::Syntax { .. })),
+                "{expr}"
+            );
+        }
+    }
+}
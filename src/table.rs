@@ -0,0 +1,230 @@
+//! Extraction of records into rows of fields, e.g. for CSV.
+
+use crate::{
+    Error, Path, PathEvent, Select, StreamPath, Tag, Xml, escape::unescape, path::Item,
+    validate::child_path,
+};
+use std::{cell::RefCell, io, mem, rc::Rc, slice};
+
+/// Extraction of records from documents into rows of string fields, for flattening exports
+/// into spreadsheets.
+///
+/// Records are selected with a [`Path`], and each field is the first value selected by
+/// another path relative to the record, or empty if there is none. Values are unescaped, and
+/// elements have their descendant text as their value.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::Table;
+/// let xml = xmlite::document(r#"<orders>
+///   <order id="1"><customer>Ann &amp; Bo</customer><total>9.50</total></order>
+///   <order id="2"><total>3</total></order>
+/// </orders>"#)?;
+/// let table = Table::new("/orders/order", &["@id", "customer", "total"])?;
+/// let rows = table.rows(&xml).collect::<Vec<_>>();
+/// assert_eq!(rows, [["1", "Ann & Bo", "9.50"], ["2", "", "3"]]);
+///
+/// let mut csv = vec![];
+/// table.write_csv(rows, &mut csv)?;
+/// assert_eq!(csv, b"@id,customer,total\r\n1,Ann & Bo,9.50\r\n2,,3\r\n");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    records: Path,
+    // the record path for streams, or the expression if it is outside the supported subset
+    stream: Result<StreamPath, String>,
+    fields: Vec<(String, Path)>,
+}
+impl Table {
+    /// Compile the record path and the field paths.
+    ///
+    /// Returns an [`Error::Syntax`] for malformed expressions.
+    pub fn new(records: &str, fields: &[&str]) -> Result<Self, Error> {
+        let fields = fields
+            .iter()
+            .map(|field| Ok((field.to_string(), Path::new(field)?)));
+        Ok(Table {
+            records: Path::new(records)?,
+            stream: StreamPath::new(records).map_err(|_| records.to_owned()),
+            fields: fields.collect::<Result<_, Error>>()?,
+        })
+    }
+
+    /// Get the field expressions, e.g. for a header row.
+    pub fn header(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|(expr, _)| expr.as_str())
+    }
+    /// Extract the fields of a record.
+    pub fn row(&self, record: &Xml) -> Vec<String> {
+        let path = child_path("", slice::from_ref(record), 0);
+        let value = |field: &Path| match field.items(record, record, &path).first() {
+            Some(Item::Node(node, _)) => node.text_content(),
+            Some(Item::Attr(value, _)) => unescape(value).into_owned(),
+            None => String::new(),
+        };
+        self.fields.iter().map(|(_, field)| value(field)).collect()
+    }
+    /// Iterate over the rows of the records in a tree, in document order.
+    pub fn rows(&self, xml: &Xml) -> impl Iterator<Item = Vec<String>> {
+        let records = self.records.select(xml);
+        records.into_iter().map(|record| self.row(record))
+    }
+    /// Iterate over the rows of the records in a stream of tags, building a tree for one
+    /// record at a time.
+    ///
+    /// The record path is matched as with [`StreamPath`], and records nested in other records
+    /// are only extracted as part of the outer record.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Syntax`] if the record path is not supported by [`StreamPath`],
+    /// and yields an error for each record whose tags are not properly nested.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Table;
+    /// let text = "<log><entry at='1'>start</entry><entry at='2'>stop</entry></log>";
+    /// let table = Table::new("entry", &["@at", "."])?;
+    /// let rows = table.stream_rows(xmlite::tags(text))?.collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(rows, [["1", "start"], ["2", "stop"]]);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn stream_rows<'a, I: IntoIterator<Item = Tag<'a>>>(
+        &self,
+        tags: I,
+    ) -> Result<TableRows<'a, '_, I::IntoIter>, Error> {
+        let path = match &self.stream {
+            Ok(path) => path,
+            Err(expr) => return Err(StreamPath::new(expr).unwrap_err()),
+        };
+        let recorder = Recorder {
+            iter: tags.into_iter(),
+            buffer: Rc::default(),
+        };
+        Ok(TableRows {
+            table: self,
+            buffer: recorder.buffer.clone(),
+            select: Select::new(recorder, path),
+            start: None,
+            depth: 0,
+        })
+    }
+
+    /// Write a header row and rows as CSV, quoting fields as needed.
+    pub fn write_csv(
+        &self,
+        rows: impl IntoIterator<Item = Vec<String>>,
+        mut writer: impl io::Write,
+    ) -> io::Result<()> {
+        write_csv_row(&mut writer, self.header())?;
+        for row in rows {
+            write_csv_row(&mut writer, row.iter().map(String::as_str))?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a row of CSV, as in RFC 4180.
+fn write_csv_row<'f>(
+    writer: &mut impl io::Write,
+    fields: impl Iterator<Item = &'f str>,
+) -> io::Result<()> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        match field.contains([',', '"', '\r', '\n']) {
+            true => write!(writer, "\"{}\"", field.replace('"', "\"\""))?,
+            false => writer.write_all(field.as_bytes())?,
+        }
+    }
+    writer.write_all(b"\r\n")
+}
+
+/// Iterator returned by [`Table::stream_rows`].
+#[derive(Debug)]
+pub struct TableRows<'a, 't, I> {
+    table: &'t Table,
+    select: Select<'a, 't, Recorder<'a, I>>,
+    buffer: Rc<RefCell<Option<Vec<Tag<'a>>>>>,
+    // start tag of the current record
+    start: Option<Tag<'a>>,
+    // nesting depth of matched records
+    depth: usize,
+}
+impl<'a, I: Iterator<Item = Tag<'a>>> Iterator for TableRows<'a, '_, I> {
+    type Item = Result<Vec<String>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.select.next()? {
+                PathEvent::Start(tag) => {
+                    if self.depth == 0 {
+                        self.start = Some(tag);
+                        *self.buffer.borrow_mut() = Some(vec![]);
+                    }
+                    self.depth += 1;
+                }
+                PathEvent::End(_) => {
+                    self.depth -= 1;
+                    if self.depth > 0 {
+                        continue;
+                    }
+                    let tags = mem::take(&mut *self.buffer.borrow_mut()).unwrap_or_default();
+                    let record = Xml::from_tags(self.start.take().into_iter().chain(tags));
+                    return Some(record.map(|record| self.table.row(&record)));
+                }
+                PathEvent::Text(_) | PathEvent::Attr(_) => {}
+            }
+        }
+    }
+}
+
+/// Tag iterator which copies the tags it passes on to a buffer, while recording.
+#[derive(Debug)]
+struct Recorder<'a, I> {
+    iter: I,
+    buffer: Rc<RefCell<Option<Vec<Tag<'a>>>>>,
+}
+impl<'a, I: Iterator<Item = Tag<'a>>> Iterator for Recorder<'a, I> {
+    type Item = Tag<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tag = self.iter.next()?;
+        if let Some(buffer) = self.buffer.borrow_mut().as_mut() {
+            buffer.push(tag.clone());
+        }
+        Some(tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream() {
+        let text = r#"<a><r k="x,&quot;y&quot;"><r k="inner"/></r><b><r/></b><r k="2"/></a>"#;
+        let table = Table::new("//r", &["@k", "r/@k"]).unwrap();
+        let xml = crate::document(text).unwrap();
+        let rows = table.rows(&xml).collect::<Vec<_>>();
+        assert_eq!(rows.len(), 4);
+        let streamed = table.stream_rows(crate::tags(text)).unwrap();
+        let streamed = streamed.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            streamed,
+            [rows[0].clone(), rows[2].clone(), rows[3].clone()]
+        );
+
+        let mut csv = vec![];
+        table.write_csv(streamed, &mut csv).unwrap();
+        let expected = "@k,r/@k\r\n\"x,\"\"y\"\"\",inner\r\n,\r\n2,\r\n";
+        assert_eq!(String::from_utf8(csv).unwrap(), expected);
+
+        let table = Table::new("//r[1]", &[]).unwrap();
+        assert!(table.stream_rows(std::iter::empty()).is_err());
+    }
+}
@@ -1,28 +1,214 @@
-use crate::{Error, token::Lexer};
-use std::collections::HashMap;
+use crate::{
+    Error, ParseOptions, Recovery, chars,
+    token::{Lexer, TokenKind},
+};
+use std::{collections::VecDeque, fmt, iter, slice};
 
 /// XML tag or text.
+///
+/// More kinds of markup may be produced in the future, so matches need a wildcard arm.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Tag<'a> {
     /// Tag.
     Tag {
         /// Tag name.
         name: &'a str,
         /// Tag attributes
-        attrs: HashMap<&'a str, &'a str>,
+        attrs: Attrs<'a>,
         /// Whether the tag is closing.
         kind: TagKind,
     },
     /// Text content.
     Text(&'a str),
+    /// Comment (`<!-- ... -->`), without its delimiters.
+    ///
+    /// Only produced when enabled with [`Tags::with_comments`].
+    Comment(&'a str),
+    /// Document type declaration (`<!DOCTYPE ...>`), without its delimiters and the
+    /// whitespace after `DOCTYPE`, e.g. `html` or `note [<!ATTLIST note lang CDATA "en">]`.
+    Doctype(&'a str),
     /// Declaration (`<?xml ... ?>`).
     Declaration {
         /// Declaration name (typically just `xml`).
         name: &'a str,
         /// Declaration attributes.
-        attrs: HashMap<&'a str, &'a str>,
+        attrs: Attrs<'a>,
     },
 }
+/// Number of attributes stored without allocating.
+const INLINE: usize = 4;
+
+/// Attributes of a tag.
+///
+/// Most tags have only a few attributes, so the first ones are stored inline and looked up
+/// with a linear scan, avoiding an allocation per tag.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::Tag;
+/// let tag = xmlite::tags(r#"<a x="1" y="2"/>"#).next().unwrap();
+/// let Tag::Tag { attrs, .. } = tag else { unreachable!() };
+/// assert_eq!(attrs.get("y"), Some("\"2\""));
+/// assert_eq!(attrs.iter().map(|(k, _)| k).collect::<Vec<_>>(), ["x", "y"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Attrs<'a> {
+    inline: [(&'a str, &'a str); INLINE],
+    len: usize,
+    spill: Vec<(&'a str, &'a str)>,
+    // unparsed attribute markup, parsed on each access until the list is modified
+    raw: &'a str,
+}
+impl<'a> Attrs<'a> {
+    /// Create an empty attribute list.
+    pub fn new() -> Self {
+        Attrs::default()
+    }
+    /// Create an attribute list from the unparsed markup between a tag name and the end of the
+    /// tag, e.g. ` x="1" y='2'`.
+    ///
+    /// The markup is parsed each time the attributes are accessed, and only stored once the
+    /// list is modified. Unlike with [`Attrs::insert`], duplicate attributes are kept, and
+    /// lookups return the last value. Parsing stops at the first malformed attribute.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Attrs;
+    /// let attrs = Attrs::from_raw(r#" x="1" y='2' x="3""#);
+    /// assert_eq!(attrs.get("x"), Some("\"3\""));
+    /// assert_eq!(attrs.len(), 3);
+    /// ```
+    pub fn from_raw(raw: &'a str) -> Self {
+        Attrs {
+            raw,
+            ..Attrs::default()
+        }
+    }
+
+    /// Get the value of an attribute.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        match self.raw {
+            "" => self.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            raw => RawAttrs(raw)
+                .filter(|(k, _)| *k == key)
+                .last()
+                .map(|(_, v)| v),
+        }
+    }
+    /// Check if an attribute is present.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+    /// Set an attribute, returning the previous value if it was already present.
+    pub fn insert(&mut self, key: &'a str, value: &'a str) -> Option<&'a str> {
+        for (k, v) in RawAttrs(std::mem::take(&mut self.raw)) {
+            self.insert(k, v);
+        }
+        let len = self.len.min(INLINE);
+        let mut slots = self.inline[..len].iter_mut().chain(&mut self.spill);
+        if let Some(slot) = slots.find(|(k, _)| *k == key) {
+            return Some(std::mem::replace(&mut slot.1, value));
+        }
+        match self.inline.get_mut(self.len) {
+            Some(slot) => *slot = (key, value),
+            None => self.spill.push((key, value)),
+        }
+        self.len += 1;
+        None
+    }
+
+    /// Get the number of attributes.
+    pub fn len(&self) -> usize {
+        match self.raw {
+            "" => self.len,
+            raw => RawAttrs(raw).count(),
+        }
+    }
+    /// Check if there are no attributes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0 && RawAttrs(self.raw).next().is_none()
+    }
+    /// Iterate over attributes in document order.
+    pub fn iter(&self) -> <&Self as IntoIterator>::IntoIter {
+        self.into_iter()
+    }
+}
+impl<'a> IntoIterator for Attrs<'a> {
+    type Item = (&'a str, &'a str);
+    type IntoIter = iter::Chain<
+        iter::Chain<
+            iter::Take<std::array::IntoIter<Self::Item, INLINE>>,
+            std::vec::IntoIter<Self::Item>,
+        >,
+        RawAttrs<'a>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let inline = self.inline.into_iter().take(self.len);
+        inline.chain(self.spill).chain(RawAttrs(self.raw))
+    }
+}
+impl<'a, 'b> IntoIterator for &'b Attrs<'a> {
+    type Item = (&'a str, &'a str);
+    type IntoIter = iter::Chain<
+        iter::Copied<iter::Chain<slice::Iter<'b, Self::Item>, slice::Iter<'b, Self::Item>>>,
+        RawAttrs<'a>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let inline = &self.inline[..self.len.min(INLINE)];
+        let stored = inline.iter().chain(&self.spill).copied();
+        stored.chain(RawAttrs(self.raw))
+    }
+}
+
+/// Iterator over the attributes in unparsed attribute markup, see [`Attrs::from_raw`].
+#[derive(Debug, Clone)]
+pub struct RawAttrs<'a>(&'a str);
+impl<'a> Iterator for RawAttrs<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let src = skip(self.0);
+        let len = src.find(|c| !chars::is_name_char(c)).unwrap_or(src.len());
+        let (key, rest) = src.split_at(len);
+        self.0 = "";
+        if key.is_empty() {
+            return None;
+        }
+        let Some(value) = skip(rest).strip_prefix('=') else {
+            self.0 = rest;
+            return Some((key, ""));
+        };
+        let value = skip(value);
+        let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let end = value[1..].find(quote)? + 2;
+        self.0 = &value[end..];
+        Some((key, &value[..end]))
+    }
+}
+
+/// Skip whitespace and comments at the start of attribute markup, as when lexing.
+fn skip(src: &str) -> &str {
+    let src = src.trim_start();
+    match src.strip_prefix("<!--") {
+        Some(rest) => skip(rest.find("-->").map_or("", |end| &rest[end + 3..])),
+        None => src,
+    }
+}
+impl<'a> FromIterator<(&'a str, &'a str)> for Attrs<'a> {
+    fn from_iter<I: IntoIterator<Item = (&'a str, &'a str)>>(iter: I) -> Self {
+        let mut attrs = Attrs::new();
+        for (key, value) in iter {
+            attrs.insert(key, value);
+        }
+        attrs
+    }
+}
+
 /// Tag kind.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TagKind {
@@ -59,7 +245,7 @@ impl<'a> Tag<'a> {
     /// Get element attribute.
     pub fn attr(&self, key: &str) -> Option<&str> {
         if let Tag::Tag { ref attrs, .. } = *self {
-            attrs.get(key).map(|s| s.as_ref())
+            attrs.get(key)
         } else {
             None
         }
@@ -98,11 +284,42 @@ impl<'a> Tag<'a> {
     }
     /// Check if tag is text
     pub fn is_text(&self) -> bool {
-        if let Tag::Text(..) = self {
-            true
-        } else {
-            false
+        matches!(self, Tag::Text(..))
+    }
+}
+impl fmt::Display for Tag<'_> {
+    /// Write the tag as markup.
+    ///
+    /// Attributes are written in their original order with their original quotes, separated by
+    /// single spaces, so unmodified tags can be re-emitted when rewriting a stream.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let text = r#"<?xml version="1.0"?><a x='1' y="2">text<b/></a>"#;
+    /// let tags = xmlite::tags(text).map(|tag| tag.to_string()).collect::<String>();
+    /// assert_eq!(tags, text);
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (open, name, attrs, close) = match self {
+            Tag::Tag { name, attrs, kind } => match kind {
+                TagKind::Opening => ("<", name, attrs, ">"),
+                TagKind::Closing => ("</", name, attrs, ">"),
+                TagKind::SelfClosing => ("<", name, attrs, "/>"),
+            },
+            Tag::Declaration { name, attrs } => ("<?", name, attrs, "?>"),
+            Tag::Text(text) => return f.write_str(text),
+            Tag::Comment(comment) => return write!(f, "<!--{comment}-->"),
+            Tag::Doctype(doctype) => return write!(f, "<!DOCTYPE {doctype}>"),
+        };
+        write!(f, "{open}{name}")?;
+        for (key, value) in attrs {
+            match value {
+                "" => write!(f, " {key}")?,
+                value => write!(f, " {key}={value}")?,
+            }
         }
+        f.write_str(close)
     }
 }
 
@@ -111,26 +328,133 @@ impl<'a> Tag<'a> {
 pub struct Tags<'a> {
     pub(crate) lexer: Lexer<'a>,
     pub(crate) diags: Vec<Error>,
-    peek: Option<<Self as Iterator>::Item>,
+    pub(crate) strict: bool,
+    pub(crate) start: (usize, usize),
+    lazy: bool,
+    ignore_whitespace: bool,
+    // whether whitespace is preserved in each open element, only tracked when ignoring
+    // whitespace
+    preserve: Vec<bool>,
+    recovery: Recovery,
+    // names of open elements, and the number to skip back to after a malformed tag, only
+    // tracked when skipping elements
+    open: Vec<&'a str>,
+    skip_to: Option<usize>,
+    max_errors: Option<usize>,
+    errors: usize,
+    stopped: bool,
+    peek: VecDeque<Tag<'a>>,
 }
 impl<'a> Tags<'a> {
     /// Create a new iterator over the tags in the provided string.
     ///
     /// Identical to the [`tags`](`crate::tags`) function.
-    pub fn new(text: &str) -> Tags {
+    pub fn new(text: &str) -> Tags<'_> {
         Tags {
             lexer: Lexer::new(text),
             diags: Vec::new(),
-            peek: None,
+            strict: false,
+            start: (1, 1),
+            lazy: false,
+            ignore_whitespace: false,
+            preserve: vec![],
+            recovery: Recovery::SkipTag,
+            open: vec![],
+            skip_to: None,
+            max_errors: None,
+            errors: 0,
+            stopped: false,
+            peek: VecDeque::new(),
         }
     }
 
+    /// Apply parsing options.
+    pub fn with_options(mut self, options: &ParseOptions) -> Self {
+        self.recovery = options.recovery;
+        self.max_errors = options.max_errors;
+        self.strict = options.strict;
+        self.lexer.comments = options.comments;
+        self.ignore_whitespace = options.ignore_whitespace;
+        self.lazy = options.lazy_attrs;
+        self
+    }
+
+    /// Enable or disable strict checking.
+    ///
+    /// In strict mode, characters that are illegal in XML are reported as diagnostics.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut tags = xmlite::Tags::new("<a>\u{1}</a>").with_strict(true);
+    /// tags.by_ref().for_each(drop);
+    /// assert!(!tags.diags().is_empty());
+    /// ```
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Enable or disable returning comments as [`Tag::Comment`].
+    ///
+    /// Comments are skipped by default. Comments inside tags are always skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Tag;
+    /// let mut tags = xmlite::Tags::new("<a><!-- note --></a>").with_comments(true);
+    /// assert!(matches!(tags.nth(1), Some(Tag::Comment(" note "))));
+    /// ```
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.lexer.comments = comments;
+        self
+    }
+
+    /// Enable or disable lazily parsed attributes.
+    ///
+    /// When enabled, tags keep their attribute markup unparsed, see [`Attrs::from_raw`], and
+    /// the lexer skips straight to the end of each tag. This saves work for consumers which
+    /// mostly look at tag names, but attribute names and values are not checked, so duplicate
+    /// attributes, invalid names and, in strict mode, illegal characters in values are not
+    /// reported.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut tags = xmlite::Tags::new(r#"<a x="1" x="2"/>"#).with_lazy_attrs(true);
+    /// assert_eq!(tags.next().unwrap().attr("x"), Some("\"2\""));
+    /// assert!(tags.diags().is_empty());
+    /// ```
+    pub fn with_lazy_attrs(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
     /// Peek at the next tag.
     pub fn peek(&mut self) -> Option<<Self as Iterator>::Item> {
-        if self.peek.is_none() {
-            self.peek = self.next();
+        self.peek_nth(0)
+    }
+    /// Peek at the tag `n` positions ahead, where `peek_nth(0)` is the next tag.
+    ///
+    /// The tags up to it are buffered, and are returned by later calls to `next` without being
+    /// lexed again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut tags = xmlite::Tags::new("<a><b/>text</a>");
+    /// assert_eq!(tags.peek_nth(2).unwrap().content(), Some("text"));
+    /// assert!(tags.peek_nth(9).is_none());
+    /// assert_eq!(tags.next().unwrap().name(), Some("a"));
+    /// assert_eq!(tags.peek_nth(0).unwrap().name(), Some("b"));
+    /// ```
+    pub fn peek_nth(&mut self, n: usize) -> Option<<Self as Iterator>::Item> {
+        while self.peek.len() <= n {
+            let tag = self.lex()?;
+            self.peek.push_back(tag);
         }
-        self.peek.clone()
+        self.peek.get(n).cloned()
     }
 
     /// Return any errors encountered during parsing.
@@ -142,20 +466,114 @@ impl<'a> Tags<'a> {
     pub fn report(&self) -> (usize, usize) {
         self.lexer.report()
     }
+    /// Report the (line, column) where the last returned or peeked tag started.
+    pub fn span(&self) -> (usize, usize) {
+        self.start
+    }
+
+    /// Record an error, stopping once the error budget is used up.
+    fn diag(&mut self, error: Error) {
+        if self.stopped {
+            return;
+        }
+        self.diags.push(error);
+        self.errors += 1;
+        if let Some(limit) = self.max_errors
+            && self.errors >= limit
+        {
+            self.diags.push(Error::TooManyErrors {
+                limit,
+                span: self.lexer.report(),
+            });
+            self.stopped = true;
+        }
+    }
+
+    /// Report illegal characters in a token if in strict mode.
+    fn check_chars(&mut self, text: &str) {
+        if !self.strict {
+            return;
+        }
+        if let Some((i, char)) = chars::find_invalid(text) {
+            let mut lexer = Lexer::new("");
+            (lexer.line, lexer.column) = self.lexer.span();
+            lexer.advance(&text[..i]);
+            self.diag(Error::InvalidChar {
+                char,
+                span: lexer.report(),
+            });
+        }
+    }
+
+    /// Report a name that does not match the `Name` production.
+    fn check_name(&mut self, name: &str) {
+        if !chars::is_name(name) {
+            self.diag(Error::InvalidName {
+                name: name.to_owned(),
+                span: self.lexer.span(),
+            });
+        }
+    }
+
+    /// Peek at the next token inside a tag, reporting and skipping comments.
+    fn peek_in_tag(&mut self) -> Option<(&'a str, TokenKind)> {
+        loop {
+            match self.lexer.peek()? {
+                (comment, TokenKind::Invalid) if comment.starts_with("<!--") => {
+                    self.diag(Error::MarkupInTag {
+                        span: self.lexer.span(),
+                    });
+                    self.lexer.next();
+                }
+                token => return Some(token),
+            }
+        }
+    }
+
+    /// Track whether whitespace is preserved in the open elements, by their `xml:space`
+    /// attributes.
+    fn track_space(&mut self, attrs: &Attrs, kind: TagKind) {
+        let inherited = self.preserve.last().copied().unwrap_or(false);
+        match kind {
+            TagKind::Opening => self.preserve.push(match attrs.get("xml:space") {
+                Some("\"preserve\"" | "'preserve'") => true,
+                Some("\"default\"" | "'default'") => false,
+                _ => inherited,
+            }),
+            TagKind::Closing => drop(self.preserve.pop()),
+            TagKind::SelfClosing => {}
+        }
+    }
 
     /// Recover and skip to next tag.
-    fn recover(&mut self, token: String) {
+    ///
+    /// A tag which is interrupted by another tag is dropped, and lexing resumes at the new tag.
+    fn recover(&mut self, (token, kind): (&str, TokenKind)) {
         // add error to diagnostics
-        self.diags.push(Error::Syntax {
-            token,
-            span: self.lexer.report(),
+        self.diag(match kind {
+            TokenKind::Open => Error::MarkupInTag {
+                span: self.lexer.span(),
+            },
+            _ => Error::Syntax {
+                token: token.to_owned(),
+                span: self.lexer.report(),
+            },
         });
         eprintln!("recovered from an error: {}", self.diags.last().unwrap());
+        match self.recovery {
+            Recovery::SkipTag => {}
+            Recovery::SkipElement if !self.open.is_empty() => self.skip_to = Some(self.open.len()),
+            Recovery::SkipElement => {}
+            Recovery::Stop => self.stopped = true,
+        }
 
         // skip to next sync point.
         while let Some(token) = self.lexer.peek() {
             match token {
-                (_, "open") | (_, "text") => break,
+                (
+                    _,
+                    TokenKind::Open | TokenKind::Text | TokenKind::Comment | TokenKind::Doctype,
+                ) => break,
                 _ => {
                     self.lexer.next();
                 }
@@ -163,99 +581,220 @@ impl<'a> Tags<'a> {
         }
     }
 }
-impl<'a> Iterator for Tags<'a> {
-    type Item = Tag<'a>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        // early return for peek
-        if self.peek.is_some() {
-            return self.peek.take();
+impl<'a> Tags<'a> {
+    /// Lex the next tag, skipping over content according to the recovery strategy.
+    fn lex(&mut self) -> Option<Tag<'a>> {
+        if self.recovery != Recovery::SkipElement {
+            return self.lex_tag();
         }
-
-        // early return with text content
-        if self.lexer.peek()?.1 == "text" {
-            let text = self.lexer.next()?.0;
-            return Some(Tag::Text(text));
+        loop {
+            let tag = self.lex_tag()?;
+            match tag {
+                Tag::Tag { name, kind, .. } if kind.is_opening() => self.open.push(name),
+                Tag::Tag { name, kind, .. } if kind.is_closing() => {
+                    // end tags close the matching open element and everything within it
+                    if let Some(i) = self.open.iter().rposition(|open| *open == name) {
+                        self.open.truncate(i);
+                    }
+                }
+                _ => {}
+            }
+            match self.skip_to {
+                // the end tag of the element the malformed tag was in
+                Some(depth) if self.open.len() < depth => self.skip_to = None,
+                Some(_) => continue,
+                None => {}
+            }
+            return Some(tag);
         }
+    }
 
-        // parse opening
-        let open = match self.lexer.peek()? {
-            (open, "open") => open,
-            (t, _) => {
-                self.recover(t.to_owned());
-                return self.next();
+    /// Lex the next tag, recovering from malformed tags.
+    fn lex_tag(&mut self) -> Option<Tag<'a>> {
+        // malformed tags are skipped by looping rather than recursing, so that long runs of
+        // them cannot overflow the stack
+        'tag: loop {
+            if self.stopped {
+                return None;
             }
-        };
-        self.lexer.next();
-
-        // parse name
-        let name = match self.lexer.peek()? {
-            (name, "name") => name,
-            (t, _) => {
-                self.recover(t.to_owned());
-                return self.next();
+
+            // early return with text content
+            let kind = self.lexer.peek()?.1;
+            self.start = self.lexer.span();
+            if kind == TokenKind::Comment {
+                let comment = self.lexer.next()?.0;
+                let comment = comment.strip_prefix("<!--").unwrap_or(comment);
+                return Some(Tag::Comment(comment.strip_suffix("-->").unwrap_or(comment)));
+            }
+            if kind == TokenKind::Doctype {
+                let doctype = self.lexer.next()?.0;
+                let doctype = doctype.strip_prefix("<!DOCTYPE").unwrap_or(doctype);
+                let doctype = doctype.strip_suffix('>').unwrap_or(doctype);
+                return Some(Tag::Doctype(doctype.trim_start()));
+            }
+            if kind == TokenKind::Text {
+                let text = self.lexer.next()?.0;
+                self.check_chars(text);
+                let preserve = self.preserve.last().copied().unwrap_or(false);
+                if self.ignore_whitespace
+                    && !preserve
+                    && text.trim_matches([' ', '\t', '\r', '\n']).is_empty()
+                {
+                    continue 'tag;
+                }
+                return Some(Tag::Text(text));
             }
-        };
-        self.lexer.next();
 
-        // parse attrs
-        let mut attrs = HashMap::new();
-        loop {
-            // attr name
-            let name = match self.lexer.peek()? {
-                (name, "name") => name,
-                (_, "close") => break,
-                (t, _) => {
-                    self.recover(t.to_owned());
-                    return self.next();
+            // parse opening
+            let open = match self.lexer.peek()? {
+                (open, TokenKind::Open) => open,
+                t => {
+                    self.recover(t);
+                    continue 'tag;
                 }
             };
             self.lexer.next();
 
-            // attr with value?
-            let value = if let Some((_eq, "eq")) = self.lexer.peek() {
-                let _eq = self.lexer.next();
-                let value = match self.lexer.next()? {
-                    (value, "value") => value,
-                    (t, _) => {
-                        self.recover(t.to_owned());
-                        return self.next();
+            // parse name
+            let name = match self.peek_in_tag()? {
+                (name, TokenKind::Name) => {
+                    self.check_name(name);
+                    name
+                }
+                t => {
+                    self.recover(t);
+                    continue 'tag;
+                }
+            };
+            self.lexer.next();
+
+            // skip over attrs, leaving them to be parsed on access, and only lex them to
+            // recover from malformed attrs
+            let raw = self.lexer.src;
+            let mut attrs = Attrs::new();
+            let skipped = self.lazy && self.lexer.skip_attrs().is_some();
+            if skipped {
+                attrs = Attrs::from_raw(&raw[..raw.len() - self.lexer.src.len()]);
+            }
+            while self.lazy && !skipped {
+                match self.peek_in_tag()? {
+                    (_, TokenKind::Name | TokenKind::Eq | TokenKind::Value) => {
+                        self.lexer.next();
+                    }
+                    (close, TokenKind::Close) => {
+                        // the peeked end of the tag has already been lexed
+                        let end = raw.len() - self.lexer.src.len() - close.len();
+                        attrs = Attrs::from_raw(&raw[..end]);
+                        break;
+                    }
+                    t => {
+                        self.recover(t);
+                        continue 'tag;
+                    }
+                }
+            }
+
+            // parse attrs
+            while !self.lazy {
+                // attr name
+                let name = match self.peek_in_tag()? {
+                    (name, TokenKind::Name) => {
+                        self.check_name(name);
+                        name
+                    }
+                    (_, TokenKind::Close) => break,
+                    t => {
+                        self.recover(t);
+                        continue 'tag;
                     }
                 };
-                value
-            } else {
-                ""
-            };
+                self.lexer.next();
 
-            attrs.insert(name, value);
-        }
+                // attr with value?
+                let value = if let Some((_eq, TokenKind::Eq)) = self.peek_in_tag() {
+                    let _eq = self.lexer.next();
+                    match self.peek_in_tag()? {
+                        (value, TokenKind::Value) => {
+                            self.lexer.next();
+                            self.check_chars(value);
+                            value
+                        }
+                        t => {
+                            self.recover(t);
+                            continue 'tag;
+                        }
+                    }
+                } else {
+                    ""
+                };
 
-        // parse closing
-        let close = match self.lexer.peek()? {
-            (close, "close") => close,
-            (t, _) => {
-                self.recover(t.to_owned());
-                return self.next();
+                if attrs.insert(name, value).is_some() {
+                    self.diag(Error::DuplicateAttr {
+                        name: name.to_owned(),
+                        span: self.lexer.span(),
+                    });
+                }
             }
-        };
-        self.lexer.next();
-        let kind = match (open, close) {
-            ("</", _) => TagKind::Closing,
-            (_, "/>") => TagKind::SelfClosing,
-            _ => TagKind::Opening,
-        };
 
-        // check that brackets are matching
-        match (open, close) {
-            ("<", ">") | ("<", "/>") | ("</", ">") => Some(Tag::Tag { name, attrs, kind }),
-            ("<?", "?>") => Some(Tag::Declaration { name, attrs }),
-            _ => {
-                self.recover(close.to_owned());
-                return self.next();
+            // parse closing
+            let close = match self.peek_in_tag()? {
+                (close, TokenKind::Close) => close,
+                t => {
+                    self.recover(t);
+                    continue 'tag;
+                }
+            };
+            self.lexer.next();
+            let kind = match (open, close) {
+                ("</", _) => TagKind::Closing,
+                (_, "/>") => TagKind::SelfClosing,
+                _ => TagKind::Opening,
+            };
+
+            // check that brackets are matching
+            match (open, close) {
+                ("<", ">") | ("<", "/>") | ("</", ">") => {
+                    if self.ignore_whitespace {
+                        self.track_space(&attrs, kind);
+                    }
+                    return Some(Tag::Tag { name, attrs, kind });
+                }
+                ("<?", "?>") => return Some(Tag::Declaration { name, attrs }),
+                _ => {
+                    self.recover((close, TokenKind::Close));
+                    continue 'tag;
+                }
             }
         }
     }
 }
+impl<'a> Iterator for Tags<'a> {
+    type Item = Tag<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.peek.pop_front().or_else(|| self.lex())
+    }
+}
+
+/// Get the quote an attribute value was parsed with, if any.
+///
+/// Parsed values are wrapped in a pair of quotes which do not occur in between.
+pub(crate) fn parsed_quote(value: &str) -> Option<char> {
+    ['"', '\''].into_iter().find(|&quote| {
+        value.len() >= 2
+            && value.starts_with(quote)
+            && value.ends_with(quote)
+            && !value[1..value.len() - 1].contains(quote)
+    })
+}
+
+/// Strip the quotes from a parsed attribute value, keeping other values as they are.
+pub(crate) fn unquote(value: &str) -> &str {
+    match parsed_quote(value) {
+        Some(_) => &value[1..value.len() - 1],
+        None => value,
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -288,6 +827,164 @@ mod tests {
         let mut tags = Tags::new(text);
         assert!(matches!(tags.next(), Some(Tag::Tag { name: "b", .. })));
         assert!(matches!(tags.next(), Some(Tag::Tag { name: "c", .. })));
-        assert!(!tags.diags().is_empty());
+        assert!(matches!(
+            tags.diags(),
+            [Error::MarkupInTag { span: (1, 4) }]
+        ));
+
+        let mut tags = Tags::new(r#"<a x="1><b y='"'/>"#);
+        assert_eq!(tags.next().unwrap().attr("y"), Some("'\"'"));
+        assert!(matches!(tags.diags(), [Error::Syntax { .. }]));
+
+        // long runs of malformed tags are skipped without deep recursion
+        let text = "<a =>".repeat(200_000) + "<b/>";
+        let mut tags = Tags::new(&text);
+        assert!(matches!(tags.next(), Some(Tag::Tag { name: "b", .. })));
+        assert_eq!(tags.diags().len(), 200_000);
+    }
+
+    #[test]
+    fn strict_chars() {
+        let text = "<a b=\"&#xFFFE;\">\n ok \u{8}</a>";
+        let mut tags = Tags::new(text).with_strict(true);
+        tags.by_ref().for_each(drop);
+        assert!(matches!(
+            tags.diags(),
+            [
+                Error::InvalidChar {
+                    char: 0xFFFE,
+                    span: (1, 7)
+                },
+                Error::InvalidChar {
+                    char: 0x8,
+                    span: (2, 5)
+                }
+            ]
+        ));
+        let mut tags = Tags::new(text);
+        tags.by_ref().for_each(drop);
+        assert!(tags.diags().is_empty());
+    }
+
+    #[test]
+    fn recovery() {
+        let text = "<a><b><c x=>t</c></b><d></a>e<f/>";
+        let tags = |recovery| {
+            let options = ParseOptions::new().with_recovery(recovery);
+            let mut tags = Tags::new(text).with_options(&options);
+            let text = tags.by_ref().map(|tag| tag.to_string()).collect::<String>();
+            (text, tags.diags().len())
+        };
+        assert_eq!(
+            tags(Recovery::SkipTag),
+            ("<a><b>t</c></b><d></a>e<f/>".to_owned(), 1)
+        );
+        assert_eq!(
+            tags(Recovery::SkipElement),
+            ("<a><b></b><d></a>e<f/>".to_owned(), 1)
+        );
+        assert_eq!(tags(Recovery::Stop), ("<a><b>".to_owned(), 1));
+
+        let options = ParseOptions::new().with_max_errors(Some(2));
+        let mut tags = Tags::new("<a x=><b y=><c z=><d/>").with_options(&options);
+        assert_eq!(tags.by_ref().count(), 0);
+        assert!(matches!(
+            tags.diags(),
+            [_, _, Error::TooManyErrors { limit: 2, .. }]
+        ));
+    }
+
+    #[test]
+    fn markup_in_tag() {
+        for lazy in [false, true] {
+            let text = "<a x='1' <!-- c --> y=<!-- d -->'2'>";
+            let mut tags = Tags::new(text).with_lazy_attrs(lazy);
+            let a = tags.next().unwrap();
+            assert_eq!((a.attr("x"), a.attr("y")), (Some("'1'"), Some("'2'")));
+            assert!(matches!(
+                tags.diags(),
+                [
+                    Error::MarkupInTag { span: (1, 10) },
+                    Error::MarkupInTag { span: (1, 23) }
+                ]
+            ));
+        }
+    }
+
+    #[test]
+    fn duplicate_attrs() {
+        let mut tags = Tags::new(r#"<a b="1" b="2"/>"#);
+        assert_eq!(tags.next().unwrap().attr("b"), Some("\"2\""));
+        assert!(matches!(tags.diags(), [Error::DuplicateAttr { .. }]));
+    }
+
+    #[test]
+    fn invalid_names() {
+        let text = r#"<1a b="c" .d="e"/>"#;
+        let mut tags = Tags::new(text);
+        assert!(matches!(tags.next(), Some(Tag::Tag { name: "1a", .. })));
+        assert!(matches!(
+            tags.diags(),
+            [
+                Error::InvalidName { span: (1, 2), .. },
+                Error::InvalidName { span: (1, 11), .. }
+            ]
+        ));
+    }
+
+    #[test]
+    fn many_attrs() {
+        let text = r#"<a a="1" b="2" c="3" d="4" e="5" f="6" b="7"/>"#;
+        let mut tags = Tags::new(text);
+        let Some(Tag::Tag { attrs, .. }) = tags.next() else {
+            panic!("expected a tag");
+        };
+        assert_eq!(attrs.len(), 6);
+        assert_eq!(attrs.get("b"), Some("\"7\""));
+        assert_eq!(attrs.get("f"), Some("\"6\""));
+        let keys = attrs.into_iter().map(|(k, _)| k).collect::<String>();
+        assert_eq!(keys, "abcdef");
+        assert_eq!(tags.diags().len(), 1);
+    }
+
+    #[test]
+    fn lazy_attrs() {
+        let text = "<a x='1' <!-- c --> y = \"'>'\" z/><b\n><c k='>' \n/>";
+        let mut tags = Tags::new(text).with_lazy_attrs(true);
+        let Some(Tag::Tag {
+            mut attrs, kind, ..
+        }) = tags.next()
+        else {
+            panic!("expected a tag");
+        };
+        assert!(kind.is_self_closing());
+        let parsed = attrs.iter().collect::<Vec<_>>();
+        assert_eq!(parsed, [("x", "'1'"), ("y", "\"'>'\""), ("z", "")]);
+        assert_eq!(attrs.insert("x", "'2'"), Some("'1'"));
+        assert_eq!(attrs.iter().count(), 3);
+        let Some(Tag::Tag { attrs, .. }) = tags.next() else {
+            panic!("expected a tag");
+        };
+        assert!(attrs.is_empty());
+        assert_eq!(tags.next().unwrap().attr("k"), Some("'>'"));
+        assert_eq!(tags.report(), (3, 3));
+        assert_eq!(tags.diags().len(), 1);
+    }
+
+    #[test]
+    fn preserve_space() {
+        let text = "<a> <b xml:space='preserve'> <c xml:space='default'> </c> <d/> </b> </a>";
+        let options = ParseOptions::new().with_ignore_whitespace(true);
+        let tags = Tags::new(text).with_options(&options);
+        let text = tags.map(|tag| tag.to_string()).collect::<String>();
+        let expected = "<a><b xml:space='preserve'> <c xml:space='default'></c> <d/> </b></a>";
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn display() {
+        let text = "<a  x = '1'\n y=\"2\" z>t</a ><b/><?pi c='d' ?>";
+        let tags = Tags::new(text).map(|t| t.to_string()).collect::<String>();
+        assert_eq!(tags, "<a x='1' y=\"2\" z>t</a><b/><?pi c='d'?>");
     }
 }
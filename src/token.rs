@@ -1,30 +1,123 @@
 //! Tokenizer/lexer
 
-/// XML lexer.
+#[cfg(feature = "simd")]
+use crate::bulk;
+use crate::chars;
+
+/// Low-level XML lexer, splitting text into tokens without checking how they are nested.
+///
+/// The lexer is what [`Tags`](crate::Tags) is built on, and is suited for tools such as syntax
+/// highlighters which need every token with its position, even in malformed documents. Each
+/// item is the text of a token and its kind, and the concatenated token texts are the
+/// original input, except for skipped comments and whitespace inside tags. The lexer never
+/// panics: characters it cannot make sense of become [`TokenKind::Invalid`] tokens.
+///
+/// The lexer starts in [`LexerState::Content`], where it produces [`TokenKind::Text`],
+/// [`TokenKind::Comment`], [`TokenKind::Doctype`] and [`TokenKind::Open`] tokens. An `Open`
+/// token switches to [`LexerState::Tag`], where it produces [`TokenKind::Name`],
+/// [`TokenKind::Eq`], [`TokenKind::Value`] and [`TokenKind::Invalid`] tokens, skipping
+/// whitespace, until a [`TokenKind::Close`] token switches back to content. Markup is not
+/// allowed inside tags: an `Open` token inside a tag starts a new tag, and a comment inside a
+/// tag is a single `Invalid` token.
+///
+/// # Examples
+///
+/// ```rust
+/// use xmlite::{Lexer, TokenKind};
+/// let mut lexer = Lexer::new("<a x='1'>\n!</a>");
+/// let tokens = lexer.by_ref().take(7).collect::<Vec<_>>();
+/// assert_eq!(tokens[4], ("'1'", TokenKind::Value));
+/// assert_eq!(tokens[6], ("\n!", TokenKind::Text));
+/// assert_eq!((lexer.span(), lexer.report()), ((1, 10), (2, 2)));
+/// ```
 #[derive(Debug, Clone)]
 pub struct Lexer<'a> {
-    pub src: &'a str,
-    pub line: usize,
-    pub column: usize,
-    pub state: &'static str,
+    pub(crate) src: &'a str,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) start: (usize, usize),
+    pub(crate) state: LexerState,
+    /// Whether comments in content are returned as tokens instead of skipped.
+    pub(crate) comments: bool,
     peek: Option<<Self as Iterator>::Item>,
 }
+
+/// Kind of a token produced by a [`Lexer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Text content, including CDATA sections (`<![CDATA[...]]>`).
+    Text,
+    /// Comment (`<!--...-->`) in content, when enabled with [`Lexer::with_comments`].
+    Comment,
+    /// Document type declaration (`<!DOCTYPE ...>`), including its internal subset.
+    Doctype,
+    /// Start of a tag: `<`, `</` or `<?`.
+    Open,
+    /// Tag or attribute name.
+    Name,
+    /// `=` between an attribute name and its value.
+    Eq,
+    /// Quoted attribute value, including its quotes.
+    Value,
+    /// End of a tag: `>`, `/>` or `?>`.
+    Close,
+    /// Text which is not allowed inside a tag, such as a stray character, a comment or an
+    /// unterminated attribute value.
+    Invalid,
+}
+
+/// State of a [`Lexer`], determining which tokens it produces next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LexerState {
+    /// Between tags.
+    Content,
+    /// Inside a tag, after its opening token.
+    Tag,
+}
+
 impl<'a> Lexer<'a> {
+    /// Create a new lexer over the provided string.
     pub fn new(src: &'a str) -> Self {
         Lexer {
             src,
             line: 1,
             column: 1,
-            state: "content",
+            start: (1, 1),
+            state: LexerState::Content,
+            comments: false,
             peek: None,
         }
     }
 
+    /// Enable or disable returning comments in content as [`TokenKind::Comment`].
+    ///
+    /// Comments are skipped by default. Comments inside tags are [`TokenKind::Invalid`].
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Get the current state, which determines the kinds of the following tokens.
+    ///
+    /// The state is updated as tokens are lexed, including peeked tokens.
+    pub fn state(&self) -> LexerState {
+        self.state
+    }
+    /// Get the input which has not been lexed yet. A peeked token has already been lexed.
+    pub fn rest(&self) -> &'a str {
+        self.src
+    }
+
     /// Report the current (line, column).
     pub fn report(&self) -> (usize, usize) {
         (self.line, self.column)
     }
 
+    /// Report the (line, column) where the last lexed token started.
+    pub fn span(&self) -> (usize, usize) {
+        self.start
+    }
+
     /// Peek at the next token in the iterator
     pub fn peek(&mut self) -> Option<<Self as Iterator>::Item> {
         if self.peek.is_none() {
@@ -34,7 +127,28 @@ impl<'a> Lexer<'a> {
     }
 
     // lexing helpers
-    fn advance(&mut self, text: &str) {
+    /// Advance the reported position past the provided text.
+    #[cfg(feature = "simd")]
+    pub(crate) fn advance(&mut self, text: &str) {
+        // short tokens are cheaper to walk directly
+        if text.len() < 16 {
+            return self.advance_chars(text);
+        }
+        let bytes = text.as_bytes();
+        match bulk::rfind(bytes, b'\n') {
+            Some(i) => {
+                self.line += bulk::count(&bytes[..=i], b'\n');
+                self.column = bulk::count_chars(&bytes[i + 1..]) + 1;
+            }
+            None => self.column += bulk::count_chars(bytes),
+        }
+    }
+    /// Advance the reported position past the provided text.
+    #[cfg(not(feature = "simd"))]
+    pub(crate) fn advance(&mut self, text: &str) {
+        self.advance_chars(text)
+    }
+    fn advance_chars(&mut self, text: &str) {
         for c in text.chars() {
             match c {
                 '\n' => (self.line, self.column) = (self.line + 1, 1),
@@ -49,23 +163,55 @@ impl<'a> Lexer<'a> {
         &self.src[..self.src.find(|c| !p(c)).unwrap_or(self.src.len())]
     }
     fn eat(&mut self, ps: &[&str]) -> Option<&'a str> {
-        ps.into_iter()
+        ps.iter()
             .find(|p| self.src.starts_with(**p))
             .map(|p| &self.src[..p.len()])
     }
-    /// Lex out a token which has the provided string on both sides.
-    /// Return `None` when token cannot be enclosed, e.g. unmatched delimiter.
-    fn enclosed(&mut self, s: &str) -> Option<&'a str> {
-        if !self.src.starts_with(s) {
+    /// Skip over the attributes of a tag up to its closing token without lexing them, returning
+    /// whether they were well-formed. Nothing is skipped if they were not, or if a token has
+    /// already been peeked.
+    pub(crate) fn skip_attrs(&mut self) -> Option<()> {
+        if self.peek.is_some() || self.state != LexerState::Tag {
             return None;
         }
-        let start = s.len();
-        let end = self.src[start..].find(s)? + start;
-        Some(&self.src[0..=end])
+        let mut i = 0;
+        loop {
+            let c = self.src[i..].chars().next()?;
+            match c {
+                '"' | '\'' => {
+                    let end = i + 1 + find_either(&self.src[i + 1..], c as u8, b'<')?;
+                    if self.src.as_bytes()[end] != c as u8 {
+                        return None;
+                    }
+                    i = end + 1;
+                    continue;
+                }
+                '>' => break,
+                '/' | '?' if self.src[i + 1..].starts_with('>') => break,
+                c if c == '=' || c.is_whitespace() || chars::is_name_char(c) => {}
+                _ => return None,
+            }
+            i += c.len_utf8();
+        }
+        self.advance(&self.src[..i]);
+        self.src = &self.src[i..];
+        Some(())
+    }
+    /// Lex out a quoted attribute value, which runs up to the next occurrence of its opening
+    /// quote. Quotes of the other kind, `>` and references are part of the value.
+    ///
+    /// Return `Err` with the text up to where the value must have ended if it is unterminated,
+    /// i.e. at a `<`, which is not allowed in attribute values, or at the end of the input.
+    fn quoted(&mut self, quote: u8) -> Result<&'a str, &'a str> {
+        match find_either(&self.src[1..], quote, b'<').map(|i| i + 1) {
+            Some(end) if self.src.as_bytes()[end] == quote => Ok(&self.src[..=end]),
+            Some(end) => Err(&self.src[..end]),
+            None => Err(self.src),
+        }
     }
 }
 impl<'a> Iterator for Lexer<'a> {
-    type Item = (&'a str, &'static str);
+    type Item = (&'a str, TokenKind);
 
     fn next(&mut self) -> Option<Self::Item> {
         // take peek if available
@@ -73,46 +219,68 @@ impl<'a> Iterator for Lexer<'a> {
             return self.peek.take();
         }
 
+        // skip comments in a loop, so long runs of them cannot overflow the stack
+        loop {
+            let (text, kind) = self.lex()?;
+            if kind != TokenKind::Comment || self.comments {
+                return Some((text, kind));
+            }
+        }
+    }
+}
+impl<'a> Lexer<'a> {
+    /// Lex the next token, including comments.
+    fn lex(&mut self) -> Option<(&'a str, TokenKind)> {
         // trim start if in tag
-        if self.state == "tag" {
+        if self.state == LexerState::Tag {
             let text = self.scan(|c| c.is_whitespace());
             self.advance(text);
             self.src = &self.src[text.len()..];
         }
 
         // eat token
-        let (text, kind, state) = match self.src.chars().next()? {
-            '<' => {
-                if self.src.starts_with("<!--") {
-                    let end = self
-                        .src
-                        .find("-->")
-                        .map(|l| l + 3)
-                        .unwrap_or(self.src.len());
-                    (&self.src[..end], "comment", self.state)
-                } else {
-                    (self.eat(&["<!--", "<?", "</", "<"]).unwrap(), "open", "tag")
-                }
+        self.start = (self.line, self.column);
+        let (state, src) = (self.state, self.src);
+        let (text, kind, state) = match (state, src.chars().next()?) {
+            (_, '<') if src.starts_with("<!--") => {
+                let end = find(src, "-->").map(|l| l + 3).unwrap_or(src.len());
+                let kind = match state {
+                    LexerState::Content => TokenKind::Comment,
+                    LexerState::Tag => TokenKind::Invalid,
+                };
+                (&src[..end], kind, state)
+            }
+            (LexerState::Content, '<') if src.starts_with("<![CDATA[") => {
+                // cdata sections are kept as raw markup in text
+                let end = find(src, "]]>").map(|l| l + 3).unwrap_or(src.len());
+                (&src[..end], TokenKind::Text, state)
+            }
+            (LexerState::Content, '<') if src.starts_with("<!DOCTYPE") => {
+                (&src[..doctype_end(src)], TokenKind::Doctype, state)
             }
-            '-' | '/' | '>' | '?' => (
-                self.eat(&["?>", "/>", ">", "-->"]).unwrap(),
-                "close",
-                "content",
+            (_, '<') => (
+                self.eat(&["<?", "</", "<"]).unwrap(),
+                TokenKind::Open,
+                LexerState::Tag,
             ),
-            '=' => (self.eat(&["="]).unwrap(), "eq", self.state),
-            '"' | '\'' => (
-                self.enclosed("\"").or_else(|| self.enclosed("'")).unwrap(),
-                "value",
-                self.state,
+            (LexerState::Content, _) => {
+                let end = find(src, "<").unwrap_or(src.len());
+                (&src[..end], TokenKind::Text, state)
+            }
+            (LexerState::Tag, '/' | '>' | '?') if self.eat(&["?>", "/>", ">"]).is_some() => (
+                self.eat(&["?>", "/>", ">"]).unwrap(),
+                TokenKind::Close,
+                LexerState::Content,
             ),
-            c @ '_' | c if c.is_alphabetic() => match self.state {
-                "content" => (self.scan(|c| c != '<'), "text", self.state),
-                "tag" | _ => (self.scan(name), "name", self.state),
-            },
-            t => match self.state {
-                "content" => (self.scan(|c| c != '<'), "text", self.state),
-                "tag" | _ => todo!("unhandled: {t:?}"),
+            (LexerState::Tag, '=') => (&src[..1], TokenKind::Eq, state),
+            (LexerState::Tag, quote @ ('"' | '\'')) => match self.quoted(quote as u8) {
+                Ok(value) => (value, TokenKind::Value, state),
+                Err(value) => (value, TokenKind::Invalid, state),
             },
+            (LexerState::Tag, c) if chars::is_name_char(c) => {
+                (self.scan(chars::is_name_char), TokenKind::Name, state)
+            }
+            (LexerState::Tag, c) => (&src[..c.len_utf8()], TokenKind::Invalid, state),
         };
         debug_assert!(!text.is_empty(), "xml lexer failed to advance");
 
@@ -120,17 +288,68 @@ impl<'a> Iterator for Lexer<'a> {
         self.src = &self.src[text.len()..];
         self.advance(text);
         self.state = state;
+        Some((text, kind))
+    }
+}
 
-        if kind == "comment" {
-            self.next()
-        } else {
-            Some((text, kind))
+/// Find the end of a document type declaration, skipping over its internal subset.
+fn doctype_end(src: &str) -> usize {
+    let (mut quote, mut depth) = (None, 0usize);
+    let mut i = 0;
+    while let Some(c) = src[i..].chars().next() {
+        match (quote, c) {
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => depth = depth.saturating_sub(1),
+            (None, '>') if depth == 0 => return i + 1,
+            (None, '<') if src[i..].starts_with("<!--") => {
+                i += find(&src[i..], "-->").map_or(src.len() - i, |end| end + 3);
+                continue;
+            }
+            _ => {}
         }
+        i += c.len_utf8();
     }
+    src.len()
 }
 
-fn name(c: char) -> bool {
-    c.is_alphanumeric() || "-_.:".contains(c)
+/// Find the byte offset of the first occurrence of `needle`.
+#[cfg(feature = "memchr")]
+fn find(haystack: &str, needle: &str) -> Option<usize> {
+    match needle.as_bytes() {
+        &[byte] => memchr::memchr(byte, haystack.as_bytes()),
+        needle => memchr::memmem::find(haystack.as_bytes(), needle),
+    }
+}
+/// Find the byte offset of the first occurrence of `needle`.
+#[cfg(all(feature = "simd", not(feature = "memchr")))]
+fn find(haystack: &str, needle: &str) -> Option<usize> {
+    match needle.as_bytes() {
+        &[byte] => bulk::find_any(haystack.as_bytes(), &[byte]),
+        _ => haystack.find(needle),
+    }
+}
+/// Find the byte offset of the first occurrence of `needle`.
+#[cfg(not(any(feature = "memchr", feature = "simd")))]
+fn find(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.find(needle)
+}
+/// Find the byte offset of the first occurrence of either of two ASCII bytes.
+#[cfg(feature = "memchr")]
+fn find_either(haystack: &str, a: u8, b: u8) -> Option<usize> {
+    memchr::memchr2(a, b, haystack.as_bytes())
+}
+/// Find the byte offset of the first occurrence of either of two ASCII bytes.
+#[cfg(all(feature = "simd", not(feature = "memchr")))]
+fn find_either(haystack: &str, a: u8, b: u8) -> Option<usize> {
+    bulk::find_any(haystack.as_bytes(), &[a, b])
+}
+/// Find the byte offset of the first occurrence of either of two ASCII bytes.
+#[cfg(not(any(feature = "memchr", feature = "simd")))]
+fn find_either(haystack: &str, a: u8, b: u8) -> Option<usize> {
+    haystack.find([char::from(a), char::from(b)])
 }
 
 #[cfg(test)]
@@ -141,13 +360,85 @@ mod tests {
     fn simple() {
         let text = r#"<a lol="123" />"#;
         let tokens = Lexer::new(text).map(|t| t.1).collect::<Vec<_>>();
-        assert_eq!(tokens, &["open", "name", "name", "eq", "value", "close"]);
+        assert_eq!(
+            tokens,
+            [
+                TokenKind::Open,
+                TokenKind::Name,
+                TokenKind::Name,
+                TokenKind::Eq,
+                TokenKind::Value,
+                TokenKind::Close
+            ]
+        );
     }
 
     #[test]
     fn comment() {
         let text = r#"<a <!-- inline --> ><!-- not inline --></a>"#;
         let mut tokens = Lexer::new(text);
-        assert!(matches!(tokens.next(), Some(("<", "open"))));
+        assert!(matches!(tokens.next(), Some(("<", TokenKind::Open))));
+        assert_eq!(tokens.nth(1), Some(("<!-- inline -->", TokenKind::Invalid)));
+        assert_eq!(tokens.nth(1), Some(("</", TokenKind::Open)));
+
+        let text = "<!---->".repeat(500_000) + "<a/>";
+        assert_eq!(Lexer::new(&text).next(), Some(("<", TokenKind::Open)));
+    }
+
+    #[test]
+    fn cdata() {
+        let text = "<a><![CDATA[<b> & ]]]]>text</a>";
+        let tokens = Lexer::new(text).collect::<Vec<_>>();
+        assert_eq!(tokens[3], ("<![CDATA[<b> & ]]]]>", TokenKind::Text));
+        assert_eq!(tokens[4], ("text", TokenKind::Text));
+    }
+
+    #[test]
+    fn doctype() {
+        let text = "<!DOCTYPE a [<!ATTLIST a b CDATA '>'><!-- ] -->]><a/>";
+        let tokens = Lexer::new(text).collect::<Vec<_>>();
+        assert_eq!(tokens[0], (&text[..text.len() - 4], TokenKind::Doctype));
+        assert_eq!(tokens[1], ("<", TokenKind::Open));
+    }
+
+    #[test]
+    fn names() {
+        let text = r#"<1a -b="c" é.d/>"#;
+        let tokens = Lexer::new(text).collect::<Vec<_>>();
+        assert_eq!(tokens[1], ("1a", TokenKind::Name));
+        assert_eq!(tokens[2], ("-b", TokenKind::Name));
+        assert_eq!(tokens[5], ("é.d", TokenKind::Name));
+    }
+
+    #[test]
+    fn delimiters() {
+        let text = "text <a b='>'><!-- a -> b --></a>";
+        let tokens = Lexer::new(text).collect::<Vec<_>>();
+        assert_eq!(tokens[0], ("text ", TokenKind::Text));
+        assert_eq!(tokens[5], ("'>'", TokenKind::Value));
+        assert_eq!(tokens[7], ("</", TokenKind::Open));
+    }
+
+    #[test]
+    fn content_and_invalid() {
+        let text = "<a>\"x\" = >y</a><!ELEMENT a (b)><c d='e";
+        let tokens = Lexer::new(text).collect::<Vec<_>>();
+        assert_eq!(tokens[3], ("\"x\" = >y", TokenKind::Text));
+        assert_eq!(tokens[8], ("!", TokenKind::Invalid));
+        assert_eq!(tokens[11], ("(", TokenKind::Invalid));
+        assert_eq!(tokens.last(), Some(&("'e", TokenKind::Invalid)));
+    }
+
+    #[test]
+    fn values() {
+        let text = r#"<a b="it's" c='say "hi"' d=">&quot;/>" e='1><f g="<h/>"#;
+        let tokens = Lexer::new(text).collect::<Vec<_>>();
+        assert_eq!(tokens[4], (r#""it's""#, TokenKind::Value));
+        assert_eq!(tokens[7], (r#"'say "hi"'"#, TokenKind::Value));
+        assert_eq!(tokens[10], (r#"">&quot;/>""#, TokenKind::Value));
+        assert_eq!(tokens[13], ("'1>", TokenKind::Invalid));
+        assert_eq!(tokens[14], ("<", TokenKind::Open));
+        assert_eq!(tokens[18], ("\"", TokenKind::Invalid));
+        assert_eq!(tokens[19], ("<", TokenKind::Open));
     }
 }
@@ -0,0 +1,172 @@
+//! URI reference resolution.
+
+/// Resolve a URI reference against a base URI, following RFC 3986 section 5.2.
+///
+/// # Examples
+///
+/// ```rust
+/// let base = "http://a/b/c/d;p?q";
+/// assert_eq!(xmlite::resolve_uri(base, "../g"), "http://a/b/g");
+/// assert_eq!(xmlite::resolve_uri(base, "#s"), "http://a/b/c/d;p?q#s");
+/// assert_eq!(xmlite::resolve_uri(base, "//g"), "http://g");
+/// ```
+pub fn resolve_uri(base: &str, reference: &str) -> String {
+    let b = Parts::new(base);
+    let r = Parts::new(reference);
+    let (scheme, authority, path, query);
+    if r.scheme.is_some() {
+        (scheme, authority) = (r.scheme, r.authority);
+        (path, query) = (remove_dot_segments(r.path), r.query);
+    } else if r.authority.is_some() {
+        (scheme, authority) = (b.scheme, r.authority);
+        (path, query) = (remove_dot_segments(r.path), r.query);
+    } else {
+        (scheme, authority) = (b.scheme, b.authority);
+        if r.path.is_empty() {
+            (path, query) = (b.path.to_owned(), r.query.or(b.query));
+        } else if r.path.starts_with('/') {
+            (path, query) = (remove_dot_segments(r.path), r.query);
+        } else {
+            let merged = if b.authority.is_some() && b.path.is_empty() {
+                format!("/{}", r.path)
+            } else {
+                let dir = b.path.rfind('/').map(|i| &b.path[..=i]).unwrap_or("");
+                format!("{dir}{}", r.path)
+            };
+            (path, query) = (remove_dot_segments(&merged), r.query);
+        }
+    }
+
+    let mut target = String::new();
+    if let Some(scheme) = scheme {
+        target += scheme;
+        target += ":";
+    }
+    if let Some(authority) = authority {
+        target += "//";
+        target += authority;
+    }
+    target += &path;
+    if let Some(query) = query {
+        target += "?";
+        target += query;
+    }
+    if let Some(fragment) = r.fragment {
+        target += "#";
+        target += fragment;
+    }
+    target
+}
+
+/// Components of a URI reference.
+struct Parts<'u> {
+    scheme: Option<&'u str>,
+    authority: Option<&'u str>,
+    path: &'u str,
+    query: Option<&'u str>,
+    fragment: Option<&'u str>,
+}
+impl<'u> Parts<'u> {
+    fn new(uri: &'u str) -> Self {
+        let (uri, fragment) = split(uri, '#');
+        let (uri, query) = split(uri, '?');
+        let scheme_end = uri.find(':').filter(|&i| {
+            let scheme = &uri[..i];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        });
+        let (scheme, uri) = match scheme_end {
+            Some(i) => (Some(&uri[..i]), &uri[i + 1..]),
+            None => (None, uri),
+        };
+        let (authority, path) = match uri.strip_prefix("//") {
+            Some(rest) => {
+                let end = rest.find('/').unwrap_or(rest.len());
+                (Some(&rest[..end]), &rest[end..])
+            }
+            None => (None, uri),
+        };
+        Parts {
+            scheme,
+            authority,
+            path,
+            query,
+            fragment,
+        }
+    }
+}
+
+fn split(s: &str, delimiter: char) -> (&str, Option<&str>) {
+    match s.split_once(delimiter) {
+        Some((a, b)) => (a, Some(b)),
+        None => (s, None),
+    }
+}
+
+/// Remove `.` and `..` segments from a path.
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output: Vec<&str> = vec![];
+    while !input.is_empty() {
+        if let Some(rest) = input.strip_prefix("../").or(input.strip_prefix("./")) {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            output.pop();
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let start = usize::from(input.starts_with('/'));
+            let end = input[start..]
+                .find('/')
+                .map(|i| i + start)
+                .unwrap_or(input.len());
+            output.push(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc3986_examples() {
+        let base = "http://a/b/c/d;p?q";
+        let cases = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../..", "http://a/"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/./y", "http://a/b/c/g;x=1/y"),
+        ];
+        for (reference, expected) in cases {
+            assert_eq!(resolve_uri(base, reference), expected, "{reference}");
+        }
+    }
+
+    #[test]
+    fn relative_base() {
+        assert_eq!(resolve_uri("docs/a.xml", "b.xml"), "docs/b.xml");
+        assert_eq!(resolve_uri("/root/", "../other/x.xml"), "/other/x.xml");
+    }
+}
@@ -0,0 +1,327 @@
+//! Well-formedness checks on document trees.
+
+use crate::{Document, Element, Error, Tag, TagKind, Tags, Xml, chars};
+use std::{collections::HashMap, iter};
+
+impl<'a> Xml<'a> {
+    /// Check the tree against the well-formedness constraints of the XML and Namespaces specifications.
+    ///
+    /// This checks name validity, character validity, escaping of markup characters in text and
+    /// attribute values, and that namespace prefixes are declared before use. Proper nesting and
+    /// attribute uniqueness are guaranteed by the tree structure itself, duplicate attributes in
+    /// source text are reported by [`Tags`](crate::Tags) as [`Error::DuplicateAttr`].
+    ///
+    /// Returns every violation found as an [`Error::Validation`]. Since trees carry no source
+    /// locations, violations are located by node path.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Xml;
+    /// let xml = Xml::element("a").with_child(Xml::element("x:b"));
+    /// assert_eq!(xml.check_well_formed().len(), 1);
+    ///
+    /// let xml = xml.with_attr("xmlns:x", "\"urn:x\"");
+    /// assert!(xml.check_well_formed().is_empty());
+    /// ```
+    pub fn check_well_formed(&self) -> Vec<Error> {
+        let mut diags = Vec::new();
+        let mut scope = HashMap::from([("xml", "http://www.w3.org/XML/1998/namespace")]);
+        let path = child_path("", std::slice::from_ref(self), 0);
+        check(self, &path, &mut scope, &mut diags);
+        diags
+    }
+}
+
+impl Document<'_> {
+    /// Check the root of the document against the well-formedness constraints, as with
+    /// [`Xml::check_well_formed`].
+    ///
+    /// Unlike [`Document::diags`], which holds the problems found while parsing and indexing,
+    /// this audits the tree as it is now, e.g. after edits with [`Document::apply`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{Document, Edit};
+    /// let mut doc = Document::parse("<a><b/></a>")?;
+    /// assert!(doc.validate_wf().is_empty());
+    /// doc.apply(Edit::set_attr(vec![0], "x:y", "\"1\""))?;
+    /// assert_eq!(doc.validate_wf().len(), 1);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn validate_wf(&self) -> Vec<Error> {
+        self.root().check_well_formed()
+    }
+}
+
+pub(crate) use crate::tag::unquote;
+
+/// Strip the namespace prefix from a name.
+#[cfg(any(
+    feature = "feed",
+    feature = "rnc",
+    feature = "soap",
+    feature = "xmldsig",
+    feature = "xsd"
+))]
+pub(crate) fn local(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Build the path of a child node, e.g. `/a/b[2]` for the second `b` element.
+pub(crate) fn child_path(parent: &str, siblings: &[Xml], index: usize) -> String {
+    match siblings[index].name() {
+        Some(name) => {
+            let nth = siblings[..index]
+                .iter()
+                .filter(|s| s.name() == Some(name))
+                .count();
+            let total = siblings.iter().filter(|s| s.name() == Some(name)).count();
+            if total > 1 {
+                format!("{parent}/{name}[{}]", nth + 1)
+            } else {
+                format!("{parent}/{name}")
+            }
+        }
+        None => format!("{parent}/text()"),
+    }
+}
+
+/// Find the (line, column) where the node at a path, as built by [`child_path`], starts in the
+/// text it was parsed from. Attributes are located at the start tag of their element.
+pub(crate) fn locate(text: &str, path: &str) -> Option<(usize, usize)> {
+    let steps = path
+        .split('/')
+        .skip(1)
+        .filter(|step| !step.starts_with('@'));
+    let steps = steps
+        .map(
+            |step| match step.strip_suffix(']').and_then(|s| s.split_once('[')) {
+                Some((name, nth)) => Some((name, nth.parse().ok()?)),
+                None => Some((step, 1)),
+            },
+        )
+        .collect::<Option<Vec<(&str, usize)>>>()?;
+
+    // children seen by name, for each element matched so far
+    let mut counts = vec![HashMap::<&str, usize>::new()];
+    let mut depth = 0;
+    let mut tags = Tags::new(text);
+    while let Some(tag) = tags.next() {
+        let matched = counts.len() - 1;
+        let step = *steps.get(matched)?;
+        match tag {
+            Tag::Tag {
+                kind: TagKind::Closing,
+                ..
+            } => {
+                depth -= 1;
+                if depth < matched {
+                    return None;
+                }
+            }
+            Tag::Tag { name, kind, .. } => {
+                if depth == matched {
+                    let count = counts[matched].entry(name).or_default();
+                    *count += 1;
+                    if step == (name, *count) {
+                        if matched + 1 == steps.len() {
+                            return Some(tags.span());
+                        }
+                        counts.push(HashMap::new());
+                    }
+                }
+                match kind {
+                    TagKind::Opening => depth += 1,
+                    _ if counts.len() - 1 > depth => return None,
+                    _ => {}
+                }
+            }
+            Tag::Text(_) if depth == matched && step.0 == "text()" => return Some(tags.span()),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn check<'a>(
+    node: &'a Xml,
+    path: &str,
+    scope: &mut HashMap<&'a str, &'a str>,
+    diags: &mut Vec<Error>,
+) {
+    let invalid = |message: String| Error::Validation {
+        message,
+        path: path.to_owned(),
+    };
+    let Xml::Element(Element {
+        name,
+        attrs,
+        children,
+    }) = node
+    else {
+        if let Some(text) = node.content() {
+            check_text(text, path, diags);
+        }
+        return;
+    };
+
+    // check attributes in a stable order, so diagnostics do not depend on hash order
+    let mut attrs = attrs.iter().collect::<Vec<_>>();
+    attrs.sort_unstable_by_key(|(key, _)| *key);
+
+    // bind namespace declarations on this element
+    let mut shadowed = vec![];
+    for &(key, value) in &attrs {
+        let value = unquote(value);
+        let Some(("xmlns", prefix)) = key.split_once(':') else {
+            continue;
+        };
+        if prefix == "xmlns" || (prefix == "xml") != (value == scope["xml"]) {
+            diags.push(invalid(format!(
+                "reserved prefix {prefix:?} cannot be rebound"
+            )));
+        }
+        if value.is_empty() {
+            diags.push(invalid(format!("prefix {prefix:?} cannot be undeclared")));
+        }
+        shadowed.push((prefix, scope.insert(prefix, value)));
+    }
+
+    // check names
+    for name in iter::once(name).chain(attrs.iter().map(|(key, _)| *key)) {
+        if !chars::is_name(name) {
+            diags.push(invalid(format!("invalid name {name:?}")));
+            continue;
+        }
+        match name.split(':').collect::<Vec<_>>()[..] {
+            [_] | ["xmlns", _] => {}
+            [prefix, local] if !local.is_empty() && !prefix.is_empty() => {
+                if !scope.contains_key(prefix) {
+                    diags.push(invalid(format!("undeclared namespace prefix {prefix:?}")));
+                }
+            }
+            _ => diags.push(invalid(format!(
+                "name {name:?} is not namespace-well-formed"
+            ))),
+        }
+    }
+
+    // check attribute values
+    for (key, value) in attrs {
+        let value = unquote(value);
+        check_text(value, &format!("{path}/@{key}"), diags);
+    }
+
+    let children = children.as_slice();
+    for (i, child) in children.iter().enumerate() {
+        check(child, &child_path(path, children, i), scope, diags);
+    }
+
+    // restore outer namespace scope
+    for (prefix, outer) in shadowed.into_iter().rev() {
+        match outer {
+            Some(outer) => scope.insert(prefix, outer),
+            None => scope.remove(prefix),
+        };
+    }
+}
+
+fn check_text(text: &str, path: &str, diags: &mut Vec<Error>) {
+    let invalid = |message: String| Error::Validation {
+        message,
+        path: path.to_owned(),
+    };
+    if let Some((_, c)) = chars::find_invalid(text) {
+        diags.push(invalid(format!("invalid character U+{c:04X}")));
+    }
+    if text.contains('<') {
+        diags.push(invalid("unescaped '<'".to_owned()));
+    }
+    if text.contains("]]>") {
+        diags.push(invalid("unescaped \"]]>\"".to_owned()));
+    }
+    for (i, _) in text.match_indices('&') {
+        let reference = text[i + 1..].split_once(';').map(|(r, _)| r);
+        let valid = match reference {
+            Some(r) if r.starts_with("#x") => {
+                r.len() > 2 && r[2..].chars().all(|c| c.is_ascii_hexdigit())
+            }
+            Some(r) if r.starts_with('#') => {
+                r.len() > 1 && r[1..].chars().all(|c| c.is_ascii_digit())
+            }
+            Some(r) => chars::is_name(r),
+            None => false,
+        };
+        if !valid {
+            diags.push(invalid("unescaped '&'".to_owned()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate() {
+        let text = "<a>\n  <b/><c>x</c>\n  <b><d/>y</b>\n</a>";
+        let located = |path| super::locate(text, path);
+        assert_eq!(located("/a"), Some((1, 1)));
+        assert_eq!(located("/a/b[2]/@k"), Some((3, 3)));
+        assert_eq!(located("/a/b[2]/d"), Some((3, 6)));
+        assert_eq!(located("/a/b[2]/text()"), Some((3, 10)));
+        assert_eq!(located("/a/c/text()"), Some((2, 10)));
+        assert_eq!(located("/a/b[1]/d"), None);
+        assert_eq!(located("/a/e"), None);
+        assert_eq!(located("/x"), None);
+    }
+
+    #[test]
+    fn well_formed() {
+        let text = r#"<a xmlns:x="urn:x"><x:b x:c="1 &amp; 2">text &#65;</x:b><b/><b/></a>"#;
+        let xml = crate::document(text).unwrap();
+        assert!(xml.check_well_formed().is_empty());
+    }
+
+    #[test]
+    fn violations() {
+        let xml = Xml::element("a")
+            .with_child(Xml::element("b").with_child(Xml::text("1 < 2 & 3")))
+            .with_child(Xml::element("b").with_attr("y:z", "\u{1}"))
+            .with_child(Xml::element("1c"));
+        let diags = xml.check_well_formed();
+        let paths = diags
+            .iter()
+            .map(|e| match e {
+                Error::Validation { path, .. } => path.as_str(),
+                _ => "",
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "/a/b[1]/text()",
+                "/a/b[1]/text()",
+                "/a/b[2]",
+                "/a/b[2]/@y:z",
+                "/a/1c"
+            ]
+        );
+
+        // attributes are checked in name order
+        let xml = Xml::element("a")
+            .with_attr("z:a", "\"\"")
+            .with_attr("m:b", "\"\"")
+            .with_attr("a:c", "\"\"");
+        let messages = xml
+            .check_well_formed()
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].contains("\"a\""));
+        assert!(messages[2].contains("\"z\""));
+    }
+}
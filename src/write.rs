@@ -0,0 +1,788 @@
+//! Serialization of document trees.
+
+use crate::{
+    Document, Element, Encoding, Tag, Xml,
+    escape::{escape, unescape},
+    html::VOID,
+    tag::{parsed_quote, unquote},
+};
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap, fmt, io};
+
+/// Options for serializing a tree with [`Xml::to_string_with`] and [`Xml::write_to`], or a
+/// document with [`Document::to_string_with`] and [`Document::write_to`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::{WriteOptions, Xml};
+/// let xml = Xml::element("a").with_attr("z", "1").with_attr("y", "2").with_attr("x", "3");
+/// let options = WriteOptions::new().with_sorted_attrs(true);
+/// assert_eq!(xml.to_string_with(&options), r#"<a x="3" y="2" z="1"/>"#);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    attr_order: Option<fn(&str, &str) -> Ordering>,
+    expand_empty: bool,
+    // per element name overrides of expand_empty
+    expand_empty_for: HashMap<String, bool>,
+    cdata_threshold: Option<usize>,
+    indent: String,
+    pub(crate) newline: String,
+    max_width: Option<usize>,
+    pub(crate) declaration: Option<bool>,
+    pub(crate) prolog: bool,
+    xhtml: bool,
+    ascii: bool,
+    encoding: Option<Encoding>,
+}
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            attr_order: None,
+            expand_empty: false,
+            expand_empty_for: HashMap::new(),
+            cdata_threshold: None,
+            indent: String::new(),
+            newline: "\n".to_owned(),
+            max_width: None,
+            declaration: None,
+            prolog: true,
+            xhtml: false,
+            ascii: false,
+            encoding: None,
+        }
+    }
+}
+impl WriteOptions {
+    /// Create options writing the tree as compactly as it is stored.
+    pub fn new() -> Self {
+        WriteOptions::default()
+    }
+
+    /// Create options following the XHTML compatibility guidelines of XHTML 1.0 appendix C, so
+    /// that the output also renders correctly in browsers treating it as HTML.
+    ///
+    /// Empty void elements like `br` are written with a space before `/>`, while other empty
+    /// elements are never collapsed, since e.g. `<p/>` opens a paragraph in HTML. The `&apos;`
+    /// entity, which HTML 4 lacks, is written as `&#39;`, and the XML declaration is omitted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::WriteOptions;
+    /// let xml = xmlite::document("<p title='it&apos;s'>a<br/><span/></p>")?;
+    /// assert_eq!(xml.to_string_with(&WriteOptions::xhtml()), "<p title='it&#39;s'>a<br /><span></span></p>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn xhtml() -> Self {
+        WriteOptions {
+            declaration: Some(false),
+            xhtml: true,
+            ..WriteOptions::default()
+        }
+    }
+
+    /// Sort attributes by name, making the output reproducible.
+    ///
+    /// Attributes are otherwise written in an unspecified order.
+    pub fn with_sorted_attrs(mut self, sorted: bool) -> Self {
+        self.attr_order = sorted.then_some(str::cmp);
+        self
+    }
+    /// Sort attributes by name with a custom comparator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{WriteOptions, Xml};
+    /// let xml = Xml::element("a").with_attr("b", "1").with_attr("id", "2");
+    /// let options = WriteOptions::new().with_attr_order(|a, b| (a != "id").cmp(&(b != "id")).then(a.cmp(b)));
+    /// assert_eq!(xml.to_string_with(&options), r#"<a id="2" b="1"/>"#);
+    /// ```
+    pub fn with_attr_order(mut self, order: fn(&str, &str) -> Ordering) -> Self {
+        self.attr_order = Some(order);
+        self
+    }
+
+    /// Write empty elements as `<a></a>` instead of `<a/>`.
+    pub fn with_expand_empty(mut self, expand: bool) -> Self {
+        self.expand_empty = expand;
+        self
+    }
+    /// Write empty elements with a given name as `<a></a>` or `<a/>`, overriding
+    /// [`WriteOptions::with_expand_empty`].
+    ///
+    /// This is needed for HTML interop, where e.g. `<script/>` is not a complete element.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{WriteOptions, Xml};
+    /// let xml = Xml::element("head").with_child(Xml::element("script")).with_child(Xml::element("meta"));
+    /// let options = WriteOptions::new().with_expand_empty_for("script", true);
+    /// assert_eq!(xml.to_string_with(&options), "<head><script></script><meta/></head>");
+    /// ```
+    pub fn with_expand_empty_for(mut self, name: impl Into<String>, expand: bool) -> Self {
+        self.expand_empty_for.insert(name.into(), expand);
+        self
+    }
+
+    /// Write text with at least `threshold` escaped characters as a CDATA section instead.
+    ///
+    /// This keeps code snippets and embedded markup readable. Text with entity references
+    /// other than the predefined entities and character references is always written escaped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{WriteOptions, Xml};
+    /// let xml = Xml::element("code").with_child(Xml::text("if (a &lt; b &amp;&amp; c) {}"));
+    /// let options = WriteOptions::new().with_cdata_threshold(Some(3));
+    /// assert_eq!(xml.to_string_with(&options), "<code><![CDATA[if (a < b && c) {}]]></code>");
+    /// ```
+    pub fn with_cdata_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.cdata_threshold = threshold;
+        self
+    }
+
+    /// Pretty print the tree, putting each child of an element on its own line with the
+    /// given indentation per level, e.g. `"\t"` or four spaces.
+    ///
+    /// Only elements without text content are indented, since whitespace is significant in
+    /// mixed content. Whitespace-only text between indented elements is replaced. An empty
+    /// indentation, the default, writes the tree without adding whitespace.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::WriteOptions;
+    /// let xml = xmlite::document("<a><b><c/></b><d>text <e/></d></a>")?;
+    /// let options = WriteOptions::new().with_indent("  ");
+    /// assert_eq!(xml.to_string_with(&options), "<a>\n  <b>\n    <c/>\n  </b>\n  <d>text <e/></d>\n</a>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn with_indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = indent.into();
+        self
+    }
+    /// Set the line ending used between lines added by the serializer, e.g. `"\r\n"`.
+    ///
+    /// Line endings within text are written as they are stored.
+    pub fn with_newline(mut self, newline: impl Into<String>) -> Self {
+        self.newline = newline.into();
+        self
+    }
+    /// Wrap start tags longer than a maximum width, in characters, by putting each
+    /// attribute on its own continuation line, indented one level deeper than the element.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{WriteOptions, Xml};
+    /// let xml = Xml::element("a").with_attr("first", "1").with_attr("second", "2");
+    /// let options = WriteOptions::new()
+    ///     .with_indent("\t")
+    ///     .with_sorted_attrs(true)
+    ///     .with_max_width(Some(16));
+    /// assert_eq!(xml.to_string_with(&options), "<a\n\tfirst=\"1\"\n\tsecond=\"2\"/>");
+    /// ```
+    pub fn with_max_width(mut self, max_width: Option<usize>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Force or omit the XML declaration when writing a document.
+    ///
+    /// With `Some(true)`, documents without a declaration get
+    /// `<?xml version="1.0" encoding="UTF-8"?>`. With `Some(false)`, the declaration is
+    /// omitted, unless it is needed for an encoding set with [`WriteOptions::with_encoding`].
+    /// By default, the declaration is written if the document has one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{Document, WriteOptions};
+    /// let doc = Document::parse("<a/>")?;
+    /// let options = WriteOptions::new().with_declaration(Some(true));
+    /// assert_eq!(doc.to_string_with(&options), "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<a/>");
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn with_declaration(mut self, declaration: Option<bool>) -> Self {
+        self.declaration = declaration;
+        self
+    }
+    /// Write the processing instructions and comments before the root element of a document.
+    ///
+    /// Enabled by default.
+    pub fn with_prolog(mut self, prolog: bool) -> Self {
+        self.prolog = prolog;
+        self
+    }
+
+    /// Write characters outside ASCII in text and attribute values as character references,
+    /// e.g. `&#xE9;`, so the output can be sent over ASCII-only channels or declared in any
+    /// ASCII-compatible encoding.
+    ///
+    /// Characters in CDATA sections are moved out of the section to be escaped. References are
+    /// not allowed in names and raw nodes such as comments, so they are written as they are by
+    /// [`Xml::to_string_with`], and are an error with [`Xml::write_to`] and
+    /// [`Document::write_to`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::WriteOptions;
+    /// let xml = xmlite::document("<a b='café'>naïve <![CDATA[€]]></a>")?;
+    /// let options = WriteOptions::new().with_ascii(true);
+    /// let expected = "<a b='caf&#xE9;'>na&#xEF;ve &#x20AC;</a>";
+    /// assert_eq!(xml.to_string_with(&options), expected);
+    ///
+    /// let xml = xmlite::document("<café/>")?;
+    /// assert!(xml.write_to(vec![], &options).is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Set the encoding of the bytes written by [`Xml::write_to`] and [`Document::write_to`].
+    ///
+    /// By default, documents are written in the encoding named in their declaration if it is
+    /// supported, and UTF-8 otherwise. A forced encoding replaces the one named in the
+    /// declaration, and encodings other than UTF-8 and UTF-16 are always declared, even for
+    /// trees and documents without a declaration. Characters which cannot be encoded are
+    /// written as character references where possible, as with [`WriteOptions::with_ascii`],
+    /// and are an error in names and raw nodes. Strings are always UTF-8, so other encodings
+    /// only affect which characters are escaped there.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::{Document, Encoding, WriteOptions};
+    /// let doc = Document::parse("<?xml version='1.0'?><a>€ & £</a>")?;
+    /// let options = WriteOptions::new().with_encoding(Some(Encoding::Latin1));
+    /// let mut output = vec![];
+    /// doc.write_to(&mut output, &options)?;
+    /// assert_eq!(output, b"<?xml version='1.0' encoding=\"ISO-8859-1\"?>\n<a>&#x20AC; & \xA3</a>");
+    ///
+    /// let options = WriteOptions::new().with_encoding(Some(Encoding::Utf16Le));
+    /// output.clear();
+    /// doc.write_to(&mut output, &options)?;
+    /// assert_eq!(output[..4], [0xFF, 0xFE, b'<', 0]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_encoding(mut self, encoding: Option<Encoding>) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Check whether an empty element with a given name is written as `<a></a>`.
+    pub(crate) fn expands(&self, name: &str) -> bool {
+        let expand = self.expand_empty_for.get(name);
+        let default = match self.xhtml {
+            true => !VOID.contains(&name),
+            false => self.expand_empty,
+        };
+        expand.copied().unwrap_or(default)
+    }
+    /// Get the end of an empty element written as `<a/>`.
+    pub(crate) fn empty_end(&self) -> &'static str {
+        match self.xhtml {
+            true => " />",
+            false => "/>",
+        }
+    }
+    /// Replace the entities which are not understood by HTML, and escape characters outside
+    /// ASCII if enabled.
+    fn compatible<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let text = match self.xhtml && text.contains("&apos;") {
+            true => Cow::Owned(text.replace("&apos;", "&#39;")),
+            false => Cow::Borrowed(text),
+        };
+        self.ascii(text)
+    }
+    /// Escape characters which are outside ASCII, if enabled, or cannot be encoded in the
+    /// output encoding as character references.
+    fn ascii<'t>(&self, text: Cow<'t, str>) -> Cow<'t, str> {
+        let max = match self.ascii {
+            true => '\u{7F}',
+            false => match self.encoding.and_then(Encoding::max_char) {
+                Some(max) => max,
+                None => return text,
+            },
+        };
+        if text.chars().all(|c| c <= max) {
+            return text;
+        }
+        let mut escaped = String::with_capacity(text.len() + 16);
+        let mut rest = text.as_ref();
+        while !rest.is_empty() {
+            // cdata sections cannot contain references, so escaped characters are moved out
+            let (plain, cdata) = match rest.find("<![CDATA[") {
+                Some(start) => {
+                    let end = rest[start..]
+                        .find("]]>")
+                        .map_or(rest.len(), |end| start + end);
+                    (&rest[..start], Some(&rest[start..end]))
+                }
+                None => (rest, None),
+            };
+            for c in plain.chars() {
+                match c <= max {
+                    true => escaped.push(c),
+                    false => escaped.push_str(&format!("&#x{:X};", u32::from(c))),
+                }
+            }
+            rest = &rest[plain.len()..];
+            if let Some(cdata) = cdata {
+                let mut section = String::with_capacity(cdata.len() + 16);
+                for c in cdata.chars() {
+                    match c <= max {
+                        true => section.push(c),
+                        false => section.push_str(&format!("]]>&#x{:X};<![CDATA[", u32::from(c))),
+                    }
+                }
+                rest = &rest[cdata.len()..];
+                if let Some(after) = rest.strip_prefix("]]>") {
+                    section.push_str("]]>");
+                    rest = after;
+                }
+                // sections left empty by moving characters out are dropped
+                escaped.push_str(&section.replace("<![CDATA[]]>", ""));
+            }
+        }
+        Cow::Owned(escaped)
+    }
+}
+
+impl Xml<'_> {
+    /// Serialize the tree to a string.
+    pub fn to_string_with(&self, options: &WriteOptions) -> String {
+        Formatted(self, options).to_string()
+    }
+
+    /// Serialize the tree to a writer.
+    ///
+    /// The writer is not buffered, so wrap it in a [`BufWriter`](io::BufWriter) when
+    /// writing to a file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::WriteOptions;
+    /// let xml = xmlite::document("<a x='1'><b/></a>")?;
+    /// let mut output = vec![];
+    /// xml.write_to(&mut output, &WriteOptions::new())?;
+    /// assert_eq!(output, b"<a x='1'><b/></a>");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn write_to(&self, writer: impl io::Write, options: &WriteOptions) -> io::Result<()> {
+        let encoding = options.encoding.unwrap_or(Encoding::Utf8);
+        let mut text = String::new();
+        if declared_only(encoding) {
+            text = format!(
+                "{}{}",
+                with_encoding(DECLARATION, encoding),
+                options.newline
+            );
+        }
+        text.push_str(&Formatted(self, options).to_string());
+        encode(&text, encoding, options, writer)
+    }
+}
+
+impl Document<'_> {
+    /// Serialize the document, including its prolog, to a string.
+    pub fn to_string_with(&self, options: &WriteOptions) -> String {
+        FormattedDocument(self, options).to_string()
+    }
+
+    /// Serialize the document, including its prolog, to a writer.
+    ///
+    /// The writer is not buffered, so wrap it in a [`BufWriter`](io::BufWriter) when
+    /// writing to a file.
+    pub fn write_to(&self, writer: impl io::Write, options: &WriteOptions) -> io::Result<()> {
+        let declared = self.declaration().and_then(declared_encoding);
+        let options = match options.encoding.or(declared).unwrap_or(Encoding::Utf8) {
+            Encoding::Utf8 => Cow::Borrowed(options),
+            encoding => Cow::Owned(options.clone().with_encoding(Some(encoding))),
+        };
+        let encoding = options.encoding.unwrap_or(Encoding::Utf8);
+        encode(
+            &FormattedDocument(self, &options).to_string(),
+            encoding,
+            &options,
+            writer,
+        )
+    }
+}
+
+/// Encode written text, which must be ASCII if [`WriteOptions::with_ascii`] is enabled.
+fn encode(
+    text: &str,
+    encoding: Encoding,
+    options: &WriteOptions,
+    writer: impl io::Write,
+) -> io::Result<()> {
+    if options.ascii && !text.is_ascii() {
+        // characters left in names and raw nodes, where references are not allowed
+        let c = text.chars().find(|c| !c.is_ascii()).unwrap_or_default();
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("character {c:?} cannot be written as ASCII"),
+        ));
+    }
+    encoding.encode(text, writer)
+}
+
+/// Check whether an encoding can only be recognized through an XML declaration, i.e. if it
+/// is not UTF-8 or UTF-16.
+fn declared_only(encoding: Encoding) -> bool {
+    !matches!(
+        encoding,
+        Encoding::Utf8 | Encoding::Utf16Le | Encoding::Utf16Be
+    )
+}
+
+/// Get the supported encoding named in an XML declaration.
+fn declared_encoding(declaration: &str) -> Option<Encoding> {
+    let declared = crate::tags(declaration)
+        .next()?
+        .attr("encoding")?
+        .to_owned();
+    Encoding::from_name(unquote(&declared))
+}
+
+/// Name an encoding in an XML declaration, keeping it as it is if it already names it.
+fn with_encoding(declaration: &str, encoding: Encoding) -> Cow<'_, str> {
+    let Some(Tag::Declaration { attrs, .. }) = crate::tags(declaration).next() else {
+        return Cow::Borrowed(declaration);
+    };
+    if declared_encoding(declaration) == Some(encoding) {
+        return Cow::Borrowed(declaration);
+    }
+    // the encoding goes after the version and before the standalone declaration
+    let mut written = String::from("<?xml");
+    let attrs = attrs.iter().filter(|(key, _)| *key != "encoding");
+    let (version, rest): (Vec<_>, Vec<_>) = attrs.partition(|(key, _)| *key == "version");
+    for (key, value) in version {
+        written.push_str(&format!(" {key}={value}"));
+    }
+    written.push_str(&format!(" encoding=\"{}\"", encoding.name()));
+    for (key, value) in rest {
+        written.push_str(&format!(" {key}={value}"));
+    }
+    written.push_str("?>");
+    Cow::Owned(written)
+}
+
+/// Tree paired with the options to write it with.
+pub(crate) struct Formatted<'x, 'a>(pub &'x Xml<'a>, pub &'x WriteOptions);
+impl fmt::Display for Formatted<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Formatted(node, options) = *self;
+        write_node(f, node, options, 0, options.indent.is_empty())
+    }
+}
+
+/// Document paired with the options to write it with.
+pub(crate) struct FormattedDocument<'x, 'a>(pub &'x Document<'a>, pub &'x WriteOptions);
+impl fmt::Display for FormattedDocument<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let FormattedDocument(document, options) = *self;
+        let declaration = match options.declaration {
+            _ if options.encoding.is_some_and(declared_only) => {
+                document.declaration().or(Some(DECLARATION))
+            }
+            Some(true) => document.declaration().or(Some(DECLARATION)),
+            Some(false) => None,
+            None => document.declaration(),
+        };
+        let declaration = declaration.map(|declaration| match options.encoding {
+            Some(encoding) => with_encoding(declaration, encoding),
+            None => Cow::Borrowed(declaration),
+        });
+        let prolog = document.prolog().iter().filter(|_| options.prolog);
+        for item in declaration.map(Xml::Raw).iter().chain(prolog) {
+            write_node(f, item, options, 0, true)?;
+            f.write_str(&options.newline)?;
+        }
+        Formatted(document.root(), options).fmt(f)
+    }
+}
+
+/// Declaration written for documents without one, when it is forced.
+pub(crate) const DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+/// Write a node at a nesting depth, either inline or indented.
+fn write_node(
+    f: &mut fmt::Formatter<'_>,
+    node: &Xml,
+    options: &WriteOptions,
+    depth: usize,
+    inline: bool,
+) -> fmt::Result {
+    let (name, attrs, children) = match node {
+        Xml::Element(Element {
+            name,
+            attrs,
+            children,
+        }) => (name, attrs, children),
+        Xml::Text(text) => return write_text(f, text, options),
+        Xml::Raw(raw) => return f.write_str(raw),
+    };
+
+    let attrs = attrs
+        .iter()
+        .map(|(key, value)| (key.as_ref(), quoted(value)));
+    write_start(f, name, attrs.collect(), options, depth)?;
+    if children.is_empty() && !options.expands(name) {
+        return f.write_str(options.empty_end());
+    }
+    f.write_str(">")?;
+
+    // content, indented unless it contains text
+    let inline = inline
+        || children
+            .iter()
+            .any(|c| c.content().is_some_and(|t| !t.trim().is_empty()));
+    if inline {
+        for child in children {
+            write_node(f, child, options, depth + 1, true)?;
+        }
+    } else {
+        let mut children = children.iter().filter(|c| !c.is_text()).peekable();
+        let indented = children.peek().is_some();
+        for child in children {
+            write_line(f, options, depth + 1)?;
+            write_node(f, child, options, depth + 1, false)?;
+        }
+        if indented {
+            write_line(f, options, depth)?;
+        }
+    }
+    write!(f, "</{name}>")
+}
+
+/// Write the start of a tag up to the end of its attributes, wrapping them if it is too long.
+pub(crate) fn write_start(
+    f: &mut impl fmt::Write,
+    name: &str,
+    mut attrs: Vec<(&str, Cow<str>)>,
+    options: &WriteOptions,
+    depth: usize,
+) -> fmt::Result {
+    if let Some(order) = options.attr_order {
+        attrs.sort_by(|(a, _), (b, _)| order(a, b));
+    }
+    let wrap = options.max_width.is_some_and(|max| {
+        let attrs = attrs
+            .iter()
+            .map(|(k, v)| k.chars().count() + v.chars().count() + 2);
+        let indent = options.indent.chars().count() * depth;
+        indent + name.chars().count() + attrs.sum::<usize>() + 3 > max
+    });
+    write!(f, "<{name}")?;
+    for (key, value) in attrs {
+        if wrap {
+            write_line(f, options, depth + 1)?;
+        } else {
+            f.write_str(" ")?;
+        }
+        match value.as_ref() {
+            "" => f.write_str(key)?,
+            value => write!(f, "{key}={}", options.compatible(value))?,
+        }
+    }
+    Ok(())
+}
+
+/// Write text, as a CDATA section if it is escaped enough.
+pub(crate) fn write_text(
+    f: &mut impl fmt::Write,
+    text: &str,
+    options: &WriteOptions,
+) -> fmt::Result {
+    match options.cdata_threshold {
+        Some(threshold) if escapes(text).is_some_and(|n| n >= threshold) => {
+            let mut cdata = String::new();
+            write_cdata(&mut cdata, &unescape(text))?;
+            f.write_str(&options.ascii(Cow::Owned(cdata)))
+        }
+        _ => f.write_str(&options.compatible(text)),
+    }
+}
+
+/// Start a new line indented to a nesting depth.
+pub(crate) fn write_line(
+    f: &mut impl fmt::Write,
+    options: &WriteOptions,
+    depth: usize,
+) -> fmt::Result {
+    f.write_str(&options.newline)?;
+    (0..depth).try_for_each(|_| f.write_str(&options.indent))
+}
+
+/// Quote an attribute value, unless it was parsed with quotes, in which case it is written
+/// as it is. Any other value is escaped and quoted.
+pub(crate) fn quoted(value: &str) -> Cow<'_, str> {
+    match parsed_quote(value) {
+        Some(_) => Cow::Borrowed(value),
+        None => Cow::Owned(format!("\"{}\"", escape(value))),
+    }
+}
+
+/// Count the escaped characters in text, or `None` if it contains references other than the
+/// predefined entities and character references.
+fn escapes(text: &str) -> Option<usize> {
+    text.match_indices('&')
+        .map(|(i, _)| {
+            let rest = &text[i + 1..];
+            let name = &rest[..rest.find(';')?];
+            let known = matches!(name, "lt" | "gt" | "amp" | "quot" | "apos");
+            (known || name.starts_with('#')).then_some(1)
+        })
+        .sum()
+}
+
+/// Write text as a CDATA section, splitting it wherever it contains the `]]>` delimiter.
+fn write_cdata(f: &mut impl fmt::Write, text: &str) -> fmt::Result {
+    f.write_str("<![CDATA[")?;
+    f.write_str(&text.replace("]]>", "]]]]><![CDATA[>"))?;
+    f.write_str("]]>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes() {
+        let xml = Xml::element("a")
+            .with_attr("w", "'1'")
+            .with_attr("x", "say \"hi\"")
+            .with_attr_value("y", "'1'")
+            .with_attr("z", "2 < 3 & 'a' \"b\"");
+        let options = WriteOptions::new().with_sorted_attrs(true);
+        let expected = r#"<a w='1' x="say &quot;hi&quot;" y="&apos;1&apos;" z="2 &lt; 3 &amp; &apos;a&apos; &quot;b&quot;"/>"#;
+        assert_eq!(xml.to_string_with(&options), expected);
+        assert_eq!(xml.attr_unescaped("y").as_deref(), Some("'1'"));
+    }
+
+    #[test]
+    fn expand_empty() {
+        let xml = crate::document("<a><b/><c></c></a>").unwrap();
+        let options = WriteOptions::new()
+            .with_expand_empty(true)
+            .with_expand_empty_for("c", false);
+        assert_eq!(xml.to_string_with(&options), "<a><b></b><c/></a>");
+    }
+
+    #[test]
+    fn cdata() {
+        let text = Xml::text("a]]&gt;b &lt;c&gt;");
+        let options = WriteOptions::new().with_cdata_threshold(Some(2));
+        let expected = "<![CDATA[a]]]]><![CDATA[>b <c>]]>";
+        assert_eq!(text.to_string_with(&options), expected);
+        let text = Xml::text("&lt;&lt;&nbsp;");
+        assert_eq!(text.to_string_with(&options), "&lt;&lt;&nbsp;");
+        assert_eq!(escapes("a &amp; b &#65; &"), None);
+    }
+
+    #[test]
+    fn raw() {
+        let xml = Xml::element("a").with_child(Xml::raw("<b>&nbsp;]]></b>"));
+        let options = WriteOptions::new().with_cdata_threshold(Some(0));
+        assert_eq!(xml.to_string_with(&options), "<a><b>&nbsp;]]></b></a>");
+    }
+
+    #[test]
+    fn pretty() {
+        let xml = crate::document("<a>\n<b x='1' y='2'><c/></b>\n<!-- c --><d/></a>").unwrap();
+        let options = WriteOptions::new()
+            .with_indent("\t")
+            .with_newline("\r\n")
+            .with_sorted_attrs(true)
+            .with_max_width(Some(12));
+        let expected =
+            "<a>\r\n\t<b\r\n\t\tx='1'\r\n\t\ty='2'>\r\n\t\t<c/>\r\n\t</b>\r\n\t<d/>\r\n</a>";
+        assert_eq!(xml.to_string_with(&options), expected);
+    }
+
+    #[test]
+    fn xhtml() {
+        let text = "<html><head><script src='a.js'/><meta charset='utf-8'/></head></html>";
+        let doc = crate::Document::parse(text).unwrap();
+        let options = WriteOptions::xhtml().with_expand_empty_for("meta", true);
+        let expected =
+            "<html><head><script src='a.js'></script><meta charset='utf-8'></meta></head></html>";
+        assert_eq!(doc.to_string_with(&options), expected);
+    }
+
+    #[test]
+    fn ascii() {
+        let xml = Xml::element("é").with_child(Xml::text("😀 &lt;€&gt;"));
+        let options = WriteOptions::new()
+            .with_ascii(true)
+            .with_cdata_threshold(Some(2));
+        let expected = "<é>&#x1F600;<![CDATA[ <]]>&#x20AC;<![CDATA[>]]></é>";
+        let output = xml.to_string_with(&options);
+        assert_eq!(output, expected);
+        assert_eq!(crate::document(&output).unwrap().text_content(), "😀 <€>");
+
+        // names and comments cannot hold references
+        assert!(xml.write_to(vec![], &options).is_err());
+        let xml = Xml::element("a").with_child(Xml::Raw("<!-- € -->".into()));
+        assert!(xml.write_to(vec![], &options).is_err());
+        let mut output = vec![];
+        xml.write_to(&mut output, &WriteOptions::new()).unwrap();
+        assert_eq!(output, "<a><!-- € --></a>".as_bytes());
+    }
+
+    #[test]
+    fn encodings() {
+        let text = "<?xml version='1.0' encoding='latin1' standalone='yes'?><a>&#xE9;</a>";
+        let doc = crate::Document::parse(text).unwrap();
+        let mut output = vec![];
+        doc.write_to(&mut output, &WriteOptions::new()).unwrap();
+        assert_eq!(output, text.replace("?><", "?>\n<").as_bytes());
+
+        let options = WriteOptions::new().with_encoding(Some(Encoding::Ascii));
+        let expected = "<?xml version='1.0' encoding=\"US-ASCII\" standalone='yes'?>";
+        assert!(doc.to_string_with(&options).starts_with(expected));
+        let xml = Xml::element("é");
+        assert!(xml.write_to(&mut output, &options).is_err());
+
+        // encodings other than UTF-8 and UTF-16 are declared even without a declaration
+        let doc = crate::Document::parse("<a>é</a>").unwrap();
+        let options = WriteOptions::new()
+            .with_encoding(Some(Encoding::Latin1))
+            .with_declaration(Some(false));
+        output.clear();
+        doc.write_to(&mut output, &options).unwrap();
+        assert_eq!(
+            output,
+            b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n<a>\xE9</a>"
+        );
+        output.clear();
+        doc.root().write_to(&mut output, &options).unwrap();
+        assert!(output.starts_with(b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n"));
+        let options = WriteOptions::new().with_encoding(Some(Encoding::Utf16Be));
+        output.clear();
+        doc.write_to(&mut output, &options).unwrap();
+        assert_eq!(output[..4], [0xFE, 0xFF, 0, b'<']);
+        assert_eq!(output[4..6], [0, b'a']);
+    }
+
+    #[test]
+    fn declaration() {
+        let text = "<?xml version='1.0'?><?pi x?><a/>";
+        let doc = crate::Document::parse(text).unwrap();
+        assert_eq!(doc.to_string(), "<?xml version='1.0'?>\n<?pi x?>\n<a/>");
+        let options = WriteOptions::new()
+            .with_declaration(Some(false))
+            .with_prolog(false);
+        assert_eq!(doc.to_string_with(&options), "<a/>");
+    }
+}
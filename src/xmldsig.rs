@@ -0,0 +1,620 @@
+//! Canonicalization and XML Signature verification.
+
+use crate::{
+    Element, Error, Xml,
+    document::walk,
+    edit::render,
+    escape::{attr_text, unescape},
+    validate::{local, unquote},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sha2::Digest;
+use std::collections::BTreeMap;
+
+const DSIG: &str = "http://www.w3.org/2000/09/xmldsig#";
+const XML: &str = "http://www.w3.org/XML/1998/namespace";
+const ENVELOPED: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+
+/// Namespace prefixes in scope, with `""` for the default namespace.
+type Scope = BTreeMap<String, String>;
+
+/// Canonicalization algorithm, as used by XML Signature.
+///
+/// Comments are left out of the canonical form, even when kept in the tree with
+/// [`ParseOptions::with_comments`](crate::ParseOptions::with_comments), so the `#WithComments`
+/// variants of the algorithms produce the same output as the plain ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Canonicalization {
+    /// Canonical XML 1.0, which renders all namespaces in scope.
+    Inclusive,
+    /// Exclusive Canonical XML 1.0, which renders only the namespaces an element uses, along
+    /// with the listed prefixes (`#default` for the default namespace).
+    Exclusive(Vec<String>),
+}
+impl Canonicalization {
+    /// Look up an algorithm by its identifier, e.g.
+    /// `http://www.w3.org/2001/10/xml-exc-c14n#`.
+    pub fn from_uri(uri: &str) -> Option<Self> {
+        match uri.strip_suffix("#WithComments").unwrap_or(uri) {
+            "http://www.w3.org/TR/2001/REC-xml-c14n-20010315" => Some(Canonicalization::Inclusive),
+            "http://www.w3.org/2001/10/xml-exc-c14n#"
+            | "http://www.w3.org/2001/10/xml-exc-c14n" => Some(Canonicalization::Exclusive(vec![])),
+            _ => None,
+        }
+    }
+}
+
+impl Xml<'_> {
+    /// Write the canonical form of a tree, as specified by Canonical XML.
+    ///
+    /// Empty elements are written with end tags, attributes are sorted with namespace
+    /// declarations first, redundant namespace declarations are dropped, CDATA sections are
+    /// replaced by their escaped text, and references are replaced by the characters they
+    /// stand for where allowed. Processing instructions in raw nodes are kept, while other raw
+    /// nodes are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use xmlite::Canonicalization;
+    /// let xml = xmlite::document(r#"<a b='&#x31;' a="2" xmlns="urn:x"><c/><![CDATA[<&>]]></a>"#)?;
+    /// assert_eq!(
+    ///     xml.canonicalize(&Canonicalization::Inclusive),
+    ///     r#"<a xmlns="urn:x" a="2" b="1"><c></c>&lt;&amp;&gt;</a>"#,
+    /// );
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn canonicalize(&self, method: &Canonicalization) -> String {
+        let mut out = String::new();
+        canonical(self, &Scope::new(), &Scope::new(), method, None, &mut out);
+        out
+    }
+
+    /// Verify an enveloped XML Signature within this document.
+    ///
+    /// The first `ds:Signature` element is located, and each of its references is resolved,
+    /// transformed and digested. Same-document references to the whole document (`""`) and to
+    /// elements by their `ID`, `Id` or `id` attribute (`#id`) are supported, along with the
+    /// enveloped signature transform and the canonicalization algorithms of
+    /// [`Canonicalization`]. Digests can be SHA-1, SHA-256, SHA-384 or SHA-512.
+    ///
+    /// To guard against signature wrapping, there must be at least one reference, IDs must be
+    /// unique in the document, and some reference must cover the document being verified,
+    /// either as `""` or by the `ID` of its root element, so a valid signature over a nested
+    /// element is not mistaken for one over the unsigned content around it.
+    ///
+    /// Finally, the canonicalized `SignedInfo` is passed to `verify` along with the identifier
+    /// of the signature algorithm and the decoded signature value, so the caller can check
+    /// the signature with their key and crypto library of choice.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if there is no signature or reference, an ID is not
+    /// unique, no reference covers the whole document, a digest does not match, an
+    /// algorithm is not supported, or `verify` rejects the signature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // an RSA library would check the signature against the public key here
+    /// let verify = |algorithm: &str, signed_info: &[u8], signature: &[u8]| {
+    ///     algorithm.ends_with("#rsa-sha256") && !signed_info.is_empty() && !signature.is_empty()
+    /// };
+    /// let text = r##"<invoice ID="i1"><total>10</total><ds:Signature xmlns:ds="http://www.w3.org/2000/09/xmldsig#">
+    ///   <ds:SignedInfo>
+    ///     <ds:CanonicalizationMethod Algorithm="http://www.w3.org/2001/10/xml-exc-c14n#"/>
+    ///     <ds:SignatureMethod Algorithm="http://www.w3.org/2001/04/xmldsig-more#rsa-sha256"/>
+    ///     <ds:Reference URI="#i1">
+    ///       <ds:Transforms>
+    ///         <ds:Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"/>
+    ///         <ds:Transform Algorithm="http://www.w3.org/2001/10/xml-exc-c14n#"/>
+    ///       </ds:Transforms>
+    ///       <ds:DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"/>
+    ///       <ds:DigestValue>7njopiEDmLKS0zpYwr7Tkb4uONo0Seb/8uMWb5/Ycac=</ds:DigestValue>
+    ///     </ds:Reference>
+    ///   </ds:SignedInfo>
+    ///   <ds:SignatureValue>c2lnbmF0dXJl</ds:SignatureValue>
+    /// </ds:Signature></invoice>"##;
+    /// let xml = xmlite::document(text)?;
+    /// assert!(xml.verify_signature(verify).is_ok());
+    ///
+    /// let tampered = text.replace(">10<", ">1000<");
+    /// assert!(xmlite::document(&tampered)?.verify_signature(verify).is_err());
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn verify_signature(
+        &self,
+        verify: impl Fn(&str, &[u8], &[u8]) -> bool,
+    ) -> Result<(), Error> {
+        let invalid = |message: String, path: &[usize]| Error::Validation {
+            message,
+            path: render(self, path),
+        };
+
+        // locate the signature
+        let mut found = None;
+        walk(self, &mut vec![], &mut |node, indices| {
+            if found.is_none() && is_dsig(self, indices, node, "Signature") {
+                found = Some((node, indices.to_vec()));
+            }
+        });
+        let Some((signature, path)) = found else {
+            return Err(invalid("expected a signature".to_owned(), &[]));
+        };
+        let child = |parent: &[usize], name: &str| {
+            let node = node_at(self, parent);
+            let i = node
+                .children()
+                .position(|child| child.name().map(local) == Some(name));
+            match i {
+                Some(i) => Ok([parent, &[i]].concat()),
+                None => Err(invalid(format!("expected {name}"), parent)),
+            }
+        };
+        let algorithm = |path: &[usize]| {
+            let algorithm = node_at(self, path).attr("Algorithm").map(unquote);
+            algorithm.ok_or_else(|| invalid("expected an algorithm".to_owned(), path))
+        };
+        let signed_info = child(&path, "SignedInfo")?;
+
+        // check the digests of the references, and that one covers the whole document
+        let (mut references, mut covered) = (0, false);
+        let info = node_at(self, &signed_info);
+        for (i, reference) in info.children().enumerate() {
+            if reference.name().map(local) != Some("Reference") {
+                continue;
+            }
+            let reference_path = [&signed_info[..], &[i]].concat();
+            let uri = reference.attr("URI").map(unquote).unwrap_or_default();
+            let target = match uri.strip_prefix('#') {
+                None if uri.is_empty() => vec![],
+                Some(id) => match &elements_by_id(self, id)[..] {
+                    [target] => target.clone(),
+                    [] => {
+                        return Err(invalid(
+                            format!("no element with ID {id:?}"),
+                            &reference_path,
+                        ));
+                    }
+                    _ => return Err(invalid(format!("duplicate ID {id:?}"), &reference_path)),
+                },
+                None => return Err(invalid(format!("unsupported URI {uri:?}"), &reference_path)),
+            };
+            let (mut method, mut enveloped) = (Canonicalization::Inclusive, false);
+            if let Ok(transforms) = child(&reference_path, "Transforms") {
+                for (j, transform) in node_at(self, &transforms).children().enumerate() {
+                    if transform.name().map(local) != Some("Transform") {
+                        continue;
+                    }
+                    let transform_path = [&transforms[..], &[j]].concat();
+                    match algorithm(&transform_path)? {
+                        ENVELOPED => enveloped = true,
+                        uri => {
+                            method = canonicalization(transform, uri).ok_or_else(|| {
+                                invalid(format!("unsupported transform {uri:?}"), &transform_path)
+                            })?;
+                        }
+                    }
+                }
+            }
+            let skip = enveloped.then_some(signature);
+            let data = canonical_at(self, &target, &method, skip);
+            let digest_method = child(&reference_path, "DigestMethod")?;
+            let digest = digest(algorithm(&digest_method)?, data.as_bytes()).ok_or_else(|| {
+                invalid("unsupported digest algorithm".to_owned(), &digest_method)
+            })?;
+            let value = child(&reference_path, "DigestValue")?;
+            if decode(node_at(self, &value)) != Some(digest) {
+                return Err(invalid("digest does not match".to_owned(), &reference_path));
+            }
+            references += 1;
+            covered |= target.is_empty();
+        }
+        if references == 0 {
+            return Err(invalid("expected a reference".to_owned(), &signed_info));
+        }
+        if !covered {
+            let message = "no reference covers the whole document".to_owned();
+            return Err(invalid(message, &path));
+        }
+
+        // check the signature of the signed info
+        let method_path = child(&signed_info, "CanonicalizationMethod")?;
+        let method = canonicalization(node_at(self, &method_path), algorithm(&method_path)?)
+            .ok_or_else(|| invalid("unsupported canonicalization".to_owned(), &method_path))?;
+        let data = canonical_at(self, &signed_info, &method, None);
+        let signature_method = algorithm(&child(&signed_info, "SignatureMethod")?)?;
+        let value = child(&path, "SignatureValue")?;
+        let value = decode(node_at(self, &value))
+            .ok_or_else(|| invalid("invalid signature value".to_owned(), &value))?;
+        match verify(signature_method, data.as_bytes(), &value) {
+            true => Ok(()),
+            false => Err(invalid("signature does not verify".to_owned(), &path)),
+        }
+    }
+}
+
+/// Get the canonicalization of a transform or canonicalization method, including the
+/// prefixes listed by an `InclusiveNamespaces` child.
+fn canonicalization(node: &Xml, uri: &str) -> Option<Canonicalization> {
+    match Canonicalization::from_uri(uri)? {
+        Canonicalization::Exclusive(_) => {
+            let prefixes = node
+                .children()
+                .find(|child| child.name().map(local) == Some("InclusiveNamespaces"))
+                .and_then(|child| child.attr("PrefixList"))
+                .map(|list| unquote(list).split_ascii_whitespace().map(str::to_owned));
+            Some(Canonicalization::Exclusive(
+                prefixes.into_iter().flatten().collect(),
+            ))
+        }
+        method => Some(method),
+    }
+}
+
+/// Compute a digest by its algorithm identifier.
+fn digest(algorithm: &str, data: &[u8]) -> Option<Vec<u8>> {
+    let digest = match algorithm {
+        "http://www.w3.org/2000/09/xmldsig#sha1" => sha1::Sha1::digest(data).to_vec(),
+        "http://www.w3.org/2001/04/xmlenc#sha256" => sha2::Sha256::digest(data).to_vec(),
+        "http://www.w3.org/2001/04/xmldsig-more#sha384" => sha2::Sha384::digest(data).to_vec(),
+        "http://www.w3.org/2001/04/xmlenc#sha512" => sha2::Sha512::digest(data).to_vec(),
+        _ => return None,
+    };
+    Some(digest)
+}
+
+/// Decode the base64 text of an element.
+fn decode(node: &Xml) -> Option<Vec<u8>> {
+    let text = node.children().filter_map(Xml::content).collect::<String>();
+    let text = text.split_ascii_whitespace().collect::<String>();
+    STANDARD.decode(text).ok()
+}
+
+fn node_at<'x, 'a>(root: &'x Xml<'a>, path: &[usize]) -> &'x Xml<'a> {
+    path.iter()
+        .fold(root, |node, &i| &node.children().as_slice()[i])
+}
+
+/// Find the child indices of the elements with an `ID`, `Id` or `id` attribute.
+fn elements_by_id(root: &Xml, id: &str) -> Vec<Vec<usize>> {
+    let mut found = vec![];
+    walk(root, &mut vec![], &mut |node, indices| {
+        let matches = ["ID", "Id", "id"]
+            .iter()
+            .any(|key| node.attr(key).map(unquote) == Some(id));
+        if matches {
+            found.push(indices.to_vec());
+        }
+    });
+    found
+}
+
+/// Check whether a node is an XML Signature element with a local name.
+fn is_dsig(root: &Xml, path: &[usize], node: &Xml, name: &str) -> bool {
+    let Some(qname) = node.name().filter(|qname| local(qname) == name) else {
+        return false;
+    };
+    let prefix = qname.rsplit_once(':').map_or("", |(prefix, _)| prefix);
+    let scope = declare(scope_at(root, path), node);
+    scope.get(prefix).map(String::as_str) == Some(DSIG)
+}
+
+/// Canonicalize the node at a path, with the namespaces of its ancestors in scope.
+fn canonical_at(
+    root: &Xml,
+    path: &[usize],
+    method: &Canonicalization,
+    skip: Option<&Xml>,
+) -> String {
+    let mut out = String::new();
+    let scope = scope_at(root, path);
+    canonical(
+        node_at(root, path),
+        &scope,
+        &Scope::new(),
+        method,
+        skip,
+        &mut out,
+    );
+    out
+}
+
+/// Get the namespaces declared by the ancestors of the node at a path.
+fn scope_at(root: &Xml, path: &[usize]) -> Scope {
+    let mut scope = Scope::new();
+    let mut node = root;
+    for &i in path {
+        scope = declare(scope, node);
+        node = &node.children().as_slice()[i];
+    }
+    scope
+}
+
+/// Add the namespace declarations of an element to a scope.
+fn declare(mut scope: Scope, node: &Xml) -> Scope {
+    let Xml::Element(Element { attrs, .. }) = node else {
+        return scope;
+    };
+    for (key, value) in attrs {
+        let prefix = match key.strip_prefix("xmlns") {
+            Some("") => "",
+            Some(rest) => match rest.strip_prefix(':') {
+                Some(prefix) => prefix,
+                None => continue,
+            },
+            None => continue,
+        };
+        scope.insert(prefix.to_owned(), attr_text(value).into_owned());
+    }
+    scope
+}
+
+/// Write the canonical form of a node.
+///
+/// `scope` holds the namespaces in scope from the ancestors, and `rendered` the namespaces
+/// declared by the output ancestors.
+fn canonical(
+    node: &Xml,
+    scope: &Scope,
+    rendered: &Scope,
+    method: &Canonicalization,
+    skip: Option<&Xml>,
+    out: &mut String,
+) {
+    let (name, attrs, children) = match node {
+        Xml::Element(Element {
+            name,
+            attrs,
+            children,
+        }) => (name, attrs, children),
+        Xml::Text(text) => {
+            let text = match text.strip_prefix("<![CDATA[") {
+                Some(cdata) => cdata.strip_suffix("]]>").unwrap_or(cdata).into(),
+                None => unescape(&newlines(text)).into_owned(),
+            };
+            return escape(&newlines(&text), false, out);
+        }
+        Xml::Raw(raw) if raw.starts_with("<?") => return out.push_str(raw),
+        Xml::Raw(_) => return,
+    };
+
+    // namespaces in scope, treating an undeclared default namespace as empty
+    let mut scope = declare(scope.clone(), node);
+    scope.entry(String::new()).or_default();
+    let prefix = |qname: &str| qname.rsplit_once(':').map(|(prefix, _)| prefix.to_owned());
+    let mut used = vec![prefix(name).unwrap_or_default()];
+    used.extend(
+        attrs
+            .keys()
+            .filter(|key| !key.starts_with("xmlns"))
+            .filter_map(|key| prefix(key)),
+    );
+
+    // namespace declarations that are visible and not already rendered by an ancestor
+    let mut rendered = rendered.clone();
+    let mut declarations = vec![];
+    for (prefix, uri) in &scope {
+        let visible = match method {
+            Canonicalization::Inclusive => true,
+            Canonicalization::Exclusive(prefixes) => {
+                let listed = match prefix.as_str() {
+                    "" => prefixes.iter().any(|p| p == "#default"),
+                    prefix => prefixes.iter().any(|p| p == prefix),
+                };
+                listed || used.contains(prefix)
+            }
+        };
+        let declared = rendered.get(prefix).map_or("", String::as_str);
+        let redundant = match rendered.get(prefix) {
+            Some(_) => declared == uri,
+            None => uri.is_empty(),
+        };
+        if prefix == "xml" || !visible || redundant {
+            continue;
+        }
+        declarations.push((prefix.clone(), uri.clone()));
+        rendered.insert(prefix.clone(), uri.clone());
+    }
+
+    // other attributes, sorted by namespace name and local name
+    let mut sorted = attrs
+        .iter()
+        .filter(|(key, _)| *key != "xmlns" && !key.starts_with("xmlns:"))
+        .map(|(key, value)| {
+            let namespace = match prefix(key).as_deref() {
+                None => "",
+                Some("xml") => XML,
+                Some(prefix) => scope.get(prefix).map_or("", String::as_str),
+            };
+            ((namespace, local(key)), key, value)
+        })
+        .collect::<Vec<_>>();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+
+    out.push('<');
+    out.push_str(name);
+    for (prefix, uri) in declarations {
+        match prefix.as_str() {
+            "" => out.push_str(" xmlns=\""),
+            prefix => {
+                out.push_str(" xmlns:");
+                out.push_str(prefix);
+                out.push_str("=\"");
+            }
+        }
+        escape(&uri, true, out);
+        out.push('"');
+    }
+    for (_, key, value) in sorted {
+        out.push(' ');
+        out.push_str(key);
+        out.push_str("=\"");
+        // attribute value normalization turns literal whitespace into spaces
+        let value = newlines(unquote(value)).replace(['\t', '\n'], " ");
+        escape(&unescape(&value), true, out);
+        out.push('"');
+    }
+    out.push('>');
+    for child in children {
+        if skip.is_some_and(|skip| std::ptr::eq(skip, child)) {
+            continue;
+        }
+        canonical(child, &scope, &rendered, method, skip, out);
+    }
+    out.push_str("</");
+    out.push_str(name);
+    out.push('>');
+}
+
+/// Normalize line endings to line feeds.
+fn newlines(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Escape text or an attribute value as required by Canonical XML.
+fn escape(text: &str, attr: bool, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' if !attr => out.push_str("&gt;"),
+            '"' if attr => out.push_str("&quot;"),
+            '\t' if attr => out.push_str("&#x9;"),
+            '\n' if attr => out.push_str("&#xA;"),
+            '\r' => out.push_str("&#xD;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_example() {
+        // example 3.3 of Canonical XML 1.0
+        let text = r#"<doc>
+   <e1   />
+   <e2   ></e2>
+   <e3   name = "elem3"   id="elem3"   />
+   <e4   name="elem4"   id="elem4"   ></e4>
+   <e5 a:attr="out" b:attr="sorted" attr2="all" attr="I'm"
+      xmlns:b="http://www.ietf.org"
+      xmlns:a="http://www.w3.org"
+      xmlns="http://example.org"/>
+   <e6 xmlns="" xmlns:a="http://www.w3.org">
+      <e7 xmlns="http://www.ietf.org">
+         <e8 xmlns="" xmlns:a="http://www.w3.org">
+            <e9 xmlns="" xmlns:a="http://www.ietf.org"/>
+         </e8>
+      </e7>
+   </e6>
+</doc>"#;
+        let expected = r#"<doc>
+   <e1></e1>
+   <e2></e2>
+   <e3 id="elem3" name="elem3"></e3>
+   <e4 id="elem4" name="elem4"></e4>
+   <e5 xmlns="http://example.org" xmlns:a="http://www.w3.org" xmlns:b="http://www.ietf.org" attr="I'm" attr2="all" b:attr="sorted" a:attr="out"></e5>
+   <e6 xmlns:a="http://www.w3.org">
+      <e7 xmlns="http://www.ietf.org">
+         <e8 xmlns="">
+            <e9 xmlns:a="http://www.ietf.org"></e9>
+         </e8>
+      </e7>
+   </e6>
+</doc>"#;
+        let xml = crate::document(text).unwrap();
+        assert_eq!(xml.canonicalize(&Canonicalization::Inclusive), expected);
+    }
+
+    /// Build a signed document by replacing `%` in a template with a signature, with
+    /// references to IDs whose digests are those of the element with ID `b`.
+    fn signed(template: &str, uris: &[&str]) -> String {
+        let references = uris.iter().map(|uri| {
+            format!(
+                r#"<ds:Reference URI="{uri}"><ds:Transforms><ds:Transform Algorithm="{ENVELOPED}"/></ds:Transforms><ds:DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"/><ds:DigestValue>$</ds:DigestValue></ds:Reference>"#
+            )
+        });
+        let signature = format!(
+            r#"<ds:Signature xmlns:ds="{DSIG}"><ds:SignedInfo><ds:CanonicalizationMethod Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"/><ds:SignatureMethod Algorithm="urn:hmac"/>{}</ds:SignedInfo><ds:SignatureValue>b2s=</ds:SignatureValue></ds:Signature>"#,
+            references.collect::<String>()
+        );
+        let text = template.replace('%', &signature);
+        let xml = crate::document(&text).unwrap();
+        let path = elements_by_id(&xml, "b").swap_remove(0);
+        let mut found = vec![];
+        walk(&xml, &mut vec![], &mut |node, indices| {
+            if node.name() == Some("ds:Signature") {
+                found = indices.to_vec();
+            }
+        });
+        let signature = node_at(&xml, &found);
+        let data = canonical_at(&xml, &path, &Canonicalization::Inclusive, Some(signature));
+        let digest = sha2::Sha256::digest(data.as_bytes());
+        text.replace('$', &STANDARD.encode(digest))
+    }
+
+    #[test]
+    fn signed_info_namespaces() {
+        let prefix =
+            r#"<ds:SignedInfo xmlns:ds="http://www.w3.org/2000/09/xmldsig#" xmlns:u="urn:unused">"#;
+        let verify = |algorithm: &str, data: &[u8], signature: &[u8]| {
+            algorithm == "urn:hmac" && data.starts_with(prefix.as_bytes()) && signature == b"ok"
+        };
+        let text = signed(r#"<r xmlns:u="urn:unused" id="b">%</r>"#, &["#b"]);
+        let xml = crate::document(&text).unwrap();
+        assert!(xml.verify_signature(verify).is_ok());
+        assert!(xml.verify_signature(|_, _, _| false).is_err());
+
+        // without references, nothing is signed
+        let text = signed(r#"<r xmlns:u="urn:unused" id="b">%</r>"#, &[]);
+        let xml = crate::document(&text).unwrap();
+        assert!(xml.verify_signature(verify).is_err());
+        assert!(
+            crate::document("<r/>")
+                .unwrap()
+                .verify_signature(verify)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn wrapping() {
+        let verify = |_: &str, _: &[u8], signature: &[u8]| signature == b"ok";
+        // the reference covers a sibling of the signature instead of its parent
+        let text = signed(r#"<r><b id="b"/>%</r>"#, &["#b"]);
+        let error = crate::document(&text).unwrap().verify_signature(verify);
+        assert!(
+            matches!(error, Err(Error::Validation { message, .. }) if message.contains("covers"))
+        );
+        // a second element with the same ID could stand in for the signed one
+        let text = signed(r#"<r id="b"><b id="b"/>%</r>"#, &["#b"]);
+        let error = crate::document(&text).unwrap().verify_signature(verify);
+        assert!(
+            matches!(error, Err(Error::Validation { message, .. }) if message.contains("duplicate"))
+        );
+        // only the element enveloping the signature is signed, not the unsigned content next
+        // to it, so the document as a whole does not verify
+        let text = signed(
+            r#"<root><payTo>attacker</payTo><inner id="b"><amount>10</amount>%</inner></root>"#,
+            &["#b"],
+        );
+        let error = crate::document(&text).unwrap().verify_signature(verify);
+        assert!(
+            matches!(error, Err(Error::Validation { message, .. }) if message.contains("covers"))
+        );
+        let text = signed(
+            r#"<root id="b"><payTo>me</payTo><inner>%</inner></root>"#,
+            &["#b"],
+        );
+        assert!(
+            crate::document(&text)
+                .unwrap()
+                .verify_signature(verify)
+                .is_ok()
+        );
+    }
+}
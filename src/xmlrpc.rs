@@ -0,0 +1,194 @@
+//! XML-RPC values.
+
+use crate::{
+    Error, Xml,
+    escape::{escape, unescape_text},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+/// XML-RPC value, as found in `<value>` elements of method calls and responses.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::RpcValue;
+/// let value = RpcValue::Struct(vec![
+///     ("name".to_owned(), RpcValue::String("Ada".to_owned())),
+///     ("tags".to_owned(), RpcValue::Array(vec![RpcValue::Int(1), RpcValue::Boolean(true)])),
+/// ]);
+/// let xml = value.to_xml();
+/// assert_eq!(
+///     xml.to_string(),
+///     "<value><struct><member><name>name</name><value><string>Ada</string></value></member>\
+///     <member><name>tags</name><value><array><data><value><int>1</int></value>\
+///     <value><boolean>1</boolean></value></data></array></value></member></struct></value>",
+/// );
+/// assert_eq!(RpcValue::from_xml(&xml)?, value);
+/// # Ok::<(), xmlite::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum RpcValue {
+    /// 32-bit signed integer, `<int>` or `<i4>`.
+    Int(i32),
+    /// Boolean, `<boolean>`.
+    Boolean(bool),
+    /// String, `<string>` or untyped text.
+    String(String),
+    /// Double-precision floating point number, `<double>`.
+    Double(f64),
+    /// Date and time as written, e.g. `19980717T14:08:55`, `<dateTime.iso8601>`.
+    DateTime(String),
+    /// Binary data, `<base64>`.
+    Base64(Vec<u8>),
+    /// Named members in order, `<struct>`.
+    Struct(Vec<(String, RpcValue)>),
+    /// List of values, `<array>`.
+    Array(Vec<RpcValue>),
+}
+impl RpcValue {
+    /// Build the `<value>` element for this value.
+    pub fn to_xml(&self) -> Xml<'static> {
+        let text = |name: &'static str, text: String| {
+            Xml::element(name).with_child(Xml::text(escape(&text).into_owned()))
+        };
+        let typed = match self {
+            RpcValue::Int(n) => text("int", n.to_string()),
+            RpcValue::Boolean(b) => text("boolean", u8::from(*b).to_string()),
+            RpcValue::String(s) => text("string", s.clone()),
+            RpcValue::Double(x) => text("double", x.to_string()),
+            RpcValue::DateTime(date) => text("dateTime.iso8601", date.clone()),
+            RpcValue::Base64(bytes) => text("base64", STANDARD.encode(bytes)),
+            RpcValue::Struct(members) => {
+                members
+                    .iter()
+                    .fold(Xml::element("struct"), |element, (name, value)| {
+                        let member = Xml::element("member")
+                            .with_child(text("name", name.clone()))
+                            .with_child(value.to_xml());
+                        element.with_child(member)
+                    })
+            }
+            RpcValue::Array(values) => {
+                let data = values.iter().fold(Xml::element("data"), |data, value| {
+                    data.with_child(value.to_xml())
+                });
+                Xml::element("array").with_child(data)
+            }
+        };
+        Xml::element("value").with_child(typed)
+    }
+
+    /// Read a value from a `<value>` element.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Validation`] if the element is not a valid XML-RPC value.
+    pub fn from_xml(xml: &Xml) -> Result<RpcValue, Error> {
+        value(xml, "/value")
+    }
+}
+
+fn value(xml: &Xml, path: &str) -> Result<RpcValue, Error> {
+    if xml.name() != Some("value") {
+        return Err(invalid("expected a value element", path));
+    }
+    let Some(typed) = elements(xml).next() else {
+        return Ok(RpcValue::String(text(xml)));
+    };
+    let text = text(typed);
+    let path = format!("{path}/{}", typed.name().unwrap_or_default());
+    let invalid = |message: &str| invalid(message, &path);
+    let value = match typed.name().unwrap_or_default() {
+        "int" | "i4" => RpcValue::Int(text.trim().parse().map_err(|_| invalid("invalid int"))?),
+        "boolean" => match text.trim() {
+            "0" => RpcValue::Boolean(false),
+            "1" => RpcValue::Boolean(true),
+            _ => return Err(invalid("invalid boolean")),
+        },
+        "string" => RpcValue::String(text),
+        "double" => RpcValue::Double(text.trim().parse().map_err(|_| invalid("invalid double"))?),
+        "dateTime.iso8601" => RpcValue::DateTime(text.trim().to_owned()),
+        "base64" => {
+            let encoded = text.split_ascii_whitespace().collect::<String>();
+            let bytes = STANDARD
+                .decode(encoded)
+                .map_err(|_| invalid("invalid base64"))?;
+            RpcValue::Base64(bytes)
+        }
+        "struct" => {
+            let mut members = vec![];
+            for (i, member) in elements(typed).enumerate() {
+                let path = format!("{path}/member[{}]", i + 1);
+                let mut parts = elements(member);
+                let (Some(name), Some(value)) = (parts.next(), parts.next()) else {
+                    return Err(invalid("expected a member with a name and a value"));
+                };
+                if name.name() != Some("name") {
+                    return Err(invalid("expected a member name"));
+                }
+                members.push((
+                    self::text(name),
+                    self::value(value, &format!("{path}/value"))?,
+                ));
+            }
+            RpcValue::Struct(members)
+        }
+        "array" => {
+            let data = elements(typed)
+                .find(|data| data.name() == Some("data"))
+                .ok_or_else(|| invalid("expected array data"))?;
+            let values = elements(data)
+                .enumerate()
+                .map(|(i, value)| self::value(value, &format!("{path}/data/value[{}]", i + 1)));
+            RpcValue::Array(values.collect::<Result<_, _>>()?)
+        }
+        _ => return Err(invalid("unknown value type")),
+    };
+    Ok(value)
+}
+
+fn invalid(message: &str, path: &str) -> Error {
+    Error::Validation {
+        message: message.to_owned(),
+        path: path.to_owned(),
+    }
+}
+
+fn elements<'x, 'a>(xml: &'x Xml<'a>) -> impl Iterator<Item = &'x Xml<'a>> {
+    xml.children().filter(|child| child.is_element())
+}
+
+/// Get the unescaped text of an element.
+fn text(xml: &Xml) -> String {
+    xml.children()
+        .filter_map(Xml::content)
+        .map(unescape_text)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scalars() {
+        let text = "<value><array><data>\n<value>plain &amp; <![CDATA[<simple>]]></value>\n<value><i4> -3 </i4></value>\n<value><base64>aGk=\n</base64></value><value><double>0.5</double></value></data></array></value>";
+        let xml = crate::document(text).unwrap();
+        let expected = RpcValue::Array(vec![
+            RpcValue::String("plain & <simple>".to_owned()),
+            RpcValue::Int(-3),
+            RpcValue::Base64(b"hi".to_vec()),
+            RpcValue::Double(0.5),
+        ]);
+        assert_eq!(RpcValue::from_xml(&xml).unwrap(), expected);
+
+        let xml = crate::document(
+            "<value><array><data><value><int>x</int></value></data></array></value>",
+        )
+        .unwrap();
+        let Err(Error::Validation { path, .. }) = RpcValue::from_xml(&xml) else {
+            panic!("invalid int was accepted");
+        };
+        assert_eq!(path, "/value/array/data/value[1]/int");
+    }
+}
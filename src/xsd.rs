@@ -0,0 +1,628 @@
+//! XML Schema validation subset.
+
+use crate::{
+    Element, Error, Xml, datatype,
+    validate::{child_path, local, unquote},
+};
+use std::{collections::BTreeSet, collections::HashMap, slice};
+
+/// XML Schema used to validate documents.
+///
+/// Supports a practical subset of XSD 1.0: global and local element declarations, element
+/// references, named and anonymous complex and simple types, `sequence`, `choice` and `all`
+/// groups, `any` wildcards, occurrence constraints, attribute declarations and simple type
+/// restrictions (`enumeration`, `length`, `minLength`, `maxLength`, `minInclusive`,
+/// `maxInclusive`, `minExclusive`, `maxExclusive`) over the common built-in types.
+///
+/// Names are compared by local name, namespaces are not taken into account.
+///
+/// # Examples
+///
+/// ```rust
+/// # use xmlite::Schema;
+/// let xsd = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+///     <xs:element name="can">
+///         <xs:complexType>
+///             <xs:sequence>
+///                 <xs:element name="beans" type="xs:string" maxOccurs="unbounded"/>
+///             </xs:sequence>
+///             <xs:attribute name="size" type="xs:positiveInteger" use="required"/>
+///         </xs:complexType>
+///     </xs:element>
+/// </xs:schema>"#;
+/// let schema = Schema::new(&xmlite::document(xsd).unwrap()).unwrap();
+///
+/// let xml = xmlite::document(r#"<can size="3"><beans>fava</beans></can>"#).unwrap();
+/// assert!(schema.validate(&xml).is_empty());
+///
+/// let xml = xmlite::document(r#"<can size="-1"><sauce/></can>"#).unwrap();
+/// assert_eq!(schema.validate(&xml).len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    elements: HashMap<String, ElementDecl>,
+    complex_types: HashMap<String, Complex>,
+    simple_types: HashMap<String, Simple>,
+}
+
+#[derive(Debug, Clone)]
+struct ElementDecl {
+    name: String,
+    kind: TypeRef,
+}
+
+#[derive(Debug, Clone)]
+enum TypeRef {
+    Named(String),
+    Complex(Box<Complex>),
+    Simple(Simple),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Complex {
+    content: Option<Particle>,
+    attrs: Vec<AttrDecl>,
+    mixed: bool,
+    text: Option<Box<TypeRef>>,
+}
+
+#[derive(Debug, Clone)]
+struct AttrDecl {
+    name: String,
+    kind: TypeRef,
+    required: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Particle {
+    term: Term,
+    min: usize,
+    max: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Element(ElementDecl),
+    Ref(String),
+    Sequence(Vec<Particle>),
+    Choice(Vec<Particle>),
+    All(Vec<Particle>),
+    Any,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Simple {
+    base: String,
+    enumeration: Vec<String>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    min: Option<(f64, bool)>,
+    max: Option<(f64, bool)>,
+    list: bool,
+}
+
+impl Schema {
+    /// Read a schema from a parsed `xs:schema` document.
+    ///
+    /// Returns an [`Error::Validation`] pointing into the schema document if it uses
+    /// constructs outside the supported subset.
+    pub fn new(schema: &Xml) -> Result<Self, Error> {
+        let path = child_path("", slice::from_ref(schema), 0);
+        if schema.name().map(local) != Some("schema") {
+            return Err(invalid("expected a schema element", &path));
+        }
+        let mut this = Schema::default();
+        let children = schema.children().as_slice();
+        for (i, node) in children.iter().enumerate() {
+            let path = child_path(&path, children, i);
+            let name = || attr(node, "name").ok_or_else(|| invalid("missing name", &path));
+            match node.name().map(local) {
+                Some("element") => {
+                    let decl = element(node, &path)?;
+                    this.elements.insert(decl.name.clone(), decl);
+                }
+                Some("complexType") => {
+                    let ty = complex(node, &path)?;
+                    this.complex_types.insert(name()?.to_owned(), ty);
+                }
+                Some("simpleType") => {
+                    let ty = simple(node, &path)?;
+                    this.simple_types.insert(name()?.to_owned(), ty);
+                }
+                Some("annotation" | "import" | "include") | None => {}
+                Some(other) => return Err(unsupported(other, &path)),
+            }
+        }
+        Ok(this)
+    }
+
+    /// Validate a document against the schema.
+    ///
+    /// Returns every violation found as an [`Error::Validation`] located by node path, which
+    /// [`Error::locate`] turns into a span in the text the document was parsed from.
+    pub fn validate(&self, xml: &Xml) -> Vec<Error> {
+        let mut diags = vec![];
+        let path = child_path("", slice::from_ref(xml), 0);
+        match xml.name().and_then(|n| self.elements.get(local(n))) {
+            Some(decl) => self.element(&decl.kind, xml, &path, &mut diags),
+            None => diags.push(invalid("no declaration for root element", &path)),
+        }
+        diags
+    }
+
+    fn element(&self, kind: &TypeRef, node: &Xml, path: &str, diags: &mut Vec<Error>) {
+        match kind {
+            TypeRef::Named(name) if self.complex_types.contains_key(name) => {
+                self.complex(&self.complex_types[name], node, path, diags)
+            }
+            TypeRef::Named(name) if name == "anyType" => {}
+            TypeRef::Complex(ty) => self.complex(ty, node, path, diags),
+            _ => {
+                self.attrs(&[], node, path, diags);
+                self.simple_element(kind, node, path, diags);
+            }
+        }
+    }
+
+    fn simple_element(&self, kind: &TypeRef, node: &Xml, path: &str, diags: &mut Vec<Error>) {
+        if node.children().any(Xml::is_element) {
+            diags.push(invalid("element content not allowed", path));
+            return;
+        }
+        let text = node.children().filter_map(Xml::content).collect::<String>();
+        self.simple(kind, &text, path, diags);
+    }
+
+    fn complex(&self, ty: &Complex, node: &Xml, path: &str, diags: &mut Vec<Error>) {
+        self.attrs(&ty.attrs, node, path, diags);
+
+        // simple content
+        if let Some(kind) = &ty.text {
+            return self.simple_element(kind, node, path, diags);
+        }
+        let text = node.children().filter_map(Xml::content);
+        if !ty.mixed && text.clone().any(|t| !t.trim().is_empty()) {
+            diags.push(invalid("text content not allowed", path));
+        }
+
+        // element content
+        let children = node.children().as_slice();
+        let elements = children
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_element())
+            .collect::<Vec<_>>();
+        let names = elements
+            .iter()
+            .filter_map(|(_, c)| c.name().map(local))
+            .collect::<Vec<_>>();
+        let mut furthest = 0;
+        let matched = match &ty.content {
+            Some(p) => self
+                .matches(p, &names, 0, &mut furthest)
+                .contains(&names.len()),
+            None => names.is_empty(),
+        };
+        if !matched {
+            match elements.get(furthest) {
+                Some(&(i, _)) => diags.push(invalid(
+                    "unexpected element",
+                    &child_path(path, children, i),
+                )),
+                None => diags.push(invalid("missing required element", path)),
+            }
+        }
+
+        // validate children against their declarations
+        for &(i, child) in &elements {
+            let path = child_path(path, children, i);
+            let name = child.name().map(local).unwrap_or_default();
+            let decl = ty.content.as_ref().and_then(|p| self.find(p, name));
+            match decl {
+                Some(Some(kind)) => self.element(kind, child, &path, diags),
+                Some(None) => {}
+                None if matched => diags.push(invalid("no declaration for element", &path)),
+                None => {}
+            }
+        }
+    }
+
+    fn attrs(&self, decls: &[AttrDecl], node: &Xml, path: &str, diags: &mut Vec<Error>) {
+        let Xml::Element(Element { attrs, .. }) = node else {
+            return;
+        };
+        // check attributes in a stable order, so diagnostics do not depend on hash order
+        let mut sorted = attrs.iter().collect::<Vec<_>>();
+        sorted.sort_unstable_by_key(|(key, _)| *key);
+        for (key, value) in sorted {
+            let path = format!("{path}/@{key}");
+            let ignored = key.starts_with("xmlns") || key.starts_with("xml:");
+            let ignored = ignored || key.starts_with("xsi:");
+            match decls.iter().find(|d| d.name == local(key)) {
+                Some(decl) => self.simple(&decl.kind, unquote(value), &path, diags),
+                None if ignored => {}
+                None => diags.push(invalid("undeclared attribute", &path)),
+            }
+        }
+        for decl in decls.iter().filter(|d| d.required) {
+            if !attrs.keys().any(|k| local(k) == decl.name) {
+                let message = format!("missing required attribute {:?}", decl.name);
+                diags.push(invalid(&message, path));
+            }
+        }
+    }
+
+    fn simple(&self, kind: &TypeRef, value: &str, path: &str, diags: &mut Vec<Error>) {
+        let ty = match kind {
+            TypeRef::Named(name) => match self.simple_types.get(name) {
+                Some(ty) => ty,
+                None => return builtin(name, value, path, diags),
+            },
+            TypeRef::Simple(ty) => ty,
+            TypeRef::Complex(_) => return,
+        };
+        let values = if ty.list {
+            value.split_whitespace().collect()
+        } else {
+            vec![value]
+        };
+        let base = TypeRef::Named(ty.base.clone());
+        for value in values {
+            self.simple(&base, value, path, diags);
+            let value = if ty.base == "string" {
+                value
+            } else {
+                value.trim()
+            };
+            let length = value.chars().count();
+            let number = value.parse::<f64>().ok();
+            let mut facet =
+                |name: &str| diags.push(invalid(&format!("violates {name} facet"), path));
+            if !ty.enumeration.is_empty() && !ty.enumeration.iter().any(|e| e == value) {
+                facet("enumeration");
+            } else if ty.min_length.is_some_and(|min| length < min) {
+                facet("minLength");
+            } else if ty.max_length.is_some_and(|max| length > max) {
+                facet("maxLength");
+            } else if let (Some((min, inclusive)), Some(n)) = (ty.min, number)
+                && (n < min || (!inclusive && n == min))
+            {
+                facet("minimum");
+            } else if let (Some((max, inclusive)), Some(n)) = (ty.max, number)
+                && (n > max || (!inclusive && n == max))
+            {
+                facet("maximum");
+            }
+        }
+    }
+
+    /// Match a particle against element names starting at `pos`, returning all possible ends.
+    fn matches(
+        &self,
+        p: &Particle,
+        names: &[&str],
+        pos: usize,
+        furthest: &mut usize,
+    ) -> BTreeSet<usize> {
+        let mut result = BTreeSet::new();
+        let mut current = BTreeSet::from([pos]);
+        for count in 0.. {
+            if count >= p.min {
+                result.extend(&current);
+            }
+            if p.max.is_some_and(|max| count >= max) || current.is_empty() {
+                break;
+            }
+            let mut next = BTreeSet::new();
+            for &pos in &current {
+                next.extend(self.term(&p.term, names, pos, furthest));
+            }
+            if count >= p.min {
+                next.retain(|n| !result.contains(n));
+            }
+            current = next;
+        }
+        result
+    }
+
+    fn term(
+        &self,
+        term: &Term,
+        names: &[&str],
+        pos: usize,
+        furthest: &mut usize,
+    ) -> BTreeSet<usize> {
+        let mut step = |matched: bool| {
+            if matched {
+                *furthest = (*furthest).max(pos + 1);
+                BTreeSet::from([pos + 1])
+            } else {
+                BTreeSet::new()
+            }
+        };
+        match term {
+            Term::Element(decl) => step(names.get(pos) == Some(&decl.name.as_str())),
+            Term::Ref(name) => step(names.get(pos) == Some(&name.as_str())),
+            Term::Any => step(pos < names.len()),
+            Term::Sequence(ps) => ps.iter().fold(BTreeSet::from([pos]), |ends, p| {
+                ends.iter()
+                    .flat_map(|&end| self.matches(p, names, end, furthest))
+                    .collect()
+            }),
+            Term::Choice(ps) => ps
+                .iter()
+                .flat_map(|p| self.matches(p, names, pos, furthest))
+                .collect(),
+            Term::All(ps) => {
+                let mut counts = vec![0; ps.len()];
+                let mut end = pos;
+                while let Some(i) = names
+                    .get(end)
+                    .and_then(|name| ps.iter().position(|p| self.find(p, name).is_some()))
+                {
+                    if counts[i] >= ps[i].max.unwrap_or(usize::MAX) {
+                        break;
+                    }
+                    counts[i] += 1;
+                    end += 1;
+                    *furthest = (*furthest).max(end);
+                }
+                let complete = ps.iter().zip(&counts).all(|(p, &n)| n >= p.min);
+                if complete {
+                    BTreeSet::from([end])
+                } else {
+                    BTreeSet::new()
+                }
+            }
+        }
+    }
+
+    /// Find the type of the element declaration for a name within a particle.
+    ///
+    /// Returns `Some(None)` for names matched by a wildcard.
+    fn find<'s>(&'s self, p: &'s Particle, name: &str) -> Option<Option<&'s TypeRef>> {
+        match &p.term {
+            Term::Element(decl) if decl.name == name => Some(Some(&decl.kind)),
+            Term::Ref(r) if r == name => self.elements.get(name).map(|d| Some(&d.kind)),
+            Term::Sequence(ps) | Term::Choice(ps) | Term::All(ps) => {
+                let mut wildcard = None;
+                for p in ps {
+                    match self.find(p, name) {
+                        Some(Some(kind)) => return Some(Some(kind)),
+                        Some(None) => wildcard = Some(None),
+                        None => {}
+                    }
+                }
+                wildcard
+            }
+            Term::Any => Some(None),
+            _ => None,
+        }
+    }
+}
+
+fn element(node: &Xml, path: &str) -> Result<ElementDecl, Error> {
+    let name = attr(node, "name").ok_or_else(|| invalid("missing name", path))?;
+    let kind = match attr(node, "type") {
+        Some(ty) => TypeRef::Named(local(ty).to_owned()),
+        None => inline_type(node, path)?.unwrap_or(TypeRef::Named("anyType".to_owned())),
+    };
+    Ok(ElementDecl {
+        name: name.to_owned(),
+        kind,
+    })
+}
+
+fn inline_type(node: &Xml, path: &str) -> Result<Option<TypeRef>, Error> {
+    let children = node.children().as_slice();
+    for (i, child) in children.iter().enumerate() {
+        let path = child_path(path, children, i);
+        match child.name().map(local) {
+            Some("complexType") => {
+                return Ok(Some(TypeRef::Complex(complex(child, &path)?.into())));
+            }
+            Some("simpleType") => return Ok(Some(TypeRef::Simple(simple(child, &path)?))),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+fn complex(node: &Xml, path: &str) -> Result<Complex, Error> {
+    let mut ty = Complex {
+        mixed: attr(node, "mixed") == Some("true"),
+        ..Default::default()
+    };
+    let children = node.children().as_slice();
+    for (i, child) in children.iter().enumerate() {
+        let path = child_path(path, children, i);
+        match child.name().map(local) {
+            Some("sequence" | "choice" | "all") => ty.content = Some(particle(child, &path)?),
+            Some("attribute") => ty.attrs.push(attribute(child, &path)?),
+            Some("simpleContent") => {
+                let extension = child
+                    .children()
+                    .find(|c| matches!(c.name().map(local), Some("extension" | "restriction")))
+                    .ok_or_else(|| invalid("expected an extension", &path))?;
+                let base = attr(extension, "base").unwrap_or("string");
+                ty.text = Some(TypeRef::Named(local(base).to_owned()).into());
+                for attribute_node in extension.children() {
+                    if attribute_node.name().map(local) == Some("attribute") {
+                        ty.attrs.push(attribute(attribute_node, &path)?);
+                    }
+                }
+            }
+            Some("annotation" | "anyAttribute") | None => {}
+            Some(other) => return Err(unsupported(other, &path)),
+        }
+    }
+    Ok(ty)
+}
+
+fn attribute(node: &Xml, path: &str) -> Result<AttrDecl, Error> {
+    let name = attr(node, "name").ok_or_else(|| invalid("missing name", path))?;
+    let kind = match attr(node, "type") {
+        Some(ty) => TypeRef::Named(local(ty).to_owned()),
+        None => inline_type(node, path)?.unwrap_or(TypeRef::Named("string".to_owned())),
+    };
+    Ok(AttrDecl {
+        name: name.to_owned(),
+        kind,
+        required: attr(node, "use") == Some("required"),
+    })
+}
+
+fn particle(node: &Xml, path: &str) -> Result<Particle, Error> {
+    let min = match attr(node, "minOccurs") {
+        Some(min) => min
+            .parse()
+            .map_err(|_| invalid("invalid minOccurs", path))?,
+        None => 1,
+    };
+    let max = match attr(node, "maxOccurs") {
+        Some("unbounded") => None,
+        Some(max) => Some(
+            max.parse()
+                .map_err(|_| invalid("invalid maxOccurs", path))?,
+        ),
+        None => Some(1),
+    };
+    let group = || -> Result<Vec<Particle>, Error> {
+        let children = node.children().as_slice();
+        let particles = children.iter().enumerate().filter(|(_, c)| {
+            matches!(
+                c.name().map(local),
+                Some("element" | "sequence" | "choice" | "any")
+            )
+        });
+        particles
+            .map(|(i, c)| particle(c, &child_path(path, children, i)))
+            .collect()
+    };
+    let term = match node.name().map(local) {
+        Some("element") => match attr(node, "ref") {
+            Some(r) => Term::Ref(local(r).to_owned()),
+            None => Term::Element(element(node, path)?),
+        },
+        Some("sequence") => Term::Sequence(group()?),
+        Some("choice") => Term::Choice(group()?),
+        Some("all") => Term::All(group()?),
+        Some("any") => Term::Any,
+        Some(other) => return Err(unsupported(other, path)),
+        None => return Err(invalid("expected a particle", path)),
+    };
+    Ok(Particle { term, min, max })
+}
+
+fn simple(node: &Xml, path: &str) -> Result<Simple, Error> {
+    let mut ty = Simple {
+        base: "string".to_owned(),
+        ..Default::default()
+    };
+    for child in node.children().filter(|c| c.is_element()) {
+        match child.name().map(local) {
+            Some("restriction") => {
+                ty.base = local(attr(child, "base").unwrap_or("string")).to_owned();
+                for facet in child.children() {
+                    let value = attr(facet, "value").unwrap_or_default();
+                    let number = || value.parse().map_err(|_| invalid("invalid facet", path));
+                    match facet.name().map(local) {
+                        Some("enumeration") => ty.enumeration.push(value.to_owned()),
+                        Some("length") => {
+                            ty.min_length = Some(number()? as usize);
+                            ty.max_length = ty.min_length;
+                        }
+                        Some("minLength") => ty.min_length = Some(number()? as usize),
+                        Some("maxLength") => ty.max_length = Some(number()? as usize),
+                        Some("minInclusive") => ty.min = Some((number()?, true)),
+                        Some("minExclusive") => ty.min = Some((number()?, false)),
+                        Some("maxInclusive") => ty.max = Some((number()?, true)),
+                        Some("maxExclusive") => ty.max = Some((number()?, false)),
+                        _ => {}
+                    }
+                }
+            }
+            Some("list") => {
+                ty.base = local(attr(child, "itemType").unwrap_or("string")).to_owned();
+                ty.list = true;
+            }
+            Some("annotation") | None => {}
+            Some(other) => return Err(unsupported(other, path)),
+        }
+    }
+    Ok(ty)
+}
+
+/// Check a value against a built-in simple type.
+fn builtin(name: &str, value: &str, path: &str, diags: &mut Vec<Error>) {
+    if !datatype::is_valid(name, value) {
+        diags.push(invalid(&format!("invalid value for type {name}"), path));
+    }
+}
+
+fn attr<'x>(node: &'x Xml, key: &str) -> Option<&'x str> {
+    node.attr(key).map(unquote)
+}
+
+fn invalid(message: &str, path: &str) -> Error {
+    Error::Validation {
+        message: message.to_owned(),
+        path: path.to_owned(),
+    }
+}
+
+fn unsupported(construct: &str, path: &str) -> Error {
+    invalid(&format!("unsupported schema construct {construct:?}"), path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"><xs:element name="protocol" type="Protocol"/><xs:complexType name="Protocol"><xs:sequence><xs:element name="copyright" type="xs:string" minOccurs="0"/><xs:element name="interface" type="Interface" maxOccurs="unbounded"/></xs:sequence><xs:attribute name="name" type="xs:NCName" use="required"/></xs:complexType><xs:complexType name="Interface"><xs:choice minOccurs="0" maxOccurs="unbounded"><xs:element name="request"/><xs:element name="event"/></xs:choice><xs:attribute name="version" type="Version"/></xs:complexType><xs:simpleType name="Version"><xs:restriction base="xs:int"><xs:minInclusive value="1"/><xs:maxInclusive value="10"/></xs:restriction></xs:simpleType></xs:schema>"#;
+
+    fn validate(text: &str) -> Vec<String> {
+        let schema = Schema::new(&crate::document(SCHEMA).unwrap()).unwrap();
+        let xml = crate::document(text).unwrap();
+        schema
+            .validate(&xml)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let text = r#"<protocol name="wayland"><copyright>x</copyright><interface version="3"><request/><event/><request/></interface><interface/></protocol>"#;
+        assert_eq!(validate(text), Vec::<String>::new());
+    }
+
+    #[test]
+    fn invalid() {
+        let text = r#"<protocol><interface version="11"><enum/></interface>text</protocol>"#;
+        assert_eq!(
+            validate(text),
+            [
+                "missing required attribute \"name\" at /protocol",
+                "text content not allowed at /protocol",
+                "violates maximum facet at /protocol/interface/@version",
+                "unexpected element at /protocol/interface/enum",
+            ]
+        );
+        let text = r#"<protocol name="a"><copyright/></protocol>"#;
+        assert_eq!(validate(text), ["missing required element at /protocol"]);
+        let text = r#"<protocol name="a" z="1" b="2" m="3" x="4" c="5"><interface/></protocol>"#;
+        let expected = ["b", "c", "m", "x", "z"]
+            .map(|key| format!("undeclared attribute at /protocol/@{key}"));
+        assert_eq!(validate(text), expected);
+    }
+
+    #[test]
+    fn spans() {
+        let text = "<protocol name=\"a\">\n  <interface version=\"0\"/>\n  <interface><enum/></interface>\n</protocol>";
+        let schema = Schema::new(&crate::document(SCHEMA).unwrap()).unwrap();
+        let diags = schema.validate(&crate::document(text).unwrap());
+        let spans = diags.iter().map(|e| e.locate(text)).collect::<Vec<_>>();
+        assert_eq!(spans, [Some((2, 3)), Some((3, 14))]);
+    }
+}
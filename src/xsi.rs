@@ -0,0 +1,169 @@
+//! XML Schema instance attributes, i.e. `xsi:nil` and `xsi:type`.
+
+use crate::{Cursor, Element, Xml, validate::unquote};
+
+/// Namespace of the XML Schema instance attributes.
+const XSI: &str = "http://www.w3.org/2001/XMLSchema-instance";
+/// Namespace bound to the `xml` prefix.
+const XML: &str = "http://www.w3.org/XML/1998/namespace";
+
+impl<'x> Cursor<'x, '_> {
+    /// Resolve a namespace prefix, or the default namespace for `""`, through the namespace
+    /// declarations on the current node and its ancestors.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document(r#"<a xmlns="urn:a" xmlns:b="urn:b"><c xmlns=""/></a>"#)?;
+    /// let mut cursor = xml.cursor();
+    /// assert_eq!(cursor.namespace_uri(""), Some("urn:a"));
+    /// cursor.first_child();
+    /// assert_eq!(cursor.namespace_uri(""), None);
+    /// assert_eq!(cursor.namespace_uri("b"), Some("urn:b"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn namespace_uri(&self, prefix: &str) -> Option<&'x str> {
+        if prefix == "xml" {
+            return Some(XML);
+        }
+        std::iter::once(self.node())
+            .chain(self.ancestors())
+            .find_map(|node| declared(node, prefix))
+            .filter(|uri| !uri.is_empty())
+    }
+
+    /// Check if the current node is marked as nil with `xsi:nil="true"`, resolving the
+    /// prefix of the attribute through the declarations in scope.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let text = r#"<r xmlns:i="http://www.w3.org/2001/XMLSchema-instance"><v i:nil="true"/></r>"#;
+    /// let xml = xmlite::document(text)?;
+    /// let mut cursor = xml.cursor();
+    /// cursor.first_child();
+    /// assert!(cursor.is_nil());
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn is_nil(&self) -> bool {
+        xsi_attr(self.node(), "nil", |prefix| self.namespace_uri(prefix)).is_some_and(is_true)
+    }
+    /// Get the type named by the `xsi:type` attribute of the current node, as its namespace
+    /// and local name.
+    ///
+    /// The prefixes of both the attribute and the type name are resolved through the
+    /// declarations in scope. Unprefixed type names are in the default namespace.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let text = r#"<r xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+    ///   <v xsi:type="xs:int">4</v>
+    /// </r>"#;
+    /// let xml = xmlite::document(text)?;
+    /// let mut cursor = xml.cursor();
+    /// cursor.first_child();
+    /// cursor.next_sibling();
+    /// assert_eq!(cursor.xsi_type(), Some((Some("http://www.w3.org/2001/XMLSchema"), "int")));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn xsi_type(&self) -> Option<(Option<&'x str>, &'x str)> {
+        let name = xsi_attr(self.node(), "type", |prefix| self.namespace_uri(prefix))?;
+        let (prefix, local) = name.split_once(':').unwrap_or(("", name));
+        Some((self.namespace_uri(prefix), local))
+    }
+}
+
+impl Xml<'_> {
+    /// Check if the element is marked as nil with `xsi:nil="true"`.
+    ///
+    /// The prefix of the attribute is resolved through the declarations on this element, and
+    /// the conventional `xsi` prefix is accepted if it is not declared here. Since nodes do
+    /// not know their ancestors, other prefixes declared on ancestors are only taken into
+    /// account through [`Cursor::is_nil`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document(r#"<price xsi:nil="true"/>"#)?;
+    /// assert!(xml.is_nil());
+    /// assert!(!xmlite::document(r#"<price xsi:nil="false"/>"#)?.is_nil());
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn is_nil(&self) -> bool {
+        xsi_attr(self, "nil", |prefix| conventional(self, prefix)).is_some_and(is_true)
+    }
+    /// Get the type name of the `xsi:type` attribute as written, e.g. `xs:int`.
+    ///
+    /// The prefix of the attribute is resolved as in [`Xml::is_nil`]. Use
+    /// [`Cursor::xsi_type`] to also resolve the prefix of the type name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document(r#"<v xmlns:i="http://www.w3.org/2001/XMLSchema-instance" i:type=" xs:int "/>"#)?;
+    /// assert_eq!(xml.xsi_type(), Some("xs:int"));
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn xsi_type(&self) -> Option<&str> {
+        xsi_attr(self, "type", |prefix| conventional(self, prefix))
+    }
+}
+
+/// Get the namespace declared for a prefix on a node, which is empty if undeclared.
+fn declared<'x>(node: &'x Xml, prefix: &str) -> Option<&'x str> {
+    let key = match prefix {
+        "" => "xmlns".to_owned(),
+        prefix => format!("xmlns:{prefix}"),
+    };
+    node.attr(&key).map(unquote)
+}
+
+/// Resolve a prefix through the declarations on a node, assuming the conventional `xsi`.
+fn conventional<'x>(node: &'x Xml, prefix: &str) -> Option<&'x str> {
+    declared(node, prefix).or((prefix == "xsi").then_some(XSI))
+}
+
+/// Get the whitespace-trimmed value of an attribute in the XSI namespace by local name.
+fn xsi_attr<'x>(
+    node: &'x Xml,
+    name: &str,
+    resolve: impl Fn(&str) -> Option<&'x str>,
+) -> Option<&'x str> {
+    let Xml::Element(Element { attrs, .. }) = node else {
+        return None;
+    };
+    attrs
+        .iter()
+        .find(|(key, _)| {
+            key.split_once(':')
+                .is_some_and(|(prefix, local)| local == name && resolve(prefix) == Some(XSI))
+        })
+        .map(|(_, value)| unquote(value).trim())
+}
+
+/// Check if an `xs:boolean` value is true.
+fn is_true(value: &str) -> bool {
+    matches!(value, "true" | "1")
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn scope() {
+        let text = r#"<r xmlns:i="http://www.w3.org/2001/XMLSchema-instance" xmlns="urn:t">
+            <a i:nil="1" xsi:nil="true" i:type="T"/><b xmlns:xsi="urn:other" xsi:nil="true"/>
+        </r>"#;
+        let xml = crate::document(text).unwrap();
+        let mut cursor = xml.cursor();
+        cursor.first_child();
+        cursor.next_sibling();
+        assert!(cursor.is_nil());
+        assert_eq!(cursor.xsi_type(), Some((Some("urn:t"), "T")));
+        cursor.next_sibling();
+        assert!(!cursor.is_nil());
+        assert!(!cursor.node().is_nil());
+        assert!(xml.child("a").unwrap().is_nil());
+        assert_eq!(xml.child("a").unwrap().xsi_type(), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! Conversion between document trees and YAML.
+
+use crate::{Element, Error, Xml};
+use serde::{Serialize, Serializer, ser::SerializeStruct};
+use std::collections::BTreeMap;
+
+impl Xml<'_> {
+    /// Serialize the tree to YAML, in the same shape as other serde formats such as JSON.
+    ///
+    /// Nodes are tagged with their kind, e.g. `!Element`, and attribute values keep their
+    /// quotes, so the tree is restored exactly by [`Xml::from_yaml`]. Attributes are sorted by
+    /// name, so equal trees give the same output.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Conversion`] if the tree cannot be serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let xml = xmlite::document("<a x='1'>t<b/></a>")?;
+    /// let yaml = xml.to_yaml()?;
+    /// assert!(yaml.starts_with("!Element\nname: a\n"));
+    /// assert_eq!(xmlite::Xml::from_yaml(&yaml)?, xml);
+    /// # Ok::<(), xmlite::Error>(())
+    /// ```
+    pub fn to_yaml(&self) -> Result<String, Error> {
+        serde_yaml::to_string(&Sorted(self)).map_err(conversion)
+    }
+    /// Deserialize a tree from YAML, as written by [`Xml::to_yaml`].
+    ///
+    /// The tree is not checked, so names and text are taken as they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Conversion`] if the YAML is malformed or not shaped like a tree.
+    pub fn from_yaml(text: &str) -> Result<Xml<'static>, Error> {
+        serde_yaml::from_str(text).map_err(conversion)
+    }
+}
+
+/// Convert a YAML error, keeping its location.
+fn conversion(error: serde_yaml::Error) -> Error {
+    Error::Conversion {
+        span: error.location().map(|at| (at.line(), at.column())),
+        message: error.to_string(),
+    }
+}
+
+/// Tree which serializes like [`Xml`], but with the attributes of its elements sorted.
+struct Sorted<'x, 'a>(&'x Xml<'a>);
+impl Serialize for Sorted<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Xml::Element(element) => {
+                serializer.serialize_newtype_variant("Xml", 0, "Element", &SortedElement(element))
+            }
+            node => node.serialize(serializer),
+        }
+    }
+}
+
+/// Element which serializes like [`Element`], but with its attributes sorted.
+struct SortedElement<'x, 'a>(&'x Element<'a>);
+impl Serialize for SortedElement<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Element {
+            name,
+            attrs,
+            children,
+        } = self.0;
+        let mut element = serializer.serialize_struct("Element", 3)?;
+        element.serialize_field("name", name)?;
+        element.serialize_field("attrs", &attrs.iter().collect::<BTreeMap<_, _>>())?;
+        element.serialize_field("children", &children.iter().map(Sorted).collect::<Vec<_>>())?;
+        element.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Xml};
+
+    #[test]
+    fn round_trip() {
+        let yaml = "!Element\nname: a\nattrs:\n  x: '\"1\"'\nchildren:\n- !Text t &amp; u\n";
+        let xml = Xml::from_yaml(yaml).unwrap();
+        assert_eq!(xml.to_string(), "<a x=\"1\">t &amp; u</a>");
+        assert_eq!(xml.to_yaml().unwrap(), yaml);
+        assert!(Xml::from_yaml("!Node a").is_err());
+    }
+
+    #[test]
+    fn sorted() {
+        let xml = crate::document("<a z='1' b='2' m='3' x='4' c='5'><b y='1' a='2'/></a>").unwrap();
+        let yaml = xml.to_yaml().unwrap();
+        let keys = yaml
+            .lines()
+            .filter(|line| line.contains(": '"))
+            .map(str::trim);
+        let keys = keys.map(|line| &line[..1]).collect::<String>();
+        assert_eq!(keys, "bcmxzay");
+
+        let error = Xml::from_yaml("!Element\nname: [a").unwrap_err();
+        assert!(matches!(error, Error::Conversion { span: Some(_), .. }));
+    }
+}
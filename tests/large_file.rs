@@ -1,3 +1,5 @@
+#![cfg(feature = "tree")]
+
 use std::fs;
 
 #[test]
@@ -6,3 +8,9 @@ fn large_file() {
     let xml = xmlite::document(&file).unwrap();
     eprintln!("{xml:?}");
 }
+
+#[test]
+fn large_file_strict() {
+    let file = fs::read_to_string("tests/wayland.xml").unwrap();
+    xmlite::document_strict(&file).unwrap();
+}